@@ -1,7 +1,10 @@
 #![allow(non_snake_case)]
-use crate::components;
+use crate::components::{self, console::CONSOLE_BUFFER_SIZE};
 use dioxus::{document, prelude::*};
+use futures_util::StreamExt;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::HashMap;
 use uuid::Uuid;
 use wasm_bindgen::prelude::*;
 
@@ -13,6 +16,224 @@ pub static APP_STATE: GlobalSignal<AppStateStruct> = Global::new(|| AppStateStru
 extern "C" {
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"])]
     pub async fn invoke(cmd: &str, args: JsValue) -> JsValue;
+
+    // Same as invoke but returns the error instead of throwing when the command fails
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    pub async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"])]
+    pub async fn listen(event: &str, handler: &Closure<dyn FnMut(JsValue)>) -> JsValue;
+}
+
+// Calls a backend command and deserializes its result, errors come back as strings
+pub async fn invoke_command<A: Serialize, T: DeserializeOwned>(
+    cmd: &str,
+    args: &A,
+) -> Result<T, String> {
+    let args = serde_wasm_bindgen::to_value(args).map_err(|err| err.to_string())?;
+    match try_invoke(cmd, args).await {
+        Ok(value) => serde_wasm_bindgen::from_value(value).map_err(|err| err.to_string()),
+        Err(err) => Err(err
+            .as_string()
+            .unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+// The parts of the backend's config.toml the interface uses
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Config {
+    pub hide_ip: bool,
+}
+
+#[derive(Serialize)]
+struct NoArgs {}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConsolePayload {
+    pub id: Uuid,
+    pub line: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StatusPayload {
+    pub id: Uuid,
+    pub status: i8,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct OnlinePlayer {
+    pub name: String,
+    // joined from bedrock through geyser and floodgate
+    pub bedrock: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PlayersPayload {
+    pub id: Uuid,
+    pub count: i32,
+    #[serde(default)]
+    pub players: Vec<OnlinePlayer>,
+}
+
+// Servers that are started and stopped together, shown grouped in the sidebar
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ServerGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub members: Vec<Uuid>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CrashPayload {
+    pub id: Uuid,
+    pub report: CrashReport,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RestartPayload {
+    pub id: Uuid,
+    // "waiting", "gave_up" or "idle"
+    pub state: String,
+    pub delay_seconds: u64,
+    pub attempt: u32,
+    pub max_restarts: u32,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TaskRunPayload {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub run: TaskRun,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CountdownPayload {
+    pub id: Uuid,
+    pub remaining_seconds: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ResourceSample {
+    pub time: u64,
+    pub cpu_percent: f64,
+    pub memory_bytes: u64,
+    pub threads: u64,
+    pub disk_bytes: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ResourcePayload {
+    pub id: Uuid,
+    pub sample: ResourceSample,
+}
+
+// Same as the backend, 10 minutes of samples
+pub const RESOURCE_HISTORY_LENGTH: usize = 300;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct DimensionTick {
+    pub name: String,
+    pub tps: f64,
+    pub mspt: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct TickSample {
+    pub time: u64,
+    pub tps: Option<f64>,
+    pub mspt: Option<f64>,
+    pub dimensions: Vec<DimensionTick>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct LagSpike {
+    pub time: u64,
+    pub behind_ms: u64,
+    pub ticks: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct PortMapping {
+    pub method: String,
+    pub protocol: String,
+    pub port: u16,
+    pub external_ip: Option<String>,
+    pub lifetime_seconds: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PortMappingsPayload {
+    pub id: Uuid,
+    pub mappings: Vec<PortMapping>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TickPayload {
+    pub id: Uuid,
+    pub sample: TickSample,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LagSpikePayload {
+    pub id: Uuid,
+    pub spike: LagSpike,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TickAlertPayload {
+    pub id: Uuid,
+    pub alerting: bool,
+    pub tps: f64,
+}
+
+// Same as the backend, an hour of tick samples
+pub const TICK_HISTORY_LENGTH: usize = 240;
+pub const SPIKE_HISTORY_LENGTH: usize = 100;
+
+// Appends to a rolling history, dropping the oldest entry once it's full
+pub fn push_limited<T>(history: &mut Vec<T>, value: T, limit: usize) {
+    if history.len() >= limit {
+        history.remove(0);
+    }
+    history.push(value);
+}
+
+pub enum ServerEvent {
+    Console(ConsolePayload),
+    Status(StatusPayload),
+    Players(PlayersPayload),
+    Crash(Box<CrashPayload>),
+    Restart(RestartPayload),
+    TaskRun(TaskRunPayload),
+    Countdown(CountdownPayload),
+    Resources(ResourcePayload),
+    Ticks(TickPayload),
+    LagSpike(LagSpikePayload),
+    TickAlert(TickAlertPayload),
+    PortMappings(PortMappingsPayload),
+}
+
+// Forwards a backend event into the channel, the tauri callback runs outside of dioxus
+// so state can only be touched from the receiving end
+fn listen_to<T: DeserializeOwned + 'static>(
+    event: &'static str,
+    tx: UnboundedSender<ServerEvent>,
+    wrap: fn(T) -> ServerEvent,
+) {
+    let closure = Closure::<dyn FnMut(JsValue)>::new(move |evt: JsValue| {
+        let Ok(payload) = js_sys::Reflect::get(&evt, &JsValue::from_str("payload")) else {
+            return;
+        };
+        if let Ok(payload) = serde_wasm_bindgen::from_value::<T>(payload) {
+            let _ = tx.unbounded_send(wrap(payload));
+        }
+    });
+    spawn(async move {
+        listen(event, &closure).await;
+        // the listener lives as long as the app
+        closure.forget();
+    });
 }
 
 pub fn App() -> Element {
@@ -23,6 +244,149 @@ pub fn App() -> Element {
             *value = format!("SERVER:{}", server.unwrap().id);
         }
     });
+    let server_events = use_coroutine(|mut rx: UnboundedReceiver<ServerEvent>| async move {
+        while let Some(event) = rx.next().await {
+            match event {
+                ServerEvent::Console(payload) => push_console_line(payload.id, payload.line),
+                ServerEvent::Status(payload) => {
+                    if payload.status == 0 {
+                        let app_state = &mut APP_STATE.write();
+                        app_state.resource_samples.remove(&payload.id);
+                        app_state.tick_samples.remove(&payload.id);
+                        app_state.lag_spikes.remove(&payload.id);
+                        app_state.tick_alerts.remove(&payload.id);
+                        app_state.online_players.remove(&payload.id);
+                    }
+                    update_server(payload.id, |server| {
+                        server.status = payload.status;
+                        if payload.status == 0 {
+                            server.current_player_count = 0;
+                        }
+                    })
+                }
+                ServerEvent::Players(payload) => {
+                    APP_STATE
+                        .write()
+                        .online_players
+                        .insert(payload.id, payload.players);
+                    update_server(payload.id, |server| {
+                        server.current_player_count = payload.count
+                    })
+                }
+                ServerEvent::Crash(payload) => {
+                    APP_STATE
+                        .write()
+                        .crash_reports
+                        .insert(payload.id, payload.report);
+                }
+                ServerEvent::Restart(payload) => {
+                    let restart_states = &mut APP_STATE.write().restart_states;
+                    match payload.state.as_str() {
+                        "idle" => restart_states.remove(&payload.id),
+                        _ => restart_states.insert(payload.id, payload),
+                    };
+                }
+                ServerEvent::Countdown(payload) => {
+                    let countdowns = &mut APP_STATE.write().restart_countdowns;
+                    match payload.remaining_seconds {
+                        0 => countdowns.remove(&payload.id),
+                        remaining => countdowns.insert(payload.id, remaining),
+                    };
+                }
+                ServerEvent::Resources(payload) => {
+                    let samples = &mut APP_STATE.write().resource_samples;
+                    let samples = samples.entry(payload.id).or_default();
+                    push_limited(samples, payload.sample, RESOURCE_HISTORY_LENGTH);
+                }
+                ServerEvent::Ticks(payload) => {
+                    let samples = &mut APP_STATE.write().tick_samples;
+                    let samples = samples.entry(payload.id).or_default();
+                    push_limited(samples, payload.sample, TICK_HISTORY_LENGTH);
+                }
+                ServerEvent::LagSpike(payload) => {
+                    let spikes = &mut APP_STATE.write().lag_spikes;
+                    let spikes = spikes.entry(payload.id).or_default();
+                    push_limited(spikes, payload.spike, SPIKE_HISTORY_LENGTH);
+                }
+                ServerEvent::TickAlert(payload) => {
+                    let tick_alerts = &mut APP_STATE.write().tick_alerts;
+                    match payload.alerting {
+                        true => tick_alerts.insert(payload.id, payload.tps),
+                        false => tick_alerts.remove(&payload.id),
+                    };
+                }
+                ServerEvent::PortMappings(payload) => {
+                    let port_mappings = &mut APP_STATE.write().port_mappings;
+                    match payload.mappings.is_empty() {
+                        true => port_mappings.remove(&payload.id),
+                        false => port_mappings.insert(payload.id, payload.mappings),
+                    };
+                }
+                ServerEvent::TaskRun(payload) => update_server(payload.id, |server| {
+                    if let Some(task) = server
+                        .tasks
+                        .iter_mut()
+                        .find(|task| task.id == payload.task_id)
+                    {
+                        task.last_run = Some(payload.run.clone());
+                    }
+                }),
+            }
+        }
+    });
+    use_hook(|| {
+        listen_to("server_console", server_events.tx(), ServerEvent::Console);
+        listen_to("server_status", server_events.tx(), ServerEvent::Status);
+        listen_to("server_players", server_events.tx(), ServerEvent::Players);
+        listen_to("server_crash", server_events.tx(), |payload| {
+            ServerEvent::Crash(Box::new(payload))
+        });
+        listen_to("server_restart", server_events.tx(), ServerEvent::Restart);
+        listen_to("server_task_run", server_events.tx(), ServerEvent::TaskRun);
+        listen_to(
+            "server_countdown",
+            server_events.tx(),
+            ServerEvent::Countdown,
+        );
+        listen_to(
+            "server_resources",
+            server_events.tx(),
+            ServerEvent::Resources,
+        );
+        listen_to("server_ticks", server_events.tx(), ServerEvent::Ticks);
+        listen_to(
+            "server_lag_spike",
+            server_events.tx(),
+            ServerEvent::LagSpike,
+        );
+        listen_to(
+            "server_tick_alert",
+            server_events.tx(),
+            ServerEvent::TickAlert,
+        );
+        listen_to(
+            "server_port_mappings",
+            server_events.tx(),
+            ServerEvent::PortMappings,
+        );
+        load_servers();
+        spawn(async {
+            // the defaults are kept when the config can't be read
+            if let Ok(config) =
+                invoke_command::<_, Config>("plugin:gaia_core|get_config", &NoArgs {}).await
+            {
+                APP_STATE.write().config = config;
+            }
+            if let Ok(groups) = invoke_command::<_, Vec<ServerGroup>>(
+                "plugin:gaia_core|get_server_groups",
+                &NoArgs {},
+            )
+            .await
+            {
+                APP_STATE.write().groups = groups;
+            }
+        });
+    });
     rsx! {
         document::Stylesheet { href: asset!("/assets/styles/main.scss") }
         document::Meta {
@@ -42,21 +406,54 @@ pub fn App() -> Element {
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct AppStateStruct {
+    pub config: Config,
     pub selected_panel: String,
     pub selected_sub_panel: String,
     pub selected_server: Option<ServerStruct>,
     pub servers: Vec<ServerStruct>,
+    pub groups: Vec<ServerGroup>,
     pub server_creation_options: Option<ServerCreationStruct>,
+    // raw console output per server id
+    pub console_lines: HashMap<Uuid, Vec<String>>,
+    // last crash of each server, cleared when it's dismissed or started again
+    pub crash_reports: HashMap<Uuid, CrashReport>,
+    // pending automatic restarts and servers the restart policy gave up on
+    pub restart_states: HashMap<Uuid, RestartPayload>,
+    // seconds left on running restart countdowns
+    pub restart_countdowns: HashMap<Uuid, u64>,
+    // cpu, memory, threads and disk of running servers, oldest first
+    pub resource_samples: HashMap<Uuid, Vec<ResourceSample>>,
+    // tps and mspt of running servers, oldest first
+    pub tick_samples: HashMap<Uuid, Vec<TickSample>>,
+    pub lag_spikes: HashMap<Uuid, Vec<LagSpike>>,
+    // tps of servers that have been under their alert threshold for too long
+    pub tick_alerts: HashMap<Uuid, f64>,
+    // ports opened on the router for running servers
+    pub port_mappings: HashMap<Uuid, Vec<PortMapping>>,
+    // names of the players on running servers, from their join and leave lines
+    pub online_players: HashMap<Uuid, Vec<OnlinePlayer>>,
 }
 
 impl Default for AppStateStruct {
     fn default() -> Self {
         AppStateStruct {
+            config: Config::default(),
             selected_panel: "".to_string(),
             selected_sub_panel: "".to_string(),
             selected_server: None,
             servers: Vec::new(),
+            groups: Vec::new(),
             server_creation_options: None,
+            console_lines: HashMap::new(),
+            crash_reports: HashMap::new(),
+            restart_states: HashMap::new(),
+            restart_countdowns: HashMap::new(),
+            resource_samples: HashMap::new(),
+            tick_samples: HashMap::new(),
+            lag_spikes: HashMap::new(),
+            tick_alerts: HashMap::new(),
+            port_mappings: HashMap::new(),
+            online_players: HashMap::new(),
         }
     }
 }
@@ -76,6 +473,8 @@ pub struct ServerCreationStruct {
     pub java_allocated_memory: i64,
     pub max_players: i32,
     pub auto_accept_eula: bool,
+    // proxies are set up from a jar the user downloaded, bedrock from the server zip
+    pub jar_path: Option<String>,
 }
 
 impl Default for ServerCreationStruct {
@@ -92,12 +491,40 @@ impl Default for ServerCreationStruct {
             java_allocated_memory: 1024,
             max_players: 20,
             auto_accept_eula: false,
+            jar_path: None,
         }
     }
 }
 
 #[allow(dead_code)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SuspectedMod {
+    pub id: String,
+    pub name: String,
+    pub source: String,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct CrashReport {
+    pub kind: String,
+    pub path: String,
+    pub file_name: String,
+    pub exit_code: Option<i32>,
+    pub time: String,
+    pub description: String,
+    pub exception: String,
+    pub stack_trace: Vec<String>,
+    pub suspected_mods: Vec<SuspectedMod>,
+    pub minecraft_version: String,
+    pub java_version: String,
+    pub java_vm: String,
+    pub operating_system: String,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
 pub struct ServerStruct {
     pub id: Uuid,
     pub status: i8,
@@ -106,6 +533,7 @@ pub struct ServerStruct {
     pub port: u16,
     pub public_ip: String,
     pub local_ip: String,
+    pub port_forwarding: bool,
     pub path: String,
     pub icon_path: String,
     pub server_type: String,
@@ -114,12 +542,52 @@ pub struct ServerStruct {
     pub server_version: String,
     pub creation_date: String,
     pub auto_start: bool,
+    // servers auto start from the lowest order up
+    pub auto_start_order: u32,
+    pub auto_start_delay_seconds: u64,
+    // waits until this server logged "Done" before auto starting
+    pub auto_start_after: Option<Uuid>,
     pub force_save_on_stop: bool,
+    // "never", "on-crash" or "always"
+    pub restart_policy: String,
+    // restarts allowed inside of restart_window_seconds before giving up
+    pub max_restarts: u32,
+    pub restart_window_seconds: u64,
+    // delay before the first restart, doubles with every restart in the window
+    pub restart_backoff_seconds: u64,
+    // crashing this soon after starting counts towards crash_loop_limit
+    pub crash_loop_seconds: u64,
+    pub crash_loop_limit: u32,
+    // when players get warned before a countdown restart, e.g. "10m, 5m, 1m, 30s, 10s"
+    pub restart_countdown: String,
+    // "say" or "tellraw"
+    pub restart_countdown_format: String,
+    // {time} is replaced with what's left, tellraw also takes a json text component
+    pub restart_countdown_message: String,
+    // warn in the console when tps stays under the threshold this long
+    pub tps_alert_enabled: bool,
+    pub tps_alert_threshold: f64,
+    pub tps_alert_seconds: u64,
+    // empty keeps backups in the app data folder
+    pub backup_path: String,
+    // "zip", "tar.zst" or "incremental"
+    pub backup_format: String,
+    // "worlds" or "full"
+    pub backup_scope: String,
+    // retention, 0 turns a rule off
+    pub backup_keep_last: u32,
+    pub backup_keep_hourly: u32,
+    pub backup_keep_daily: u32,
+    pub backup_keep_weekly: u32,
+    pub backup_max_total_mb: u64,
     pub java_path: String,
     pub java_allocated_memory: i64,
     pub java_startup_line: String,
     pub current_player_count: i32,
     pub max_players: i32,
+    pub tasks: Vec<ScheduledTask>,
+    pub proxy_id: Option<Uuid>,
+    pub proxy_name: String,
 }
 
 impl Default for ServerStruct {
@@ -132,6 +600,7 @@ impl Default for ServerStruct {
             port: 25565,
             public_ip: "0.0.0.0".to_string(),
             local_ip: "0.0.0.0".to_string(),
+            port_forwarding: false,
             path: "path".to_string(),
             icon_path: "path".to_string(),
             server_type: "vanilla".to_string(),
@@ -140,12 +609,85 @@ impl Default for ServerStruct {
             server_version: "1.21.10".to_string(),
             creation_date: "today".to_string(),
             auto_start: false,
+            auto_start_order: 0,
+            auto_start_delay_seconds: 0,
+            auto_start_after: None,
             force_save_on_stop: true,
+            restart_policy: "never".to_string(),
+            max_restarts: 5,
+            restart_window_seconds: 3600,
+            restart_backoff_seconds: 10,
+            crash_loop_seconds: 60,
+            crash_loop_limit: 3,
+            restart_countdown: "10m, 5m, 1m, 30s, 10s, 5s, 4s, 3s, 2s, 1s".to_string(),
+            restart_countdown_format: "say".to_string(),
+            restart_countdown_message: "Server restarting in {time}".to_string(),
+            tps_alert_enabled: false,
+            tps_alert_threshold: 15.0,
+            tps_alert_seconds: 60,
+            backup_path: "".to_string(),
+            backup_format: "zip".to_string(),
+            backup_scope: "worlds".to_string(),
+            backup_keep_last: 5,
+            backup_keep_hourly: 24,
+            backup_keep_daily: 7,
+            backup_keep_weekly: 4,
+            backup_max_total_mb: 0,
             java_path: "path".to_string(),
             java_allocated_memory: 4096,
             java_startup_line: "".to_string(),
             current_player_count: 0,
             max_players: 20,
+            tasks: Vec::new(),
+            proxy_id: None,
+            proxy_name: "".to_string(),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TaskStep {
+    // "start", "stop", "restart", "backup", "command", "broadcast" or "wait"
+    pub action: String,
+    // the command, message, or seconds for a wait
+    pub value: String,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TaskRun {
+    pub time: String,
+    pub success: bool,
+    pub message: String,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScheduledTask {
+    pub id: Uuid,
+    pub name: String,
+    pub enabled: bool,
+    // "cron" or "interval"
+    pub schedule: String,
+    pub cron: String,
+    pub interval_seconds: u64,
+    pub steps: Vec<TaskStep>,
+    pub last_run: Option<TaskRun>,
+}
+
+impl Default for ScheduledTask {
+    fn default() -> Self {
+        ScheduledTask {
+            id: Uuid::nil(),
+            name: "".to_string(),
+            enabled: true,
+            schedule: "cron".to_string(),
+            cron: "0 4 * * *".to_string(),
+            interval_seconds: 3600,
+            steps: Vec::new(),
+            last_run: None,
         }
     }
 }
@@ -197,8 +739,26 @@ pub fn generate_random_server() -> ServerStruct {
     }
 }
 
+// Replaces the server list with what's registered in the backend
 pub fn load_servers() {
-    APP_STATE.write().servers.push(generate_random_server());
+    spawn(async {
+        if let Ok(servers) =
+            invoke_command::<_, Vec<ServerStruct>>("plugin:gaia_core|list_servers", &NoArgs {})
+                .await
+        {
+            APP_STATE.write().servers = servers;
+        }
+    });
+}
+
+pub fn push_console_line(server_id: Uuid, line: String) {
+    let app_state = &mut APP_STATE.write();
+    let lines = app_state.console_lines.entry(server_id).or_default();
+    lines.push(line);
+    if lines.len() > CONSOLE_BUFFER_SIZE {
+        let overflow = lines.len() - CONSOLE_BUFFER_SIZE;
+        lines.drain(..overflow);
+    }
 }
 
 pub fn set_selected_server(value: Uuid) {
@@ -212,6 +772,30 @@ pub fn set_selected_server(value: Uuid) {
     app_state.selected_server = Some(value.unwrap());
 }
 
+// Applies a change to a server in the list and to the selected copy of it
+pub fn update_server(id: Uuid, update: impl Fn(&mut ServerStruct)) {
+    let app_state = &mut APP_STATE.write();
+    if let Some(server) = app_state.servers.iter_mut().find(|server| server.id == id) {
+        update(server);
+    }
+    if let Some(server) = app_state
+        .selected_server
+        .as_mut()
+        .filter(|server| server.id == id)
+    {
+        update(server);
+    }
+}
+
+// Velocity, BungeeCord and Waterfall sit in front of other servers instead of running a world
+pub fn is_proxy(server_type: &str) -> bool {
+    matches!(server_type, "velocity" | "bungeecord" | "waterfall")
+}
+
+pub fn is_bedrock(server_type: &str) -> bool {
+    server_type == "bedrock"
+}
+
 pub fn set_selected_panel(value: &str) {
     reset_option_states();
     let selected_panel: &mut String = &mut APP_STATE.write().selected_panel;
@@ -0,0 +1,476 @@
+use dioxus::{document, prelude::*};
+use regex::{Regex, RegexBuilder};
+
+// Lines kept per server before the oldest ones get dropped
+pub const CONSOLE_BUFFER_SIZE: usize = 5000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsoleLine {
+    pub timestamp: String,
+    pub log_from: String,
+    pub thread: String,
+    pub level: String,
+    pub logger: String,
+    pub content: String,
+}
+
+impl ConsoleLine {
+    // css class used to color the line, matches the classes in server_panel.scss
+    pub fn class(&self) -> &'static str {
+        if self.content.starts_with("Done (") || self.content == "Server started." {
+            "serverstarted"
+        } else if self.content.contains(" logged in with entity id ")
+            || self.content.ends_with(" joined the game")
+            || self.content.starts_with("Player connected: ")
+        {
+            "userjoined"
+        } else {
+            match self.level_group() {
+                "WARN" => "warn",
+                "ERROR" => "error",
+                _ => "info",
+            }
+        }
+    }
+
+    // Collapses every log level into one of the three toggleable groups
+    pub fn level_group(&self) -> &'static str {
+        match self.level.as_str() {
+            "WARN" | "WARNING" => "WARN",
+            "ERROR" | "FATAL" | "SEVERE" => "ERROR",
+            _ => "INFO",
+        }
+    }
+}
+
+// Splits a raw log line into its parts, supports the vanilla/paper format
+// "[15:58:02] [Server thread/INFO]: ..." the forge format
+// "[10/26 15:58:02] [Server thread/INFO] [minecraft/DedicatedServer]: ..."
+// the short format "[15:58:02 INFO]: ..." and bedrock's "[2024-05-01 15:58:02:123 INFO] ...".
+// Lines that don't match (stack traces etc.) inherit the previous line's level.
+pub fn parse_console_line(raw: &str, previous_level: &str) -> ConsoleLine {
+    let mut line = ConsoleLine {
+        timestamp: "".to_string(),
+        log_from: "".to_string(),
+        thread: "".to_string(),
+        level: previous_level.to_string(),
+        logger: "".to_string(),
+        content: raw.to_string(),
+    };
+    if !raw.starts_with('[') {
+        return line;
+    }
+    let Some(timestamp_end) = raw.find(']') else {
+        return line;
+    };
+    let timestamp = &raw[1..timestamp_end];
+    if !timestamp.chars().any(|c| c.is_ascii_digit()) {
+        return line;
+    }
+    let mut rest = &raw[timestamp_end + 1..];
+    // short format has the level inside of the timestamp brackets, bedrock too but without the colon
+    if let Some((time, level)) = timestamp.rsplit_once(' ')
+        && level.chars().all(|c| c.is_ascii_uppercase())
+        && let Some((separator, content)) = rest
+            .strip_prefix(": ")
+            .map(|content| (": ", content))
+            .or_else(|| rest.strip_prefix(' ').map(|content| (" ", content)))
+    {
+        line.timestamp = format!("[{}] ", time);
+        line.level = level.to_string();
+        line.log_from = format!("[{}]{}", level, separator);
+        line.content = content.to_string();
+        return line;
+    }
+    line.timestamp = format!("[{}] ", timestamp);
+    rest = rest.trim_start();
+    let log_from_start = raw.len() - rest.len();
+    if rest.starts_with('[')
+        && let Some(thread_end) = rest.find(']')
+    {
+        let thread = &rest[1..thread_end];
+        match thread.rsplit_once('/') {
+            Some((name, level)) => {
+                line.thread = name.to_string();
+                line.level = level.to_uppercase();
+            }
+            None => line.thread = thread.to_string(),
+        }
+        rest = &rest[thread_end + 1..];
+        if rest.starts_with(" [")
+            && let Some(logger_end) = rest.find(']')
+        {
+            line.logger = rest[2..logger_end].to_string();
+            rest = &rest[logger_end + 1..];
+        }
+    }
+    let rest = match rest.strip_prefix(':') {
+        Some(content) => content.strip_prefix(' ').unwrap_or(content),
+        None => rest,
+    };
+    line.log_from = raw[log_from_start..raw.len() - rest.len()].to_string();
+    line.content = rest.to_string();
+    line
+}
+
+pub fn parse_console_lines(raw: &[String]) -> Vec<ConsoleLine> {
+    let mut level = "INFO".to_string();
+    raw.iter()
+        .map(|raw| {
+            let line = parse_console_line(raw, &level);
+            level = line.level.clone();
+            line
+        })
+        .collect()
+}
+
+// Builds the search matcher, plain searches get escaped so they can share the regex path
+fn build_matcher(query: &str, use_regex: bool) -> Result<Option<Regex>, regex::Error> {
+    if query.is_empty() {
+        return Ok(None);
+    }
+    let pattern = match use_regex {
+        true => query.to_string(),
+        false => regex::escape(query),
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .map(Some)
+}
+
+// Splits text into (text, is_match) segments for highlighting
+fn highlight(text: &str, matcher: &Option<Regex>) -> Vec<(String, bool)> {
+    let Some(matcher) = matcher else {
+        return vec![(text.to_string(), false)];
+    };
+    let mut segments = Vec::new();
+    let mut last = 0;
+    for found in matcher.find_iter(text) {
+        if found.start() == found.end() {
+            continue;
+        }
+        if found.start() > last {
+            segments.push((text[last..found.start()].to_string(), false));
+        }
+        segments.push((found.as_str().to_string(), true));
+        last = found.end();
+    }
+    if last < text.len() {
+        segments.push((text[last..].to_string(), false));
+    }
+    segments
+}
+
+fn logger_matches(line: &ConsoleLine, filter: &str) -> bool {
+    let filter = filter.trim().trim_start_matches('[').trim_end_matches(']');
+    if filter.is_empty() {
+        return true;
+    }
+    let filter = filter.to_lowercase();
+    line.logger.to_lowercase().contains(&filter) || line.thread.to_lowercase().contains(&filter)
+}
+
+#[derive(Props, PartialEq, Clone)]
+pub struct ConsoleViewProps {
+    // id of the <pre> element, also used to prefix the line ids
+    id: String,
+    lines: Vec<String>,
+    // whether the view should follow new lines while not paused
+    auto_scroll: bool,
+}
+
+#[component]
+pub fn console_view(props: ConsoleViewProps) -> Element {
+    let mut search = use_signal(|| "".to_string());
+    let mut use_regex = use_signal(|| false);
+    let mut hide_info = use_signal(|| false);
+    let mut hide_warn = use_signal(|| false);
+    let mut hide_error = use_signal(|| false);
+    let mut logger_filter = use_signal(|| "".to_string());
+    // snapshot of the lines at the time the view was paused, new lines keep
+    // getting buffered by the caller while this is shown
+    let mut paused = use_signal(|| None::<Vec<String>>);
+    let mut current_match = use_signal(|| 0usize);
+
+    let lines = match &*paused.read() {
+        Some(snapshot) => parse_console_lines(snapshot),
+        None => parse_console_lines(&props.lines),
+    };
+    let shown = lines.len();
+    let matcher = build_matcher(&search(), use_regex());
+    let search_error = matcher.is_err();
+    let matcher = matcher.unwrap_or(None);
+
+    let visible: Vec<(usize, ConsoleLine)> = lines
+        .into_iter()
+        .enumerate()
+        .filter(|(_, line)| match line.level_group() {
+            "WARN" => !hide_warn(),
+            "ERROR" => !hide_error(),
+            _ => !hide_info(),
+        })
+        .filter(|(_, line)| logger_matches(line, &logger_filter()))
+        .collect();
+    let matches: Vec<usize> = match &matcher {
+        Some(matcher) => visible
+            .iter()
+            .filter(|(_, line)| {
+                matcher.is_match(&line.timestamp)
+                    || matcher.is_match(&line.log_from)
+                    || matcher.is_match(&line.content)
+            })
+            .map(|(index, _)| *index)
+            .collect(),
+        None => Vec::new(),
+    };
+    let current = match matches.is_empty() {
+        true => None,
+        false => Some(current_match().min(matches.len() - 1)),
+    };
+    let current_line = current.map(|index| matches[index]);
+
+    let id = props.id.clone();
+    let follow = props.auto_scroll && paused.read().is_none();
+    use_effect(use_reactive!(|(id, follow, shown)| {
+        let _ = shown;
+        if follow {
+            document::eval(&format!(
+                "let el = document.getElementById('{id}'); if (el) {{ el.scrollTop = el.scrollHeight; }}"
+            ));
+        }
+    }));
+
+    let match_count = matches.len();
+    let lines_snapshot = props.lines.clone();
+    let id = props.id.clone();
+    let go_to_match = move |index: usize| {
+        current_match.set(index);
+        if let Some(line) = matches.get(index) {
+            document::eval(&format!(
+                "let el = document.getElementById('{id}_line_{line}'); if (el) {{ el.scrollIntoView({{ block: 'center' }}); }}"
+            ));
+        }
+    };
+
+    rsx! {
+        div {
+            class: "console_toolbar",
+            input {
+                class: if search_error { "search invalid" } else { "search" },
+                type: "text",
+                placeholder: "Search",
+                value: "{search}",
+                oninput: move |evt| {
+                    search.set(evt.value());
+                    current_match.set(0);
+                }
+            }
+            div {
+                label {
+                    for: "{props.id}_regex",
+                    "Regex"
+                }
+                input {
+                    id: "{props.id}_regex",
+                    type: "checkbox",
+                    checked: use_regex(),
+                    onchange: move |evt| use_regex.set(evt.checked())
+                }
+            }
+            span {
+                class: "match_count",
+                match current {
+                    Some(index) => format!("{}/{}", index + 1, match_count),
+                    None => "0/0".to_string(),
+                }
+            }
+            button {
+                disabled: match_count == 0,
+                onclick: {
+                    let mut go_to_match = go_to_match.clone();
+                    move |_| {
+                        if match_count > 0 {
+                            let index = current.unwrap_or(0);
+                            go_to_match((index + match_count - 1) % match_count);
+                        }
+                    }
+                },
+                "Prev"
+            }
+            button {
+                disabled: match_count == 0,
+                onclick: {
+                    let mut go_to_match = go_to_match.clone();
+                    move |_| {
+                        if match_count > 0 {
+                            let index = current.map(|index| index + 1).unwrap_or(0);
+                            go_to_match(index % match_count);
+                        }
+                    }
+                },
+                "Next"
+            }
+            button {
+                class: if hide_info() { "level_toggle hidden" } else { "level_toggle info" },
+                onclick: move |_| hide_info.set(!hide_info()),
+                "INFO"
+            }
+            button {
+                class: if hide_warn() { "level_toggle hidden" } else { "level_toggle warn" },
+                onclick: move |_| hide_warn.set(!hide_warn()),
+                "WARN"
+            }
+            button {
+                class: if hide_error() { "level_toggle hidden" } else { "level_toggle error" },
+                onclick: move |_| hide_error.set(!hide_error()),
+                "ERROR"
+            }
+            input {
+                class: "logger_filter",
+                type: "text",
+                placeholder: "Logger / thread e.g. [FML]",
+                value: "{logger_filter}",
+                oninput: move |evt| logger_filter.set(evt.value())
+            }
+            button {
+                class: if paused.read().is_some() { "pause paused" } else { "pause" },
+                onclick: move |_| {
+                    if paused.read().is_some() {
+                        paused.set(None);
+                    } else {
+                        paused.set(Some(lines_snapshot.clone()));
+                    }
+                },
+                if paused.read().is_some() {
+                    "Resume"
+                } else {
+                    "Pause"
+                }
+            }
+        }
+        pre {
+            id: "{props.id}",
+            class: "console",
+            for (index, line) in visible {
+                div {
+                    key: "{index}",
+                    id: "{props.id}_line_{index}",
+                    class: if current_line == Some(index) {
+                        format!("{} current_match", line.class())
+                    } else {
+                        line.class().to_string()
+                    },
+                    span {
+                        class: "timestamp",
+                        for (text, hit) in highlight(&line.timestamp, &matcher) {
+                            if hit { mark { "{text}" } } else { "{text}" }
+                        }
+                    }
+                    span {
+                        class: "logfrom",
+                        for (text, hit) in highlight(&line.log_from, &matcher) {
+                            if hit { mark { "{text}" } } else { "{text}" }
+                        }
+                    }
+                    span {
+                        class: "content",
+                        for (text, hit) in highlight(&line.content, &matcher) {
+                            if hit { mark { "{text}" } } else { "{text}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORGE_LINES: [&str; 7] = [
+        "[10/26 15:58:02] [Server thread/INFO] [minecraft/DedicatedServer]: Done (145.789s)! For help, type \"help\" or \"?\"",
+        "[10/26 15:58:02] [Netty Server IO #6/INFO] [FML]: Client attempting to join with 225 mods",
+        "[10/26 15:58:02] [Server thread/INFO] [minecraft/PlayerList]: SeaBass7612[/0.0.0.0:60963] logged in with entity id 140 at (632.4353965983014, 6.0, -1708.403939166827)",
+        "[10/26 15:58:02] [Server thread/WARN] [minecraft/MinecraftServer]: Can't keep up! Did the system time change, or is the server overloaded? Running 176809ms behind, skipping 3536 tick(s)",
+        "[10/26 15:58:02] [Server thread/ERROR] [minecraft/MinecraftServer]: Encountered an unexpected exception",
+        "\tat net.minecraft.server.MinecraftServer.tickServer(MinecraftServer.java:812)",
+        "[10/26 15:58:02] [Server thread/INFO] [minecraft/MinecraftServer]: Stopping server",
+    ];
+
+    fn forge_lines() -> Vec<ConsoleLine> {
+        parse_console_lines(&FORGE_LINES.map(str::to_string))
+    }
+
+    #[test]
+    fn parses_the_forge_format() {
+        let lines = forge_lines();
+        assert_eq!(lines[1].timestamp, "[10/26 15:58:02] ");
+        assert_eq!(lines[1].thread, "Netty Server IO #6");
+        assert_eq!(lines[1].level, "INFO");
+        assert_eq!(lines[1].logger, "FML");
+        assert_eq!(lines[1].content, "Client attempting to join with 225 mods");
+        assert_eq!(lines[1].log_from, "[Netty Server IO #6/INFO] [FML]: ");
+    }
+
+    #[test]
+    fn colors_lines_by_content_and_level() {
+        let classes: Vec<&str> = forge_lines().iter().map(ConsoleLine::class).collect();
+        assert_eq!(
+            classes,
+            [
+                "serverstarted",
+                "info",
+                "userjoined",
+                "warn",
+                "error",
+                "error",
+                "info"
+            ]
+        );
+    }
+
+    #[test]
+    fn stack_traces_keep_the_previous_level() {
+        let lines = forge_lines();
+        assert_eq!(lines[5].level, "ERROR");
+        assert_eq!(lines[5].timestamp, "");
+        assert_eq!(lines[5].content, FORGE_LINES[5]);
+    }
+
+    #[test]
+    fn parses_the_short_and_bedrock_formats() {
+        let short = parse_console_line("[15:58:02 WARN]: Something happened", "INFO");
+        assert_eq!(
+            (short.timestamp.as_str(), short.level.as_str()),
+            ("[15:58:02] ", "WARN")
+        );
+        assert_eq!(short.content, "Something happened");
+        let bedrock = parse_console_line("[2024-05-01 15:58:02:123 INFO] Server started.", "INFO");
+        assert_eq!(bedrock.timestamp, "[2024-05-01 15:58:02:123] ");
+        assert_eq!(bedrock.log_from, "[INFO] ");
+        assert_eq!(bedrock.class(), "serverstarted");
+    }
+
+    #[test]
+    fn filters_by_logger_or_thread() {
+        let lines = forge_lines();
+        assert!(logger_matches(&lines[1], "[fml]"));
+        assert!(logger_matches(&lines[1], "netty"));
+        assert!(!logger_matches(&lines[0], "FML"));
+        assert!(logger_matches(&lines[0], " "));
+    }
+
+    #[test]
+    fn highlights_plain_and_regex_searches() {
+        let plain = build_matcher("(632", false).unwrap();
+        let segments = highlight("at (632.4, 6.0)", &plain);
+        assert_eq!(segments[1], ("(632".to_string(), true));
+        let regex = build_matcher("\\d+ms", true).unwrap();
+        let segments = highlight(FORGE_LINES[3], &regex);
+        assert!(segments.contains(&("176809ms".to_string(), true)));
+        assert!(build_matcher("(", true).is_err());
+        assert_eq!(highlight("text", &None), [("text".to_string(), false)]);
+    }
+}
@@ -1,4 +1,6 @@
 use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -8,9 +10,285 @@ extern "C" {
 }
 
 use crate::app::{
-    APP_STATE, ServerCreationStruct, set_selected_sub_panel, set_server_creation_options,
+    APP_STATE, ServerCreationStruct, ServerStruct, invoke_command, is_bedrock, is_proxy,
+    set_selected_panel, set_selected_server, set_selected_sub_panel, set_server_creation_options,
 };
 
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct PortInUse {
+    kind: String,
+    port: u16,
+    owner: String,
+}
+
+#[derive(Serialize)]
+struct CheckPortsArgs {
+    server: ServerStruct,
+}
+
+#[derive(Serialize)]
+struct SuggestPortArgs {
+    start: u16,
+}
+
+// Port of the new server, warns when it's taken and offers the next free one
+fn new_server_port() -> Element {
+    let Some(options) = APP_STATE.read().server_creation_options.to_owned() else {
+        return rsx! {};
+    };
+    let port = options.port;
+    let path = options.destination_path.clone().unwrap_or_default();
+    let check = use_resource(use_reactive!(|(port, path)| async move {
+        let server = ServerStruct {
+            id: Uuid::nil(),
+            port,
+            path,
+            ..Default::default()
+        };
+        let conflicts = invoke_command::<_, Vec<PortInUse>>(
+            "plugin:gaia_core|check_server_ports",
+            &CheckPortsArgs { server },
+        )
+        .await
+        .unwrap_or_default();
+        if conflicts.is_empty() {
+            return (conflicts, None);
+        }
+        let suggestion = invoke_command::<_, Option<u16>>(
+            "plugin:gaia_core|suggest_server_port",
+            &SuggestPortArgs { start: port },
+        )
+        .await
+        .ok()
+        .flatten();
+        (conflicts, suggestion)
+    }));
+    let set_port = move |port: u16| {
+        if let Some(options) = APP_STATE.write().server_creation_options.as_mut() {
+            options.port = port;
+        }
+    };
+    rsx! {
+        div {
+            class: "new_server_port",
+            label {
+                "Port"
+                input {
+                    r#type: "number",
+                    min: "1",
+                    max: "65535",
+                    value: "{port}",
+                    oninput: move |evt| {
+                        if let Ok(port) = evt.value().parse::<u16>() {
+                            set_port(port);
+                        }
+                    }
+                }
+            }
+            if let Some((conflicts, suggestion)) = check() {
+                for conflict in conflicts {
+                    span {
+                        key: "{conflict.kind}",
+                        class: "port_conflict",
+                        "The {conflict.kind} port {conflict.port} is already used by {conflict.owner}"
+                    }
+                }
+                if let Some(suggestion) = suggestion {
+                    button {
+                        onclick: move |_| set_port(suggestion),
+                        "Use {suggestion}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn select_proxy_type(server_type: &str) {
+    set_selected_sub_panel("configure_new_server");
+    set_server_creation_options(Some(ServerCreationStruct {
+        server_type: server_type.to_string(),
+        port: 25577,
+        java_allocated_memory: 512,
+        ..Default::default()
+    }));
+}
+
+fn select_bedrock_type() {
+    set_selected_sub_panel("configure_new_server");
+    set_server_creation_options(Some(ServerCreationStruct {
+        server_type: "bedrock".to_string(),
+        port: 19132,
+        max_players: 10,
+        ..Default::default()
+    }));
+}
+
+fn set_option(change: impl FnOnce(&mut ServerCreationStruct)) {
+    if let Some(options) = APP_STATE.write().server_creation_options.as_mut() {
+        change(options);
+    }
+}
+
+// Proxies and bedrock aren't copied from an existing folder, they get set up from the download
+fn new_setup_options() -> Element {
+    let Some(options) = APP_STATE.read().server_creation_options.to_owned() else {
+        return rsx! {};
+    };
+    let bedrock = is_bedrock(&options.server_type);
+    rsx! {
+        div {
+            class: "new_setup_options",
+            label {
+                "Name"
+                input {
+                    value: "{options.name}",
+                    oninput: move |evt| set_option(|options| options.name = evt.value())
+                }
+            }
+            label {
+                "Folder"
+                input {
+                    placeholder: "Where the server is set up",
+                    value: options.destination_path.clone().unwrap_or_default(),
+                    oninput: move |evt| set_option(|options| options.destination_path = Some(evt.value()))
+                }
+            }
+            // bedrock_server isn't java, there's no heap to size
+            if bedrock {
+                label {
+                    "Server Zip"
+                    input {
+                        placeholder: "Path to the Bedrock Dedicated Server zip or its unpacked folder",
+                        value: options.jar_path.clone().unwrap_or_default(),
+                        oninput: move |evt| set_option(|options| options.jar_path = Some(evt.value()))
+                    }
+                }
+            } else {
+                label {
+                    "Proxy Jar"
+                    input {
+                        placeholder: "Path to the downloaded {options.server_type} jar",
+                        value: options.jar_path.clone().unwrap_or_default(),
+                        oninput: move |evt| set_option(|options| options.jar_path = Some(evt.value()))
+                    }
+                }
+                label {
+                    "Memory (MB)"
+                    input {
+                        r#type: "number",
+                        min: "128",
+                        value: "{options.java_allocated_memory}",
+                        oninput: move |evt| {
+                            if let Ok(memory) = evt.value().parse::<i64>() {
+                                set_option(|options| options.java_allocated_memory = memory);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateProxyArgs {
+    server: ServerStruct,
+    jar_path: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateBedrockArgs {
+    server: ServerStruct,
+    source_path: String,
+}
+
+fn create_server_button() -> Element {
+    let mut error = use_signal(|| None::<String>);
+    let mut creating = use_signal(|| false);
+    let create = move |_| {
+        let Some(options) = APP_STATE.read().server_creation_options.to_owned() else {
+            return;
+        };
+        // only proxies and bedrock can be created from here so far
+        let bedrock = is_bedrock(&options.server_type);
+        if !is_proxy(&options.server_type) && !bedrock {
+            return;
+        }
+        let (Some(path), Some(jar_path)) =
+            (options.destination_path.clone(), options.jar_path.clone())
+        else {
+            error.set(Some(match bedrock {
+                true => "Pick a folder and the server zip first".to_string(),
+                false => "Pick a folder and the proxy jar first".to_string(),
+            }));
+            return;
+        };
+        let server = ServerStruct {
+            name: options.name.clone(),
+            description: options.description.clone(),
+            server_type: options.server_type.clone(),
+            port: options.port,
+            path,
+            java_allocated_memory: options.java_allocated_memory,
+            max_players: options.max_players,
+            ..Default::default()
+        };
+        creating.set(true);
+        spawn(async move {
+            let created = match bedrock {
+                true => {
+                    let args = CreateBedrockArgs {
+                        server,
+                        source_path: jar_path,
+                    };
+                    invoke_command::<_, ServerStruct>(
+                        "plugin:gaia_core|create_bedrock_server",
+                        &args,
+                    )
+                    .await
+                }
+                false => {
+                    invoke_command::<_, ServerStruct>(
+                        "plugin:gaia_core|create_proxy_server",
+                        &CreateProxyArgs { server, jar_path },
+                    )
+                    .await
+                }
+            };
+            match created {
+                Ok(server) => {
+                    error.set(None);
+                    let id = server.id;
+                    APP_STATE.write().servers.push(server);
+                    set_server_creation_options(None);
+                    set_selected_panel(format!("SERVER:{}", id).as_str());
+                    set_selected_sub_panel("dashboard");
+                    set_selected_server(id);
+                }
+                Err(err) => error.set(Some(err)),
+            }
+            creating.set(false);
+        });
+    };
+    rsx! {
+        if let Some(err) = error() {
+            span {
+                class: "create_server_error",
+                "{err}"
+            }
+        }
+        button {
+            style: "margin-left: 1rem;",
+            disabled: creating(),
+            onclick: create,
+            "Create Server"
+        }
+    }
+}
+
 #[component]
 pub fn main_panel() -> Element {
     let selected = APP_STATE.read().selected_panel.to_string();
@@ -18,6 +296,11 @@ pub fn main_panel() -> Element {
     if selected.starts_with("create_server") == false {
         return rsx! {};
     }
+    let setup_selected = APP_STATE
+        .read()
+        .server_creation_options
+        .as_ref()
+        .is_some_and(|options| is_proxy(&options.server_type) || is_bedrock(&options.server_type));
     if selected_sub_panel == "select_server_type".to_string() {
         rsx! {
             div {
@@ -85,52 +368,54 @@ pub fn main_panel() -> Element {
                         }
                     }
                 }
-                // div {
-                //     class: "panel_header",
-                //     div {
-                //         h3 {
-                //             style: "margin-bottom: 0.2rem; margin-left: -1rem;",
-                //             "Proxies"
-                //         }
-                //     }
-                // }
-                // div {
-                //     class: "sub_section",
-                //     server_type {
-                //         name: "BungeeCord".to_string(),
-                //         description: "".to_string(),
-                //         asset: asset!("/assets/images/pack.webp"),
-                //         onclick: move |_| {
-                //             set_selected_sub_panel("configure_new_server");
-                //         }
-                //     }
-                //     server_type {
-                //         name: "Velocity".to_string(),
-                //         description: "Modern, high-performance proxy".to_string(),
-                //         asset: asset!("/assets/images/velocity_logo_white.webp"),
-                //         onclick: move |_| {
-                //             set_selected_sub_panel("configure_new_server");
-                //         }
-                //     }
-                // }
-                // div {
-                //     class: "panel_header",
-                //     div {
-                //         h3 {
-                //             style: "margin-bottom: 0.2rem; margin-left: -1rem;",
-                //             "Experimental"
-                //         }
-                //     }
-                // }
-                // div {
-                //     class: "sub_section",
-                //     server_type {
-                //         name: "Bedrock Edition".to_string(),
-                //         description: "Cross-platform version of Minecraft".to_string(),
-                //         asset: asset!("/assets/images/Bedrock_JE2_BE2.png"),
-                //         onclick: move |_| {}
-                //     }
-                // }
+                div {
+                    class: "panel_header",
+                    div {
+                        h3 {
+                            style: "margin-bottom: 0.2rem; margin-left: -1rem;",
+                            "Proxies"
+                        }
+                    }
+                }
+                div {
+                    class: "sub_section",
+                    server_type {
+                        name: "Velocity".to_string(),
+                        description: "Modern, high-performance proxy".to_string(),
+                        asset: asset!("/assets/images/velocity_logo_white.webp"),
+                        onclick: move |_| select_proxy_type("velocity")
+                    }
+                    server_type {
+                        name: "BungeeCord".to_string(),
+                        description: "The original proxy, runs BungeeCord plugins".to_string(),
+                        asset: asset!("/assets/images/pack.webp"),
+                        onclick: move |_| select_proxy_type("bungeecord")
+                    }
+                    server_type {
+                        name: "Waterfall".to_string(),
+                        description: "BungeeCord fork from PaperMC, no longer maintained".to_string(),
+                        asset: asset!("/assets/images/velocity_logo_white.webp"),
+                        onclick: move |_| select_proxy_type("waterfall")
+                    }
+                }
+                div {
+                    class: "panel_header",
+                    div {
+                        h3 {
+                            style: "margin-bottom: 0.2rem; margin-left: -1rem;",
+                            "Experimental"
+                        }
+                    }
+                }
+                div {
+                    class: "sub_section",
+                    server_type {
+                        name: "Bedrock Edition".to_string(),
+                        description: "Cross-platform version of Minecraft, Linux only".to_string(),
+                        asset: asset!("/assets/images/Bedrock_JE2_BE2.png"),
+                        onclick: move |_| select_bedrock_type()
+                    }
+                }
                 // div {
                 //     class: "panel_header",
                 //     div {
@@ -150,14 +435,6 @@ pub fn main_panel() -> Element {
                 //             set_selected_sub_panel("configure_new_server");
                 //         }
                 //     }
-                //     server_type {
-                //         name: "Waterfall".to_string(),
-                //         description: "".to_string(),
-                //         asset: asset!("/assets/images/velocity_logo_white.webp"),
-                //         onclick: move |_| {
-                //             set_selected_sub_panel("configure_new_server");
-                //         }
-                //     }
                 // }
             }
         }
@@ -182,13 +459,14 @@ pub fn main_panel() -> Element {
                         },
                         "Back"
                     }
-                    button {
-                        style: "margin-left: 1rem;",
-                        "Create Server"
-                    }
+                    create_server_button {}
                 }
                 div {
                     class: "sub_section",
+                    new_server_port {}
+                    if setup_selected {
+                        new_setup_options {}
+                    }
                 }
             }
         }
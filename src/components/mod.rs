@@ -1,3 +1,4 @@
+pub mod console;
 pub mod create_server_panel;
 pub mod files_panel;
 pub mod server_panel;
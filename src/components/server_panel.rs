@@ -10,8 +10,15 @@ extern "C" {
 }
 
 use crate::{
-    app::{APP_STATE, set_selected_sub_panel},
-    components::svgs::{caret_down, play, rotate, skull, stop},
+    app::{
+        APP_STATE, LagSpike, OnlinePlayer, PortMapping, RESOURCE_HISTORY_LENGTH, ResourceSample,
+        ScheduledTask, ServerGroup, ServerStruct, TaskRun, TaskStep, TickSample, invoke_command,
+        is_bedrock, is_proxy, push_console_line, set_selected_sub_panel, update_server,
+    },
+    components::{
+        console,
+        svgs::{caret_down, play, rotate, skull, stop},
+    },
 };
 
 #[component]
@@ -25,6 +32,7 @@ pub fn main_panel() -> Element {
             id: "server_panel",
             server_header {}
             server_chips {}
+            server_crash_card {}
             sub_panel {}
         }
     }
@@ -87,9 +95,35 @@ fn server_icon() -> Element {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct ServerArgs {
+    server: ServerStruct,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IdArgs {
+    id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ServerCommandArgs {
+    id: Uuid,
+    command: String,
+}
+
+// Runs a server action in the background, failures get printed to the server's console
+fn server_action<A: Serialize + 'static>(id: Uuid, cmd: &'static str, args: A) {
+    spawn(async move {
+        if let Err(err) = invoke_command::<_, ()>(cmd, &args).await {
+            push_console_line(id, format!("[Gaia Manager] {}", err));
+        }
+    });
+}
+
 fn server_actions() -> Element {
     let server = APP_STATE.read().selected_server.to_owned().unwrap();
     let mut menu_opened = use_signal(|| false);
+    let mut countdown_opened = use_signal(|| false);
     let menu_opened_value: &bool = &menu_opened.read();
     let menu_opened_value_static = menu_opened_value.clone();
     // if server is offline show start else show full actions
@@ -98,6 +132,11 @@ fn server_actions() -> Element {
             div {
                 class: "server_actions offline",
                 div {
+                    onclick: move |_| {
+                        APP_STATE.write().crash_reports.remove(&server.id);
+                        APP_STATE.write().restart_states.remove(&server.id);
+                        server_action(server.id, "plugin:gaia_core|start_server", IdArgs { id: server.id });
+                    },
                     play::svg {}
                     "Start"
                 }
@@ -108,6 +147,7 @@ fn server_actions() -> Element {
             div {
                 class: "server_actions online",
                 div {
+                    onclick: move |_| server_action(server.id, "plugin:gaia_core|stop_server", IdArgs { id: server.id }),
                     stop::svg {}
                     "Stop Server"
                 }
@@ -129,21 +169,255 @@ fn server_actions() -> Element {
                 div {
                     class: "server_actions_menu",
                     div {
+                        onclick: move |_| {
+                            menu_opened.set(false);
+                            server_action(server.id, "plugin:gaia_core|kill_server", IdArgs { id: server.id });
+                        },
                         skull::svg {}
                         "Terminate"
                     }
                     div {
+                        onclick: {
+                            let server = server.clone();
+                            move |_| {
+                                menu_opened.set(false);
+                                server_action(server.id, "plugin:gaia_core|restart_server", IdArgs { id: server.id });
+                            }
+                        },
+                        rotate::svg {}
+                        "Restart Now"
+                    }
+                    div {
+                        onclick: move |_| {
+                            menu_opened.set(false);
+                            countdown_opened.set(true);
+                        },
                         rotate::svg {}
-                        "Restart"
+                        "Restart with Countdown"
                     }
                 }
             }
+            if countdown_opened() {
+                restart_countdown_menu {
+                    on_close: move |_| countdown_opened.set(false),
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn restart_countdown_menu(on_close: EventHandler<()>) -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let mut steps = use_signal(|| server.restart_countdown.clone());
+    let mut format = use_signal(|| server.restart_countdown_format.clone());
+    let mut message = use_signal(|| server.restart_countdown_message.clone());
+    let mut error = use_signal(|| None::<String>);
+    let start = move |_| {
+        let server = ServerStruct {
+            restart_countdown: steps(),
+            restart_countdown_format: format(),
+            restart_countdown_message: message(),
+            ..server.clone()
+        };
+        spawn(async move {
+            let args = ServerArgs {
+                server: server.clone(),
+            };
+            let result = match invoke_command::<_, ()>("plugin:gaia_core|save_server", &args).await
+            {
+                Ok(_) => {
+                    invoke_command::<_, ()>(
+                        "plugin:gaia_core|restart_server_with_countdown",
+                        &IdArgs { id: server.id },
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(_) => {
+                    update_server(server.id, |existing| {
+                        existing.restart_countdown = server.restart_countdown.clone();
+                        existing.restart_countdown_format = server.restart_countdown_format.clone();
+                        existing.restart_countdown_message =
+                            server.restart_countdown_message.clone();
+                    });
+                    on_close.call(());
+                }
+                Err(err) => error.set(Some(err)),
+            }
+        });
+    };
+    rsx! {
+        div {
+            class: "restart_countdown_menu",
+            label {
+                "Warn players at"
+                input {
+                    r#type: "text",
+                    value: "{steps}",
+                    placeholder: "10m, 5m, 1m, 30s, 10s",
+                    oninput: move |evt| steps.set(evt.value())
+                }
+            }
+            label {
+                "Message"
+                input {
+                    r#type: "text",
+                    value: "{message}",
+                    title: "{{time}} becomes the time left, tellraw also takes a JSON text component",
+                    oninput: move |evt| message.set(evt.value())
+                }
+            }
+            label {
+                "Send with"
+                select {
+                    value: "{format}",
+                    onchange: move |evt| format.set(evt.value()),
+                    option { value: "say", "say" }
+                    option { value: "tellraw", "tellraw" }
+                }
+            }
+            if let Some(err) = error() {
+                span { class: "error", "{err}" }
+            }
+            div {
+                class: "restart_countdown_buttons",
+                button {
+                    onclick: start,
+                    "Start Countdown"
+                }
+                button {
+                    onclick: move |_| on_close.call(()),
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ServerIps {
+    local_ip: String,
+    public_ip: String,
+}
+
+// Shown instead of addresses when hide_ip is set, so they don't end up on stream
+const MASKED_IP: &str = "•••.•••.•••.•••";
+
+// ipv6 addresses need brackets before a port
+fn ip_with_port(ip: &str, port: u16) -> String {
+    match ip.contains(':') {
+        true => format!("[{}]:{}", ip, port),
+        false => format!("{}:{}", ip, port),
+    }
+}
+
+#[component]
+fn ip_chip(id: String, label: String, ip: String, port: u16) -> Element {
+    let hide_ip = APP_STATE.read().config.hide_ip;
+    let address = ip_with_port(&ip, port);
+    let shown = match hide_ip {
+        true => format!("{}:{}", MASKED_IP, port),
+        false => address.clone(),
+    };
+    rsx! {
+        div {
+            id,
+            class: "chip ip_chip",
+            title: "Click to copy",
+            onclick: move |_| {
+                document::eval(&format!("navigator.clipboard.writeText({:?})", address));
+            },
+            div {
+                class: "icon"
+            }
+            "{label} {shown}"
+        }
+    }
+}
+
+fn port_forwarding_chip() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let mappings = APP_STATE
+        .read()
+        .port_mappings
+        .get(&server.id)
+        .cloned()
+        .unwrap_or_default();
+    let class = if mappings.is_empty() {
+        "chip"
+    } else {
+        "chip active"
+    };
+    let id = server.id;
+    use_effect(use_reactive!(|id| {
+        spawn(async move {
+            let Ok(mappings) = invoke_command::<_, Vec<PortMapping>>(
+                "plugin:gaia_core|get_port_mappings",
+                &IdArgs { id },
+            )
+            .await
+            else {
+                return;
+            };
+            let port_mappings = &mut APP_STATE.write().port_mappings;
+            match mappings.is_empty() {
+                true => port_mappings.remove(&id),
+                false => port_mappings.insert(id, mappings),
+            };
+        });
+    }));
+    rsx! {
+        div {
+            id: "server_forwarding_chip",
+            class,
+            title: "Opens the server's ports on the router over UPnP or NAT-PMP while it runs, takes effect on the next start",
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: server.port_forwarding,
+                    onchange: move |evt| save_server_setting(|server, value| server.port_forwarding = value == "true", evt.checked().to_string())
+                }
+                "Port Forwarding"
+            }
+            for mapping in mappings {
+                span {
+                    class: "mapping",
+                    "{mapping.protocol} {mapping.port} via {mapping.method}"
+                }
+            }
         }
     }
 }
 
 fn server_chips() -> Element {
     let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let restart_state = APP_STATE.read().restart_states.get(&server.id).cloned();
+    let countdown = APP_STATE.read().restart_countdowns.get(&server.id).copied();
+    let tick_alert = APP_STATE.read().tick_alerts.get(&server.id).copied();
+    // "0.0.0.0" means the address hasn't been detected yet
+    let public_ip = Some(server.public_ip.clone()).filter(|ip| ip != "0.0.0.0");
+    let id = server.id;
+    let detect_server = server.clone();
+    use_effect(use_reactive!(|id| {
+        let server = detect_server.clone();
+        spawn(async move {
+            let Ok(ips) = invoke_command::<_, ServerIps>(
+                "plugin:gaia_core|detect_server_ips",
+                &ServerArgs { server },
+            )
+            .await
+            else {
+                return;
+            };
+            update_server(id, |server| {
+                server.local_ip = ips.local_ip.clone();
+                server.public_ip = ips.public_ip.clone();
+            });
+        });
+    }));
     rsx! {
         div {
             id: "server_chips",
@@ -166,14 +440,21 @@ fn server_chips() -> Element {
                     "Online"
                 }
             }
-            div {
+            ip_chip {
                 id: "server_ip_chip",
-                class: "chip",
-                div {
-                    class: "icon"
+                label: "LAN",
+                ip: server.local_ip.clone(),
+                port: server.port,
+            }
+            if let Some(public_ip) = public_ip {
+                ip_chip {
+                    id: "server_public_ip_chip",
+                    label: "Public",
+                    ip: public_ip,
+                    port: server.port,
                 }
-                "{server.local_ip}:{server.port}"
             }
+            port_forwarding_chip {}
             div {
                 id: "player_count_chip",
                 class: "chip",
@@ -190,6 +471,184 @@ fn server_chips() -> Element {
                 }
                 "{server.minecraft_version} | {server.server_type} {server.server_version}"
             }
+            if let Some(tps) = tick_alert {
+                div {
+                    id: "server_tps_chip",
+                    class: "chip",
+                    title: "TPS has been under {server.tps_alert_threshold} for over {server.tps_alert_seconds} seconds",
+                    {format!("Low TPS {:.1}", tps)}
+                }
+            }
+            if let Some(remaining) = countdown {
+                div {
+                    id: "server_countdown_chip",
+                    class: "chip",
+                    {format!("Restarting in {}:{:02}", remaining / 60, remaining % 60)}
+                    button {
+                        onclick: {
+                            let server = server.clone();
+                            move |_| server_action(server.id, "plugin:gaia_core|cancel_restart_countdown", IdArgs { id: server.id })
+                        },
+                        "Cancel"
+                    }
+                }
+            }
+            if let Some(restart_state) = restart_state {
+                if restart_state.state == "waiting" {
+                    div {
+                        id: "server_restart_chip",
+                        class: "chip waiting",
+                        "Restarting in {restart_state.delay_seconds}s ({restart_state.attempt}/{restart_state.max_restarts})"
+                        button {
+                            onclick: move |_| server_action(server.id, "plugin:gaia_core|cancel_server_restart", ServerArgs { server: server.clone() }),
+                            "Cancel"
+                        }
+                    }
+                } else {
+                    div {
+                        id: "server_restart_chip",
+                        class: "chip gave_up",
+                        title: "{restart_state.reason}",
+                        "Auto restart stopped: {restart_state.reason}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RevealArgs {
+    paths: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CrashFileArgs {
+    server_id: Uuid,
+    file_name: String,
+}
+
+fn server_crash_card() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let report = APP_STATE.read().crash_reports.get(&server.id).cloned();
+    let mut full_report = use_signal(|| None::<String>);
+    let Some(report) = report else {
+        return rsx! {};
+    };
+    let suspects = report
+        .suspected_mods
+        .iter()
+        .map(|suspect| format!("{} ({})", suspect.name, suspect.id))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let exit_code = match report.exit_code {
+        Some(code) => format!("exit code {}", code),
+        None => "killed by a signal".to_string(),
+    };
+    let file_name = report.file_name.clone();
+    let reveal_path = report.path.clone();
+    rsx! {
+        div {
+            id: "server_crash_card",
+            div {
+                class: "crash_header",
+                span {
+                    class: "crash_title",
+                    "Server crashed ({exit_code})"
+                }
+                if !report.time.is_empty() {
+                    span {
+                        class: "crash_time",
+                        "{report.time}"
+                    }
+                }
+                if !report.path.is_empty() {
+                    button {
+                        onclick: move |_| {
+                            if full_report.read().is_some() {
+                                full_report.set(None);
+                                return;
+                            }
+                            let args = CrashFileArgs { server_id: server.id, file_name: file_name.clone() };
+                            spawn(async move {
+                                let content = invoke_command::<_, String>("plugin:gaia_core|read_crash_report", &args)
+                                    .await
+                                    .unwrap_or_else(|err| err);
+                                full_report.set(Some(content));
+                            });
+                        },
+                        if full_report.read().is_some() { "Hide Report" } else { "View Full Report" }
+                    }
+                    button {
+                        onclick: move |_| {
+                            let reveal_path = reveal_path.clone();
+                            spawn(async move {
+                                let _ = invoke_command::<_, ()>("plugin:opener|reveal_item_in_dir", &RevealArgs { paths: vec![reveal_path] }).await;
+                            });
+                        },
+                        "Show in Folder"
+                    }
+                }
+                button {
+                    onclick: move |_| {
+                        APP_STATE.write().crash_reports.remove(&server.id);
+                    },
+                    "Dismiss"
+                }
+            }
+            div {
+                class: "crash_description",
+                "{report.description}"
+            }
+            if !report.exception.is_empty() {
+                div {
+                    class: "crash_exception",
+                    "{report.exception}"
+                }
+            }
+            if !suspects.is_empty() {
+                div {
+                    class: "crash_detail",
+                    span { "Suspected Mods: " }
+                    "{suspects}"
+                }
+            }
+            if !report.minecraft_version.is_empty() {
+                div {
+                    class: "crash_detail",
+                    span { "Minecraft: " }
+                    "{report.minecraft_version}"
+                }
+            }
+            if !report.java_version.is_empty() {
+                div {
+                    class: "crash_detail",
+                    span { "Java: " }
+                    "{report.java_version}"
+                    if !report.java_vm.is_empty() {
+                        " | {report.java_vm}"
+                    }
+                }
+            }
+            if !report.operating_system.is_empty() {
+                div {
+                    class: "crash_detail",
+                    span { "OS: " }
+                    "{report.operating_system}"
+                }
+            }
+            if let Some(content) = full_report() {
+                pre {
+                    class: "crash_full_report",
+                    "{content}"
+                }
+            } else if !report.stack_trace.is_empty() {
+                pre {
+                    class: "crash_stack_trace",
+                    {report.stack_trace.join("\n")}
+                }
+            }
         }
     }
 }
@@ -222,6 +681,13 @@ fn sub_panel() -> Element {
                 onclick: move |_| set_selected_sub_panel("backups"),
                 "Backups"
             }
+            div {
+                class: if selected == "worlds" {
+                     "selected"
+                },
+                onclick: move |_| set_selected_sub_panel("worlds"),
+                "Worlds"
+            }
             div {
                 class: if selected == "scheduler".to_string() {
                      "selected"
@@ -236,6 +702,20 @@ fn sub_panel() -> Element {
             //     onclick: move |_| set_selected_sub_panel("maps"),
             //     "Maps"
             // }
+            div {
+                class: if selected == "logs".to_string() {
+                     "selected"
+                },
+                onclick: move |_| set_selected_sub_panel("logs"),
+                "Logs"
+            }
+            div {
+                class: if selected == "network" {
+                     "selected"
+                },
+                onclick: move |_| set_selected_sub_panel("network"),
+                "Network"
+            }
             div {
                 class: if selected == "config_editor".to_string() {
                      "selected"
@@ -270,12 +750,21 @@ fn sub_panel_display() -> Element {
             if selected == "backups".to_string() {
                  sub_panel_backups {}
             }
+            if selected == "worlds" {
+                 sub_panel_worlds {}
+            }
             if selected == "scheduler".to_string() {
                  sub_panel_scheduler {}
             }
             if selected == "maps".to_string() {
                  sub_panel_maps {}
             }
+            if selected == "logs".to_string() {
+                 sub_panel_logs {}
+            }
+            if selected == "network" {
+                 sub_panel_network {}
+            }
             if selected == "config_editor".to_string() {
                  sub_panel_config_editor {}
             }
@@ -292,6 +781,11 @@ fn sub_panel_dashboard() -> Element {
         div {
             id: "sub_panel_dashboard",
             dashboard_console {}
+            div {
+                id: "dashboard_stats",
+                dashboard_ticks {}
+                dashboard_resources {}
+            }
             dashboard_online_players {}
         }
     }
@@ -299,91 +793,35 @@ fn sub_panel_dashboard() -> Element {
 
 fn dashboard_console() -> Element {
     let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let lines = APP_STATE
+        .read()
+        .console_lines
+        .get(&server.id)
+        .cloned()
+        .unwrap_or_default();
+    let mut auto_scroll = use_signal(|| true);
+    let mut command = use_signal(|| "".to_string());
     rsx! {
         div {
             id: "dashboard_console",
-            pre {
-                id: "console",
-                div {
-                    class: "serverstarted",
-                    span {
-                        class: "timestamp",
-                        "[10/26 15:58:02] "
-                    }
-                    span {
-                        class: "logfrom",
-                        "[Server thread/INFO] [minecraft/DedicatedServer]: "
-                    }
-                    span {
-                        class: "content",
-                        "Done (145.789s)! For help, type \"help\" or \"?\""
-                    }
-                }
-                div {
-                    class: "info",
-                    span {
-                        class: "timestamp",
-                        "[10/26 15:58:02] "
-                    }
-                    span {
-                        class: "logfrom",
-                        "[Netty Server IO #6/INFO] [FML]: "
-                    }
-                    span {
-                        class: "content",
-                        "Client attempting to join with 225 mods"
-                    }
-                }
-                div {
-                    class: "userjoined",
-                    span {
-                        class: "timestamp",
-                        "[10/26 15:58:02] "
-                    }
-                    span {
-                        class: "logfrom",
-                        "[Server thread/INFO] [minecraft/PlayerList]: "
-                    }
-                    span {
-                        class: "content",
-                        "SeaBass7612[/0.0.0.0:60963] logged in with entity id 140 at (632.4353965983014, 6.0, -1708.403939166827)"
-                    }
-                }
-                div {
-                    class: "warn",
-                    span {
-                        class: "timestamp",
-                        "[10/26 15:58:02] "
-                    }
-                    span {
-                        class: "logfrom",
-                        "[Server thread/WARN] [minecraft/MinecraftServer]: "
-                    }
-                    span {
-                        class: "content",
-                        "Can't keep up! Did the system time change, or is the server overloaded? Running 176809ms behind, skipping 3536 tick(s)"
-                    }
-                }
-                div {
-                    class: "error",
-                    span {
-                        class: "timestamp",
-                        "[10/26 15:58:02] "
-                    }
-                    span {
-                        class: "logfrom",
-                        "[Server thread/INFO] [minecraft/MinecraftServer]: "
-                    }
-                    span {
-                        class: "content",
-                        "Stopping server"
-                    }
-                }
+            console::console_view {
+                id: "console".to_string(),
+                lines,
+                auto_scroll: auto_scroll(),
             }
             div {
                 input {
                     id: "console_input",
-                    type: "text"
+                    type: "text",
+                    value: "{command}",
+                    oninput: move |evt| command.set(evt.value()),
+                    onkeydown: move |evt| {
+                        if evt.key() == Key::Enter && !command.read().trim().is_empty() {
+                            let value = command.read().trim().to_string();
+                            command.set("".to_string());
+                            server_action(server.id, "plugin:gaia_core|send_server_command", ServerCommandArgs { id: server.id, command: value });
+                        }
+                    }
                 }
                 div {
                     label {
@@ -402,7 +840,9 @@ fn dashboard_console() -> Element {
                     }
                     input {
                         id: "auto_scroll",
-                        type: "checkbox"
+                        type: "checkbox",
+                        checked: auto_scroll(),
+                        onchange: move |evt| auto_scroll.set(evt.checked())
                     }
                 }
             }
@@ -410,141 +850,3371 @@ fn dashboard_console() -> Element {
     }
 }
 
-fn dashboard_online_players() -> Element {
-    let server = APP_STATE.read().selected_server.to_owned().unwrap();
-    rsx! {
-        div {
-            id: "dashboard_players",
-            div {
-                class: "dashboard_subheader",
-                {format!("Online Players {}/{}", server.current_player_count, server.max_players)}
-            }
-            div {
-                class: "player_list",
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-                dashboard_player {}
-            }
-        }
-    }
-}
-
-fn dashboard_player() -> Element {
+// Line graph of the newest samples, scaled so max is the top edge
+#[component]
+fn usage_graph(values: Vec<f64>, max: f64) -> Element {
+    let width = RESOURCE_HISTORY_LENGTH as f64;
+    // the newest sample sits on the right edge
+    let offset = width - values.len() as f64;
+    let max = if max > 0.0 { max } else { 1.0 };
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            format!(
+                "{:.1},{:.1}",
+                offset + index as f64,
+                60.0 - (value / max).min(1.0) * 60.0
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
     rsx! {
-        div {
-            class: "dashboard_player",
-            img {
-                class: "player_icon",
-                src: "r"
-            }
-            span {
-                class: "player_name",
-                "USERNAME_THATS_QUITE_LONG"
-            }
-            button {
-                caret_down::svg {}
+        svg {
+            class: "usage_graph",
+            view_box: "0 0 {width} 60",
+            preserve_aspect_ratio: "none",
+            polyline {
+                points: "{points}",
+                fill: "none",
+                stroke: "currentColor",
+                stroke_width: "1.5",
+                vector_effect: "non-scaling-stroke",
             }
         }
     }
 }
 
-// PLAYERS
-fn sub_panel_players() -> Element {
-    let server = APP_STATE.read().selected_server.to_owned().unwrap();
-    rsx! {
-        div {
-            id: "sub_panel_players",
-            {format!("players display for server {:?}", server)}
-        }
-    }
+// Clock time of a unix timestamp in the user's timezone
+fn local_time(time: u64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(time as f64 * 1000.0))
+        .to_locale_time_string("default")
+        .as_string()
+        .unwrap_or_default()
 }
 
-// BACKUPS
-fn sub_panel_backups() -> Element {
-    let server = APP_STATE.read().selected_server.to_owned().unwrap();
-    rsx! {
-        div {
-            id: "sub_panel_backups",
-            {format!("backups display for server {:?}", server)}
+// Changes one setting of the selected server and saves it right away
+fn save_server_setting(change: fn(&mut ServerStruct, String), value: String) {
+    let Some(mut changed) = APP_STATE.read().selected_server.to_owned() else {
+        return;
+    };
+    change(&mut changed, value.clone());
+    spawn(async move {
+        let id = changed.id;
+        match invoke_command::<_, ()>(
+            "plugin:gaia_core|save_server",
+            &ServerArgs { server: changed },
+        )
+        .await
+        {
+            Ok(_) => update_server(id, |existing| change(existing, value.clone())),
+            Err(err) => push_console_line(id, format!("[Gaia Manager] {}", err)),
         }
-    }
+    });
 }
 
-// SCHEDUELER
-fn sub_panel_scheduler() -> Element {
+fn tick_alert_settings() -> Element {
     let server = APP_STATE.read().selected_server.to_owned().unwrap();
     rsx! {
         div {
-            id: "sub_panel_scheduler",
-            {format!("scheduler display for server {:?}", server)}
+            class: "tick_alert_settings",
+            input {
+                r#type: "checkbox",
+                checked: server.tps_alert_enabled,
+                onchange: move |evt| save_server_setting(|server, value| server.tps_alert_enabled = value == "true", evt.checked().to_string())
+            }
+            "Alert below"
+            input {
+                r#type: "number",
+                min: "1",
+                max: "20",
+                value: "{server.tps_alert_threshold}",
+                onchange: move |evt| save_server_setting(|server, value| server.tps_alert_threshold = value.parse().unwrap_or(15.0), evt.value())
+            }
+            "TPS for"
+            input {
+                r#type: "number",
+                min: "0",
+                value: "{server.tps_alert_seconds}",
+                onchange: move |evt| save_server_setting(|server, value| server.tps_alert_seconds = value.parse().unwrap_or(60), evt.value())
+            }
+            "s"
         }
     }
 }
 
-// MAPS
-fn sub_panel_maps() -> Element {
+#[derive(Clone, Debug, Default, Deserialize)]
+struct TickHistory {
+    samples: Vec<TickSample>,
+    spikes: Vec<LagSpike>,
+    alerting: bool,
+}
+
+fn dashboard_ticks() -> Element {
     let server = APP_STATE.read().selected_server.to_owned().unwrap();
-    rsx! {
-        div {
-            id: "sub_panel_maps",
-            {format!("maps display for server {:?}", server)}
+    let id = server.id;
+    use_effect(use_reactive!(|id| {
+        spawn(async move {
+            let Ok(history) = invoke_command::<_, TickHistory>(
+                "plugin:gaia_core|get_tick_history",
+                &IdArgs { id },
+            )
+            .await
+            else {
+                return;
+            };
+            let app_state = &mut APP_STATE.write();
+            if !history.samples.is_empty() {
+                app_state.tick_samples.insert(id, history.samples.clone());
+            }
+            if !history.spikes.is_empty() {
+                app_state.lag_spikes.insert(id, history.spikes);
+            }
+            if let Some(tps) = history
+                .samples
+                .last()
+                .and_then(|sample| sample.tps)
+                .filter(|_| history.alerting)
+            {
+                app_state.tick_alerts.insert(id, tps);
+            }
+        });
+    }));
+    let samples = APP_STATE
+        .read()
+        .tick_samples
+        .get(&id)
+        .cloned()
+        .unwrap_or_default();
+    let spikes = APP_STATE
+        .read()
+        .lag_spikes
+        .get(&id)
+        .cloned()
+        .unwrap_or_default();
+    let latest = samples.last().cloned();
+    let tps: Vec<f64> = samples.iter().filter_map(|sample| sample.tps).collect();
+    let mspt: Vec<f64> = samples.iter().filter_map(|sample| sample.mspt).collect();
+    let max_mspt = mspt.iter().copied().fold(50.0, f64::max);
+    let low = latest
+        .as_ref()
+        .and_then(|sample| sample.tps)
+        .is_some_and(|tps| tps < server.tps_alert_threshold);
+    rsx! {
+        div {
+            id: "dashboard_ticks",
+            div {
+                class: "dashboard_subheader",
+                "Tick Health"
+            }
+            match latest {
+                None => rsx! {
+                    span {
+                        class: "resources_empty",
+                        if server.status == 0 { "Server is offline" } else { "Waiting for tick times..." }
+                    }
+                },
+                Some(latest) => rsx! {
+                    div {
+                        class: if low { "resource tps low" } else { "resource tps" },
+                        div {
+                            class: "resource_header",
+                            span { "TPS" }
+                            span { {latest.tps.map(|tps| format!("{:.1}", tps)).unwrap_or("-".to_string())} }
+                        }
+                        usage_graph {
+                            values: tps,
+                            max: 20.0,
+                        }
+                    }
+                    div {
+                        class: "resource mspt",
+                        div {
+                            class: "resource_header",
+                            span { "MSPT" }
+                            span { {latest.mspt.map(|mspt| format!("{:.1} ms", mspt)).unwrap_or("-".to_string())} }
+                        }
+                        usage_graph {
+                            values: mspt,
+                            max: max_mspt,
+                        }
+                    }
+                    if !latest.dimensions.is_empty() {
+                        div {
+                            class: "tick_dimensions",
+                            for dimension in latest.dimensions {
+                                div {
+                                    key: "{dimension.name}",
+                                    span { class: "tick_dimension_name", title: "{dimension.name}", "{dimension.name}" }
+                                    span { {format!("{:.1} TPS {:.1} ms", dimension.tps, dimension.mspt)} }
+                                }
+                            }
+                        }
+                    }
+                },
+            }
+            div {
+                class: "lag_spikes",
+                div {
+                    class: "resource_header",
+                    span { "Lag spikes" }
+                    span { "{spikes.len()}" }
+                }
+                for spike in spikes.iter().rev().take(5) {
+                    div {
+                        key: "{spike.time}-{spike.behind_ms}",
+                        class: "lag_spike",
+                        span { {local_time(spike.time)} }
+                        span { {format!("{:.1}s behind ({} ticks)", spike.behind_ms as f64 / 1000.0, spike.ticks)} }
+                    }
+                }
+            }
+            tick_alert_settings {}
+        }
+    }
+}
+
+fn dashboard_resources() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    // fills in what was sampled before the dashboard was opened
+    let id = server.id;
+    use_effect(use_reactive!(|id| {
+        spawn(async move {
+            let history = invoke_command::<_, Vec<ResourceSample>>(
+                "plugin:gaia_core|get_resource_history",
+                &IdArgs { id },
+            )
+            .await;
+            if let Some(history) = history.ok().filter(|history| !history.is_empty()) {
+                APP_STATE.write().resource_samples.insert(id, history);
+            }
+        });
+    }));
+    let samples = APP_STATE
+        .read()
+        .resource_samples
+        .get(&server.id)
+        .cloned()
+        .unwrap_or_default();
+    let Some(latest) = samples.last().cloned() else {
+        return rsx! {
+            div {
+                id: "dashboard_resources",
+                div {
+                    class: "dashboard_subheader",
+                    "Resources"
+                }
+                span {
+                    class: "resources_empty",
+                    if server.status == 0 { "Server is offline" } else { "Waiting for data..." }
+                }
+            }
+        };
+    };
+    let allocated_bytes = server.java_allocated_memory.max(0) as u64 * 1024 * 1024;
+    let max_threads = samples
+        .iter()
+        .map(|sample| sample.threads)
+        .max()
+        .unwrap_or(0);
+    let max_disk = samples
+        .iter()
+        .map(|sample| sample.disk_bytes)
+        .max()
+        .unwrap_or(0);
+    rsx! {
+        div {
+            id: "dashboard_resources",
+            div {
+                class: "dashboard_subheader",
+                "Resources"
+            }
+            div {
+                class: "resource cpu",
+                div {
+                    class: "resource_header",
+                    span { "CPU" }
+                    span { {format!("{:.1}%", latest.cpu_percent)} }
+                }
+                usage_graph {
+                    values: samples.iter().map(|sample| sample.cpu_percent).collect::<Vec<f64>>(),
+                    max: 100.0,
+                }
+            }
+            div {
+                class: if allocated_bytes > 0 && latest.memory_bytes > allocated_bytes { "resource memory over" } else { "resource memory" },
+                div {
+                    class: "resource_header",
+                    span { "Memory" }
+                    span { {format!("{} / {}", format_size(latest.memory_bytes), format_size(allocated_bytes))} }
+                }
+                usage_graph {
+                    values: samples.iter().map(|sample| sample.memory_bytes as f64).collect::<Vec<f64>>(),
+                    max: allocated_bytes.max(latest.memory_bytes) as f64,
+                }
+            }
+            div {
+                class: "resource threads",
+                div {
+                    class: "resource_header",
+                    span { "Threads" }
+                    span { "{latest.threads}" }
+                }
+                usage_graph {
+                    values: samples.iter().map(|sample| sample.threads as f64).collect::<Vec<f64>>(),
+                    max: (max_threads as f64 * 1.2).max(1.0),
+                }
+            }
+            div {
+                class: "resource disk",
+                div {
+                    class: "resource_header",
+                    span { "Disk" }
+                    span { {format_size(latest.disk_bytes)} }
+                }
+                usage_graph {
+                    values: samples.iter().map(|sample| sample.disk_bytes as f64).collect::<Vec<f64>>(),
+                    max: (max_disk as f64 * 1.2).max(1.0),
+                }
+            }
+        }
+    }
+}
+
+fn dashboard_online_players() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let players = APP_STATE
+        .read()
+        .online_players
+        .get(&server.id)
+        .cloned()
+        .unwrap_or_default();
+    rsx! {
+        div {
+            id: "dashboard_players",
+            div {
+                class: "dashboard_subheader",
+                {format!("Online Players {}/{}", server.current_player_count, server.max_players)}
+            }
+            div {
+                class: "player_list",
+                for player in players {
+                    dashboard_player { key: "{player.name}", player }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn dashboard_player(player: OnlinePlayer) -> Element {
+    rsx! {
+        div {
+            class: "dashboard_player",
+            img {
+                class: "player_icon",
+                src: "r"
+            }
+            span {
+                class: "player_name",
+                "{player.name}"
+            }
+            if player.bedrock {
+                span {
+                    class: "bedrock_tag",
+                    title: "Joined from Bedrock Edition",
+                    "Bedrock"
+                }
+            }
+            button {
+                caret_down::svg {}
+            }
+        }
+    }
+}
+
+// PLAYERS
+fn sub_panel_players() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    if is_bedrock(&server.server_type) {
+        return rsx! {
+            div {
+                id: "sub_panel_players",
+                bedrock_access {}
+            }
+        };
+    }
+    rsx! {
+        div {
+            id: "sub_panel_players",
+            {format!("players display for server {:?}", server)}
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct AllowlistEntry {
+    #[serde(rename = "ignoresPlayerLimit", default)]
+    ignores_player_limit: bool,
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    xuid: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct PermissionEntry {
+    permission: String,
+    xuid: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct BedrockAccess {
+    allowlist: Vec<AllowlistEntry>,
+    permissions: Vec<PermissionEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BedrockAccessArgs {
+    id: Uuid,
+    access: BedrockAccess,
+}
+
+const PERMISSION_LEVELS: [&str; 3] = ["visitor", "member", "operator"];
+
+// allowlist.json and permissions.json of a bedrock server, bedrock has no ops.json or whitelist
+fn bedrock_access() -> Element {
+    let id = APP_STATE.read().selected_server.as_ref().unwrap().id;
+    let mut access = use_signal(|| None::<BedrockAccess>);
+    let mut message = use_signal(|| None::<(bool, String)>);
+    let mut new_name = use_signal(String::new);
+    let mut new_xuid = use_signal(String::new);
+    use_effect(use_reactive!(|id| {
+        spawn(async move {
+            match invoke_command::<_, BedrockAccess>(
+                "plugin:gaia_core|get_bedrock_access",
+                &IdArgs { id },
+            )
+            .await
+            {
+                Ok(loaded) => access.set(Some(loaded)),
+                Err(err) => message.set(Some((false, err))),
+            }
+        });
+    }));
+
+    let Some(current) = access() else {
+        return rsx! {
+            if let Some((_, text)) = message() {
+                span { class: "error", "{text}" }
+            } else {
+                span { "Loading allowlist..." }
+            }
+        };
+    };
+    let mut edit = move |change: Box<dyn FnOnce(&mut BedrockAccess)>| {
+        if let Some(access) = access.write().as_mut() {
+            change(access);
+        }
+    };
+    let save = move |_| {
+        let Some(current) = access() else {
+            return;
+        };
+        spawn(async move {
+            match invoke_command::<_, ()>(
+                "plugin:gaia_core|save_bedrock_access",
+                &BedrockAccessArgs {
+                    id,
+                    access: current,
+                },
+            )
+            .await
+            {
+                Ok(_) => message.set(Some((true, "Saved".to_string()))),
+                Err(err) => message.set(Some((false, err))),
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            class: "network_toolbar",
+            button {
+                onclick: save,
+                "Save"
+            }
+            if let Some((ok, text)) = message() {
+                span {
+                    class: if ok { "network_message" } else { "network_message error" },
+                    "{text}"
+                }
+            }
+        }
+        div {
+            class: "network_section",
+            h3 { "Allowlist" }
+            span { class: "network_hint", "Only used when allow-list is true in server.properties" }
+            for (index, entry) in current.allowlist.iter().cloned().enumerate() {
+                div {
+                    key: "{index}",
+                    class: "access_entry",
+                    span { class: "access_name", "{entry.name}" }
+                    span { if entry.xuid.is_empty() { "not joined yet" } else { "{entry.xuid}" } }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: entry.ignores_player_limit,
+                            onchange: move |evt: Event<FormData>| {
+                                let checked = evt.checked();
+                                edit(Box::new(move |access| access.allowlist[index].ignores_player_limit = checked));
+                            }
+                        }
+                        "Ignores player limit"
+                    }
+                    button {
+                        onclick: move |_| edit(Box::new(move |access| {
+                            access.allowlist.remove(index);
+                        })),
+                        "Remove"
+                    }
+                }
+            }
+            div {
+                class: "access_entry",
+                input {
+                    placeholder: "gamertag",
+                    value: "{new_name}",
+                    oninput: move |evt| new_name.set(evt.value())
+                }
+                button {
+                    disabled: new_name().trim().is_empty(),
+                    onclick: move |_| {
+                        let name = new_name().trim().to_string();
+                        edit(Box::new(move |access| access.allowlist.push(AllowlistEntry { name, ..Default::default() })));
+                        new_name.set(String::new());
+                    },
+                    "Add"
+                }
+            }
+        }
+        div {
+            class: "network_section",
+            h3 { "Permissions" }
+            for (index, entry) in current.permissions.iter().cloned().enumerate() {
+                div {
+                    key: "{index}",
+                    class: "access_entry",
+                    span { class: "access_name", "{entry.xuid}" }
+                    select {
+                        value: "{entry.permission}",
+                        onchange: move |evt| {
+                            let permission = evt.value();
+                            edit(Box::new(move |access| access.permissions[index].permission = permission));
+                        },
+                        for level in PERMISSION_LEVELS {
+                            option { value: level, selected: entry.permission == level, "{level}" }
+                        }
+                    }
+                    button {
+                        onclick: move |_| edit(Box::new(move |access| {
+                            access.permissions.remove(index);
+                        })),
+                        "Remove"
+                    }
+                }
+            }
+            div {
+                class: "access_entry",
+                input {
+                    placeholder: "xuid",
+                    value: "{new_xuid}",
+                    oninput: move |evt| new_xuid.set(evt.value())
+                }
+                button {
+                    disabled: new_xuid().trim().is_empty(),
+                    onclick: move |_| {
+                        let xuid = new_xuid().trim().to_string();
+                        edit(Box::new(move |access| access.permissions.push(PermissionEntry { permission: "member".to_string(), xuid })));
+                        new_xuid.set(String::new());
+                    },
+                    "Add"
+                }
+            }
+            span { class: "network_hint", "A player's xuid shows up in the allowlist after they first join" }
+        }
+    }
+}
+
+// BACKUPS
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct BackupManifest {
+    name: String,
+    archive: String,
+    server_id: Uuid,
+    server_name: String,
+    created: String,
+    created_unix: u64,
+    format: String,
+    scope: String,
+    worlds: Vec<String>,
+    size: u64,
+    #[serde(default)]
+    total_size: u64,
+    file_count: u64,
+    hash: String,
+    minecraft_version: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupOptions {
+    format: String,
+    scope: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CreateBackupArgs {
+    id: Uuid,
+    options: BackupOptions,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupNameArgs {
+    id: Uuid,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreBackupArgs {
+    id: Uuid,
+    name: String,
+    new_path: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct RetentionPolicy {
+    keep_last: u32,
+    keep_hourly: u32,
+    keep_daily: u32,
+    keep_weekly: u32,
+    max_total_mb: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct RetentionDecision {
+    name: String,
+    created: String,
+    size: u64,
+    keep: bool,
+    reasons: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RetentionArgs {
+    id: Uuid,
+    policy: RetentionPolicy,
+}
+
+// label, current value and how to write it back into the policy
+type RetentionField = (&'static str, u64, fn(&mut RetentionPolicy, u64));
+
+fn backup_retention() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let mut policy = use_signal(|| RetentionPolicy {
+        keep_last: server.backup_keep_last,
+        keep_hourly: server.backup_keep_hourly,
+        keep_daily: server.backup_keep_daily,
+        keep_weekly: server.backup_keep_weekly,
+        max_total_mb: server.backup_max_total_mb,
+    });
+    let mut preview = use_signal(|| None::<Vec<RetentionDecision>>);
+    let mut message = use_signal(|| None::<(bool, String)>);
+
+    let preview_server = server.clone();
+    let run_preview = move |_| {
+        let args = RetentionArgs {
+            id: preview_server.id,
+            policy: policy(),
+        };
+        spawn(async move {
+            match invoke_command::<_, Vec<RetentionDecision>>(
+                "plugin:gaia_core|preview_backup_retention",
+                &args,
+            )
+            .await
+            {
+                Ok(decisions) => {
+                    message.set(None);
+                    preview.set(Some(decisions));
+                }
+                Err(err) => message.set(Some((false, err))),
+            }
+        });
+    };
+    let save_server = server.clone();
+    let save_policy = move |_| {
+        let current = policy();
+        let server = ServerStruct {
+            backup_keep_last: current.keep_last,
+            backup_keep_hourly: current.keep_hourly,
+            backup_keep_daily: current.keep_daily,
+            backup_keep_weekly: current.keep_weekly,
+            backup_max_total_mb: current.max_total_mb,
+            ..save_server.clone()
+        };
+        spawn(async move {
+            let id = server.id;
+            match invoke_command::<_, ()>(
+                "plugin:gaia_core|save_server",
+                &ServerArgs {
+                    server: server.clone(),
+                },
+            )
+            .await
+            {
+                Ok(_) => {
+                    update_server(id, |existing| {
+                        existing.backup_keep_last = current.keep_last;
+                        existing.backup_keep_hourly = current.keep_hourly;
+                        existing.backup_keep_daily = current.keep_daily;
+                        existing.backup_keep_weekly = current.keep_weekly;
+                        existing.backup_max_total_mb = current.max_total_mb;
+                    });
+                    message.set(Some((true, "Retention policy saved".to_string())));
+                }
+                Err(err) => message.set(Some((false, err))),
+            }
+        });
+    };
+
+    let current = policy();
+    let fields: [RetentionField; 5] = [
+        ("Keep last", current.keep_last as u64, |policy, value| {
+            policy.keep_last = value as u32
+        }),
+        ("Hourly", current.keep_hourly as u64, |policy, value| {
+            policy.keep_hourly = value as u32
+        }),
+        ("Daily", current.keep_daily as u64, |policy, value| {
+            policy.keep_daily = value as u32
+        }),
+        ("Weekly", current.keep_weekly as u64, |policy, value| {
+            policy.keep_weekly = value as u32
+        }),
+        ("Max MB", current.max_total_mb, |policy, value| {
+            policy.max_total_mb = value
+        }),
+    ];
+    rsx! {
+        div {
+            class: "backup_retention",
+            for (label, value, set) in fields {
+                label {
+                    key: "{label}",
+                    "{label}"
+                    input {
+                        r#type: "number",
+                        min: "0",
+                        value: "{value}",
+                        oninput: move |evt| {
+                            let value = evt.value().parse::<u64>().unwrap_or(0);
+                            set(&mut policy.write(), value);
+                            preview.set(None);
+                        }
+                    }
+                }
+            }
+            button {
+                onclick: run_preview,
+                "Preview"
+            }
+            button {
+                onclick: save_policy,
+                "Save Policy"
+            }
+            if let Some((ok, text)) = message() {
+                span {
+                    class: if ok { "backup_message" } else { "backup_message error" },
+                    "{text}"
+                }
+            }
+        }
+        if let Some(decisions) = preview() {
+            div {
+                class: "retention_preview",
+                div {
+                    class: "retention_header",
+                    span {
+                        {format!(
+                            "Would delete {} of {} backups",
+                            decisions.iter().filter(|decision| !decision.keep).count(),
+                            decisions.len()
+                        )}
+                    }
+                    button {
+                        onclick: move |_| preview.set(None),
+                        "Close"
+                    }
+                }
+                for decision in decisions {
+                    div {
+                        key: "{decision.name}",
+                        class: if decision.keep { "retention_decision keep" } else { "retention_decision delete" },
+                        span { "{decision.created} UTC" }
+                        span { {format_size(decision.size)} }
+                        span {
+                            if decision.keep { "Keep" } else { "Delete" }
+                            " ({decision.reasons.join(\", \")})"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn sub_panel_backups() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let mut refresh = use_signal(|| 0);
+    let list_server = server.clone();
+    let backups = use_resource(use_reactive!(|(list_server,)| async move {
+        refresh();
+        invoke_command::<_, Vec<BackupManifest>>(
+            "plugin:gaia_core|list_server_backups",
+            &IdArgs { id: list_server.id },
+        )
+        .await
+    }));
+    let mut format = use_signal(|| server.backup_format.clone());
+    let mut scope = use_signal(|| server.backup_scope.clone());
+    let mut working = use_signal(|| false);
+    let mut message = use_signal(|| None::<(bool, String)>);
+    // name of the backup that's waiting on a second click, with the action it's for
+    let mut confirming = use_signal(|| None::<(String, &'static str)>);
+    let mut new_path = use_signal(|| "".to_string());
+
+    let backup_server = server.clone();
+    let create_backup = move |_| {
+        let args = CreateBackupArgs {
+            id: backup_server.id,
+            options: BackupOptions {
+                format: format(),
+                scope: scope(),
+            },
+        };
+        working.set(true);
+        message.set(Some((true, "Backing up...".to_string())));
+        spawn(async move {
+            match invoke_command::<_, BackupManifest>(
+                "plugin:gaia_core|create_server_backup",
+                &args,
+            )
+            .await
+            {
+                Ok(manifest) => message.set(Some((
+                    true,
+                    format!(
+                        "Created {} ({})",
+                        manifest.archive,
+                        format_size(manifest.size)
+                    ),
+                ))),
+                Err(err) => message.set(Some((false, err))),
+            }
+            working.set(false);
+            refresh += 1;
+        });
+    };
+
+    let mut run_action = move |action: &'static str, server: ServerStruct, name: String| {
+        confirming.set(None);
+        working.set(true);
+        spawn(async move {
+            let result = match action {
+                "verify" => invoke_command::<_, ()>(
+                    "plugin:gaia_core|verify_server_backup",
+                    &BackupNameArgs {
+                        id: server.id,
+                        name: name.clone(),
+                    },
+                )
+                .await
+                .map(|_| format!("{} is intact", name)),
+                "delete" => invoke_command::<_, ()>(
+                    "plugin:gaia_core|delete_server_backup",
+                    &BackupNameArgs {
+                        id: server.id,
+                        name: name.clone(),
+                    },
+                )
+                .await
+                .map(|_| format!("Deleted {}", name)),
+                "restore" => invoke_command::<_, Option<ServerStruct>>(
+                    "plugin:gaia_core|restore_server_backup",
+                    &RestoreBackupArgs {
+                        id: server.id,
+                        name: name.clone(),
+                        new_path: None,
+                    },
+                )
+                .await
+                .map(|_| format!("Restored {}", name)),
+                _ => invoke_command::<_, Option<ServerStruct>>(
+                    "plugin:gaia_core|restore_server_backup",
+                    &RestoreBackupArgs {
+                        id: server.id,
+                        name: name.clone(),
+                        new_path: Some(new_path()),
+                    },
+                )
+                .await
+                .map(|restored| match restored {
+                    Some(restored) => {
+                        let text = format!("Restored {} as {}", name, restored.name);
+                        APP_STATE.write().servers.push(restored);
+                        text
+                    }
+                    None => format!("Restored {}", name),
+                }),
+            };
+            match result {
+                Ok(text) => message.set(Some((true, text))),
+                Err(err) => message.set(Some((false, err))),
+            }
+            working.set(false);
+            refresh += 1;
+        });
+    };
+
+    let online = server.status != 0;
+    rsx! {
+        div {
+            id: "sub_panel_backups",
+            div {
+                class: "backup_toolbar",
+                select {
+                    value: "{format}",
+                    onchange: move |evt| format.set(evt.value()),
+                    option { value: "zip", "zip" }
+                    option { value: "tar.zst", "tar.zst" }
+                    option { value: "incremental", "Incremental" }
+                }
+                select {
+                    value: "{scope}",
+                    onchange: move |evt| scope.set(evt.value()),
+                    option { value: "worlds", "Worlds only" }
+                    option { value: "full", "Whole server folder" }
+                }
+                button {
+                    disabled: working(),
+                    onclick: create_backup,
+                    "Back Up Now"
+                }
+                if let Some((ok, text)) = message() {
+                    span {
+                        class: if ok { "backup_message" } else { "backup_message error" },
+                        "{text}"
+                    }
+                }
+            }
+            backup_retention {}
+            div {
+                class: "backup_list",
+                match &*backups.read() {
+                    Some(Ok(backups)) if backups.is_empty() => rsx! {
+                        span { class: "backup_empty", "No backups yet" }
+                    },
+                    Some(Ok(backups)) => rsx! {
+                        for backup in backups.clone() {
+                            div {
+                                key: "{backup.name}",
+                                class: "backup",
+                                div {
+                                    class: "backup_info",
+                                    span { class: "backup_name", "{backup.created} UTC" }
+                                    span { "{backup.format} | {backup.scope} | {format_size(backup.size)} of {format_size(backup.total_size)} | {backup.file_count} files | {backup.minecraft_version}" }
+                                    span {
+                                        class: "backup_hash",
+                                        title: "{backup.hash}",
+                                        "sha256 {backup.hash.get(..12).unwrap_or_default()}"
+                                    }
+                                }
+                                div {
+                                    class: "backup_buttons",
+                                    match confirming() {
+                                        Some((name, action)) if name == backup.name => rsx! {
+                                            if action == "restore_new" {
+                                                input {
+                                                    r#type: "text",
+                                                    placeholder: "Folder for the new server",
+                                                    value: "{new_path}",
+                                                    oninput: move |evt| new_path.set(evt.value())
+                                                }
+                                            }
+                                            button {
+                                                disabled: working(),
+                                                onclick: {
+                                                    let server = server.clone();
+                                                    let name = backup.name.clone();
+                                                    move |_| run_action(action, server.clone(), name.clone())
+                                                },
+                                                match action {
+                                                    "delete" => "Confirm Delete",
+                                                    "restore" => "Confirm Restore",
+                                                    _ => "Create Server",
+                                                }
+                                            }
+                                            button {
+                                                onclick: move |_| confirming.set(None),
+                                                "Cancel"
+                                            }
+                                        },
+                                        _ => rsx! {
+                                            button {
+                                                disabled: working(),
+                                                onclick: {
+                                                    let server = server.clone();
+                                                    let name = backup.name.clone();
+                                                    move |_| run_action("verify", server.clone(), name.clone())
+                                                },
+                                                "Verify"
+                                            }
+                                            button {
+                                                disabled: working() || online,
+                                                title: if online { "Stop the server to restore in place" } else { "" },
+                                                onclick: {
+                                                    let name = backup.name.clone();
+                                                    move |_| confirming.set(Some((name.clone(), "restore")))
+                                                },
+                                                "Restore"
+                                            }
+                                            button {
+                                                disabled: working(),
+                                                onclick: {
+                                                    let name = backup.name.clone();
+                                                    move |_| confirming.set(Some((name.clone(), "restore_new")))
+                                                },
+                                                "Restore as New Server"
+                                            }
+                                            button {
+                                                disabled: working(),
+                                                onclick: {
+                                                    let name = backup.name.clone();
+                                                    move |_| confirming.set(Some((name.clone(), "delete")))
+                                                },
+                                                "Delete"
+                                            }
+                                        },
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    Some(Err(err)) => rsx! {
+                        span { class: "error", "{err}" }
+                    },
+                    None => rsx! {
+                        span { "Loading backups..." }
+                    },
+                }
+            }
+        }
+    }
+}
+
+// WORLDS
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct GameRule {
+    name: String,
+    value: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct WorldInfo {
+    folder: String,
+    bedrock: bool,
+    level_name: String,
+    seed: String,
+    game_type: i32,
+    difficulty: i32,
+    hardcore: bool,
+    spawn: [i32; 3],
+    day_time: String,
+    game_time: String,
+    data_version: i32,
+    version_name: String,
+    game_rules: Vec<GameRule>,
+    hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveWorldArgs {
+    id: Uuid,
+    world: WorldInfo,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct WorldEntry {
+    name: String,
+    folder: String,
+    active: bool,
+    size: u64,
+    modified: String,
+    data_version: i32,
+    dimensions: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ChunkSummary {
+    x: i32,
+    z: i32,
+    size: u64,
+    timestamp: u32,
+    compression: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ChunkProblem {
+    file: String,
+    chunk: Option<[i32; 2]>,
+    error: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct DimensionStats {
+    dimension: String,
+    region_files: u32,
+    chunk_count: u32,
+    total_size: u64,
+    average_size: u64,
+    largest: Vec<ChunkSummary>,
+    problems: Vec<ChunkProblem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PruneOptions {
+    min_inhabited_ticks: i64,
+    area: String,
+    radius: u32,
+    box_size: [u32; 2],
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct DimensionPrune {
+    dimension: String,
+    chunks_checked: u64,
+    chunks_pruned: u64,
+    regions_deleted: u64,
+    bytes_saved: u64,
+    skipped: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PruneReport {
+    dry_run: bool,
+    chunks_pruned: u64,
+    bytes_saved: u64,
+    dimensions: Vec<DimensionPrune>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PruneWorldArgs {
+    id: Uuid,
+    name: String,
+    options: PruneOptions,
+    dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WorldNameArgs {
+    id: Uuid,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportWorldArgs {
+    id: Uuid,
+    source_path: String,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportWorldArgs {
+    id: Uuid,
+    name: String,
+    dest_path: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RegenerateWorldArgs {
+    id: Uuid,
+    name: String,
+    seed: String,
+}
+
+const GAME_TYPES: [&str; 4] = ["Survival", "Creative", "Adventure", "Spectator"];
+const DIFFICULTIES: [&str; 4] = ["Peaceful", "Easy", "Normal", "Hard"];
+
+fn sub_panel_worlds() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let id = server.id;
+    let online = server.status != 0;
+    let bedrock = server.server_type == "bedrock";
+    let mut worlds = use_signal(Vec::<WorldInfo>::new);
+    let mut selected = use_signal(|| 0usize);
+    let mut message = use_signal(|| None::<(bool, String)>);
+    let load = move || {
+        spawn(async move {
+            match invoke_command::<_, Vec<WorldInfo>>("plugin:gaia_core|get_worlds", &IdArgs { id })
+                .await
+            {
+                Ok(loaded) => {
+                    if selected() >= loaded.len() {
+                        selected.set(0);
+                    }
+                    worlds.set(loaded);
+                }
+                Err(err) => message.set(Some((false, err))),
+            }
+        });
+    };
+    // the server rewrites level.dat while it runs, so it's read again once it stops
+    use_effect(use_reactive!(|(id, online)| {
+        let _ = (id, online);
+        load();
+    }));
+
+    let Some(world) = worlds().get(selected()).cloned() else {
+        return rsx! {
+            div {
+                id: "sub_panel_worlds",
+                world_list { id, online, bedrock, on_change: move |_| load() }
+                span { class: "worlds_hint", "No worlds with a level.dat yet, start the server once to generate one" }
+            }
+        };
+    };
+    let mut edit = move |change: Box<dyn FnOnce(&mut WorldInfo)>| {
+        if let Some(world) = worlds.write().get_mut(selected()) {
+            change(world);
+        }
+    };
+    let save = move |_| {
+        let Some(world) = worlds().get(selected()).cloned() else {
+            return;
+        };
+        spawn(async move {
+            match invoke_command::<_, WorldInfo>(
+                "plugin:gaia_core|save_world_settings",
+                &SaveWorldArgs { id, world },
+            )
+            .await
+            {
+                Ok(saved) => {
+                    if let Some(world) = worlds.write().get_mut(selected()) {
+                        *world = saved;
+                    }
+                    message.set(Some((
+                        true,
+                        "Saved, the old level.dat is kept as level.dat.bak".to_string(),
+                    )));
+                }
+                Err(err) => message.set(Some((false, err))),
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            id: "sub_panel_worlds",
+            world_list { id, online, bedrock, on_change: move |_| load() }
+            div {
+                class: "worlds_toolbar",
+                select {
+                    onchange: move |evt| {
+                        if let Ok(index) = evt.value().parse::<usize>() {
+                            selected.set(index);
+                            message.set(None);
+                        }
+                    },
+                    for (index, other) in worlds().iter().enumerate() {
+                        option { value: "{index}", selected: index == selected(), "{other.folder}" }
+                    }
+                }
+                button {
+                    onclick: move |_| {
+                        message.set(None);
+                        load();
+                    },
+                    "Reload"
+                }
+                button {
+                    disabled: online,
+                    onclick: save,
+                    "Save"
+                }
+                if online {
+                    span { class: "worlds_hint", "Stop the server to change its world settings" }
+                }
+                if let Some((ok, text)) = message() {
+                    span {
+                        class: if ok { "worlds_message" } else { "worlds_message error" },
+                        "{text}"
+                    }
+                }
+            }
+            div {
+                class: "world_settings",
+                label { "Level name" span { "{world.level_name}" } }
+                label { "Version" span { "{world.version_name} (data version {world.data_version})" } }
+                label {
+                    "Seed"
+                    input {
+                        disabled: online,
+                        value: "{world.seed}",
+                        onchange: move |evt| {
+                            let seed = evt.value();
+                            edit(Box::new(move |world| world.seed = seed));
+                        }
+                    }
+                }
+                label {
+                    "Game mode"
+                    select {
+                        disabled: online,
+                        onchange: move |evt| {
+                            if let Ok(game_type) = evt.value().parse::<i32>() {
+                                edit(Box::new(move |world| world.game_type = game_type));
+                            }
+                        },
+                        for (index, name) in GAME_TYPES.iter().enumerate() {
+                            option { value: "{index}", selected: index as i32 == world.game_type, "{name}" }
+                        }
+                    }
+                }
+                label {
+                    "Difficulty"
+                    select {
+                        disabled: online,
+                        onchange: move |evt| {
+                            if let Ok(difficulty) = evt.value().parse::<i32>() {
+                                edit(Box::new(move |world| world.difficulty = difficulty));
+                            }
+                        },
+                        for (index, name) in DIFFICULTIES.iter().enumerate() {
+                            option { value: "{index}", selected: index as i32 == world.difficulty, "{name}" }
+                        }
+                    }
+                }
+                label {
+                    "Hardcore"
+                    input {
+                        r#type: "checkbox",
+                        disabled: online,
+                        checked: world.hardcore,
+                        onchange: move |evt| {
+                            let hardcore = evt.checked();
+                            edit(Box::new(move |world| world.hardcore = hardcore));
+                        }
+                    }
+                }
+                label {
+                    "Spawn"
+                    for axis in 0..3 {
+                        input {
+                            r#type: "number",
+                            disabled: online,
+                            value: "{world.spawn[axis]}",
+                            onchange: move |evt| {
+                                if let Ok(value) = evt.value().parse::<i32>() {
+                                    edit(Box::new(move |world| world.spawn[axis] = value));
+                                }
+                            }
+                        }
+                    }
+                }
+                label {
+                    "Time of day"
+                    input {
+                        disabled: online,
+                        value: "{world.day_time}",
+                        onchange: move |evt| {
+                            let day_time = evt.value();
+                            edit(Box::new(move |world| world.day_time = day_time));
+                        }
+                    }
+                }
+                label { "World age" span { "{world.game_time} ticks" } }
+            }
+            div {
+                class: "world_rules",
+                h3 { "Game Rules" }
+                for rule in world.game_rules.clone() {
+                    label {
+                        key: "{rule.name}",
+                        span { "{rule.name}" }
+                        if rule.value == "true" || rule.value == "false" {
+                            input {
+                                r#type: "checkbox",
+                                disabled: online,
+                                checked: rule.value == "true",
+                                onchange: {
+                                    let name = rule.name.clone();
+                                    move |evt: Event<FormData>| {
+                                        let (name, value) = (name.clone(), evt.checked().to_string());
+                                        edit(Box::new(move |world| set_game_rule(world, &name, value)));
+                                    }
+                                }
+                            }
+                        } else {
+                            input {
+                                disabled: online,
+                                value: "{rule.value}",
+                                onchange: {
+                                    let name = rule.name.clone();
+                                    move |evt: Event<FormData>| {
+                                        let (name, value) = (name.clone(), evt.value());
+                                        edit(Box::new(move |world| set_game_rule(world, &name, value)));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The server's worlds with what can be done to them as a whole, on_change reloads the settings below
+#[component]
+fn world_list(id: Uuid, online: bool, bedrock: bool, on_change: EventHandler<()>) -> Element {
+    let mut refresh = use_signal(|| 0);
+    let worlds = use_resource(use_reactive!(|(id, online)| async move {
+        let _ = online;
+        refresh();
+        invoke_command::<_, Vec<WorldEntry>>("plugin:gaia_core|list_worlds", &IdArgs { id }).await
+    }));
+    let mut working = use_signal(|| false);
+    let mut message = use_signal(|| None::<(bool, String)>);
+    // name of the world that's waiting on a second click, with the action it's for
+    let mut confirming = use_signal(|| None::<(String, &'static str)>);
+    let mut import_path = use_signal(|| "".to_string());
+    let mut import_name = use_signal(|| "".to_string());
+    let mut export_path = use_signal(|| "".to_string());
+    let mut seed = use_signal(|| "".to_string());
+    // world name with the stats of each of its dimensions
+    let mut stats = use_signal(|| None::<(String, Vec<DimensionStats>)>);
+    let mut pruning = use_signal(|| None::<String>);
+
+    let mut load_stats = move |name: String| {
+        working.set(true);
+        stats.set(None);
+        message.set(Some((true, format!("Reading every chunk of {}...", name))));
+        spawn(async move {
+            match invoke_command::<_, Vec<DimensionStats>>(
+                "plugin:gaia_core|get_chunk_stats",
+                &WorldNameArgs {
+                    id,
+                    name: name.clone(),
+                },
+            )
+            .await
+            {
+                Ok(loaded) => {
+                    message.set(None);
+                    stats.set(Some((name, loaded)));
+                }
+                Err(err) => message.set(Some((false, err))),
+            }
+            working.set(false);
+        });
+    };
+
+    let mut run_action = move |action: &'static str, name: String| {
+        confirming.set(None);
+        working.set(true);
+        if action == "regenerate" {
+            message.set(Some((true, "Backing up...".to_string())));
+        }
+        spawn(async move {
+            let result = match action {
+                "activate" => invoke_command::<_, ()>(
+                    "plugin:gaia_core|set_active_world",
+                    &WorldNameArgs {
+                        id,
+                        name: name.clone(),
+                    },
+                )
+                .await
+                .map(|_| format!("{} is loaded on the next start", name)),
+                "import" => invoke_command::<_, ()>(
+                    "plugin:gaia_core|import_world",
+                    &ImportWorldArgs {
+                        id,
+                        source_path: import_path(),
+                        name: name.clone(),
+                    },
+                )
+                .await
+                .map(|_| format!("Imported {}", name)),
+                "export" => invoke_command::<_, ()>(
+                    "plugin:gaia_core|export_world",
+                    &ExportWorldArgs {
+                        id,
+                        name: name.clone(),
+                        dest_path: export_path(),
+                    },
+                )
+                .await
+                .map(|_| format!("Exported {} to {}", name, export_path())),
+                "regenerate" => invoke_command::<_, BackupManifest>(
+                    "plugin:gaia_core|regenerate_world",
+                    &RegenerateWorldArgs {
+                        id,
+                        name: name.clone(),
+                        seed: seed(),
+                    },
+                )
+                .await
+                .map(|manifest| {
+                    format!(
+                        "Backed up to {}, {} is generated again on the next start",
+                        manifest.archive, name
+                    )
+                }),
+                _ => invoke_command::<_, ()>(
+                    "plugin:gaia_core|delete_world",
+                    &WorldNameArgs {
+                        id,
+                        name: name.clone(),
+                    },
+                )
+                .await
+                .map(|_| format!("Deleted {}", name)),
+            };
+            match result {
+                Ok(text) => {
+                    if action == "import" {
+                        import_path.set("".to_string());
+                        import_name.set("".to_string());
+                    }
+                    message.set(Some((true, text)));
+                }
+                Err(err) => message.set(Some((false, err))),
+            }
+            working.set(false);
+            refresh += 1;
+            on_change.call(());
+        });
+    };
+
+    rsx! {
+        div {
+            class: "world_list",
+            div {
+                class: "worlds_toolbar",
+                input {
+                    r#type: "text",
+                    placeholder: "World folder or zip to import",
+                    value: "{import_path}",
+                    oninput: move |evt| import_path.set(evt.value())
+                }
+                input {
+                    r#type: "text",
+                    placeholder: "Name",
+                    value: "{import_name}",
+                    oninput: move |evt| import_name.set(evt.value())
+                }
+                button {
+                    disabled: working() || import_path().is_empty() || import_name().is_empty(),
+                    onclick: move |_| run_action("import", import_name()),
+                    "Import"
+                }
+                if let Some((ok, text)) = message() {
+                    span {
+                        class: if ok { "worlds_message" } else { "worlds_message error" },
+                        "{text}"
+                    }
+                }
+            }
+            match &*worlds.read() {
+                Some(Ok(worlds)) => rsx! {
+                    for world in worlds.clone() {
+                        div {
+                            key: "{world.name}",
+                            class: "world_entry",
+                            div {
+                                class: "world_info",
+                                span {
+                                    class: "world_name",
+                                    "{world.name}"
+                                    if world.active {
+                                        span { class: "world_active", "Active" }
+                                    }
+                                }
+                                span {
+                                    "{format_size(world.size)} | modified {world.modified} UTC | data version {world.data_version}"
+                                    if !world.dimensions.is_empty() {
+                                        " | {world.dimensions.join(\", \")}"
+                                    }
+                                }
+                            }
+                            div {
+                                class: "world_buttons",
+                                match confirming() {
+                                    Some((name, action)) if name == world.name => rsx! {
+                                        if action == "export" {
+                                            input {
+                                                r#type: "text",
+                                                placeholder: "Zip file to write",
+                                                value: "{export_path}",
+                                                oninput: move |evt| export_path.set(evt.value())
+                                            }
+                                        }
+                                        if action == "regenerate" {
+                                            input {
+                                                r#type: "text",
+                                                placeholder: "Seed, empty for random",
+                                                value: "{seed}",
+                                                oninput: move |evt| seed.set(evt.value())
+                                            }
+                                        }
+                                        button {
+                                            disabled: working() || (action == "export" && export_path().is_empty()),
+                                            onclick: {
+                                                let name = world.name.clone();
+                                                move |_| run_action(action, name.clone())
+                                            },
+                                            match action {
+                                                "export" => "Export",
+                                                "regenerate" => "Back Up and Regenerate",
+                                                _ => "Confirm Delete",
+                                            }
+                                        }
+                                        button {
+                                            onclick: move |_| confirming.set(None),
+                                            "Cancel"
+                                        }
+                                    },
+                                    _ => rsx! {
+                                        if !world.active {
+                                            button {
+                                                disabled: working() || online,
+                                                onclick: {
+                                                    let name = world.name.clone();
+                                                    move |_| run_action("activate", name.clone())
+                                                },
+                                                "Make Active"
+                                            }
+                                        }
+                                        if !bedrock {
+                                            button {
+                                                disabled: working(),
+                                                onclick: {
+                                                    let name = world.name.clone();
+                                                    move |_| load_stats(name.clone())
+                                                },
+                                                "Chunk Stats"
+                                            }
+                                            button {
+                                                disabled: working(),
+                                                onclick: {
+                                                    let name = world.name.clone();
+                                                    move |_| pruning.set(Some(name.clone()))
+                                                },
+                                                "Prune"
+                                            }
+                                        }
+                                        button {
+                                            disabled: working(),
+                                            onclick: {
+                                                let name = world.name.clone();
+                                                move |_| confirming.set(Some((name.clone(), "export")))
+                                            },
+                                            "Export"
+                                        }
+                                        if world.active {
+                                            button {
+                                                disabled: working() || online,
+                                                onclick: {
+                                                    let name = world.name.clone();
+                                                    move |_| confirming.set(Some((name.clone(), "regenerate")))
+                                                },
+                                                "Regenerate"
+                                            }
+                                        } else {
+                                            button {
+                                                disabled: working() || online,
+                                                onclick: {
+                                                    let name = world.name.clone();
+                                                    move |_| confirming.set(Some((name.clone(), "delete")))
+                                                },
+                                                "Delete"
+                                            }
+                                        }
+                                    },
+                                }
+                            }
+                        }
+                    }
+                },
+                Some(Err(err)) => rsx! {
+                    span { class: "worlds_message error", "{err}" }
+                },
+                None => rsx! {
+                    span { class: "worlds_hint", "Loading worlds..." }
+                },
+            }
+            if let Some(name) = pruning() {
+                world_prune {
+                    key: "{name}",
+                    id,
+                    name: name.clone(),
+                    online,
+                    on_close: move |_| pruning.set(None),
+                    on_change: move |_| {
+                        refresh += 1;
+                        on_change.call(());
+                    }
+                }
+            }
+            if let Some((name, dimensions)) = stats() {
+                div {
+                    class: "chunk_stats",
+                    div {
+                        class: "chunk_stats_title",
+                        h3 { "Chunks of {name}" }
+                        button {
+                            onclick: move |_| stats.set(None),
+                            "Close"
+                        }
+                    }
+                    for dimension in dimensions {
+                        div {
+                            key: "{dimension.dimension}",
+                            class: "chunk_dimension",
+                            span {
+                                class: "world_name",
+                                "{dimension.dimension}"
+                            }
+                            span { "{dimension.chunk_count} chunks in {dimension.region_files} region files | {format_size(dimension.total_size)} total | {format_size(dimension.average_size)} average" }
+                            for chunk in dimension.largest {
+                                span {
+                                    class: "chunk_largest",
+                                    "chunk {chunk.x}, {chunk.z} (block {chunk.x * 16}, {chunk.z * 16}) {format_size(chunk.size)} {chunk.compression}"
+                                }
+                            }
+                            for problem in dimension.problems {
+                                span {
+                                    class: "error",
+                                    match problem.chunk {
+                                        Some([x, z]) => format!("{} chunk {}, {}: {}", problem.file, x, z, problem.error),
+                                        None => format!("{}: {}", problem.file, problem.error),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Deletes chunks by InhabitedTime or outside of an area around spawn, a dry run shows what would go
+#[component]
+fn world_prune(
+    id: Uuid,
+    name: String,
+    online: bool,
+    on_close: EventHandler<()>,
+    on_change: EventHandler<()>,
+) -> Element {
+    // minutes are easier to pick than ticks, minecraft runs 1200 ticks a minute
+    let mut min_minutes = use_signal(|| 0i64);
+    let mut area = use_signal(|| "none".to_string());
+    let mut radius = use_signal(|| 2000u32);
+    let mut box_size = use_signal(|| [2000u32, 2000u32]);
+    let mut working = use_signal(|| false);
+    let mut message = use_signal(|| None::<(bool, String)>);
+    let mut report = use_signal(|| None::<PruneReport>);
+
+    let mut run = move |dry_run: bool, name: String| {
+        let args = PruneWorldArgs {
+            id,
+            name,
+            options: PruneOptions {
+                min_inhabited_ticks: min_minutes() * 1200,
+                area: area(),
+                radius: radius(),
+                box_size: box_size(),
+            },
+            dry_run,
+        };
+        working.set(true);
+        report.set(None);
+        message.set(Some((
+            true,
+            match dry_run {
+                true => "Reading every chunk...".to_string(),
+                false => "Backing up and pruning...".to_string(),
+            },
+        )));
+        spawn(async move {
+            match invoke_command::<_, PruneReport>("plugin:gaia_core|prune_world", &args).await {
+                Ok(done) => {
+                    message.set(None);
+                    report.set(Some(done));
+                    if !dry_run {
+                        on_change.call(());
+                    }
+                }
+                Err(err) => message.set(Some((false, err))),
+            }
+            working.set(false);
+        });
+    };
+
+    rsx! {
+        div {
+            class: "chunk_stats",
+            div {
+                class: "chunk_stats_title",
+                h3 { "Prune {name}" }
+                button {
+                    onclick: move |_| on_close.call(()),
+                    "Close"
+                }
+            }
+            div {
+                class: "prune_options",
+                label {
+                    "Prune chunks players spent less than"
+                    input {
+                        r#type: "number",
+                        min: "0",
+                        value: "{min_minutes}",
+                        onchange: move |evt| min_minutes.set(evt.value().parse().unwrap_or(0))
+                    }
+                    "minutes in, 0 for any time"
+                }
+                label {
+                    "Keep overworld chunks"
+                    select {
+                        value: "{area}",
+                        onchange: move |evt| area.set(evt.value()),
+                        option { value: "none", "anywhere" }
+                        option { value: "radius", "within a radius of spawn" }
+                        option { value: "box", "within a box around spawn" }
+                    }
+                    if area() == "radius" {
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            value: "{radius}",
+                            onchange: move |evt| radius.set(evt.value().parse().unwrap_or(0))
+                        }
+                        "blocks"
+                    }
+                    if area() == "box" {
+                        for axis in 0..2 {
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                value: "{box_size()[axis]}",
+                                onchange: move |evt| {
+                                    if let Ok(value) = evt.value().parse::<u32>() {
+                                        box_size.write()[axis] = value;
+                                    }
+                                }
+                            }
+                        }
+                        "blocks along x and z each way"
+                    }
+                }
+            }
+            div {
+                class: "worlds_toolbar",
+                button {
+                    disabled: working(),
+                    onclick: {
+                        let name = name.clone();
+                        move |_| run(true, name.clone())
+                    },
+                    "Dry Run"
+                }
+                button {
+                    disabled: working() || online,
+                    title: if online { "Stop the server to prune" } else { "" },
+                    onclick: {
+                        let name = name.clone();
+                        move |_| run(false, name.clone())
+                    },
+                    "Back Up and Prune"
+                }
+                if let Some((ok, text)) = message() {
+                    span {
+                        class: if ok { "worlds_message" } else { "worlds_message error" },
+                        "{text}"
+                    }
+                }
+            }
+            if let Some(report) = report() {
+                span {
+                    class: "world_name",
+                    if report.dry_run {
+                        "Would prune {report.chunks_pruned} chunks and save about {format_size(report.bytes_saved)}"
+                    } else {
+                        "Pruned {report.chunks_pruned} chunks and saved {format_size(report.bytes_saved)}"
+                    }
+                }
+                for dimension in report.dimensions {
+                    div {
+                        key: "{dimension.dimension}",
+                        class: "chunk_dimension",
+                        span { class: "world_name", "{dimension.dimension}" }
+                        span { "{dimension.chunks_pruned} of {dimension.chunks_checked} chunks | {dimension.regions_deleted} region files emptied | {format_size(dimension.bytes_saved)}" }
+                        for skipped in dimension.skipped {
+                            span { class: "error", "Left alone {skipped}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn set_game_rule(world: &mut WorldInfo, name: &str, value: String) {
+    if let Some(rule) = world.game_rules.iter_mut().find(|rule| rule.name == name) {
+        rule.value = value;
+    }
+}
+
+// SCHEDUELER
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ScheduledTaskInfo {
+    task: ScheduledTask,
+    next_run: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveTaskArgs {
+    id: Uuid,
+    task: ScheduledTask,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskIdArgs {
+    id: Uuid,
+    task_id: Uuid,
+}
+
+const TASK_ACTIONS: [(&str, &str); 8] = [
+    ("start", "Start"),
+    ("stop", "Stop"),
+    ("restart", "Restart"),
+    ("countdown_restart", "Restart with countdown"),
+    ("backup", "Back up"),
+    ("command", "Run command"),
+    ("broadcast", "Broadcast"),
+    ("wait", "Wait (seconds)"),
+];
+
+fn describe_schedule(task: &ScheduledTask) -> String {
+    match task.schedule.as_str() {
+        "interval" => format!("every {} minutes", task.interval_seconds / 60),
+        _ => format!("cron {}", task.cron),
+    }
+}
+
+fn describe_steps(task: &ScheduledTask) -> String {
+    task.steps
+        .iter()
+        .map(|step| match step.action.as_str() {
+            "wait" => format!("wait {}s", step.value),
+            "countdown_restart" => "countdown restart".to_string(),
+            "command" => format!("/{}", step.value),
+            "broadcast" => format!("say \"{}\"", step.value),
+            action => action.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(" > ")
+}
+
+// Keeps the server's copy of the tasks in sync with what the backend saved
+fn store_tasks(id: Uuid, infos: &[ScheduledTaskInfo]) {
+    let tasks: Vec<ScheduledTask> = infos.iter().map(|info| info.task.clone()).collect();
+    update_server(id, |server| server.tasks = tasks.clone());
+}
+
+#[component]
+fn task_editor(id: Uuid, task: ScheduledTask, on_close: EventHandler<()>) -> Element {
+    let mut task = use_signal(|| task);
+    let mut error = use_signal(|| None::<String>);
+    let save = move |_| {
+        let args = SaveTaskArgs { id, task: task() };
+        spawn(async move {
+            match invoke_command::<_, Vec<ScheduledTaskInfo>>(
+                "plugin:gaia_core|save_scheduled_task",
+                &args,
+            )
+            .await
+            {
+                Ok(infos) => {
+                    store_tasks(id, &infos);
+                    on_close.call(());
+                }
+                Err(err) => error.set(Some(err)),
+            }
+        });
+    };
+    let current = task();
+    rsx! {
+        div {
+            class: "task_editor",
+            div {
+                class: "task_editor_row",
+                input {
+                    r#type: "text",
+                    placeholder: "Task name",
+                    value: "{current.name}",
+                    oninput: move |evt| task.write().name = evt.value()
+                }
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: current.enabled,
+                        onchange: move |evt| task.write().enabled = evt.checked()
+                    }
+                    "Enabled"
+                }
+            }
+            div {
+                class: "task_editor_row",
+                select {
+                    value: "{current.schedule}",
+                    onchange: move |evt| task.write().schedule = evt.value(),
+                    option { value: "cron", "Cron" }
+                    option { value: "interval", "Interval" }
+                }
+                if current.schedule == "interval" {
+                    label {
+                        "Every"
+                        input {
+                            r#type: "number",
+                            min: "1",
+                            value: "{current.interval_seconds / 60}",
+                            oninput: move |evt| {
+                                task.write().interval_seconds = evt.value().parse::<u64>().unwrap_or(0) * 60
+                            }
+                        }
+                        "minutes"
+                    }
+                } else {
+                    input {
+                        r#type: "text",
+                        class: "task_cron",
+                        placeholder: "minute hour day month weekday",
+                        value: "{current.cron}",
+                        oninput: move |evt| task.write().cron = evt.value()
+                    }
+                    span {
+                        class: "task_hint",
+                        "e.g. 0 4 * * * for 4:00 every day, */30 * * * * or @hourly"
+                    }
+                }
+            }
+            for (index, step) in current.steps.iter().cloned().enumerate() {
+                div {
+                    key: "{index}",
+                    class: "task_editor_row task_step",
+                    span { "{index + 1}." }
+                    select {
+                        value: "{step.action}",
+                        onchange: move |evt| task.write().steps[index].action = evt.value(),
+                        for (value, label) in TASK_ACTIONS {
+                            option { value: value, "{label}" }
+                        }
+                    }
+                    if matches!(step.action.as_str(), "command" | "broadcast" | "wait") {
+                        input {
+                            r#type: "text",
+                            placeholder: match step.action.as_str() {
+                                "command" => "Command, without the /",
+                                "broadcast" => "Message",
+                                _ => "Seconds",
+                            },
+                            value: "{step.value}",
+                            oninput: move |evt| task.write().steps[index].value = evt.value()
+                        }
+                    }
+                    button {
+                        onclick: move |_| {
+                            task.write().steps.remove(index);
+                        },
+                        "Remove"
+                    }
+                }
+            }
+            div {
+                class: "task_editor_row",
+                button {
+                    onclick: move |_| task.write().steps.push(TaskStep {
+                        action: "broadcast".to_string(),
+                        value: "".to_string(),
+                    }),
+                    "Add Step"
+                }
+                button {
+                    onclick: save,
+                    "Save Task"
+                }
+                button {
+                    onclick: move |_| on_close.call(()),
+                    "Cancel"
+                }
+                if let Some(err) = error() {
+                    span { class: "error", "{err}" }
+                }
+            }
+        }
+    }
+}
+
+fn sub_panel_scheduler() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let id = server.id;
+    // the server changes whenever a task is saved or finishes, which reloads the next runs
+    let tasks = use_resource(use_reactive!(|(server,)| async move {
+        invoke_command::<_, Vec<ScheduledTaskInfo>>(
+            "plugin:gaia_core|list_scheduled_tasks",
+            &IdArgs { id: server.id },
+        )
+        .await
+    }));
+    let mut editing = use_signal(|| None::<ScheduledTask>);
+    let mut running = use_signal(Vec::<Uuid>::new);
+    let mut confirming = use_signal(|| None::<Uuid>);
+    let mut message = use_signal(|| None::<(bool, String)>);
+
+    let mut run_now = move |task_id: Uuid| {
+        running.write().push(task_id);
+        spawn(async move {
+            match invoke_command::<_, TaskRun>(
+                "plugin:gaia_core|run_scheduled_task",
+                &TaskIdArgs { id, task_id },
+            )
+            .await
+            {
+                Ok(run) => message.set(Some((run.success, run.message))),
+                Err(err) => message.set(Some((false, err))),
+            }
+            running.write().retain(|running| *running != task_id);
+        });
+    };
+    let mut delete = move |task_id: Uuid| {
+        confirming.set(None);
+        spawn(async move {
+            match invoke_command::<_, Vec<ScheduledTaskInfo>>(
+                "plugin:gaia_core|delete_scheduled_task",
+                &TaskIdArgs { id, task_id },
+            )
+            .await
+            {
+                Ok(infos) => store_tasks(id, &infos),
+                Err(err) => message.set(Some((false, err))),
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            id: "sub_panel_scheduler",
+            div {
+                class: "scheduler_toolbar",
+                button {
+                    disabled: editing().is_some(),
+                    onclick: move |_| editing.set(Some(ScheduledTask {
+                        name: "Daily restart".to_string(),
+                        steps: vec![
+                            TaskStep { action: "broadcast".to_string(), value: "Restarting in 60 seconds".to_string() },
+                            TaskStep { action: "wait".to_string(), value: "60".to_string() },
+                            TaskStep { action: "restart".to_string(), value: "".to_string() },
+                        ],
+                        ..Default::default()
+                    })),
+                    "New Task"
+                }
+                if let Some((ok, text)) = message() {
+                    span {
+                        class: if ok { "scheduler_message" } else { "scheduler_message error" },
+                        "{text}"
+                    }
+                }
+            }
+            if let Some(task) = editing() {
+                task_editor {
+                    key: "{task.id}",
+                    id: id,
+                    task: task,
+                    on_close: move |_| editing.set(None),
+                }
+            }
+            div {
+                class: "task_list",
+                match &*tasks.read() {
+                    Some(Ok(infos)) if infos.is_empty() => rsx! {
+                        span { class: "task_empty", "No scheduled tasks" }
+                    },
+                    Some(Ok(infos)) => rsx! {
+                        for info in infos.clone() {
+                            div {
+                                key: "{info.task.id}",
+                                class: if info.task.enabled { "task" } else { "task disabled" },
+                                div {
+                                    class: "task_info",
+                                    span { class: "task_name", "{info.task.name}" }
+                                    span { {format!("{} | {}", describe_schedule(&info.task), describe_steps(&info.task))} }
+                                    span {
+                                        match &info.next_run {
+                                            Some(next_run) => format!("Next run {}", next_run),
+                                            None if info.task.enabled => "Never runs".to_string(),
+                                            None => "Disabled".to_string(),
+                                        }
+                                    }
+                                    if let Some(run) = &info.task.last_run {
+                                        span {
+                                            class: if run.success { "task_run" } else { "task_run error" },
+                                            "Last run {run.time}: {run.message}"
+                                        }
+                                    }
+                                }
+                                div {
+                                    class: "task_buttons",
+                                    if confirming() == Some(info.task.id) {
+                                        button {
+                                            onclick: move |_| delete(info.task.id),
+                                            "Confirm Delete"
+                                        }
+                                        button {
+                                            onclick: move |_| confirming.set(None),
+                                            "Cancel"
+                                        }
+                                    } else {
+                                        button {
+                                            disabled: running().contains(&info.task.id),
+                                            onclick: move |_| run_now(info.task.id),
+                                            if running().contains(&info.task.id) { "Running..." } else { "Run Now" }
+                                        }
+                                        button {
+                                            disabled: editing().is_some(),
+                                            onclick: {
+                                                let task = info.task.clone();
+                                                move |_| editing.set(Some(task.clone()))
+                                            },
+                                            "Edit"
+                                        }
+                                        button {
+                                            onclick: move |_| confirming.set(Some(info.task.id)),
+                                            "Delete"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    Some(Err(err)) => rsx! {
+                        span { class: "error", "{err}" }
+                    },
+                    None => rsx! {
+                        span { "Loading tasks..." }
+                    },
+                }
+            }
+        }
+    }
+}
+
+// MAPS
+fn sub_panel_maps() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    rsx! {
+        div {
+            id: "sub_panel_maps",
+            {format!("maps display for server {:?}", server)}
+        }
+    }
+}
+
+// LOGS
+const LOG_PAGE_SIZE: usize = 1000;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct LogFile {
+    name: String,
+    date: String,
+    index: u32,
+    size: u64,
+    compressed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct LogPage {
+    name: String,
+    page: usize,
+    page_size: usize,
+    lines: Vec<String>,
+    has_more: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct LogSearchHit {
+    name: String,
+    date: String,
+    line_number: usize,
+    line: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadServerLogArgs {
+    id: Uuid,
+    name: String,
+    page: usize,
+    page_size: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchServerLogsArgs {
+    id: Uuid,
+    from: Option<String>,
+    to: Option<String>,
+    query: String,
+    use_regex: bool,
+}
+
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} {}", bytes, units[0]),
+        _ => format!("{:.1} {}", size, units[unit]),
+    }
+}
+
+fn sub_panel_logs() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let id = server.id;
+    let files = use_resource(use_reactive!(|(id,)| async move {
+        invoke_command::<_, Vec<LogFile>>("plugin:gaia_core|list_server_logs", &IdArgs { id }).await
+    }));
+    let mut page = use_signal(|| None::<LogPage>);
+    let mut search_from = use_signal(|| "".to_string());
+    let mut search_to = use_signal(|| "".to_string());
+    let mut search_query = use_signal(|| "".to_string());
+    let mut search_regex = use_signal(|| false);
+    let mut search_hits = use_signal(|| None::<Vec<LogSearchHit>>);
+    let mut error = use_signal(|| None::<String>);
+
+    let open_page = move |name: String, number: usize| {
+        spawn(async move {
+            let args = ReadServerLogArgs {
+                id,
+                name,
+                page: number,
+                page_size: LOG_PAGE_SIZE,
+            };
+            match invoke_command::<_, LogPage>("plugin:gaia_core|read_server_log", &args).await {
+                Ok(value) => {
+                    search_hits.set(None);
+                    error.set(None);
+                    page.set(Some(value));
+                }
+                Err(err) => error.set(Some(err)),
+            }
+        });
+    };
+
+    let run_search = move |_| {
+        spawn(async move {
+            let optional = |value: String| match value.is_empty() {
+                true => None,
+                false => Some(value),
+            };
+            let args = SearchServerLogsArgs {
+                id,
+                from: optional(search_from()),
+                to: optional(search_to()),
+                query: search_query(),
+                use_regex: search_regex(),
+            };
+            match invoke_command::<_, Vec<LogSearchHit>>(
+                "plugin:gaia_core|search_server_logs",
+                &args,
+            )
+            .await
+            {
+                Ok(value) => {
+                    error.set(None);
+                    search_hits.set(Some(value));
+                }
+                Err(err) => error.set(Some(err)),
+            }
+        });
+    };
+
+    let (title, lines) = match (&*search_hits.read(), &*page.read()) {
+        (Some(hits), _) => {
+            let file_count = hits
+                .iter()
+                .map(|hit| hit.name.as_str())
+                .collect::<std::collections::HashSet<_>>()
+                .len();
+            (
+                format!("{} matches in {} files", hits.len(), file_count),
+                hits.iter()
+                    .map(|hit| hit.line.clone())
+                    .collect::<Vec<String>>(),
+            )
+        }
+        (None, Some(page)) => (
+            format!(
+                "{} | lines {}-{}",
+                page.name,
+                page.page * page.page_size + 1,
+                page.page * page.page_size + page.lines.len()
+            ),
+            page.lines.clone(),
+        ),
+        (None, None) => ("Select a log file".to_string(), Vec::new()),
+    };
+    let current_page = page.read().clone();
+
+    rsx! {
+        div {
+            id: "sub_panel_logs",
+            div {
+                class: "log_files",
+                match &*files.read() {
+                    Some(Ok(files)) => rsx! {
+                        for file in files.clone() {
+                            div {
+                                key: "{file.name}",
+                                class: if current_page.as_ref().is_some_and(|page| page.name == file.name) {
+                                    "log_file selected"
+                                } else {
+                                    "log_file"
+                                },
+                                onclick: {
+                                    let name = file.name.clone();
+                                    move |_| open_page(name.clone(), 0)
+                                },
+                                span {
+                                    class: "log_file_name",
+                                    "{file.name}"
+                                }
+                                span {
+                                    class: "log_file_size",
+                                    {format_size(file.size)}
+                                }
+                            }
+                        }
+                    },
+                    Some(Err(err)) => rsx! {
+                        span { class: "error", "{err}" }
+                    },
+                    None => rsx! {
+                        span { "Loading logs..." }
+                    },
+                }
+            }
+            div {
+                class: "log_viewer",
+                div {
+                    class: "log_search",
+                    input {
+                        type: "date",
+                        value: "{search_from}",
+                        oninput: move |evt| search_from.set(evt.value())
+                    }
+                    input {
+                        type: "date",
+                        value: "{search_to}",
+                        oninput: move |evt| search_to.set(evt.value())
+                    }
+                    input {
+                        type: "text",
+                        placeholder: "Search all logs in range",
+                        value: "{search_query}",
+                        oninput: move |evt| search_query.set(evt.value())
+                    }
+                    div {
+                        label {
+                            for: "log_search_regex",
+                            "Regex"
+                        }
+                        input {
+                            id: "log_search_regex",
+                            type: "checkbox",
+                            checked: search_regex(),
+                            onchange: move |evt| search_regex.set(evt.checked())
+                        }
+                    }
+                    button {
+                        disabled: search_query.read().is_empty(),
+                        onclick: run_search,
+                        "Search"
+                    }
+                }
+                div {
+                    class: "log_header",
+                    span {
+                        "{title}"
+                    }
+                    if let Some(err) = error() {
+                        span {
+                            class: "error",
+                            "{err}"
+                        }
+                    }
+                    if let (None, Some(current)) = (search_hits.read().as_ref(), current_page.clone()) {
+                        button {
+                            disabled: current.page == 0,
+                            onclick: {
+                                let current = current.clone();
+                                move |_| open_page(current.name.clone(), current.page.saturating_sub(1))
+                            },
+                            "Previous Page"
+                        }
+                        button {
+                            disabled: !current.has_more,
+                            onclick: {
+                                let current = current.clone();
+                                move |_| open_page(current.name.clone(), current.page + 1)
+                            },
+                            "Next Page"
+                        }
+                    }
+                }
+                console::console_view {
+                    id: "log_history_console".to_string(),
+                    lines,
+                    auto_scroll: false,
+                }
+            }
+        }
+    }
+}
+
+// NETWORK
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+struct ProxyServer {
+    name: String,
+    address: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+struct ProxyConfig {
+    bind_port: u16,
+    servers: Vec<ProxyServer>,
+    try_servers: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ProxyConfigArgs {
+    id: Uuid,
+    config: ProxyConfig,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProxyLinkArgs {
+    proxy_id: Uuid,
+    backend_id: Uuid,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackendArgs {
+    backend_id: Uuid,
+}
+
+fn load_proxy_config(
+    id: Uuid,
+    mut config: Signal<Option<ProxyConfig>>,
+    mut message: Signal<Option<(bool, String)>>,
+) {
+    spawn(async move {
+        match invoke_command::<_, ProxyConfig>("plugin:gaia_core|get_proxy_config", &IdArgs { id })
+            .await
+        {
+            Ok(loaded) => config.set(Some(loaded)),
+            Err(err) => message.set(Some((false, err))),
+        }
+    });
+}
+
+// Links or unlinks a backend, then shows the proxy's config as it is on disk again
+fn change_proxy_link<A: Serialize + 'static>(
+    cmd: &'static str,
+    args: A,
+    proxy_id: Uuid,
+    config: Signal<Option<ProxyConfig>>,
+    mut message: Signal<Option<(bool, String)>>,
+) {
+    spawn(async move {
+        match invoke_command::<_, ServerStruct>(cmd, &args).await {
+            Ok(backend) => {
+                update_server(backend.id, |server| {
+                    server.proxy_id = backend.proxy_id;
+                    server.proxy_name = backend.proxy_name.clone();
+                });
+                load_proxy_config(proxy_id, config, message);
+            }
+            Err(err) => message.set(Some((false, err))),
+        }
+    });
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct ForwardingIssue {
+    id: Uuid,
+    name: String,
+    problem: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct ForwardingReport {
+    mode: String,
+    issues: Vec<ForwardingIssue>,
+}
+
+// Velocity's modern forwarding, checked again whenever a backend is linked or unlinked
+#[component]
+fn proxy_forwarding(id: Uuid, linked: String) -> Element {
+    let mut report = use_signal(|| None::<ForwardingReport>);
+    let mut error = use_signal(|| None::<String>);
+    let run = move |cmd: &'static str| {
+        spawn(async move {
+            match invoke_command::<_, ForwardingReport>(cmd, &IdArgs { id }).await {
+                Ok(result) => {
+                    error.set(None);
+                    report.set(Some(result));
+                }
+                Err(err) => error.set(Some(err)),
+            }
+        });
+    };
+    use_effect(use_reactive!(|(id, linked)| {
+        let _ = (id, linked);
+        run("plugin:gaia_core|check_proxy_forwarding");
+    }));
+
+    rsx! {
+        div {
+            class: "network_section",
+            h3 { "Forwarding" }
+            if let Some(report) = report() {
+                if report.issues.is_empty() && report.mode == "modern" {
+                    span { class: "proxy_linked", "Modern forwarding is set up on every linked server" }
+                } else if report.issues.is_empty() {
+                    span { class: "network_hint", "Forwarding mode is \"{report.mode}\"" }
+                }
+                for issue in report.issues {
+                    div {
+                        key: "{issue.id}{issue.problem}",
+                        class: "proxy_server",
+                        span { class: "proxy_server_name", "{issue.name}" }
+                        span { class: "error", "{issue.problem}" }
+                    }
+                }
+            }
+            div {
+                class: "proxy_server",
+                button {
+                    onclick: move |_| run("plugin:gaia_core|setup_proxy_forwarding"),
+                    "Set up modern forwarding"
+                }
+                span { class: "network_hint", "Restart the proxy and its servers to apply it" }
+            }
+            if let Some(err) = error() {
+                span { class: "error", "{err}" }
+            }
+        }
+    }
+}
+
+// The servers behind a proxy and the order players are sent to them in
+fn proxy_servers() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let servers = APP_STATE.read().servers.clone();
+    let id = server.id;
+    let mut config = use_signal(|| None::<ProxyConfig>);
+    let mut message = use_signal(|| None::<(bool, String)>);
+    let mut new_name = use_signal(String::new);
+    let mut new_address = use_signal(String::new);
+    let mut link_id = use_signal(String::new);
+    use_effect(use_reactive!(|id| load_proxy_config(id, config, message)));
+
+    let Some(current) = config() else {
+        return rsx! {
+            if let Some((_, text)) = message() {
+                span { class: "error", "{text}" }
+            } else {
+                span { "Loading proxy config..." }
+            }
+        };
+    };
+    let mut edit = move |change: Box<dyn FnOnce(&mut ProxyConfig)>| {
+        if let Some(config) = config.write().as_mut() {
+            change(config);
+        }
+    };
+    let save = move |_| {
+        let Some(current) = config() else {
+            return;
+        };
+        spawn(async move {
+            let bind_port = current.bind_port;
+            match invoke_command::<_, ()>(
+                "plugin:gaia_core|save_proxy_config",
+                &ProxyConfigArgs {
+                    id,
+                    config: current,
+                },
+            )
+            .await
+            {
+                Ok(_) => {
+                    update_server(id, |server| server.port = bind_port);
+                    message.set(Some((
+                        true,
+                        "Saved, restart the proxy to apply it".to_string(),
+                    )));
+                }
+                Err(err) => message.set(Some((false, err))),
+            }
+        });
+    };
+    // servers that can still be linked, proxies can't sit behind each other
+    let linkable: Vec<ServerStruct> = servers
+        .iter()
+        .filter(|other| !is_proxy(&other.server_type) && other.proxy_id != Some(id))
+        .cloned()
+        .collect();
+    let try_count = current.try_servers.len();
+    let linked: String = servers
+        .iter()
+        .filter(|other| other.proxy_id == Some(id))
+        .map(|other| format!("{}:{};", other.id, other.proxy_name))
+        .collect();
+
+    rsx! {
+        div {
+            class: "network_toolbar",
+            label {
+                "Bind port"
+                input {
+                    r#type: "number",
+                    min: "1",
+                    max: "65535",
+                    value: "{current.bind_port}",
+                    onchange: move |evt| {
+                        if let Ok(port) = evt.value().parse::<u16>() {
+                            edit(Box::new(move |config| config.bind_port = port));
+                        }
+                    }
+                }
+            }
+            button {
+                onclick: save,
+                "Save"
+            }
+            if let Some((ok, text)) = message() {
+                span {
+                    class: if ok { "network_message" } else { "network_message error" },
+                    "{text}"
+                }
+            }
+        }
+        div {
+            class: "network_section",
+            h3 { "Servers" }
+            for entry in current.servers.clone() {
+                div {
+                    key: "{entry.name}",
+                    class: "proxy_server",
+                    span { class: "proxy_server_name", "{entry.name}" }
+                    match servers.iter().find(|other| other.proxy_id == Some(id) && other.proxy_name == entry.name) {
+                        Some(backend) => rsx! {
+                            span { "{entry.address}" }
+                            span { class: "proxy_linked", "Linked to {backend.name}" }
+                            button {
+                                onclick: {
+                                    let backend_id = backend.id;
+                                    move |_| change_proxy_link("plugin:gaia_core|unlink_proxy_backend", BackendArgs { backend_id }, id, config, message)
+                                },
+                                "Unlink"
+                            }
+                        },
+                        None => rsx! {
+                            input {
+                                value: "{entry.address}",
+                                onchange: {
+                                    let name = entry.name.clone();
+                                    move |evt: Event<FormData>| {
+                                        let (name, address) = (name.clone(), evt.value());
+                                        edit(Box::new(move |config| {
+                                            if let Some(server) = config.servers.iter_mut().find(|server| server.name == name) {
+                                                server.address = address;
+                                            }
+                                        }));
+                                    }
+                                }
+                            }
+                            button {
+                                onclick: {
+                                    let name = entry.name.clone();
+                                    move |_| {
+                                        let name = name.clone();
+                                        edit(Box::new(move |config| {
+                                            config.servers.retain(|server| server.name != name);
+                                            config.try_servers.retain(|server| *server != name);
+                                        }));
+                                    }
+                                },
+                                "Remove"
+                            }
+                        },
+                    }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: current.try_servers.contains(&entry.name),
+                            onchange: {
+                                let name = entry.name.clone();
+                                move |evt: Event<FormData>| {
+                                    let (name, checked) = (name.clone(), evt.checked());
+                                    edit(Box::new(move |config| {
+                                        config.try_servers.retain(|server| *server != name);
+                                        if checked {
+                                            config.try_servers.push(name);
+                                        }
+                                    }));
+                                }
+                            }
+                        }
+                        "Join on connect"
+                    }
+                }
+            }
+            div {
+                class: "proxy_server",
+                input {
+                    placeholder: "name",
+                    value: "{new_name}",
+                    oninput: move |evt| new_name.set(evt.value())
+                }
+                input {
+                    placeholder: "host:port",
+                    value: "{new_address}",
+                    oninput: move |evt| new_address.set(evt.value())
+                }
+                button {
+                    disabled: new_name().trim().is_empty() || new_address().trim().is_empty(),
+                    onclick: move |_| {
+                        let server = ProxyServer {
+                            name: new_name().trim().to_string(),
+                            address: new_address().trim().to_string(),
+                        };
+                        edit(Box::new(move |config| {
+                            config.servers.retain(|other| other.name != server.name);
+                            config.servers.push(server);
+                        }));
+                        new_name.set(String::new());
+                        new_address.set(String::new());
+                    },
+                    "Add Server"
+                }
+            }
+            div {
+                class: "proxy_server",
+                select {
+                    value: "{link_id}",
+                    onchange: move |evt| link_id.set(evt.value()),
+                    option { value: "", "Link a registered server" }
+                    for other in linkable {
+                        option { key: "{other.id}", value: "{other.id}", "{other.name}" }
+                    }
+                }
+                button {
+                    disabled: Uuid::parse_str(&link_id()).is_err(),
+                    onclick: move |_| {
+                        if let Ok(backend_id) = Uuid::parse_str(&link_id()) {
+                            link_id.set(String::new());
+                            change_proxy_link("plugin:gaia_core|link_proxy_backend", ProxyLinkArgs { proxy_id: id, backend_id }, id, config, message);
+                        }
+                    },
+                    "Link"
+                }
+            }
+        }
+        div {
+            class: "network_section",
+            h3 { "Join order" }
+            if try_count == 0 {
+                span { class: "network_hint", "Players can't join until a server is picked to join on connect" }
+            }
+            for (index, name) in current.try_servers.clone().into_iter().enumerate() {
+                div {
+                    key: "{name}",
+                    class: "proxy_server",
+                    span { "{index + 1}. {name}" }
+                    button {
+                        disabled: index == 0,
+                        onclick: move |_| edit(Box::new(move |config| config.try_servers.swap(index - 1, index))),
+                        "Up"
+                    }
+                    button {
+                        disabled: index + 1 == try_count,
+                        onclick: move |_| edit(Box::new(move |config| config.try_servers.swap(index, index + 1))),
+                        "Down"
+                    }
+                }
+            }
+        }
+        if server.server_type == "velocity" {
+            proxy_forwarding { id, linked }
+        }
+    }
+}
+
+// A backend server picks which proxy it sits behind
+fn proxy_link() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let proxies: Vec<ServerStruct> = APP_STATE
+        .read()
+        .servers
+        .iter()
+        .filter(|other| is_proxy(&other.server_type))
+        .cloned()
+        .collect();
+    let linked = server
+        .proxy_id
+        .and_then(|proxy_id| proxies.iter().find(|proxy| proxy.id == proxy_id).cloned());
+    let mut message = use_signal(|| None::<String>);
+    let mut proxy_id = use_signal(String::new);
+    let backend_id = server.id;
+    let change = move |cmd: &'static str, proxy: Option<Uuid>| {
+        spawn(async move {
+            let result = match proxy {
+                Some(proxy_id) => {
+                    invoke_command::<_, ServerStruct>(
+                        cmd,
+                        &ProxyLinkArgs {
+                            proxy_id,
+                            backend_id,
+                        },
+                    )
+                    .await
+                }
+                None => invoke_command::<_, ServerStruct>(cmd, &BackendArgs { backend_id }).await,
+            };
+            match result {
+                Ok(backend) => {
+                    message.set(None);
+                    update_server(backend_id, |server| {
+                        server.proxy_id = backend.proxy_id;
+                        server.proxy_name = backend.proxy_name.clone();
+                    });
+                }
+                Err(err) => message.set(Some(err)),
+            }
+        });
+    };
+    rsx! {
+        div {
+            class: "network_section",
+            h3 { "Proxy" }
+            match linked {
+                Some(proxy) => rsx! {
+                    div {
+                        class: "proxy_server",
+                        span { "Behind {proxy.name} as \"{server.proxy_name}\"" }
+                        button {
+                            onclick: move |_| change("plugin:gaia_core|unlink_proxy_backend", None),
+                            "Unlink"
+                        }
+                    }
+                },
+                None if proxies.is_empty() => rsx! {
+                    span { class: "network_hint", "Create a Velocity, BungeeCord or Waterfall server to put this server behind a proxy" }
+                },
+                None => rsx! {
+                    div {
+                        class: "proxy_server",
+                        select {
+                            value: "{proxy_id}",
+                            onchange: move |evt| proxy_id.set(evt.value()),
+                            option { value: "", "Pick a proxy" }
+                            for proxy in proxies {
+                                option { key: "{proxy.id}", value: "{proxy.id}", "{proxy.name}" }
+                            }
+                        }
+                        button {
+                            disabled: Uuid::parse_str(&proxy_id()).is_err(),
+                            onclick: move |_| {
+                                if let Ok(proxy) = Uuid::parse_str(&proxy_id()) {
+                                    change("plugin:gaia_core|link_proxy_backend", Some(proxy));
+                                }
+                            },
+                            "Link"
+                        }
+                    }
+                },
+            }
+            if let Some(err) = message() {
+                span { class: "error", "{err}" }
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GroupArgs {
+    group: ServerGroup,
+}
+
+// Joins, creates or leaves the group the server is started and stopped with
+fn server_group_picker() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    let groups = APP_STATE.read().groups.clone();
+    let current = groups
+        .iter()
+        .find(|group| group.members.contains(&server.id))
+        .cloned();
+    let mut group_id = use_signal(String::new);
+    let mut new_name = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+    let id = server.id;
+    let save = move |group: ServerGroup| {
+        spawn(async move {
+            // a group without members has nothing left to start, it goes away
+            let result = match group.members.is_empty() {
+                true => {
+                    invoke_command::<_, Vec<ServerGroup>>(
+                        "plugin:gaia_core|delete_server_group",
+                        &IdArgs { id: group.id },
+                    )
+                    .await
+                }
+                false => {
+                    invoke_command::<_, Vec<ServerGroup>>(
+                        "plugin:gaia_core|save_server_group",
+                        &GroupArgs { group },
+                    )
+                    .await
+                }
+            };
+            match result {
+                Ok(groups) => {
+                    error.set(None);
+                    APP_STATE.write().groups = groups;
+                }
+                Err(err) => error.set(Some(err)),
+            }
+        });
+    };
+    // a proxy brings the servers behind it along into a new group
+    let mut members = vec![id];
+    if is_proxy(&server.server_type) {
+        members.extend(
+            APP_STATE
+                .read()
+                .servers
+                .iter()
+                .filter(|other| other.proxy_id == Some(id))
+                .map(|other| other.id),
+        );
+    }
+    let others = groups.clone();
+
+    rsx! {
+        div {
+            class: "network_section",
+            h3 { "Group" }
+            match current {
+                Some(group) => rsx! {
+                    div {
+                        class: "proxy_server",
+                        span { class: "proxy_server_name", "{group.name}" }
+                        span {
+                            match group.members.len() - 1 {
+                                0 => "No other servers in it yet".to_string(),
+                                1 => "Started and stopped with 1 other server".to_string(),
+                                others => format!("Started and stopped with {} other servers", others),
+                            }
+                        }
+                        button {
+                            onclick: move |_| {
+                                let mut group = group.clone();
+                                group.members.retain(|member| *member != id);
+                                save(group);
+                            },
+                            "Leave"
+                        }
+                    }
+                },
+                None => rsx! {
+                    if !others.is_empty() {
+                        div {
+                            class: "proxy_server",
+                            select {
+                                value: "{group_id}",
+                                onchange: move |evt| group_id.set(evt.value()),
+                                option { value: "", "Pick a group" }
+                                for group in others {
+                                    option { key: "{group.id}", value: "{group.id}", "{group.name}" }
+                                }
+                            }
+                            button {
+                                disabled: Uuid::parse_str(&group_id()).is_err(),
+                                onclick: move |_| {
+                                    let picked = Uuid::parse_str(&group_id()).ok();
+                                    if let Some(mut group) = groups.iter().find(|group| Some(group.id) == picked).cloned() {
+                                        group.members.push(id);
+                                        group_id.set(String::new());
+                                        save(group);
+                                    }
+                                },
+                                "Join"
+                            }
+                        }
+                    }
+                    div {
+                        class: "proxy_server",
+                        input {
+                            placeholder: "new group name",
+                            value: "{new_name}",
+                            oninput: move |evt| new_name.set(evt.value())
+                        }
+                        button {
+                            disabled: new_name().trim().is_empty(),
+                            onclick: move |_| {
+                                save(ServerGroup {
+                                    id: Uuid::nil(),
+                                    name: new_name().trim().to_string(),
+                                    members: members.clone(),
+                                });
+                                new_name.set(String::new());
+                            },
+                            "Create Group"
+                        }
+                        if members.len() > 1 {
+                            span { class: "network_hint", "The servers behind this proxy join the group too" }
+                        }
+                    }
+                },
+            }
+            if let Some(err) = error() {
+                span { class: "error", "{err}" }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct GeyserSetup {
+    platform: String,
+    geyser: bool,
+    floodgate: bool,
+    bedrock_port: u16,
+    remote_address: String,
+    remote_port: u16,
+    auth_type: String,
+    username_prefix: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallGeyserArgs {
+    id: Uuid,
+    geyser_path: String,
+    floodgate_path: Option<String>,
+    bedrock_port: u16,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigureGeyserArgs {
+    id: Uuid,
+    bedrock_port: u16,
+}
+
+// Geyser lets bedrock players join a java server, floodgate lets them in without a java account
+fn geyser_setup() -> Element {
+    let id = APP_STATE.read().selected_server.as_ref().unwrap().id;
+    let mut setup = use_signal(|| None::<GeyserSetup>);
+    let mut message = use_signal(|| None::<(bool, String)>);
+    let mut geyser_path = use_signal(String::new);
+    let mut floodgate_path = use_signal(String::new);
+    let mut port = use_signal(|| 19132u16);
+    use_effect(use_reactive!(|id| {
+        spawn(async move {
+            match invoke_command::<_, GeyserSetup>(
+                "plugin:gaia_core|get_geyser_setup",
+                &IdArgs { id },
+            )
+            .await
+            {
+                Ok(loaded) => {
+                    port.set(loaded.bedrock_port);
+                    setup.set(Some(loaded));
+                }
+                Err(err) => message.set(Some((false, err))),
+            }
+        });
+    }));
+    let mut apply = move |result: Result<GeyserSetup, String>| match result {
+        Ok(loaded) => {
+            setup.set(Some(loaded));
+            message.set(Some((
+                true,
+                "Saved, restart the server to apply it".to_string(),
+            )));
+        }
+        Err(err) => message.set(Some((false, err))),
+    };
+    let install = move |_| {
+        let args = InstallGeyserArgs {
+            id,
+            geyser_path: geyser_path(),
+            floodgate_path: Some(floodgate_path()).filter(|path| !path.trim().is_empty()),
+            bedrock_port: port(),
+        };
+        spawn(async move {
+            apply(invoke_command::<_, GeyserSetup>("plugin:gaia_core|install_geyser", &args).await)
+        });
+    };
+    let save_port = move |_| {
+        let args = ConfigureGeyserArgs {
+            id,
+            bedrock_port: port(),
+        };
+        spawn(async move {
+            apply(
+                invoke_command::<_, GeyserSetup>("plugin:gaia_core|configure_geyser", &args).await,
+            )
+        });
+    };
+
+    let Some(current) = setup() else {
+        return rsx! {};
+    };
+    rsx! {
+        div {
+            class: "network_section",
+            h3 { "Bedrock Players" }
+            if current.platform.is_empty() {
+                span { class: "network_hint", "Geyser has no build for this server type" }
+            } else {
+                div {
+                    class: "proxy_server",
+                    span { class: "proxy_server_name", "Geyser" }
+                    if current.geyser {
+                        span { class: "proxy_linked", "Installed" }
+                        span { "forwards to {current.remote_address}:{current.remote_port}, auth {current.auth_type}" }
+                    } else {
+                        span { "Not installed" }
+                    }
+                }
+                div {
+                    class: "proxy_server",
+                    span { class: "proxy_server_name", "Floodgate" }
+                    if current.floodgate {
+                        span { class: "proxy_linked", "Installed" }
+                        span { "bedrock names start with \"{current.username_prefix}\"" }
+                    } else {
+                        span { "Not installed, bedrock players need a java account" }
+                    }
+                }
+                div {
+                    class: "proxy_server",
+                    label {
+                        "Bedrock port"
+                        input {
+                            r#type: "number",
+                            min: "1",
+                            max: "65535",
+                            value: "{port}",
+                            onchange: move |evt| {
+                                if let Ok(value) = evt.value().parse::<u16>() {
+                                    port.set(value);
+                                }
+                            }
+                        }
+                    }
+                    if current.geyser {
+                        button {
+                            onclick: save_port,
+                            "Save"
+                        }
+                    }
+                }
+                div {
+                    class: "proxy_server",
+                    input {
+                        placeholder: "Geyser-{current.platform}.jar path",
+                        value: "{geyser_path}",
+                        oninput: move |evt| geyser_path.set(evt.value())
+                    }
+                    input {
+                        placeholder: "Floodgate jar path (optional)",
+                        value: "{floodgate_path}",
+                        oninput: move |evt| floodgate_path.set(evt.value())
+                    }
+                    button {
+                        disabled: geyser_path().trim().is_empty(),
+                        onclick: install,
+                        if current.geyser { "Update" } else { "Install" }
+                    }
+                }
+                span { class: "network_hint", "Use the {current.platform} builds from geysermc.org" }
+            }
+            if let Some((ok, text)) = message() {
+                span {
+                    class: if ok { "network_message" } else { "network_message error" },
+                    "{text}"
+                }
+            }
+        }
+    }
+}
+
+fn sub_panel_network() -> Element {
+    let server = APP_STATE.read().selected_server.to_owned().unwrap();
+    rsx! {
+        div {
+            id: "sub_panel_network",
+            if is_proxy(&server.server_type) {
+                proxy_servers {}
+            } else {
+                proxy_link {}
+            }
+            if !is_bedrock(&server.server_type) {
+                geyser_setup {}
+            }
+            server_group_picker {}
         }
     }
 }
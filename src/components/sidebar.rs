@@ -1,4 +1,4 @@
-use std::format;
+use std::{collections::HashSet, format};
 
 use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -13,8 +13,8 @@ extern "C" {
 
 use crate::{
     app::{
-        APP_STATE, ServerStruct, load_servers, set_selected_panel, set_selected_server,
-        set_selected_sub_panel,
+        APP_STATE, ServerGroup, ServerStruct, generate_random_server, invoke_command, is_proxy,
+        set_selected_panel, set_selected_server, set_selected_sub_panel,
     },
     components::svgs::{folder, gear, plus},
 };
@@ -65,16 +65,136 @@ struct ServerListItemProps {
     selected: bool,
 }
 
+#[derive(Serialize)]
+struct GroupArgs {
+    id: Uuid,
+}
+
+// Header of a group in the server list, members are listed under it unless it's collapsed
+#[component]
+fn server_group_header(
+    group: ServerGroup,
+    members: Vec<ServerStruct>,
+    mut collapsed: Signal<HashSet<Uuid>>,
+) -> Element {
+    let id = group.id;
+    let online = members.iter().filter(|server| server.status == 1).count();
+    let status = match online {
+        0 => "offline",
+        online if online == members.len() => "online",
+        _ => "partial",
+    };
+    // players sit on the backends, a proxy's slots would count them twice
+    let players: i32 = members
+        .iter()
+        .map(|server| server.current_player_count)
+        .sum();
+    let max_players: i32 = members
+        .iter()
+        .filter(|server| !is_proxy(&server.server_type))
+        .map(|server| server.max_players)
+        .sum();
+    let mut error = use_signal(|| None::<String>);
+    let run = move |cmd: &'static str| {
+        spawn(async move {
+            let result = invoke_command::<_, ()>(cmd, &GroupArgs { id }).await;
+            error.set(result.err());
+        });
+    };
+    let is_collapsed = collapsed.read().contains(&id);
+    rsx! {
+        div {
+            class: "server_group_header",
+            title: error().unwrap_or_default(),
+            onclick: move |_| {
+                let collapsed = &mut collapsed.write();
+                if !collapsed.remove(&id) {
+                    collapsed.insert(id);
+                }
+            },
+            div {
+                class: "server_info",
+                span {
+                    class: "server_name",
+                    if is_collapsed { "▸ " } else { "▾ " }
+                    "{group.name}"
+                }
+                div {
+                    class: "server_extended_info",
+                    span {
+                        class: status,
+                        "{online}/{members.len()} online"
+                    }
+                    span {
+                        "{players}/{max_players} Players"
+                    }
+                }
+            }
+            div {
+                class: "server_group_buttons",
+                button {
+                    disabled: online == members.len(),
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        run("plugin:gaia_core|start_server_group");
+                    },
+                    "Start"
+                }
+                button {
+                    disabled: online == 0,
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        run("plugin:gaia_core|stop_server_group");
+                    },
+                    "Stop"
+                }
+            }
+        }
+    }
+}
+
 #[component]
 pub fn server_list() -> Element {
     let servers_lock = APP_STATE.read().servers.to_owned();
-    let servers_rendered = servers_lock
+    let groups = APP_STATE.read().groups.to_owned();
+    let collapsed = use_signal(HashSet::<Uuid>::new);
+    let grouped: Vec<(ServerGroup, Vec<ServerStruct>)> = groups
+        .into_iter()
+        .map(|group| {
+            let members: Vec<ServerStruct> = servers_lock
+                .iter()
+                .filter(|server| group.members.contains(&server.id))
+                .cloned()
+                .collect();
+            (group, members)
+        })
+        .filter(|(_, members)| !members.is_empty())
+        .collect();
+    let ungrouped: Vec<ServerStruct> = servers_lock
         .iter()
-        .map(|server| server_list_item(server.clone()));
+        .filter(|server| {
+            !grouped
+                .iter()
+                .any(|(group, _)| group.members.contains(&server.id))
+        })
+        .cloned()
+        .collect();
+    let servers_rendered = ungrouped.into_iter().map(server_list_item);
 
     rsx! {
         div {
             class: "server_list",
+            for (group, members) in grouped {
+                server_group_header {
+                    key: "{group.id}",
+                    group: group.clone(),
+                    members: members.clone(),
+                    collapsed,
+                }
+                if !collapsed.read().contains(&group.id) {
+                    {members.into_iter().map(server_list_item)}
+                }
+            }
             {servers_rendered}
             div {
                 class: "debug_info",
@@ -90,7 +210,7 @@ pub fn server_list() -> Element {
                 {format!("Server Creation Options: {:?}", APP_STATE.read().server_creation_options)}
                 br {}
                 button {
-                    onclick: move |_| load_servers(),
+                    onclick: move |_| APP_STATE.write().servers.push(generate_random_server()),
                     "Add placeholder server"
                 }
             }
@@ -145,6 +265,9 @@ pub fn server_list_item(props: ServerStruct) -> Element {
             class: if selected_panel.starts_with("SERVER:") && Uuid::parse_str(selected_panel.replace("SERVER:", "").as_str()).unwrap() == id {
                 "selected"
             },
+            class: if APP_STATE.read().groups.iter().any(|group| group.members.contains(&id)) {
+                "grouped"
+            },
             img {
                 class: "server_icon",
                 src: format!("data:image/png;base64, {}", base64.read())
@@ -0,0 +1,587 @@
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::gaia_core::nbt::{self, Compound, Compression, Format};
+
+pub const SECTOR: usize = 4096;
+// 1024 chunk locations followed by 1024 timestamps
+pub const HEADER: usize = 2 * SECTOR;
+pub const CHUNKS: usize = 1024;
+// How many of the biggest chunks a dimension's stats list
+const LARGEST: usize = 10;
+// Set on the compression byte when the chunk didn't fit and lives in a c.<x>.<z>.mcc file
+const EXTERNAL: u8 = 0x80;
+// lz4-java's LZ4BlockOutputStream, what minecraft writes with since 1.20.5
+const LZ4_MAGIC: &[u8] = b"LZ4Block";
+const LZ4_RAW: u8 = 0x10;
+const LZ4_COMPRESSED: u8 = 0x20;
+
+// Where a chunk sits in a region file, as the header tells it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChunkSlot {
+    // x + z * 32 inside of the region
+    pub index: usize,
+    // in sectors from the start of the file
+    pub offset: usize,
+    pub sectors: usize,
+    // unix seconds of the chunk's last save
+    pub timestamp: u32,
+}
+
+// A chunk as stored, still compressed
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawChunk {
+    pub compression: u8,
+    pub data: Vec<u8>,
+    pub external: bool,
+}
+
+pub struct Region {
+    // region coordinates from the file name, r.<x>.<z>.mca
+    pub x: i32,
+    pub z: i32,
+    // where .mcc files for oversized chunks are looked for
+    folder: PathBuf,
+    bytes: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChunkSummary {
+    // chunk coordinates in the world, not in the region
+    pub x: i32,
+    pub z: i32,
+    // compressed size on disk
+    pub size: u64,
+    pub timestamp: u32,
+    pub compression: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChunkProblem {
+    pub file: String,
+    // None when the whole region file is unreadable
+    pub chunk: Option<[i32; 2]>,
+    pub error: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DimensionStats {
+    // "overworld", "nether" or "the_end"
+    pub dimension: String,
+    pub region_files: u32,
+    // chunks that read fine, the broken ones are in problems
+    pub chunk_count: u32,
+    pub total_size: u64,
+    pub average_size: u64,
+    pub largest: Vec<ChunkSummary>,
+    pub problems: Vec<ChunkProblem>,
+}
+
+// r.-1.2.mca is region x -1, z 2
+pub fn region_position(path: &Path) -> Option<(i32, i32)> {
+    let name = path.file_name()?.to_str()?;
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(x), Some(z), None) => Some((x.parse().ok()?, z.parse().ok()?)),
+        _ => None,
+    }
+}
+
+pub fn compression_name(compression: u8) -> &'static str {
+    match compression & !EXTERNAL {
+        1 => "gzip",
+        2 => "zlib",
+        3 => "none",
+        4 => "lz4",
+        127 => "custom",
+        _ => "unknown",
+    }
+}
+
+impl Region {
+    pub fn load(path: &Path) -> Result<Region, String> {
+        let (x, z) =
+            region_position(path).ok_or_else(|| format!("{:?} is not a region file", path))?;
+        let bytes = fs::read(path).map_err(|err| format!("Failed to read {:?}: {}", path, err))?;
+        let folder = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Region::from_bytes(x, z, folder, bytes)
+    }
+
+    // Minecraft leaves empty region files behind, those have no chunks rather than a bad header
+    pub fn from_bytes(x: i32, z: i32, folder: PathBuf, bytes: Vec<u8>) -> Result<Region, String> {
+        if !bytes.is_empty() && bytes.len() < HEADER {
+            return Err(format!("Header is cut off at {} bytes", bytes.len()));
+        }
+        Ok(Region {
+            x,
+            z,
+            folder,
+            bytes,
+        })
+    }
+
+    // Chunk coordinates in the world of a slot index
+    pub fn chunk_position(&self, index: usize) -> (i32, i32) {
+        (
+            self.x * 32 + (index % 32) as i32,
+            self.z * 32 + (index / 32) as i32,
+        )
+    }
+
+    // Every slot the header has something in, whether it makes sense or not
+    pub fn slots(&self) -> Vec<ChunkSlot> {
+        if self.bytes.is_empty() {
+            return Vec::new();
+        }
+        (0..CHUNKS)
+            .map(|index| {
+                let location = &self.bytes[index * 4..index * 4 + 4];
+                let timestamp = &self.bytes[SECTOR + index * 4..SECTOR + index * 4 + 4];
+                ChunkSlot {
+                    index,
+                    offset: u32::from_be_bytes([0, location[0], location[1], location[2]]) as usize,
+                    sectors: location[3] as usize,
+                    timestamp: u32::from_be_bytes(timestamp.try_into().unwrap()),
+                }
+            })
+            .filter(|slot| slot.offset != 0 || slot.sectors != 0)
+            .collect()
+    }
+
+    // The stored bytes of a chunk, checking the header points somewhere sensible
+    pub fn raw(&self, slot: &ChunkSlot) -> Result<RawChunk, String> {
+        if slot.offset < HEADER / SECTOR {
+            return Err(format!("Offset {} points into the header", slot.offset));
+        }
+        if slot.sectors == 0 {
+            return Err("Takes up no sectors".to_string());
+        }
+        let start = slot.offset * SECTOR;
+        let end = start + slot.sectors * SECTOR;
+        // the last chunk isn't always padded out to a full sector
+        if start + 5 > self.bytes.len() || end - SECTOR >= self.bytes.len() {
+            return Err(format!(
+                "Sectors {} to {} run past the end of the file",
+                slot.offset,
+                slot.offset + slot.sectors
+            ));
+        }
+        let sectors = &self.bytes[start..end.min(self.bytes.len())];
+        let length = u32::from_be_bytes(sectors[..4].try_into().unwrap()) as usize;
+        if length == 0 || length + 4 > sectors.len() {
+            return Err(format!(
+                "Length of {} bytes doesn't fit in {} sectors",
+                length, slot.sectors
+            ));
+        }
+        let compression = sectors[4];
+        if compression & EXTERNAL == 0 {
+            return Ok(RawChunk {
+                compression,
+                data: sectors[5..4 + length].to_vec(),
+                external: false,
+            });
+        }
+        let (x, z) = self.chunk_position(slot.index);
+        let path = self.folder.join(format!("c.{}.{}.mcc", x, z));
+        let data = fs::read(&path).map_err(|err| format!("Failed to read {:?}: {}", path, err))?;
+        Ok(RawChunk {
+            compression: compression & !EXTERNAL,
+            data,
+            external: true,
+        })
+    }
+
+    // The region packed again with only the chunks keep says yes to, None when nothing is left.
+    // Fails when a chunk can't be read, dropping it would lose it for good
+    pub fn rebuild(&self, keep: impl Fn(&ChunkSlot) -> bool) -> Result<Option<Vec<u8>>, String> {
+        let mut bytes = vec![0; HEADER];
+        for slot in self.slots().iter().filter(|slot| keep(slot)) {
+            self.raw(slot).map_err(|err| {
+                let (x, z) = self.chunk_position(slot.index);
+                format!("Chunk {}, {}: {}", x, z, err)
+            })?;
+            // copied as stored, external chunks keep their .mcc file
+            let start = slot.offset * SECTOR;
+            let length =
+                u32::from_be_bytes(self.bytes[start..start + 4].try_into().unwrap()) as usize;
+            let offset = bytes.len() / SECTOR;
+            bytes.extend_from_slice(&self.bytes[start..start + 4 + length]);
+            bytes.resize(bytes.len().div_ceil(SECTOR) * SECTOR, 0);
+            let sectors = bytes.len() / SECTOR - offset;
+            if sectors > 255 || offset > 0xffffff {
+                return Err("Chunk doesn't fit in a region file".to_string());
+            }
+            let location = (offset as u32) << 8 | sectors as u32;
+            bytes[slot.index * 4..slot.index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+            bytes[SECTOR + slot.index * 4..SECTOR + slot.index * 4 + 4]
+                .copy_from_slice(&slot.timestamp.to_be_bytes());
+        }
+        Ok(match bytes.len() > HEADER {
+            true => Some(bytes),
+            false => None,
+        })
+    }
+
+    pub fn chunk(&self, slot: &ChunkSlot) -> Result<Compound, String> {
+        let raw = self.raw(slot)?;
+        let data = decompress(raw.compression, &raw.data)?;
+        nbt::read_root(&data, Format::Java(Compression::None)).map(|file| file.root)
+    }
+}
+
+pub fn decompress(compression: u8, data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    match compression {
+        1 => GzDecoder::new(data)
+            .read_to_end(&mut decoded)
+            .map_err(|err| format!("Invalid gzip: {}", err))?,
+        2 => ZlibDecoder::new(data)
+            .read_to_end(&mut decoded)
+            .map_err(|err| format!("Invalid zlib: {}", err))?,
+        3 => return Ok(data.to_vec()),
+        4 => return decompress_lz4(data),
+        127 => return Err("Uses a custom compression from a mod".to_string()),
+        other => return Err(format!("Unknown compression {}", other)),
+    };
+    Ok(decoded)
+}
+
+// A run of LZ4Block frames ending in an empty one. The xxhash checksums aren't checked, a
+// damaged block already fails to decompress to the length it claims
+fn decompress_lz4(mut data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    loop {
+        if data.len() < 21 || &data[..8] != LZ4_MAGIC {
+            return Err("Invalid lz4 block header".to_string());
+        }
+        let method = data[8] & 0xf0;
+        let compressed = u32::from_le_bytes(data[9..13].try_into().unwrap()) as usize;
+        let original = u32::from_le_bytes(data[13..17].try_into().unwrap()) as usize;
+        let block = data
+            .get(21..21 + compressed)
+            .ok_or_else(|| "lz4 block is cut off".to_string())?;
+        data = &data[21 + compressed..];
+        if original == 0 {
+            return Ok(decoded);
+        }
+        match method {
+            LZ4_RAW if compressed == original => decoded.extend_from_slice(block),
+            LZ4_COMPRESSED => {
+                let start = decoded.len();
+                decoded.resize(start + original, 0);
+                let written = lz4_flex::block::decompress_into(block, &mut decoded[start..])
+                    .map_err(|err| format!("Invalid lz4: {}", err))?;
+                if written != original {
+                    return Err(format!("lz4 block gave {} of {} bytes", written, original));
+                }
+            }
+            _ => return Err(format!("Unknown lz4 block method {:#x}", method)),
+        }
+    }
+}
+
+// Reads every chunk in <folder>/region, anything that doesn't decompress into nbt is a problem
+pub fn dimension_stats(dimension: &str, folder: &Path) -> DimensionStats {
+    let mut stats = DimensionStats {
+        dimension: dimension.to_string(),
+        ..Default::default()
+    };
+    let mut files: Vec<PathBuf> = fs::read_dir(folder.join("region"))
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| region_position(path).is_some());
+    files.sort();
+    for path in files {
+        let file = path.file_name().unwrap().to_string_lossy().to_string();
+        stats.region_files += 1;
+        let region = match Region::load(&path) {
+            Ok(region) => region,
+            Err(err) => {
+                stats.problems.push(ChunkProblem {
+                    file,
+                    chunk: None,
+                    error: err,
+                });
+                continue;
+            }
+        };
+        // which chunk took each sector, two chunks claiming one means the header is damaged
+        let mut owners = vec![None::<usize>; region.bytes.len().div_ceil(SECTOR)];
+        for slot in region.slots() {
+            let (x, z) = region.chunk_position(slot.index);
+            let problem = |error: String| ChunkProblem {
+                file: file.clone(),
+                chunk: Some([x, z]),
+                error,
+            };
+            let end = (slot.offset + slot.sectors).min(owners.len());
+            let overlap = owners
+                .get_mut(slot.offset..end)
+                .unwrap_or_default()
+                .iter_mut()
+                .find_map(|owner| owner.replace(slot.index));
+            if let Some(other) = overlap {
+                let (other_x, other_z) = region.chunk_position(other);
+                stats.problems.push(problem(format!(
+                    "Shares sectors with chunk {}, {}",
+                    other_x, other_z
+                )));
+                continue;
+            }
+            let raw = match region.raw(&slot) {
+                Ok(raw) => raw,
+                Err(err) => {
+                    stats.problems.push(problem(err));
+                    continue;
+                }
+            };
+            let checked = decompress(raw.compression, &raw.data)
+                .and_then(|data| nbt::read_root(&data, Format::Java(Compression::None)));
+            if let Err(err) = checked {
+                stats.problems.push(problem(err));
+                continue;
+            }
+            stats.chunk_count += 1;
+            stats.total_size += raw.data.len() as u64;
+            stats.largest.push(ChunkSummary {
+                x,
+                z,
+                size: raw.data.len() as u64,
+                timestamp: slot.timestamp,
+                compression: compression_name(raw.compression).to_string(),
+            });
+        }
+        stats.largest.sort_by_key(|chunk| Reverse(chunk.size));
+        stats.largest.truncate(LARGEST);
+    }
+    if stats.chunk_count > 0 {
+        stats.average_size = stats.total_size / stats.chunk_count as u64;
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use std::io::Write;
+
+    // { xPos: x, zPos: z } as uncompressed nbt
+    fn chunk_nbt(x: i32, z: i32) -> Vec<u8> {
+        let mut bytes = vec![10, 0, 0];
+        for (name, value) in [("xPos", x), ("zPos", z)] {
+            bytes.extend([3, 0, 4]);
+            bytes.extend(name.as_bytes());
+            bytes.extend(value.to_be_bytes());
+        }
+        bytes.push(0);
+        bytes
+    }
+
+    fn compress(compression: u8, data: &[u8]) -> Vec<u8> {
+        match compression {
+            1 => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            2 => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            4 => {
+                let block = lz4_flex::block::compress(data);
+                let mut bytes = LZ4_MAGIC.to_vec();
+                bytes.push(LZ4_COMPRESSED);
+                bytes.extend((block.len() as u32).to_le_bytes());
+                bytes.extend((data.len() as u32).to_le_bytes());
+                bytes.extend([0; 4]);
+                bytes.extend(block);
+                bytes.extend(LZ4_MAGIC);
+                bytes.push(LZ4_RAW);
+                bytes.extend([0; 12]);
+                bytes
+            }
+            _ => data.to_vec(),
+        }
+    }
+
+    // A region with the given chunks packed one after the other
+    fn region_bytes(chunks: &[(usize, u8, Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = vec![0; HEADER];
+        for (index, compression, data) in chunks {
+            let offset = bytes.len() / SECTOR;
+            bytes.extend((data.len() as u32 + 1).to_be_bytes());
+            bytes.push(*compression);
+            bytes.extend(data);
+            bytes.resize(bytes.len().div_ceil(SECTOR) * SECTOR, 0);
+            let sectors = bytes.len() / SECTOR - offset;
+            bytes[index * 4..index * 4 + 4]
+                .copy_from_slice(&((offset as u32) << 8 | sectors as u32).to_be_bytes());
+            bytes[SECTOR + index * 4..SECTOR + index * 4 + 4]
+                .copy_from_slice(&1_700_000_000u32.to_be_bytes());
+        }
+        bytes
+    }
+
+    fn x_pos(compound: &Compound) -> Option<i64> {
+        compound.get("xPos").and_then(|tag| tag.as_i64())
+    }
+
+    #[test]
+    fn parses_region_names() {
+        assert_eq!(
+            region_position(Path::new("region/r.-1.2.mca")),
+            Some((-1, 2))
+        );
+        assert_eq!(region_position(Path::new("r.0.0.mcc")), None);
+        assert_eq!(region_position(Path::new("r.0.mca")), None);
+    }
+
+    #[test]
+    fn reads_every_compression() {
+        let chunks: Vec<(usize, u8, Vec<u8>)> = [1u8, 2, 3, 4]
+            .iter()
+            .enumerate()
+            .map(|(index, compression)| {
+                (
+                    index,
+                    *compression,
+                    compress(*compression, &chunk_nbt(-32 + index as i32, 64)),
+                )
+            })
+            .collect();
+        let region = Region::from_bytes(-1, 2, PathBuf::new(), region_bytes(&chunks)).unwrap();
+        let slots = region.slots();
+        assert_eq!(slots.len(), 4);
+        for slot in &slots {
+            assert_eq!(
+                region.chunk_position(slot.index),
+                (-32 + slot.index as i32, 64)
+            );
+            assert_eq!(slot.timestamp, 1_700_000_000);
+            assert_eq!(
+                x_pos(&region.chunk(slot).unwrap()),
+                Some(-32 + slot.index as i64)
+            );
+        }
+    }
+
+    #[test]
+    fn reports_damaged_headers() {
+        let mut bytes = region_bytes(&[(0, 2, compress(2, &chunk_nbt(0, 0)))]);
+        // into the header, past the end of the file, and no sectors at all
+        bytes[4..8].copy_from_slice(&(1u32 << 8 | 1).to_be_bytes());
+        bytes[8..12].copy_from_slice(&(40u32 << 8 | 1).to_be_bytes());
+        bytes[12..16].copy_from_slice(&(2u32 << 8).to_be_bytes());
+        let region = Region::from_bytes(0, 0, PathBuf::new(), bytes).unwrap();
+        let errors: Vec<bool> = region
+            .slots()
+            .iter()
+            .map(|slot| region.raw(slot).is_err())
+            .collect();
+        assert_eq!(errors, [false, true, true, true]);
+        assert!(Region::from_bytes(0, 0, PathBuf::new(), vec![0; 100]).is_err());
+        assert!(Region::from_bytes(0, 0, PathBuf::new(), Vec::new())
+            .unwrap()
+            .slots()
+            .is_empty());
+    }
+
+    #[test]
+    fn reads_external_chunks() {
+        let folder = std::env::temp_dir().join(format!("anvil-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("c.33.1.mcc"), compress(2, &chunk_nbt(33, 1))).unwrap();
+        let region = Region::from_bytes(
+            1,
+            0,
+            folder.clone(),
+            region_bytes(&[(32 + 1, 2 | EXTERNAL, Vec::new())]),
+        )
+        .unwrap();
+        let slot = region.slots()[0];
+        assert!(region.raw(&slot).unwrap().external);
+        assert_eq!(x_pos(&region.chunk(&slot).unwrap()), Some(33));
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn rebuilds_without_chunks() {
+        let chunks: Vec<(usize, u8, Vec<u8>)> = (0..4)
+            .map(|index| (index * 33, 2, compress(2, &chunk_nbt(index as i32 * 33, 0))))
+            .collect();
+        let region = Region::from_bytes(0, 0, PathBuf::new(), region_bytes(&chunks)).unwrap();
+        let rebuilt = region.rebuild(|slot| slot.index != 33).unwrap().unwrap();
+        assert_eq!(rebuilt.len(), HEADER + 3 * SECTOR);
+        let rebuilt = Region::from_bytes(0, 0, PathBuf::new(), rebuilt).unwrap();
+        let slots = rebuilt.slots();
+        assert_eq!(
+            slots.iter().map(|slot| slot.index).collect::<Vec<usize>>(),
+            [0, 66, 99]
+        );
+        for slot in &slots {
+            assert_eq!(
+                x_pos(&rebuilt.chunk(slot).unwrap()),
+                Some(slot.index as i64)
+            );
+            assert_eq!(slot.timestamp, 1_700_000_000);
+        }
+        assert_eq!(region.rebuild(|_| false).unwrap(), None);
+    }
+
+    #[test]
+    fn collects_dimension_stats() {
+        let folder = std::env::temp_dir().join(format!("anvil-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(folder.join("region")).unwrap();
+        let big: Vec<u8> = (0..20_000u32)
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        let mut large = chunk_nbt(1, 0);
+        large.pop();
+        large.extend([7, 0, 4]);
+        large.extend(b"data");
+        large.extend((big.len() as u32).to_be_bytes());
+        large.extend(&big);
+        large.push(0);
+        let good = region_bytes(&[
+            (0, 2, compress(2, &chunk_nbt(0, 0))),
+            (1, 3, large),
+            (2, 2, vec![1, 2, 3]),
+        ]);
+        fs::write(folder.join("region/r.0.0.mca"), good).unwrap();
+        let mut overlapping = region_bytes(&[(0, 2, compress(2, &chunk_nbt(32, 0)))]);
+        overlapping.copy_within(0..4, 4);
+        fs::write(folder.join("region/r.1.0.mca"), overlapping).unwrap();
+        fs::write(folder.join("region/r.2.0.mca"), [0; 10]).unwrap();
+
+        let stats = dimension_stats("overworld", &folder);
+        assert_eq!(stats.region_files, 3);
+        assert_eq!(stats.chunk_count, 3);
+        assert_eq!(stats.largest[0].x, 1);
+        assert_eq!(stats.largest[0].compression, "none");
+        assert_eq!(stats.average_size, stats.total_size / 3);
+        let problems: Vec<(&str, Option<[i32; 2]>)> = stats
+            .problems
+            .iter()
+            .map(|problem| (problem.file.as_str(), problem.chunk))
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                ("r.0.0.mca", Some([2, 0])),
+                ("r.1.0.mca", Some([33, 0])),
+                ("r.2.0.mca", None)
+            ]
+        );
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
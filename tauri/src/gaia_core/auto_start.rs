@@ -0,0 +1,197 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use uuid::Uuid;
+
+use crate::gaia_core::{
+    core_structs::ServerStruct,
+    supervisor::{ConsolePayload, Supervisor},
+};
+
+// How long a server waits on the one it depends on before giving up on it
+const DEPENDENCY_TIMEOUT: Duration = Duration::from_secs(600);
+const DEPENDENCY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Sorts by auto_start_order but always puts a server after the one it waits on
+pub fn start_order(servers: &[ServerStruct]) -> Vec<ServerStruct> {
+    let mut remaining: Vec<ServerStruct> = servers
+        .iter()
+        .filter(|server| server.auto_start)
+        .cloned()
+        .collect();
+    remaining.sort_by_key(|server| server.auto_start_order);
+    dependency_order(remaining)
+}
+
+// Keeps the given order except where a server has to come after the one it waits on
+pub fn dependency_order(mut remaining: Vec<ServerStruct>) -> Vec<ServerStruct> {
+    let mut ordered = Vec::new();
+    while !remaining.is_empty() {
+        let index = remaining
+            .iter()
+            .position(|server| {
+                server
+                    .auto_start_after
+                    .is_none_or(|after| !remaining.iter().any(|other| other.id == after))
+            })
+            // dependency cycle, nothing can go first so just keep the user's order
+            .unwrap_or(0);
+        ordered.push(remaining.remove(index));
+    }
+    ordered
+}
+
+pub(crate) fn manager_message<R: Runtime>(app: &AppHandle<R>, id: Uuid, message: String) {
+    println!("{}", message);
+    let _ = app.emit(
+        "server_console",
+        ConsolePayload {
+            id,
+            line: format!("[Gaia Manager] {}", message),
+        },
+    );
+}
+
+// Gives up right away once the server is no longer running, it won't become ready anymore
+pub fn wait_until_ready(supervisor: &Supervisor, id: Uuid) -> bool {
+    let started = Instant::now();
+    while started.elapsed() < DEPENDENCY_TIMEOUT {
+        if supervisor.is_ready(id) {
+            return true;
+        }
+        if !supervisor.is_running(id) {
+            return false;
+        }
+        thread::sleep(DEPENDENCY_POLL_INTERVAL);
+    }
+    false
+}
+
+// Starts the auto start servers one after another, runs on its own thread so setup isn't blocked
+pub fn start_servers<R: Runtime>(app: AppHandle<R>, servers: Vec<ServerStruct>) {
+    let ordered = start_order(&servers);
+    if ordered.is_empty() {
+        return;
+    }
+    let auto_started: Vec<Uuid> = ordered.iter().map(|server| server.id).collect();
+    thread::spawn(move || {
+        let supervisor = app.state::<Supervisor>();
+        for server in ordered {
+            if let Some(after) = server.auto_start_after {
+                let name = servers
+                    .iter()
+                    .find(|other| other.id == after)
+                    .map(|other| other.name.clone())
+                    .unwrap_or_else(|| after.to_string());
+                // nothing is going to start it, no point in waiting
+                let will_start = auto_started.contains(&after) || supervisor.is_running(after);
+                if !will_start || !wait_until_ready(&supervisor, after) {
+                    manager_message(
+                        &app,
+                        server.id,
+                        format!(
+                            "Not auto starting {}, {} never finished starting",
+                            server.name, name
+                        ),
+                    );
+                    continue;
+                }
+            }
+            thread::sleep(Duration::from_secs(server.auto_start_delay_seconds));
+            if let Err(err) = supervisor.start(&app, server.clone()) {
+                manager_message(&app, server.id, format!("Failed to auto start: {}", err));
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str, order: u32, after: Option<Uuid>) -> ServerStruct {
+        ServerStruct {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            auto_start: true,
+            auto_start_order: order,
+            auto_start_after: after,
+            ..Default::default()
+        }
+    }
+
+    fn names(servers: &[ServerStruct]) -> Vec<&str> {
+        servers.iter().map(|server| server.name.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_by_auto_start_order() {
+        let servers = vec![
+            server("c", 3, None),
+            server("a", 1, None),
+            server("b", 2, None),
+        ];
+        assert_eq!(names(&start_order(&servers)), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn skips_servers_without_auto_start() {
+        let manual = ServerStruct {
+            auto_start: false,
+            ..server("manual", 0, None)
+        };
+        let servers = vec![manual, server("auto", 1, None)];
+        assert_eq!(names(&start_order(&servers)), vec!["auto"]);
+    }
+
+    #[test]
+    fn starts_dependencies_first() {
+        let proxy = server("proxy", 5, None);
+        let lobby = server("lobby", 1, Some(proxy.id));
+        let survival = server("survival", 2, Some(lobby.id));
+        let servers = vec![survival, lobby, proxy, server("other", 3, None)];
+        assert_eq!(
+            names(&start_order(&servers)),
+            vec!["other", "proxy", "lobby", "survival"]
+        );
+    }
+
+    #[test]
+    fn ignores_missing_dependencies() {
+        let manual = ServerStruct {
+            auto_start: false,
+            ..server("manual", 0, None)
+        };
+        let servers = vec![
+            server("a", 1, Some(Uuid::new_v4())),
+            server("b", 2, Some(manual.id)),
+            server("c", 3, None),
+            manual,
+        ];
+        // the server it waits on isn't auto started, so it keeps its place
+        assert_eq!(names(&start_order(&servers)), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn keeps_the_user_order_on_a_cycle() {
+        let mut a = server("a", 1, None);
+        let b = server("b", 2, Some(a.id));
+        a.auto_start_after = Some(b.id);
+        let c = server("c", 3, Some(b.id));
+        let servers = vec![c, b, a];
+        assert_eq!(names(&start_order(&servers)), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn dependency_order_keeps_the_given_order() {
+        let first = server("first", 9, None);
+        let second = server("second", 0, Some(first.id));
+        let third = server("third", 0, None);
+        assert_eq!(
+            names(&dependency_order(vec![second, third, first])),
+            vec!["third", "first", "second"]
+        );
+    }
+}
@@ -0,0 +1,673 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::gaia_core::{backup_store, core_structs::ServerStruct, log_archive, server_properties};
+
+// zstd level 3 is the default, good compression without making backups slow
+const ZSTD_LEVEL: i32 = 3;
+
+// Pruning while an incremental backup is being written would delete its new blobs before
+// its snapshot points to them, so backups and deletes take turns
+static BACKUP_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BackupOptions {
+    // "zip", "tar.zst" or "incremental"
+    pub format: String,
+    // "worlds" for just the world folders, "full" for the whole server folder
+    pub scope: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct BackupManifest {
+    pub name: String,
+    pub archive: String,
+    pub server_id: Uuid,
+    pub server_name: String,
+    // UTC, YYYY-MM-DD HH:MM:SS
+    pub created: String,
+    pub created_unix: u64,
+    pub format: String,
+    pub scope: String,
+    // world folders that were backed up, restoring replaces these
+    pub worlds: Vec<String>,
+    // disk space the backup took, for incremental ones only the data that was new
+    pub size: u64,
+    // size of the backed up files before compression
+    #[serde(default)]
+    pub total_size: u64,
+    pub file_count: u64,
+    // sha256 of the archive, or of the snapshot file for incremental backups
+    pub hash: String,
+    pub minecraft_version: String,
+}
+
+pub fn timestamp(time: SystemTime) -> (String, String) {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let date = log_archive::system_time_to_date(time);
+    let (hours, minutes, seconds) = (seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+    (
+        format!("{}_{:02}-{:02}-{:02}", date, hours, minutes, seconds),
+        format!("{} {:02}:{:02}:{:02}", date, hours, minutes, seconds),
+    )
+}
+
+fn archive_extension(format: &str) -> Result<&'static str, String> {
+    match format {
+        "zip" => Ok("zip"),
+        "tar.zst" => Ok("tar.zst"),
+        "incremental" => Ok("snapshot"),
+        _ => Err(format!("Unknown backup format: {}", format)),
+    }
+}
+
+fn manifest_path(dest_dir: &Path, name: &str) -> PathBuf {
+    dest_dir.join(format!("{}.json", name))
+}
+
+// The world plus its nether and end folders, bukkit based servers keep those separate.
+// Bedrock keeps its worlds in worlds/<level-name>
+pub fn world_folders(server_path: &Path) -> Vec<String> {
+    let level = server_properties::level_name(server_path);
+    [
+        level.clone(),
+        format!("{}_nether", level),
+        format!("{}_the_end", level),
+        format!("worlds/{}", level),
+    ]
+    .into_iter()
+    .filter(|folder| server_path.join(folder).is_dir())
+    .collect()
+}
+
+// Files on disk with the name they're stored under in the backup
+type BackupFiles = Vec<(PathBuf, String)>;
+
+// Paths relative to the server folder with forward slashes, that's what every format stores.
+// Returns the files and every folder they're in
+fn collect_files(
+    server_path: &Path,
+    roots: &[String],
+    exclude: &Path,
+) -> Result<(BackupFiles, Vec<String>), String> {
+    let mut files = Vec::new();
+    let mut directories = Vec::new();
+    for root in roots {
+        for entry in WalkDir::new(server_path.join(root)) {
+            let entry = entry.map_err(|err| err.to_string())?;
+            let path = entry.path();
+            if path.starts_with(exclude) {
+                continue;
+            }
+            // held open by the running server and useless in a backup
+            if entry.file_name() == "session.lock" {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(server_path) else {
+                continue;
+            };
+            let name = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if entry.file_type().is_dir() && !name.is_empty() {
+                directories.push(name);
+            } else if entry.file_type().is_file() {
+                files.push((path.to_path_buf(), name));
+            }
+        }
+    }
+    Ok((files, directories))
+}
+
+pub fn write_zip(archive_path: &Path, files: &[(PathBuf, String)]) -> Result<(), String> {
+    let file = File::create(archive_path).map_err(|err| err.to_string())?;
+    let mut writer = zip::ZipWriter::new(BufWriter::new(file));
+    for (path, name) in files {
+        let size = fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(size >= u32::MAX as u64);
+        writer
+            .start_file(name.as_str(), options)
+            .map_err(|err| err.to_string())?;
+        let mut source = File::open(path).map_err(|err| err.to_string())?;
+        io::copy(&mut source, &mut writer).map_err(|err| err.to_string())?;
+    }
+    writer.finish().map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+fn write_tar_zst(archive_path: &Path, files: &[(PathBuf, String)]) -> Result<(), String> {
+    let file = File::create(archive_path).map_err(|err| err.to_string())?;
+    let encoder =
+        zstd::Encoder::new(BufWriter::new(file), ZSTD_LEVEL).map_err(|err| err.to_string())?;
+    let mut builder = tar::Builder::new(encoder);
+    for (path, name) in files {
+        builder
+            .append_path_with_name(path, name)
+            .map_err(|err| err.to_string())?;
+    }
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file = BufReader::new(File::open(path).map_err(|err| err.to_string())?);
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).map_err(|err| err.to_string())?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Reads every entry to the end so crc and zstd checksums get checked, returns the file count
+fn read_archive(archive_path: &Path, format: &str, dest_dir: &Path) -> Result<u64, String> {
+    if format == "incremental" {
+        return backup_store::verify_snapshot(archive_path, dest_dir);
+    }
+    let file = BufReader::new(File::open(archive_path).map_err(|err| err.to_string())?);
+    let mut count = 0;
+    match format {
+        "zip" => {
+            let mut archive = zip::ZipArchive::new(file).map_err(|err| err.to_string())?;
+            for index in 0..archive.len() {
+                let mut entry = archive.by_index(index).map_err(|err| err.to_string())?;
+                if entry.is_file() {
+                    io::copy(&mut entry, &mut io::sink()).map_err(|err| err.to_string())?;
+                    count += 1;
+                }
+            }
+        }
+        _ => {
+            let decoder = zstd::Decoder::new(file).map_err(|err| err.to_string())?;
+            let mut archive = tar::Archive::new(decoder);
+            for entry in archive.entries().map_err(|err| err.to_string())? {
+                let mut entry = entry.map_err(|err| err.to_string())?;
+                if entry.header().entry_type().is_file() {
+                    io::copy(&mut entry, &mut io::sink()).map_err(|err| err.to_string())?;
+                    count += 1;
+                }
+            }
+        }
+    }
+    Ok(count)
+}
+
+fn extract(
+    archive_path: &Path,
+    format: &str,
+    dest_dir: &Path,
+    target: &Path,
+) -> Result<(), String> {
+    if format == "incremental" {
+        return backup_store::restore_snapshot(archive_path, dest_dir, target);
+    }
+    let file = BufReader::new(File::open(archive_path).map_err(|err| err.to_string())?);
+    match format {
+        "zip" => {
+            let mut archive = zip::ZipArchive::new(file).map_err(|err| err.to_string())?;
+            // extract refuses entries that would land outside of the target
+            archive.extract(target).map_err(|err| err.to_string())
+        }
+        _ => {
+            let decoder = zstd::Decoder::new(file).map_err(|err| err.to_string())?;
+            let mut archive = tar::Archive::new(decoder);
+            archive.set_preserve_mtime(true);
+            archive.unpack(target).map_err(|err| err.to_string())
+        }
+    }
+}
+
+pub fn create_backup(
+    server: &ServerStruct,
+    dest_dir: &Path,
+    options: &BackupOptions,
+) -> Result<BackupManifest, String> {
+    let server_path = PathBuf::from(&server.path);
+    let extension = archive_extension(&options.format)?;
+    let worlds = world_folders(&server_path);
+    let roots = match options.scope.as_str() {
+        "worlds" if worlds.is_empty() => {
+            return Err(format!("{} has no world folder to back up", server.name))
+        }
+        "worlds" => worlds.clone(),
+        "full" => vec!["".to_string()],
+        _ => return Err(format!("Unknown backup scope: {}", options.scope)),
+    };
+    fs::create_dir_all(dest_dir).map_err(|err| err.to_string())?;
+    let _lock = BACKUP_LOCK.lock().unwrap();
+    // never back up the backups when they're kept inside of the server folder
+    let (files, directories) = collect_files(&server_path, &roots, dest_dir)?;
+    let total_size = files
+        .iter()
+        .map(|(path, _)| {
+            fs::metadata(path)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0)
+        })
+        .sum();
+
+    let now = SystemTime::now();
+    let (mut name, created) = timestamp(now);
+    let base_name = name.clone();
+    let mut suffix = 1;
+    while manifest_path(dest_dir, &name).exists() {
+        name = format!("{}-{}", base_name, suffix);
+        suffix += 1;
+    }
+    let archive = format!("{}.{}", name, extension);
+    let archive_path = dest_dir.join(&archive);
+    let partial_path = dest_dir.join(format!("{}.partial", archive));
+    let written = match options.format.as_str() {
+        "zip" => write_zip(&partial_path, &files).map(|_| None),
+        "tar.zst" => write_tar_zst(&partial_path, &files).map(|_| None),
+        _ => backup_store::write_snapshot(&partial_path, dest_dir, &files, &directories).map(Some),
+    };
+    let stored = match written {
+        Ok(stored) => stored,
+        Err(err) => {
+            let _ = fs::remove_file(&partial_path);
+            return Err(err);
+        }
+    };
+    fs::rename(&partial_path, &archive_path).map_err(|err| err.to_string())?;
+
+    let manifest = BackupManifest {
+        name: name.clone(),
+        archive,
+        server_id: server.id,
+        server_name: server.name.clone(),
+        created,
+        created_unix: now
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0),
+        format: options.format.clone(),
+        scope: options.scope.clone(),
+        worlds,
+        size: stored.unwrap_or_else(|| {
+            fs::metadata(&archive_path)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0)
+        }),
+        total_size,
+        file_count: files.len() as u64,
+        hash: hash_file(&archive_path)?,
+        minecraft_version: server.minecraft_version.clone(),
+    };
+    let content = serde_json::to_string_pretty(&manifest).map_err(|err| err.to_string())?;
+    fs::write(manifest_path(dest_dir, &name), content).map_err(|err| err.to_string())?;
+    Ok(manifest)
+}
+
+// Every backup in the folder whichever server it's of, newest first
+fn all_backups(dest_dir: &Path) -> Vec<BackupManifest> {
+    let Ok(entries) = fs::read_dir(dest_dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<BackupManifest> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .filter_map(|path| serde_json::from_str(&fs::read_to_string(path).ok()?).ok())
+        .collect();
+    backups.sort_by(|a, b| {
+        b.created_unix
+            .cmp(&a.created_unix)
+            .then(b.name.cmp(&a.name))
+    });
+    backups
+}
+
+// Backups of one server, several servers can share a backup folder
+pub fn list_backups(dest_dir: &Path, server_id: Uuid) -> Vec<BackupManifest> {
+    let mut backups = all_backups(dest_dir);
+    backups.retain(|backup| backup.server_id == server_id);
+    backups
+}
+
+pub fn load_backup(dest_dir: &Path, server_id: Uuid, name: &str) -> Result<BackupManifest, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid backup name: {}", name));
+    }
+    let content = fs::read_to_string(manifest_path(dest_dir, name))
+        .map_err(|_| format!("Backup {} does not exist", name))?;
+    let manifest: BackupManifest = serde_json::from_str(&content).map_err(|err| err.to_string())?;
+    if manifest.server_id != server_id {
+        return Err(format!("Backup {} belongs to another server", name));
+    }
+    Ok(manifest)
+}
+
+// Drops blobs that no incremental snapshot uses anymore, returns the bytes freed. The blob store
+// is shared by every server backing up into the folder
+fn prune_store(dest_dir: &Path) -> Result<u64, String> {
+    let snapshots: Vec<PathBuf> = all_backups(dest_dir)
+        .into_iter()
+        .filter(|backup| backup.format == "incremental")
+        .map(|backup| dest_dir.join(backup.archive))
+        .collect();
+    backup_store::prune_blobs(dest_dir, &snapshots)
+}
+
+fn remove_backup_files(dest_dir: &Path, manifest: &BackupManifest) -> Result<(), String> {
+    match fs::remove_file(dest_dir.join(&manifest.archive)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.to_string()),
+        _ => {}
+    }
+    fs::remove_file(manifest_path(dest_dir, &manifest.name)).map_err(|err| err.to_string())
+}
+
+pub fn delete_backup(dest_dir: &Path, server_id: Uuid, name: &str) -> Result<(), String> {
+    delete_backups(dest_dir, server_id, &[name.to_string()])
+}
+
+// Deletes several backups and only walks the blob store once afterwards
+pub fn delete_backups(dest_dir: &Path, server_id: Uuid, names: &[String]) -> Result<(), String> {
+    let manifests = names
+        .iter()
+        .map(|name| load_backup(dest_dir, server_id, name))
+        .collect::<Result<Vec<_>, String>>()?;
+    let _lock = BACKUP_LOCK.lock().unwrap();
+    for manifest in &manifests {
+        remove_backup_files(dest_dir, manifest)?;
+    }
+    if manifests
+        .iter()
+        .any(|manifest| manifest.format == "incremental")
+    {
+        let freed = prune_store(dest_dir)?;
+        println!("Pruned {} bytes of unused backup data", freed);
+    }
+    Ok(())
+}
+
+// Checks the archive against its manifest and reads every file in it
+pub fn verify_backup(dest_dir: &Path, server_id: Uuid, name: &str) -> Result<(), String> {
+    let manifest = load_backup(dest_dir, server_id, name)?;
+    let archive_path = dest_dir.join(&manifest.archive);
+    if !archive_path.is_file() {
+        return Err(format!("Archive {} is missing", manifest.archive));
+    }
+    if hash_file(&archive_path)? != manifest.hash {
+        return Err("Archive hash does not match the manifest".to_string());
+    }
+    let count = read_archive(&archive_path, &manifest.format, dest_dir)?;
+    if count != manifest.file_count {
+        return Err(format!(
+            "Archive has {} files, the manifest expects {}",
+            count, manifest.file_count
+        ));
+    }
+    Ok(())
+}
+
+// Extracts a backup into target, world backups replace the world folders completely
+// so chunks that were generated after the backup don't linger
+pub fn restore_backup(
+    dest_dir: &Path,
+    server_id: Uuid,
+    name: &str,
+    target: &Path,
+) -> Result<(), String> {
+    let manifest = load_backup(dest_dir, server_id, name)?;
+    let archive_path = dest_dir.join(&manifest.archive);
+    if !archive_path.is_file() {
+        return Err(format!("Archive {} is missing", manifest.archive));
+    }
+    fs::create_dir_all(target).map_err(|err| err.to_string())?;
+    if manifest.scope == "worlds" {
+        for world in &manifest.worlds {
+            // bedrock worlds sit one folder down in worlds/<level-name>
+            let relative = Path::new(world);
+            if world.is_empty()
+                || !relative
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(format!("Invalid world folder in manifest: {}", world));
+            }
+            let world_path = target.join(world);
+            if world_path.exists() {
+                fs::remove_dir_all(&world_path).map_err(|err| err.to_string())?;
+            }
+        }
+    }
+    extract(&archive_path, &manifest.format, dest_dir, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A bukkit style server with its nether and end in their own folders
+    fn java_server(root: &Path) -> ServerStruct {
+        let path = root.join("server");
+        for world in ["world", "world_nether", "world_the_end"] {
+            fs::create_dir_all(path.join(world).join("region")).unwrap();
+            fs::write(path.join(world).join("level.dat"), world).unwrap();
+        }
+        fs::write(path.join("world/region/r.0.0.mca"), vec![1; 8192]).unwrap();
+        fs::write(path.join("world/session.lock"), "locked").unwrap();
+        fs::create_dir_all(path.join("plugins")).unwrap();
+        fs::write(path.join("plugins/Essentials.jar"), "jar").unwrap();
+        fs::write(path.join("server.properties"), "level-name=world\n").unwrap();
+        ServerStruct {
+            id: Uuid::new_v4(),
+            name: "Survival".to_string(),
+            path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn world_backups_round_trip() {
+        for format in ["zip", "tar.zst"] {
+            let root = std::env::temp_dir().join(format!("backup-{}", Uuid::new_v4()));
+            let server = java_server(&root);
+            let server_path = Path::new(&server.path);
+            let dest_dir = root.join("backups");
+            let options = BackupOptions {
+                format: format.to_string(),
+                scope: "worlds".to_string(),
+            };
+            let manifest = create_backup(&server, &dest_dir, &options).unwrap();
+            assert_eq!(manifest.worlds, ["world", "world_nether", "world_the_end"]);
+            // the three level.dat files and the region file, session.lock is left out
+            assert_eq!(manifest.file_count, 4, "{}", format);
+            assert_eq!(manifest.archive, format!("{}.{}", manifest.name, format));
+            assert_eq!(list_backups(&dest_dir, server.id).len(), 1);
+            verify_backup(&dest_dir, server.id, &manifest.name).unwrap();
+
+            fs::write(server_path.join("world/level.dat"), "changed").unwrap();
+            fs::write(server_path.join("world/region/r.1.0.mca"), "new chunks").unwrap();
+            fs::remove_dir_all(server_path.join("world_the_end")).unwrap();
+            fs::write(server_path.join("plugins/Essentials.jar"), "updated").unwrap();
+            restore_backup(&dest_dir, server.id, &manifest.name, server_path).unwrap();
+            assert_eq!(
+                fs::read_to_string(server_path.join("world/level.dat")).unwrap(),
+                "world"
+            );
+            assert_eq!(
+                fs::read(server_path.join("world/region/r.0.0.mca")).unwrap(),
+                vec![1; 8192]
+            );
+            assert!(!server_path.join("world/region/r.1.0.mca").exists());
+            assert!(server_path.join("world_the_end/level.dat").exists());
+            // only the worlds get replaced
+            assert_eq!(
+                fs::read_to_string(server_path.join("plugins/Essentials.jar")).unwrap(),
+                "updated"
+            );
+            fs::remove_dir_all(root).unwrap();
+        }
+    }
+
+    #[test]
+    fn full_backups_restore_into_a_new_folder() {
+        for format in ["zip", "tar.zst"] {
+            let root = std::env::temp_dir().join(format!("backup-{}", Uuid::new_v4()));
+            let server = java_server(&root);
+            // backups kept inside of the server folder don't end up in themselves
+            let dest_dir = Path::new(&server.path).join("backups");
+            let options = BackupOptions {
+                format: format.to_string(),
+                scope: "full".to_string(),
+            };
+            let first = create_backup(&server, &dest_dir, &options).unwrap();
+            let second = create_backup(&server, &dest_dir, &options).unwrap();
+            assert_ne!(first.name, second.name);
+            assert_eq!(second.file_count, 6, "{}", format);
+
+            let target = root.join("copy");
+            restore_backup(&dest_dir, server.id, &second.name, &target).unwrap();
+            assert_eq!(
+                fs::read_to_string(target.join("server.properties")).unwrap(),
+                "level-name=world\n"
+            );
+            assert!(target.join("plugins/Essentials.jar").exists());
+            assert!(target.join("world_nether/level.dat").exists());
+            assert!(!target.join("backups").exists());
+            assert!(!target.join("world/session.lock").exists());
+            fs::remove_dir_all(root).unwrap();
+        }
+    }
+
+    #[test]
+    fn verify_catches_damaged_archives() {
+        let root = std::env::temp_dir().join(format!("backup-{}", Uuid::new_v4()));
+        let server = java_server(&root);
+        let dest_dir = root.join("backups");
+        let options = BackupOptions {
+            format: "tar.zst".to_string(),
+            scope: "worlds".to_string(),
+        };
+        let manifest = create_backup(&server, &dest_dir, &options).unwrap();
+        let archive_path = dest_dir.join(&manifest.archive);
+        let mut bytes = fs::read(&archive_path).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xff;
+        fs::write(&archive_path, bytes).unwrap();
+        assert!(verify_backup(&dest_dir, server.id, &manifest.name).is_err());
+
+        fs::remove_file(&archive_path).unwrap();
+        assert!(verify_backup(&dest_dir, server.id, &manifest.name).is_err());
+        assert!(restore_backup(&dest_dir, server.id, &manifest.name, &root.join("copy")).is_err());
+        delete_backup(&dest_dir, server.id, &manifest.name).unwrap();
+        assert!(list_backups(&dest_dir, server.id).is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    const WORLD: &str = "worlds/Bedrock level";
+
+    fn bedrock_server(root: &Path) -> ServerStruct {
+        let path = root.join("server");
+        fs::create_dir_all(path.join(WORLD).join("db")).unwrap();
+        fs::write(path.join("server.properties"), "level-name=Bedrock level\n").unwrap();
+        fs::write(path.join(WORLD).join("level.dat"), [8, 0, 0, 0, 1, 2, 3]).unwrap();
+        fs::write(path.join(WORLD).join("levelname.txt"), "Bedrock level").unwrap();
+        fs::write(path.join(WORLD).join("db/000005.ldb"), vec![7; 4096]).unwrap();
+        ServerStruct {
+            id: Uuid::new_v4(),
+            name: "Bedrock".to_string(),
+            server_type: "bedrock".to_string(),
+            path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bedrock_world_backups_round_trip() {
+        for format in ["zip", "tar.zst", "incremental"] {
+            let root = std::env::temp_dir().join(format!("backup-{}", Uuid::new_v4()));
+            let server = bedrock_server(&root);
+            let server_path = Path::new(&server.path);
+            let dest_dir = root.join("backups");
+            let options = BackupOptions {
+                format: format.to_string(),
+                scope: "worlds".to_string(),
+            };
+            let manifest = create_backup(&server, &dest_dir, &options).unwrap();
+            assert_eq!(manifest.worlds, [WORLD]);
+            assert_eq!(manifest.file_count, 3);
+            verify_backup(&dest_dir, server.id, &manifest.name).unwrap();
+
+            // changes made after the backup are undone, files outside of the world stay
+            fs::write(server_path.join(WORLD).join("level.dat"), "changed").unwrap();
+            fs::write(server_path.join(WORLD).join("db/000099.ldb"), "new").unwrap();
+            fs::write(
+                server_path.join("server.properties"),
+                "level-name=Bedrock level\nmotd=x\n",
+            )
+            .unwrap();
+            restore_backup(&dest_dir, server.id, &manifest.name, server_path).unwrap();
+            assert_eq!(
+                fs::read(server_path.join(WORLD).join("level.dat")).unwrap(),
+                [8, 0, 0, 0, 1, 2, 3],
+                "{}",
+                format
+            );
+            assert_eq!(
+                fs::read(server_path.join(WORLD).join("db/000005.ldb")).unwrap(),
+                vec![7; 4096]
+            );
+            assert!(!server_path.join(WORLD).join("db/000099.ldb").exists());
+            assert!(fs::read_to_string(server_path.join("server.properties"))
+                .unwrap()
+                .contains("motd=x"));
+
+            // another server can't restore it
+            assert!(
+                restore_backup(&dest_dir, Uuid::new_v4(), &manifest.name, server_path).is_err()
+            );
+            fs::remove_dir_all(root).unwrap();
+        }
+    }
+
+    #[test]
+    fn restore_rejects_worlds_outside_of_the_server() {
+        let root = std::env::temp_dir().join(format!("backup-{}", Uuid::new_v4()));
+        let server = bedrock_server(&root);
+        let dest_dir = root.join("backups");
+        let options = BackupOptions {
+            format: "zip".to_string(),
+            scope: "worlds".to_string(),
+        };
+        let manifest = create_backup(&server, &dest_dir, &options).unwrap();
+        let target = root.join("server");
+        for world in ["../backups", "worlds/../..", "/tmp", ""] {
+            let tampered = BackupManifest {
+                worlds: vec![world.to_string()],
+                ..manifest.clone()
+            };
+            fs::write(
+                manifest_path(&dest_dir, &manifest.name),
+                serde_json::to_string(&tampered).unwrap(),
+            )
+            .unwrap();
+            assert!(restore_backup(&dest_dir, server.id, &manifest.name, &target).is_err());
+        }
+        assert!(dest_dir.join(&manifest.archive).exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
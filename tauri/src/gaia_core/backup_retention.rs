@@ -0,0 +1,341 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::Path};
+use uuid::Uuid;
+
+use crate::gaia_core::{
+    backup::{self, BackupManifest},
+    core_structs::ServerStruct,
+};
+
+const HOUR: u64 = 3600;
+const DAY: u64 = 24 * HOUR;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RetentionPolicy {
+    pub keep_last: u32,
+    // newest backup of each of the last N hours/days/weeks
+    pub keep_hourly: u32,
+    pub keep_daily: u32,
+    pub keep_weekly: u32,
+    // 0 is no cap, otherwise the oldest kept backups go until everything fits
+    pub max_total_mb: u64,
+}
+
+impl RetentionPolicy {
+    pub fn from_server(server: &ServerStruct) -> RetentionPolicy {
+        RetentionPolicy {
+            keep_last: server.backup_keep_last,
+            keep_hourly: server.backup_keep_hourly,
+            keep_daily: server.backup_keep_daily,
+            keep_weekly: server.backup_keep_weekly,
+            max_total_mb: server.backup_max_total_mb,
+        }
+    }
+
+    // A policy with every rule off keeps everything instead of deleting every backup
+    pub fn is_enabled(&self) -> bool {
+        self.keep_last > 0
+            || self.keep_hourly > 0
+            || self.keep_daily > 0
+            || self.keep_weekly > 0
+            || self.max_total_mb > 0
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RetentionDecision {
+    pub name: String,
+    pub created: String,
+    pub size: u64,
+    pub keep: bool,
+    // the rules that kept it, or why it goes
+    pub reasons: Vec<String>,
+}
+
+// Keeps the newest backup of each bucket until count buckets are covered
+fn keep_buckets(
+    backups: &[BackupManifest],
+    decisions: &mut [RetentionDecision],
+    count: u32,
+    bucket: impl Fn(u64) -> u64,
+    reason: &str,
+) {
+    let mut seen = HashSet::new();
+    for (backup, decision) in backups.iter().zip(decisions.iter_mut()) {
+        if seen.len() >= count as usize {
+            break;
+        }
+        if seen.insert(bucket(backup.created_unix)) {
+            decision.keep = true;
+            decision.reasons.push(reason.to_string());
+        }
+    }
+}
+
+// Decides for every backup whether the policy keeps it, backups have to be newest first
+pub fn plan(backups: &[BackupManifest], policy: &RetentionPolicy) -> Vec<RetentionDecision> {
+    let mut decisions: Vec<RetentionDecision> = backups
+        .iter()
+        .map(|backup| RetentionDecision {
+            name: backup.name.clone(),
+            created: backup.created.clone(),
+            size: backup.size,
+            keep: !policy.is_enabled(),
+            reasons: Vec::new(),
+        })
+        .collect();
+    if !policy.is_enabled() {
+        return decisions;
+    }
+    for decision in decisions.iter_mut().take(policy.keep_last as usize) {
+        decision.keep = true;
+        decision.reasons.push("last".to_string());
+    }
+    keep_buckets(
+        backups,
+        &mut decisions,
+        policy.keep_hourly,
+        |time| time / HOUR,
+        "hourly",
+    );
+    keep_buckets(
+        backups,
+        &mut decisions,
+        policy.keep_daily,
+        |time| time / DAY,
+        "daily",
+    );
+    // the unix epoch was a thursday, shifting by 3 days makes weeks start on monday
+    keep_buckets(
+        backups,
+        &mut decisions,
+        policy.keep_weekly,
+        |time| (time / DAY + 3) / 7,
+        "weekly",
+    );
+    // only the size cap on its own, keep everything it allows
+    let size_only = policy.keep_last == 0
+        && policy.keep_hourly == 0
+        && policy.keep_daily == 0
+        && policy.keep_weekly == 0;
+    for decision in decisions.iter_mut() {
+        if size_only {
+            decision.keep = true;
+        } else if !decision.keep {
+            decision.reasons.push("not covered by any rule".to_string());
+        }
+    }
+    if policy.max_total_mb > 0 {
+        let cap = policy.max_total_mb * 1024 * 1024;
+        let mut total = 0;
+        // the newest backup is never dropped for size, it would leave nothing behind
+        for (index, decision) in decisions.iter_mut().enumerate() {
+            if !decision.keep {
+                continue;
+            }
+            total += decision.size;
+            if total > cap && index > 0 {
+                decision.keep = false;
+                decision.reasons = vec![format!("over the {} MB size cap", policy.max_total_mb)];
+            }
+        }
+    }
+    decisions
+}
+
+// Deletes every backup of the server the policy doesn't keep, returns what was deleted
+pub fn apply(
+    dest_dir: &Path,
+    server_id: Uuid,
+    policy: &RetentionPolicy,
+) -> Result<Vec<RetentionDecision>, String> {
+    let removed: Vec<RetentionDecision> = plan(&backup::list_backups(dest_dir, server_id), policy)
+        .into_iter()
+        .filter(|decision| !decision.keep)
+        .collect();
+    if removed.is_empty() {
+        return Ok(removed);
+    }
+    let names: Vec<String> = removed
+        .iter()
+        .map(|decision| decision.name.clone())
+        .collect();
+    backup::delete_backups(dest_dir, server_id, &names)?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const MB: u64 = 1024 * 1024;
+    // 2024-01-01 00:00:00 UTC, a monday
+    const MONDAY: u64 = 1_704_067_200;
+
+    fn backup(name: &str, created_unix: u64, size: u64) -> BackupManifest {
+        BackupManifest {
+            name: name.to_string(),
+            created: name.to_string(),
+            created_unix,
+            size,
+            ..Default::default()
+        }
+    }
+
+    fn kept(decisions: &[RetentionDecision]) -> Vec<&str> {
+        decisions
+            .iter()
+            .filter(|decision| decision.keep)
+            .map(|decision| decision.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn disabled_policy_keeps_everything() {
+        let backups = [backup("a", MONDAY, MB), backup("b", MONDAY - 1, MB)];
+        let decisions = plan(&backups, &RetentionPolicy::default());
+        assert_eq!(kept(&decisions), ["a", "b"]);
+    }
+
+    #[test]
+    fn keeps_the_last_backups() {
+        let backups: Vec<BackupManifest> = (0..5)
+            .map(|index| backup(&index.to_string(), MONDAY - index * HOUR, MB))
+            .collect();
+        let policy = RetentionPolicy {
+            keep_last: 2,
+            ..Default::default()
+        };
+        let decisions = plan(&backups, &policy);
+        assert_eq!(kept(&decisions), ["0", "1"]);
+        assert_eq!(decisions[0].reasons, ["last"]);
+        assert_eq!(decisions[4].reasons, ["not covered by any rule"]);
+    }
+
+    #[test]
+    fn keeps_the_newest_of_each_hour_and_day() {
+        let backups = [
+            backup("10:50", MONDAY + 10 * HOUR + 50 * 60, MB),
+            backup("10:10", MONDAY + 10 * HOUR + 10 * 60, MB),
+            backup("09:30", MONDAY + 9 * HOUR + 30 * 60, MB),
+            backup("08:59", MONDAY + 8 * HOUR + 59 * 60, MB),
+            backup("yesterday", MONDAY - HOUR, MB),
+            backup("two days ago", MONDAY - DAY - HOUR, MB),
+        ];
+        let hourly = RetentionPolicy {
+            keep_hourly: 2,
+            ..Default::default()
+        };
+        assert_eq!(kept(&plan(&backups, &hourly)), ["10:50", "09:30"]);
+        let daily = RetentionPolicy {
+            keep_daily: 2,
+            ..Default::default()
+        };
+        assert_eq!(kept(&plan(&backups, &daily)), ["10:50", "yesterday"]);
+        let both = RetentionPolicy {
+            keep_hourly: 1,
+            keep_daily: 3,
+            ..Default::default()
+        };
+        let decisions = plan(&backups, &both);
+        assert_eq!(kept(&decisions), ["10:50", "yesterday", "two days ago"]);
+        assert_eq!(decisions[0].reasons, ["hourly", "daily"]);
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        let backups = [
+            backup("monday", MONDAY + 12 * HOUR, MB),
+            backup("sunday", MONDAY - 12 * HOUR, MB),
+            backup("saturday", MONDAY - DAY - 12 * HOUR, MB),
+            backup("last monday", MONDAY - 7 * DAY, MB),
+            backup("sunday before", MONDAY - 8 * DAY, MB),
+        ];
+        let policy = RetentionPolicy {
+            keep_weekly: 3,
+            ..Default::default()
+        };
+        // sunday belongs to the week before monday, not the same one as it would from thursday
+        assert_eq!(
+            kept(&plan(&backups, &policy)),
+            ["monday", "sunday", "sunday before"]
+        );
+    }
+
+    #[test]
+    fn size_cap_alone_keeps_the_newest_that_fit() {
+        let backups = [
+            backup("a", MONDAY, 400 * 1024),
+            backup("b", MONDAY - HOUR, 400 * 1024),
+            backup("c", MONDAY - 2 * HOUR, 400 * 1024),
+            backup("d", MONDAY - 3 * HOUR, 100 * 1024),
+        ];
+        let policy = RetentionPolicy {
+            max_total_mb: 1,
+            ..Default::default()
+        };
+        let decisions = plan(&backups, &policy);
+        assert_eq!(kept(&decisions), ["a", "b"]);
+        assert!(decisions[0].reasons.is_empty());
+        assert_eq!(decisions[2].reasons, ["over the 1 MB size cap"]);
+    }
+
+    #[test]
+    fn size_cap_never_drops_the_newest() {
+        let backups = [
+            backup("huge", MONDAY, 5 * MB),
+            backup("older", MONDAY - HOUR, MB),
+        ];
+        let size_only = RetentionPolicy {
+            max_total_mb: 1,
+            ..Default::default()
+        };
+        assert_eq!(kept(&plan(&backups, &size_only)), ["huge"]);
+        let with_rules = RetentionPolicy {
+            keep_last: 2,
+            max_total_mb: 1,
+            ..Default::default()
+        };
+        assert_eq!(kept(&plan(&backups, &with_rules)), ["huge"]);
+    }
+
+    #[test]
+    fn apply_only_deletes_backups_of_that_server() {
+        let dest_dir = std::env::temp_dir().join(format!("retention-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dest_dir).unwrap();
+        let (ours, theirs) = (Uuid::new_v4(), Uuid::new_v4());
+        for (index, server_id) in [ours, theirs, ours, theirs].into_iter().enumerate() {
+            let manifest = BackupManifest {
+                archive: format!("{}.zip", index),
+                server_id,
+                format: "zip".to_string(),
+                ..backup(&index.to_string(), MONDAY - index as u64 * HOUR, MB)
+            };
+            fs::write(dest_dir.join(&manifest.archive), "zip").unwrap();
+            fs::write(
+                dest_dir.join(format!("{}.json", index)),
+                serde_json::to_string(&manifest).unwrap(),
+            )
+            .unwrap();
+        }
+        let policy = RetentionPolicy {
+            keep_last: 1,
+            ..Default::default()
+        };
+        let removed = apply(&dest_dir, ours, &policy).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].name, "2");
+        let names = |server_id| -> Vec<String> {
+            backup::list_backups(&dest_dir, server_id)
+                .into_iter()
+                .map(|backup| backup.name)
+                .collect()
+        };
+        assert_eq!(names(ours), ["0"]);
+        assert_eq!(names(theirs), ["1", "3"]);
+        assert!(!dest_dir.join("2.zip").exists());
+        assert!(backup::load_backup(&dest_dir, ours, "1").is_err());
+        fs::remove_dir_all(dest_dir).unwrap();
+    }
+}
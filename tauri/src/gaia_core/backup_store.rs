@@ -0,0 +1,411 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+// Files are split into fixed size chunks, region files mostly rewrite their sectors in place
+// so an hourly snapshot only stores the chunks of the world that actually changed
+const CHUNK_SIZE: usize = 1024 * 1024;
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SnapshotFile {
+    pub path: String,
+    pub size: u64,
+    // sha256 of every chunk in order
+    pub chunks: Vec<String>,
+}
+
+// What one incremental backup contains, the data itself lives in the shared blob store
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Snapshot {
+    // every folder, so empty ones come back on restore too
+    pub directories: Vec<String>,
+    pub files: Vec<SnapshotFile>,
+}
+
+fn blobs_dir(dest_dir: &Path) -> PathBuf {
+    dest_dir.join("blobs")
+}
+
+fn blob_path(dest_dir: &Path, hash: &str) -> PathBuf {
+    blobs_dir(dest_dir)
+        .join(hash.get(..2).unwrap_or("00"))
+        .join(format!("{}.zst", hash))
+}
+
+// Joins a path from a snapshot onto target, refusing anything that would escape it
+fn safe_join(target: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(format!("Invalid path in snapshot: {:?}", relative));
+    }
+    Ok(target.join(relative))
+}
+
+// Stores a chunk unless a blob with the same hash already exists, returns the bytes written
+fn store_chunk(dest_dir: &Path, hash: &str, data: &[u8]) -> Result<u64, String> {
+    let path = blob_path(dest_dir, hash);
+    if path.exists() {
+        return Ok(0);
+    }
+    let parent = path.parent().unwrap();
+    fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    let compressed = zstd::encode_all(data, ZSTD_LEVEL).map_err(|err| err.to_string())?;
+    // written next to the blob first so a cancelled backup never leaves half a blob behind
+    let partial = parent.join(format!("{}.partial", hash));
+    fs::write(&partial, &compressed).map_err(|err| err.to_string())?;
+    fs::rename(&partial, &path).map_err(|err| err.to_string())?;
+    Ok(compressed.len() as u64)
+}
+
+fn read_chunk(dest_dir: &Path, hash: &str) -> Result<Vec<u8>, String> {
+    let file =
+        File::open(blob_path(dest_dir, hash)).map_err(|_| format!("Blob {} is missing", hash))?;
+    zstd::decode_all(BufReader::new(file))
+        .map_err(|err| format!("Blob {} is corrupt: {}", hash, err))
+}
+
+fn hash_bytes(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+// Fills the buffer as far as the file goes, a plain read can return less on its own
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+// Chunks every file into the blob store and writes the snapshot to snapshot_path,
+// returns how many bytes of new blobs had to be stored
+pub fn write_snapshot(
+    snapshot_path: &Path,
+    dest_dir: &Path,
+    files: &[(PathBuf, String)],
+    directories: &[String],
+) -> Result<u64, String> {
+    let mut snapshot = Snapshot {
+        directories: directories.to_vec(),
+        files: Vec::with_capacity(files.len()),
+    };
+    let mut stored = 0;
+    let mut buffer = vec![0; CHUNK_SIZE];
+    for (path, name) in files {
+        let mut file = BufReader::new(File::open(path).map_err(|err| err.to_string())?);
+        let mut entry = SnapshotFile {
+            path: name.clone(),
+            size: 0,
+            chunks: Vec::new(),
+        };
+        loop {
+            let read = read_full(&mut file, &mut buffer).map_err(|err| err.to_string())?;
+            if read == 0 {
+                break;
+            }
+            let hash = hash_bytes(&buffer[..read]);
+            stored += store_chunk(dest_dir, &hash, &buffer[..read])?;
+            entry.size += read as u64;
+            entry.chunks.push(hash);
+        }
+        snapshot.files.push(entry);
+    }
+    let content = serde_json::to_vec(&snapshot).map_err(|err| err.to_string())?;
+    fs::write(snapshot_path, &content).map_err(|err| err.to_string())?;
+    Ok(stored + content.len() as u64)
+}
+
+pub fn load_snapshot(snapshot_path: &Path) -> Result<Snapshot, String> {
+    let content = fs::read(snapshot_path).map_err(|err| err.to_string())?;
+    serde_json::from_slice(&content).map_err(|err| err.to_string())
+}
+
+// Decompresses every chunk the snapshot needs and checks it against its hash,
+// returns the file count
+pub fn verify_snapshot(snapshot_path: &Path, dest_dir: &Path) -> Result<u64, String> {
+    let snapshot = load_snapshot(snapshot_path)?;
+    // chunk sizes by hash, every blob only gets decompressed once
+    let mut checked: HashMap<String, u64> = HashMap::new();
+    for file in &snapshot.files {
+        let mut size = 0;
+        for hash in &file.chunks {
+            if let Some(chunk_size) = checked.get(hash) {
+                size += chunk_size;
+                continue;
+            }
+            let data = read_chunk(dest_dir, hash)?;
+            if hash_bytes(&data) != *hash {
+                return Err(format!("Blob {} does not match its hash", hash));
+            }
+            size += data.len() as u64;
+            checked.insert(hash.clone(), data.len() as u64);
+        }
+        if size != file.size {
+            return Err(format!("{} does not add up to its size", file.path));
+        }
+    }
+    Ok(snapshot.files.len() as u64)
+}
+
+// Rebuilds the tree the snapshot was taken of inside of target
+pub fn restore_snapshot(
+    snapshot_path: &Path,
+    dest_dir: &Path,
+    target: &Path,
+) -> Result<(), String> {
+    let snapshot = load_snapshot(snapshot_path)?;
+    for directory in &snapshot.directories {
+        fs::create_dir_all(safe_join(target, directory)?).map_err(|err| err.to_string())?;
+    }
+    for file in &snapshot.files {
+        let path = safe_join(target, &file.path)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let mut writer = BufWriter::new(File::create(&path).map_err(|err| err.to_string())?);
+        let mut size = 0;
+        for hash in &file.chunks {
+            let data = read_chunk(dest_dir, hash)?;
+            size += data.len() as u64;
+            writer.write_all(&data).map_err(|err| err.to_string())?;
+        }
+        writer.flush().map_err(|err| err.to_string())?;
+        if size != file.size {
+            return Err(format!("{} restored with the wrong size", file.path));
+        }
+    }
+    Ok(())
+}
+
+// Removes blobs no snapshot points to anymore, returns the bytes freed
+pub fn prune_blobs(dest_dir: &Path, snapshot_paths: &[PathBuf]) -> Result<u64, String> {
+    let mut referenced = HashSet::new();
+    for path in snapshot_paths {
+        // if a snapshot can't be read its blobs can't be told apart, so keep everything
+        let snapshot = load_snapshot(path)
+            .map_err(|err| format!("Not pruning, failed to read {:?}: {}", path, err))?;
+        for file in snapshot.files {
+            referenced.extend(file.chunks);
+        }
+    }
+    let Ok(prefixes) = fs::read_dir(blobs_dir(dest_dir)) else {
+        return Ok(0);
+    };
+    let mut freed = 0;
+    for prefix in prefixes.flatten() {
+        let Ok(blobs) = fs::read_dir(prefix.path()) else {
+            continue;
+        };
+        for blob in blobs.flatten() {
+            let name = blob.file_name().to_string_lossy().to_string();
+            // partial blobs belong to a backup that's still being written
+            let Some(hash) = name.strip_suffix(".zst") else {
+                continue;
+            };
+            if referenced.contains(hash) {
+                continue;
+            }
+            let size = blob.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            if fs::remove_file(blob.path()).is_ok() {
+                freed += size;
+            }
+        }
+    }
+    Ok(freed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Bytes that don't repeat per chunk, so every chunk of the file gets its own blob
+    fn noise(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    struct Store {
+        root: PathBuf,
+        source: PathBuf,
+        dest: PathBuf,
+    }
+
+    impl Store {
+        fn new() -> Store {
+            let root = std::env::temp_dir().join(format!("backup-store-{}", uuid::Uuid::new_v4()));
+            let source = root.join("source");
+            let dest = root.join("dest");
+            fs::create_dir_all(source.join("world/region")).unwrap();
+            fs::create_dir_all(source.join("world/empty")).unwrap();
+            fs::create_dir_all(&dest).unwrap();
+            fs::write(
+                source.join("server.properties"),
+                "motd=A Minecraft Server\n",
+            )
+            .unwrap();
+            fs::write(source.join("world/session.lock"), []).unwrap();
+            fs::write(
+                source.join("world/region/r.0.0.mca"),
+                noise(CHUNK_SIZE * 2 + 12_345, 1),
+            )
+            .unwrap();
+            Store { root, source, dest }
+        }
+
+        fn snapshot(&self, name: &str) -> (PathBuf, u64) {
+            let files: Vec<(PathBuf, String)> = [
+                "server.properties",
+                "world/session.lock",
+                "world/region/r.0.0.mca",
+            ]
+            .iter()
+            .map(|name| (self.source.join(name), name.to_string()))
+            .collect();
+            let directories = ["world", "world/empty", "world/region"].map(String::from);
+            let path = self.dest.join(name);
+            let stored = write_snapshot(&path, &self.dest, &files, &directories).unwrap();
+            (path, stored)
+        }
+
+        fn blob_count(&self) -> usize {
+            fs::read_dir(blobs_dir(&self.dest))
+                .unwrap()
+                .flatten()
+                .flat_map(|prefix| fs::read_dir(prefix.path()).unwrap().flatten())
+                .filter(|blob| blob.file_name().to_string_lossy().ends_with(".zst"))
+                .count()
+        }
+    }
+
+    impl Drop for Store {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn restores_the_tree_byte_for_byte() {
+        let store = Store::new();
+        let (snapshot, _) = store.snapshot("first.json");
+        assert_eq!(verify_snapshot(&snapshot, &store.dest).unwrap(), 3);
+        let big = load_snapshot(&snapshot).unwrap().files[2].clone();
+        assert_eq!(big.chunks.len(), 3);
+        assert_eq!(big.size, CHUNK_SIZE as u64 * 2 + 12_345);
+
+        let target = store.root.join("restored");
+        restore_snapshot(&snapshot, &store.dest, &target).unwrap();
+        assert!(target.join("world/empty").is_dir());
+        for name in [
+            "server.properties",
+            "world/session.lock",
+            "world/region/r.0.0.mca",
+        ] {
+            assert_eq!(
+                fs::read(target.join(name)).unwrap(),
+                fs::read(store.source.join(name)).unwrap(),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(
+            fs::metadata(target.join("world/session.lock"))
+                .unwrap()
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn unchanged_files_store_no_new_blobs() {
+        let store = Store::new();
+        let (_, first) = store.snapshot("first.json");
+        let blobs = store.blob_count();
+        let (second, stored) = store.snapshot("second.json");
+        // only the snapshot itself is new
+        assert_eq!(stored, fs::metadata(&second).unwrap().len());
+        assert!(first > stored);
+        assert_eq!(store.blob_count(), blobs);
+    }
+
+    #[test]
+    fn prunes_only_unreferenced_blobs() {
+        let store = Store::new();
+        let (first, _) = store.snapshot("first.json");
+        let blobs = store.blob_count();
+        // change the last chunk of the region file only
+        let region = store.source.join("world/region/r.0.0.mca");
+        let mut bytes = fs::read(&region).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&region, bytes).unwrap();
+        let (second, _) = store.snapshot("second.json");
+        assert_eq!(store.blob_count(), blobs + 1);
+        let partial = blobs_dir(&store.dest).join("00").join("abc.partial");
+        fs::create_dir_all(partial.parent().unwrap()).unwrap();
+        fs::write(&partial, "still being written").unwrap();
+
+        // both snapshots still point at every blob
+        assert_eq!(
+            prune_blobs(&store.dest, &[first.clone(), second.clone()]).unwrap(),
+            0
+        );
+        fs::remove_file(&first).unwrap();
+        assert!(prune_blobs(&store.dest, std::slice::from_ref(&second)).unwrap() > 0);
+        assert_eq!(store.blob_count(), blobs);
+        assert!(partial.exists());
+        assert_eq!(verify_snapshot(&second, &store.dest).unwrap(), 3);
+        // an unreadable snapshot stops pruning instead of dropping its blobs
+        assert!(prune_blobs(&store.dest, &[first]).is_err());
+    }
+
+    #[test]
+    fn rejects_paths_outside_of_the_target() {
+        let store = Store::new();
+        let target = store.root.join("restored");
+        for snapshot in [
+            Snapshot {
+                directories: vec!["../escaped".to_string()],
+                files: Vec::new(),
+            },
+            Snapshot {
+                directories: Vec::new(),
+                files: vec![SnapshotFile {
+                    path: "world/../../escaped.txt".to_string(),
+                    size: 0,
+                    chunks: Vec::new(),
+                }],
+            },
+            Snapshot {
+                directories: Vec::new(),
+                files: vec![SnapshotFile {
+                    path: "/tmp/escaped.txt".to_string(),
+                    size: 0,
+                    chunks: Vec::new(),
+                }],
+            },
+        ] {
+            let path = store.dest.join("evil.json");
+            fs::write(&path, serde_json::to_vec(&snapshot).unwrap()).unwrap();
+            assert!(restore_snapshot(&path, &store.dest, &target).is_err());
+        }
+        assert!(!store.root.join("escaped").exists());
+        assert!(!store.root.join("escaped.txt").exists());
+    }
+}
@@ -0,0 +1,186 @@
+use fs_more::directory::{
+    copy_directory, BrokenSymlinkBehaviour, DestinationDirectoryRule, DirectoryCopyDepthLimit,
+    DirectoryCopyOptions, SymlinkBehaviour,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::gaia_core::{
+    core_structs::ServerStruct,
+    ports::server_ports,
+    raknet,
+    server_properties::set_property,
+    supervisor::{PlayersPayload, Supervisor},
+};
+
+const BINARY: &str = "bedrock_server";
+const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+pub const PERMISSION_LEVELS: [&str; 3] = ["visitor", "member", "operator"];
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct AllowlistEntry {
+    #[serde(rename = "ignoresPlayerLimit", default)]
+    pub ignores_player_limit: bool,
+    pub name: String,
+    // filled in by the server the first time the player joins
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub xuid: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct PermissionEntry {
+    // "visitor", "member" or "operator"
+    pub permission: String,
+    pub xuid: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct BedrockAccess {
+    pub allowlist: Vec<AllowlistEntry>,
+    pub permissions: Vec<PermissionEntry>,
+}
+
+pub fn is_bedrock(server_type: &str) -> bool {
+    server_type == "bedrock"
+}
+
+// "bedrock-server-1.21.50.07.zip" is how mojang names the download
+fn version_from_name(source: &Path) -> String {
+    source
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .and_then(|name| name.strip_prefix("bedrock-server-").map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// Unpacks the dedicated server zip, or copies an already unpacked folder, into the server's
+// folder. Returns the path of bedrock_server and the version it was named with
+pub fn provision(server: &ServerStruct, source: &Path) -> Result<(PathBuf, String), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("Bedrock Dedicated Server is only supported on Linux".to_string());
+    }
+    let directory = Path::new(&server.path);
+    fs::create_dir_all(directory).map_err(|err| err.to_string())?;
+    if source.is_dir() {
+        copy_directory(
+            source,
+            directory,
+            DirectoryCopyOptions {
+                destination_directory_rule: DestinationDirectoryRule::AllowEmpty,
+                copy_depth_limit: DirectoryCopyDepthLimit::Unlimited,
+                symlink_behaviour: SymlinkBehaviour::Keep,
+                broken_symlink_behaviour: BrokenSymlinkBehaviour::Keep,
+            },
+        )
+        .map_err(|err| err.to_string())?;
+    } else {
+        let file =
+            File::open(source).map_err(|err| format!("Failed to open {:?}: {}", source, err))?;
+        zip::ZipArchive::new(file)
+            .and_then(|mut archive| archive.extract(directory))
+            .map_err(|err| format!("Failed to unpack {:?}: {}", source, err))?;
+    }
+    let binary = directory.join(BINARY);
+    if !binary.is_file() {
+        return Err(format!("{:?} doesn't contain {}", source, BINARY));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755))
+            .map_err(|err| err.to_string())?;
+    }
+    set_property(directory, "server-name", &server.name)?;
+    set_property(directory, "server-port", &server.port.to_string())?;
+    // the ipv6 port has to be free as well, it's kept next to the ipv4 one
+    set_property(
+        directory,
+        "server-portv6",
+        &server.port.saturating_add(1).to_string(),
+    )?;
+    Ok((binary, version_from_name(source)))
+}
+
+fn read_list<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Vec<T>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) if !content.trim().is_empty() => {
+            serde_json::from_str(&content).map_err(|err| format!("Invalid {:?}: {}", path, err))
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn write_list<T: Serialize>(path: &Path, list: &[T]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(list).map_err(|err| err.to_string())?;
+    fs::write(path, content).map_err(|err| format!("Failed to write {:?}: {}", path, err))
+}
+
+pub fn read_access(server: &ServerStruct) -> Result<BedrockAccess, String> {
+    let directory = Path::new(&server.path);
+    Ok(BedrockAccess {
+        allowlist: read_list(&directory.join("allowlist.json"))?,
+        permissions: read_list(&directory.join("permissions.json"))?,
+    })
+}
+
+pub fn write_access(server: &ServerStruct, access: &BedrockAccess) -> Result<(), String> {
+    if let Some(entry) = access
+        .allowlist
+        .iter()
+        .find(|entry| entry.name.trim().is_empty())
+    {
+        return Err(format!(
+            "An allowlist entry with xuid {} has no name",
+            entry.xuid
+        ));
+    }
+    for entry in &access.permissions {
+        if entry.xuid.is_empty() || !entry.xuid.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("\"{}\" is not a xuid", entry.xuid));
+        }
+        if !PERMISSION_LEVELS.contains(&entry.permission.as_str()) {
+            return Err(format!("Unknown permission level {}", entry.permission));
+        }
+    }
+    let directory = Path::new(&server.path);
+    write_list(&directory.join("allowlist.json"), &access.allowlist)?;
+    write_list(&directory.join("permissions.json"), &access.permissions)
+}
+
+// Bedrock has no "Done" line that can be trusted, the server counts as ready once it answers
+// a raknet ping. The same pings report the player count while it runs
+pub fn spawn_probe<R: Runtime>(app: AppHandle<R>, server: ServerStruct) {
+    let port = server_ports(&server)
+        .first()
+        .map_or(server.port, |(_, port)| *port);
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    thread::spawn(move || {
+        let supervisor = app.state::<Supervisor>();
+        let mut players = None;
+        while supervisor.is_running(server.id) {
+            if let Ok(pong) = raknet::ping(address, PROBE_TIMEOUT) {
+                supervisor.mark_ready(server.id);
+                if players != Some(pong.players) {
+                    players = Some(pong.players);
+                    let _ = app.emit(
+                        "server_players",
+                        PlayersPayload {
+                            id: server.id,
+                            count: pong.players as usize,
+                            players: Vec::new(),
+                        },
+                    );
+                }
+            }
+            thread::sleep(PROBE_INTERVAL);
+        }
+    });
+}
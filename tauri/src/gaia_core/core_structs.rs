@@ -1,11 +1,23 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::gaia_core::scheduler::ScheduledTask;
+
+// Values for ServerStruct::status
+pub const STATUS_OFFLINE: i8 = 0;
+pub const STATUS_ONLINE: i8 = 1;
+
 #[derive(Deserialize, Serialize)]
+#[serde(default)]
 pub struct Config {
     pub auto_update: bool,
     pub auto_start: bool,
     pub hide_ip: bool,
+    // "endpoint", "manual" or "router"
+    pub public_ip_source: String,
+    // has to answer with just the address, only plain http is supported
+    pub public_ip_endpoint: String,
+    pub public_ip_manual: String,
     pub startup_display_id: i16,
     pub start_minimized: bool,
     pub start_in_tray: bool,
@@ -42,6 +54,9 @@ impl Default for Config {
             auto_update: true,
             auto_start: false,
             hide_ip: false,
+            public_ip_source: "endpoint".to_string(),
+            public_ip_endpoint: "http://api.ipify.org".to_string(),
+            public_ip_manual: "".to_string(),
             startup_display_id: 0,
             start_minimized: false,
             start_in_tray: false,
@@ -85,6 +100,7 @@ impl Default for InterfaceConsoleConfig {
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
 pub struct ServerStruct {
     pub id: Uuid,
     pub status: i8,
@@ -93,6 +109,8 @@ pub struct ServerStruct {
     pub port: u16,
     pub public_ip: String,
     pub local_ip: String,
+    // opens the server's ports on the router over UPnP or NAT-PMP while it runs
+    pub port_forwarding: bool,
     pub path: String,
     pub icon_path: String,
     pub server_type: String,
@@ -101,12 +119,53 @@ pub struct ServerStruct {
     pub server_version: String,
     pub creation_date: String,
     pub auto_start: bool,
+    // servers auto start from the lowest order up
+    pub auto_start_order: u32,
+    pub auto_start_delay_seconds: u64,
+    // waits until this server logged "Done" before auto starting
+    pub auto_start_after: Option<Uuid>,
     pub force_save_on_stop: bool,
+    // "never", "on-crash" or "always"
+    pub restart_policy: String,
+    // restarts allowed inside of restart_window_seconds before giving up
+    pub max_restarts: u32,
+    pub restart_window_seconds: u64,
+    // delay before the first restart, doubles with every restart in the window
+    pub restart_backoff_seconds: u64,
+    // crashing this soon after starting counts towards crash_loop_limit
+    pub crash_loop_seconds: u64,
+    pub crash_loop_limit: u32,
+    // when players get warned before a countdown restart, e.g. "10m, 5m, 1m, 30s, 10s"
+    pub restart_countdown: String,
+    // "say" or "tellraw"
+    pub restart_countdown_format: String,
+    // {time} is replaced with what's left, tellraw also takes a json text component
+    pub restart_countdown_message: String,
+    // warn in the console when tps stays under the threshold this long
+    pub tps_alert_enabled: bool,
+    pub tps_alert_threshold: f64,
+    pub tps_alert_seconds: u64,
+    // empty keeps backups in the app data folder
+    pub backup_path: String,
+    // "zip", "tar.zst" or "incremental"
+    pub backup_format: String,
+    // "worlds" or "full"
+    pub backup_scope: String,
+    // retention, 0 turns a rule off
+    pub backup_keep_last: u32,
+    pub backup_keep_hourly: u32,
+    pub backup_keep_daily: u32,
+    pub backup_keep_weekly: u32,
+    pub backup_max_total_mb: u64,
     pub java_path: String,
     pub java_allocated_memory: i64,
     pub java_startup_line: String,
     pub current_player_count: i32,
     pub max_players: i32,
+    pub tasks: Vec<ScheduledTask>,
+    // proxy this server is a backend of, and what the proxy calls it
+    pub proxy_id: Option<Uuid>,
+    pub proxy_name: String,
 }
 
 impl Default for ServerStruct {
@@ -119,6 +178,7 @@ impl Default for ServerStruct {
             port: 25565,
             public_ip: "0.0.0.0".to_string(),
             local_ip: "0.0.0.0".to_string(),
+            port_forwarding: false,
             path: "path".to_string(),
             icon_path: "path".to_string(),
             server_type: "vanilla".to_string(),
@@ -127,12 +187,38 @@ impl Default for ServerStruct {
             server_version: "1.21.10".to_string(),
             creation_date: "today".to_string(),
             auto_start: false,
+            auto_start_order: 0,
+            auto_start_delay_seconds: 0,
+            auto_start_after: None,
             force_save_on_stop: true,
+            restart_policy: "never".to_string(),
+            max_restarts: 5,
+            restart_window_seconds: 3600,
+            restart_backoff_seconds: 10,
+            crash_loop_seconds: 60,
+            crash_loop_limit: 3,
+            restart_countdown: "10m, 5m, 1m, 30s, 10s, 5s, 4s, 3s, 2s, 1s".to_string(),
+            restart_countdown_format: "say".to_string(),
+            restart_countdown_message: "Server restarting in {time}".to_string(),
+            tps_alert_enabled: false,
+            tps_alert_threshold: 15.0,
+            tps_alert_seconds: 60,
+            backup_path: "".to_string(),
+            backup_format: "zip".to_string(),
+            backup_scope: "worlds".to_string(),
+            backup_keep_last: 5,
+            backup_keep_hourly: 24,
+            backup_keep_daily: 7,
+            backup_keep_weekly: 4,
+            backup_max_total_mb: 0,
             java_path: "path".to_string(),
             java_allocated_memory: 4096,
             java_startup_line: "".to_string(),
             current_player_count: 0,
             max_players: 20,
+            tasks: Vec::new(),
+            proxy_id: None,
+            proxy_name: "".to_string(),
         }
     }
 }
@@ -0,0 +1,493 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::gaia_core::mod_scanner::{mod_for_class, ModInfo};
+
+// Only the top of the stack is useful on the summary card
+const MAX_STACK_LINES: usize = 40;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SuspectedMod {
+    pub id: String,
+    pub name: String,
+    // "forge" when the loader named it, "stack" when found from the stack trace
+    pub source: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CrashReport {
+    // "minecraft" for crash-reports/*.txt, "jvm" for hs_err_pid*.log
+    pub kind: String,
+    pub path: String,
+    pub file_name: String,
+    pub exit_code: Option<i32>,
+    pub time: String,
+    pub description: String,
+    pub exception: String,
+    pub stack_trace: Vec<String>,
+    pub suspected_mods: Vec<SuspectedMod>,
+    pub minecraft_version: String,
+    pub java_version: String,
+    pub java_vm: String,
+    pub operating_system: String,
+}
+
+fn modified_since(path: &Path, since: SystemTime) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified >= since)
+}
+
+// Crash reports and jvm error logs written after the server was started, newest first
+pub fn find_new_crash_files(server_path: &Path, since: SystemTime) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(server_path.join("crash-reports")) {
+        files.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == "txt")),
+        );
+    }
+    if let Ok(entries) = fs::read_dir(server_path) {
+        files.extend(entries.flatten().map(|entry| entry.path()).filter(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy())
+                .is_some_and(|name| name.starts_with("hs_err_pid") && name.ends_with(".log"))
+        }));
+    }
+    files.retain(|path| modified_since(path, since));
+    files.sort_by_key(|path| {
+        std::cmp::Reverse(
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+        )
+    });
+    files
+}
+
+// Where a crash file the frontend asks for by name lives, crash-* reports sit in crash-reports/
+// and jvm logs in the server folder. Only bare file names are accepted
+pub fn crash_file_path(server_path: &Path, file_name: &str) -> Result<PathBuf, String> {
+    if file_name.is_empty() || file_name.contains(['/', '\\']) || file_name.starts_with('.') {
+        return Err(format!("{} is not a crash report", file_name));
+    }
+    if file_name.starts_with("crash-") {
+        Ok(server_path.join("crash-reports").join(file_name))
+    } else if file_name.starts_with("hs_err_pid") && file_name.ends_with(".log") {
+        Ok(server_path.join(file_name))
+    } else {
+        Err(format!("{} is not a crash report", file_name))
+    }
+}
+
+// Value of a "Key: value" detail line, crash reports indent these with a tab
+fn detail(content: &str, key: &str) -> String {
+    content
+        .lines()
+        .map(|line| line.trim())
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .map(|value| value.trim().to_string())
+        .unwrap_or_default()
+}
+
+// Parses "Create (create), Flywheel (flywheel)" into (name, id) pairs
+fn parse_mod_list(value: &str) -> Vec<(String, String)> {
+    let mut mods = Vec::new();
+    let mut rest = value;
+    while let Some(open) = rest.find(" (") {
+        let Some(close) = rest[open..].find(')') else {
+            break;
+        };
+        let name = rest[..open].trim().trim_start_matches(',').trim();
+        let id = &rest[open + 2..open + close];
+        if !name.is_empty() && !id.is_empty() {
+            mods.push((name.to_string(), id.to_string()));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    mods
+}
+
+// Forge writes either "Suspected Mods: A (a), B (b)" on one line or
+// "Suspected Mod:" followed by one tab indented "Name (id), Version: x" line per mod
+fn forge_suspected_mods(content: &str) -> Vec<(String, String)> {
+    let mut mods = Vec::new();
+    let mut lines = content.lines().peekable();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        let Some(value) = trimmed
+            .strip_prefix("Suspected Mods:")
+            .or_else(|| trimmed.strip_prefix("Suspected Mod:"))
+        else {
+            continue;
+        };
+        let value = value.trim();
+        if !value.is_empty() {
+            if value != "NONE" {
+                mods.extend(parse_mod_list(value));
+            }
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        while let Some(next) = lines.peek() {
+            let next_indent = next.len() - next.trim_start().len();
+            if next.trim().is_empty() || next_indent <= indent {
+                break;
+            }
+            if next_indent == indent + 1 {
+                let entry = next.trim().split(", Version:").next().unwrap_or_default();
+                mods.extend(parse_mod_list(entry));
+            }
+            lines.next();
+        }
+    }
+    mods
+}
+
+// Class name out of a stack frame, strips forge's "TRANSFORMER/modid@version/" prefix
+fn frame_class(frame: &str) -> Option<(Option<String>, String)> {
+    let frame = frame.trim().strip_prefix("at ")?;
+    let frame = frame.split('(').next()?;
+    let (module, method) = match frame.rsplit_once('/') {
+        Some((module, method)) => (Some(module), method),
+        None => (None, frame),
+    };
+    // jdk modules like "java.base" have dots, mod ids never do
+    let module_mod = module.and_then(|module| {
+        let module = module.rsplit('/').next()?;
+        let id = module.split('@').next()?;
+        match id.is_empty() || id.contains('.') || ["minecraft", "forge", "neoforge"].contains(&id)
+        {
+            true => None,
+            false => Some(id.to_string()),
+        }
+    });
+    let (class, _) = method.rsplit_once('.')?;
+    Some((module_mod, class.to_string()))
+}
+
+fn push_suspect(suspects: &mut Vec<SuspectedMod>, id: String, name: String, source: &str) {
+    if suspects.iter().any(|suspect| suspect.id == id) {
+        return;
+    }
+    suspects.push(SuspectedMod {
+        id,
+        name,
+        source: source.to_string(),
+    });
+}
+
+pub fn parse_minecraft_crash(content: &str, mods: &[ModInfo]) -> CrashReport {
+    let mut report = CrashReport {
+        kind: "minecraft".to_string(),
+        time: detail(content, "Time"),
+        description: detail(content, "Description"),
+        minecraft_version: detail(content, "Minecraft Version"),
+        java_version: detail(content, "Java Version"),
+        java_vm: detail(content, "Java VM Version"),
+        operating_system: detail(content, "Operating System"),
+        ..Default::default()
+    };
+    // The exception and its stack sit right after the description
+    let mut lines = content
+        .lines()
+        .skip_while(|line| !line.starts_with("Description:"))
+        .skip(1)
+        .skip_while(|line| line.trim().is_empty());
+    if let Some(exception) = lines.next() {
+        report.exception = exception.trim().to_string();
+    }
+    report.stack_trace = lines
+        .take_while(|line| !line.trim().is_empty())
+        .take(MAX_STACK_LINES)
+        .map(|line| line.trim().to_string())
+        .collect();
+
+    let mut suspects = Vec::new();
+    for (name, id) in forge_suspected_mods(content) {
+        push_suspect(&mut suspects, id, name, "forge");
+    }
+    for frame in &report.stack_trace {
+        let Some((module_mod, class)) = frame_class(frame) else {
+            continue;
+        };
+        if let Some(info) = mod_for_class(mods, &class) {
+            push_suspect(&mut suspects, info.id.clone(), info.name.clone(), "stack");
+        } else if let Some(id) = module_mod {
+            let name = mods
+                .iter()
+                .find(|info| info.id == id)
+                .map(|info| info.name.clone())
+                .unwrap_or_else(|| id.clone());
+            push_suspect(&mut suspects, id, name, "stack");
+        }
+    }
+    report.suspected_mods = suspects;
+    report
+}
+
+// hs_err_pid logs are written by the jvm itself when it dies (segfaults, out of memory etc.)
+pub fn parse_jvm_crash(content: &str) -> CrashReport {
+    let header: Vec<&str> = content
+        .lines()
+        .take_while(|line| line.starts_with('#'))
+        .map(|line| line.trim_start_matches('#').trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+    let description = header
+        .iter()
+        .position(|line| line.starts_with("A fatal error has been detected"))
+        .and_then(|index| header.get(index + 1))
+        .or_else(|| header.first())
+        .map(|line| line.to_string())
+        .unwrap_or_default();
+    let problematic_frame = header
+        .iter()
+        .position(|line| line.starts_with("Problematic frame:"))
+        .and_then(|index| header.get(index + 1))
+        .map(|line| line.to_string())
+        .unwrap_or_default();
+    let operating_system = content
+        .lines()
+        .skip_while(|line| !line.starts_with("OS:"))
+        .map(|line| line.trim_start_matches("OS:").trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string();
+    let stack_trace = content
+        .lines()
+        .skip_while(|line| !line.starts_with("Java frames:"))
+        .skip(1)
+        .take_while(|line| !line.trim().is_empty())
+        .take(MAX_STACK_LINES)
+        .map(|line| line.trim().to_string())
+        .collect();
+    CrashReport {
+        kind: "jvm".to_string(),
+        description,
+        exception: problematic_frame,
+        stack_trace,
+        java_version: detail(content, "# JRE version"),
+        java_vm: detail(content, "# Java VM"),
+        operating_system,
+        ..Default::default()
+    }
+}
+
+// Picks the newest crash file written since the server started and parses it
+pub fn analyze_crash(
+    server_path: &Path,
+    since: SystemTime,
+    exit_code: Option<i32>,
+    mods: &[ModInfo],
+) -> Option<CrashReport> {
+    let path = find_new_crash_files(server_path, since)
+        .into_iter()
+        .next()?;
+    let bytes = fs::read(&path).ok()?;
+    let content = String::from_utf8_lossy(&bytes);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut report = match file_name.starts_with("hs_err_pid") {
+        true => parse_jvm_crash(&content),
+        false => parse_minecraft_crash(&content, mods),
+    };
+    report.path = path.to_string_lossy().to_string();
+    report.file_name = file_name;
+    report.exit_code = exit_code;
+    Some(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORGE_CRASH: &str = "---- Minecraft Crash Report ----
+// Who set us up the TNT?
+
+Time: 2025-10-26 14:03:11
+Description: Ticking block entity
+
+java.lang.NullPointerException: Cannot invoke \"Object.hashCode()\" because \"key\" is null
+\tat java.base/java.util.HashMap.hash(HashMap.java:338)
+\tat TRANSFORMER/create@0.5.1.f/com.simibubi.create.content.kinetics.base.KineticBlockEntity.tick(KineticBlockEntity.java:120)
+\tat TRANSFORMER/minecraft@1.20.1/net.minecraft.world.level.Level.tickBlockEntities(Level.java:480)
+\tat com.example.mymod.Thing.run(Thing.java:5)
+\tat TRANSFORMER/flywheel@0.6.10/com.jozufozu.flywheel.backend.Backend.refresh(Backend.java:91)
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+
+-- Head --
+Thread: Server thread
+Suspected Mod: 
+\tCreate (create), Version: 0.5.1.f
+\t\tIssue tracker URL: https://github.com/Creators-of-Create/Create/issues
+\t\tat TRANSFORMER/create@0.5.1.f/com.simibubi.create.Create.init(Create.java:10)
+Stacktrace:
+\tat java.base/java.util.HashMap.hash(HashMap.java:338)
+
+-- System Details --
+Details:
+\tMinecraft Version: 1.20.1
+\tJava Version: 17.0.8, Eclipse Adoptium
+\tJava VM Version: OpenJDK 64-Bit Server VM (mixed mode), Eclipse Adoptium
+\tOperating System: Linux (amd64) version 6.1.0
+";
+
+    const JVM_CRASH: &str = "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  SIGSEGV (0xb) at pc=0x00007f3a2c1d4e50, pid=4242, tid=4243
+#
+# JRE version: OpenJDK Runtime Environment Temurin-17.0.8+7 (17.0.8+7) (build 17.0.8+7)
+# Java VM: OpenJDK 64-Bit Server VM Temurin-17.0.8+7 (17.0.8+7, mixed mode, linux-amd64)
+# Problematic frame:
+# C  [liblwjgl.so+0x1234]  Java_org_lwjgl_system_JNI_invoke
+#
+
+---------------  T H R E A D  ---------------
+
+Java frames: (J=compiled Java code, j=interpreted, Vv=VM code)
+j  org.lwjgl.system.JNI.invoke()V+0
+J 1234 c2 net.minecraft.server.MinecraftServer.runServer()V
+
+---------------  S Y S T E M  ---------------
+
+OS:
+PRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\"
+";
+
+    fn my_mod() -> ModInfo {
+        ModInfo {
+            id: "mymod".to_string(),
+            name: "My Mod".to_string(),
+            version: "1.0".to_string(),
+            file_name: "mymod-1.0.jar".to_string(),
+            packages: vec!["com.example.mymod".to_string()],
+        }
+    }
+
+    fn suspects(report: &CrashReport) -> Vec<(&str, &str, &str)> {
+        report
+            .suspected_mods
+            .iter()
+            .map(|suspect| {
+                (
+                    suspect.id.as_str(),
+                    suspect.name.as_str(),
+                    suspect.source.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_the_description_and_details() {
+        let report = parse_minecraft_crash(FORGE_CRASH, &[]);
+        assert_eq!(report.kind, "minecraft");
+        assert_eq!(report.time, "2025-10-26 14:03:11");
+        assert_eq!(report.description, "Ticking block entity");
+        assert_eq!(
+            report.exception,
+            "java.lang.NullPointerException: Cannot invoke \"Object.hashCode()\" because \"key\" is null"
+        );
+        assert_eq!(report.minecraft_version, "1.20.1");
+        assert_eq!(report.java_version, "17.0.8, Eclipse Adoptium");
+        assert_eq!(
+            report.java_vm,
+            "OpenJDK 64-Bit Server VM (mixed mode), Eclipse Adoptium"
+        );
+        assert_eq!(report.operating_system, "Linux (amd64) version 6.1.0");
+    }
+
+    #[test]
+    fn stack_stops_at_the_first_blank_line() {
+        let report = parse_minecraft_crash(FORGE_CRASH, &[]);
+        assert_eq!(report.stack_trace.len(), 5);
+        assert_eq!(
+            report.stack_trace[0],
+            "at java.base/java.util.HashMap.hash(HashMap.java:338)"
+        );
+        assert!(report.stack_trace[4].contains("flywheel@0.6.10"));
+    }
+
+    #[test]
+    fn suspects_come_from_forge_then_the_stack() {
+        let report = parse_minecraft_crash(FORGE_CRASH, &[my_mod()]);
+        // create is named by forge and in the stack but only listed once, minecraft and the jdk
+        // never are
+        assert_eq!(
+            suspects(&report),
+            [
+                ("create", "Create", "forge"),
+                ("mymod", "My Mod", "stack"),
+                ("flywheel", "flywheel", "stack"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_the_one_line_suspected_mods() {
+        let content = "Description: Exception in server tick loop\n\njava.lang.RuntimeException\n\nSuspected Mods: Create (create), Flywheel (flywheel)\n";
+        let report = parse_minecraft_crash(content, &[]);
+        assert_eq!(
+            suspects(&report),
+            [
+                ("create", "Create", "forge"),
+                ("flywheel", "Flywheel", "forge")
+            ]
+        );
+        let none = parse_minecraft_crash("Suspected Mods: NONE\n", &[]);
+        assert!(none.suspected_mods.is_empty());
+    }
+
+    #[test]
+    fn parses_jvm_error_logs() {
+        let report = parse_jvm_crash(JVM_CRASH);
+        assert_eq!(report.kind, "jvm");
+        assert_eq!(
+            report.description,
+            "SIGSEGV (0xb) at pc=0x00007f3a2c1d4e50, pid=4242, tid=4243"
+        );
+        assert_eq!(
+            report.exception,
+            "C  [liblwjgl.so+0x1234]  Java_org_lwjgl_system_JNI_invoke"
+        );
+        assert_eq!(report.stack_trace.len(), 2);
+        assert!(report
+            .java_version
+            .starts_with("OpenJDK Runtime Environment Temurin"));
+        assert!(report.java_vm.starts_with("OpenJDK 64-Bit Server VM"));
+        assert_eq!(
+            report.operating_system,
+            "PRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\""
+        );
+    }
+
+    #[test]
+    fn crash_files_resolve_inside_of_the_server() {
+        let server = Path::new("/servers/survival");
+        assert_eq!(
+            crash_file_path(server, "crash-2025-10-26_14.03.11-server.txt").unwrap(),
+            server.join("crash-reports/crash-2025-10-26_14.03.11-server.txt")
+        );
+        assert_eq!(
+            crash_file_path(server, "hs_err_pid4242.log").unwrap(),
+            server.join("hs_err_pid4242.log")
+        );
+        assert!(crash_file_path(server, "../crash-reports/crash-x.txt").is_err());
+        assert!(crash_file_path(server, "crash-../../etc/passwd").is_err());
+        assert!(crash_file_path(server, "crash-..\\server.properties").is_err());
+        assert!(crash_file_path(server, "server.properties").is_err());
+        assert!(crash_file_path(server, "hs_err_pid4242.txt").is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, TimeZone, Timelike};
+
+// Nothing valid is ever further away than this, feb 29th on a monday can take 28 years
+const MAX_SEARCH_STEPS: usize = 100_000;
+
+// Standard 5 field cron expression: minute hour day-of-month month day-of-week
+#[derive(Clone, Debug, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    // when both day fields are restricted cron runs on either of them
+    any_day: bool,
+    any_weekday: bool,
+}
+
+// Parses one field into a bitmask, supports "*", "5", "1-5", "*/15", "1-30/5" and lists of those
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("Invalid step in {}", part))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (
+                    start
+                        .parse()
+                        .map_err(|_| format!("Invalid value {}", start))?,
+                    end.parse().map_err(|_| format!("Invalid value {}", end))?,
+                ),
+                // "5/10" means every 10 starting at 5
+                None => {
+                    let start = range
+                        .parse()
+                        .map_err(|_| format!("Invalid value {}", range))?;
+                    (start, if part.contains('/') { max } else { start })
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("{} is out of range {}-{}", part, min, max));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<CronSchedule, String> {
+        let expression = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expression => expression,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Expected 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // 0 and 7 are both sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(CronSchedule {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)? as u32,
+            days: parse_field(day, 1, 31)? as u32,
+            months: parse_field(month, 1, 12)? as u16,
+            weekdays: weekdays as u8,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    // First time strictly after `after` that matches, worked out on after's local clock so
+    // daylight saving changes don't shift the schedule
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+        let at_midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0);
+        let mut time =
+            after.naive_local().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        for _ in 0..MAX_SEARCH_STEPS {
+            let date = time.date();
+            if self.months & (1 << time.month()) == 0 {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = at_midnight(NaiveDate::from_ymd_opt(year, month, 1)?)?;
+            } else if !self.matches_day(date) {
+                time = at_midnight(date.succ_opt()?)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+            } else {
+                // a time the clock skips runs once it's past the gap, one it passes twice
+                // only runs the first time
+                let found = match timezone.from_local_datetime(&time) {
+                    LocalResult::None => timezone
+                        .from_local_datetime(&(time + Duration::hours(1)))
+                        .earliest(),
+                    local => local.earliest(),
+                };
+                match found {
+                    Some(found) if found > *after => return Some(found),
+                    _ => time += Duration::minutes(1),
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use chrono_tz::Europe::Berlin;
+
+    fn at(text: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(text).unwrap()
+    }
+
+    fn next(expression: &str, after: &str) -> String {
+        CronSchedule::parse(expression)
+            .unwrap()
+            .next_after(&at(after))
+            .unwrap()
+            .to_rfc3339()
+    }
+
+    #[test]
+    fn every_fifteen_minutes() {
+        assert_eq!(
+            next("*/15 * * * *", "2025-10-26T15:58:02+00:00"),
+            "2025-10-26T16:00:00+00:00"
+        );
+        assert_eq!(
+            next("*/15 * * * *", "2025-10-26T16:00:00+00:00"),
+            "2025-10-26T16:15:00+00:00"
+        );
+    }
+
+    #[test]
+    fn daily_at_four_rolls_over_to_the_next_day() {
+        assert_eq!(
+            next("0 4 * * *", "2025-10-26T04:00:00+02:00"),
+            "2025-10-27T04:00:00+02:00"
+        );
+        assert_eq!(
+            next("@daily", "2025-12-31T23:59:00+00:00"),
+            "2026-01-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn weekday_ranges_and_sunday_as_seven() {
+        // 2025-10-26 is a sunday
+        assert_eq!(
+            next("30 6 * * 1-5", "2025-10-25T12:00:00+00:00"),
+            "2025-10-27T06:30:00+00:00"
+        );
+        assert_eq!(
+            next("0 12 * * 7", "2025-10-20T00:00:00+00:00"),
+            "2025-10-26T12:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn day_of_month_or_weekday_when_both_are_set() {
+        // the 1st or any friday, whichever comes first
+        assert_eq!(
+            next("0 0 1 * 5", "2025-10-26T00:00:00+00:00"),
+            "2025-10-31T00:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 1 * 5", "2025-10-31T00:00:00+00:00"),
+            "2025-11-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn lists_and_leap_days() {
+        assert_eq!(
+            next("0 8,20 * * *", "2025-10-26T09:00:00+00:00"),
+            "2025-10-26T20:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 29 2 *", "2025-03-01T00:00:00+00:00"),
+            "2028-02-29T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+        assert!(CronSchedule::parse("a * * * *").is_err());
+    }
+
+    #[test]
+    fn impossible_dates_never_match() {
+        assert_eq!(
+            CronSchedule::parse("0 0 31 2 *")
+                .unwrap()
+                .next_after(&at("2025-01-01T00:00:00+00:00")),
+            None
+        );
+    }
+
+    fn next_in_berlin(expression: &str, after: &str) -> String {
+        CronSchedule::parse(expression)
+            .unwrap()
+            .next_after(&at(after).with_timezone(&Berlin))
+            .unwrap()
+            .to_rfc3339()
+    }
+
+    #[test]
+    fn keeps_local_time_across_daylight_saving_changes() {
+        // clocks went back from 03:00 to 02:00 on 2025-10-26 and forward on 2026-03-29
+        assert_eq!(
+            next_in_berlin("0 4 * * *", "2025-10-25T04:00:00+02:00"),
+            "2025-10-26T04:00:00+01:00"
+        );
+        assert_eq!(
+            next_in_berlin("0 4 * * *", "2026-03-28T04:00:00+01:00"),
+            "2026-03-29T04:00:00+02:00"
+        );
+    }
+
+    #[test]
+    fn skipped_and_repeated_times_run_once() {
+        // 02:30 doesn't exist on 2026-03-29, it runs when the clock is past the gap
+        assert_eq!(
+            next_in_berlin("30 2 * * *", "2026-03-28T12:00:00+01:00"),
+            "2026-03-29T03:30:00+02:00"
+        );
+        // 02:30 happens twice on 2025-10-26, the second one is skipped
+        assert_eq!(
+            next_in_berlin("30 2 * * *", "2025-10-25T12:00:00+02:00"),
+            "2025-10-26T02:30:00+02:00"
+        );
+        assert_eq!(
+            next_in_berlin("30 2 * * *", "2025-10-26T02:30:00+02:00"),
+            "2025-10-27T02:30:00+01:00"
+        );
+    }
+}
@@ -0,0 +1,477 @@
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use toml_edit::{value, DocumentMut};
+use uuid::Uuid;
+
+use crate::gaia_core::{
+    core_structs::ServerStruct,
+    mod_scanner::scan_mods,
+    proxy::{block_end, config_path, indent, unquote},
+    server_properties::{read_properties, set_property},
+};
+
+const DEFAULT_SECRET_FILE: &str = "forwarding.secret";
+const FABRIC_PROXY_ID: &str = "fabricproxy-lite";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ForwardingIssue {
+    // the proxy itself or one of its backends
+    pub id: Uuid,
+    pub name: String,
+    pub problem: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ForwardingReport {
+    // velocity's player-info-forwarding-mode
+    pub mode: String,
+    pub issues: Vec<ForwardingIssue>,
+}
+
+// Paper and its forks read the secret from their global config, fabric needs FabricProxy-Lite
+pub fn is_supported(server_type: &str) -> bool {
+    matches!(server_type, "paper" | "purpur" | "folia" | "fabric")
+}
+
+fn is_paper(server_type: &str) -> bool {
+    matches!(server_type, "paper" | "purpur" | "folia")
+}
+
+fn read_velocity(proxy: &ServerStruct) -> Result<DocumentMut, String> {
+    let path = config_path(proxy);
+    fs::read_to_string(&path)
+        .map_err(|err| format!("Failed to read {:?}: {}", path, err))?
+        .parse()
+        .map_err(|err| format!("Invalid velocity.toml: {}", err))
+}
+
+fn secret_path(proxy: &ServerStruct, document: &DocumentMut) -> PathBuf {
+    let file = document
+        .get("forwarding-secret-file")
+        .and_then(|file| file.as_str())
+        .filter(|file| !file.trim().is_empty())
+        .unwrap_or(DEFAULT_SECRET_FILE);
+    Path::new(&proxy.path).join(file)
+}
+
+fn read_secret(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|secret| secret.trim().to_string())
+        .filter(|secret| !secret.is_empty())
+}
+
+// Switches the proxy to modern forwarding and returns its secret, one is generated when
+// the secret file is missing or empty
+pub fn enable_modern(proxy: &ServerStruct) -> Result<String, String> {
+    let mut document = read_velocity(proxy)?;
+    let path = secret_path(proxy, &document);
+    let secret = match read_secret(&path) {
+        Some(secret) => secret,
+        None => {
+            let secret = Uuid::new_v4().simple().to_string();
+            fs::write(&path, &secret)
+                .map_err(|err| format!("Failed to write {:?}: {}", path, err))?;
+            secret
+        }
+    };
+    if document
+        .get("player-info-forwarding-mode")
+        .and_then(|mode| mode.as_str())
+        != Some("modern")
+    {
+        document["player-info-forwarding-mode"] = value("modern");
+        let config = config_path(proxy);
+        fs::write(&config, document.to_string())
+            .map_err(|err| format!("Failed to write {:?}: {}", config, err))?;
+    }
+    Ok(secret)
+}
+
+fn yaml_key(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    if trimmed.starts_with('#') {
+        return None;
+    }
+    trimmed.split_once(':').map(|(key, _)| unquote(key))
+}
+
+// Line of the value at `path`, like ["proxies", "velocity", "secret"]
+fn find_yaml(lines: &[String], path: &[&str]) -> Option<usize> {
+    let (mut start, mut end, mut key_indent) = (0, lines.len(), 0);
+    for (depth, key) in path.iter().enumerate() {
+        let index = (start..end).find(|index| {
+            indent(&lines[*index]) == key_indent && yaml_key(&lines[*index]) == Some(key)
+        })?;
+        if depth + 1 == path.len() {
+            return Some(index);
+        }
+        start = index + 1;
+        end = block_end(lines, index);
+        key_indent = lines[start..end]
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| indent(line))
+            .min()?;
+    }
+    None
+}
+
+// A scalar as yaml reads it, '' is a quote inside of single quotes and \ escapes the next
+// character inside of double quotes. Plain values end at a comment
+fn yaml_scalar(value: &str) -> String {
+    let value = value.trim();
+    let mut text = String::new();
+    match value.chars().next() {
+        Some('\'') => {
+            let mut chars = value[1..].chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\'' if chars.peek() == Some(&'\'') => {
+                        chars.next();
+                        text.push('\'');
+                    }
+                    '\'' => break,
+                    c => text.push(c),
+                }
+            }
+        }
+        Some('"') => {
+            let mut chars = value[1..].chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => text.extend(chars.next()),
+                    '"' => break,
+                    c => text.push(c),
+                }
+            }
+        }
+        _ => text.push_str(value.split(" #").next().unwrap_or_default().trim_end()),
+    }
+    text
+}
+
+pub fn get_yaml(content: &str, path: &[&str]) -> Option<String> {
+    let lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let index = find_yaml(&lines, path)?;
+    let (_, value) = lines[index].split_once(':')?;
+    Some(yaml_scalar(value))
+}
+
+// Sets a nested key line by line so the comments paper writes stay, missing parents are added
+pub fn set_yaml(content: &str, path: &[&str], value: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let (mut start, mut end, mut key_indent) = (0, lines.len(), 0);
+    for (depth, key) in path.iter().enumerate() {
+        let last = depth + 1 == path.len();
+        let found = (start..end).find(|index| {
+            indent(&lines[*index]) == key_indent && yaml_key(&lines[*index]) == Some(key)
+        });
+        let index = match found {
+            Some(index) => index,
+            None => {
+                let mut index = end;
+                while index > start && lines[index - 1].trim().is_empty() {
+                    index -= 1;
+                }
+                lines.insert(index, String::new());
+                index
+            }
+        };
+        let prefix = " ".repeat(key_indent);
+        if last {
+            lines[index] = format!("{}{}: {}", prefix, key, value);
+            break;
+        }
+        // a parent written inline like "velocity: {}" gets opened up into a block
+        lines[index] = format!("{}{}:", prefix, key);
+        start = index + 1;
+        end = block_end(&lines, index);
+        key_indent = lines[start..end]
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| indent(line))
+            .min()
+            .unwrap_or(key_indent + 2);
+    }
+    lines.join("\n") + "\n"
+}
+
+// Paper 1.19 and up has config/paper-global.yml, older versions keep it in paper.yml
+fn paper_config(backend: &ServerStruct) -> (PathBuf, [&'static str; 2]) {
+    let directory = Path::new(&backend.path);
+    let legacy = directory.join("paper.yml");
+    match !directory.join("config").join("paper-global.yml").exists() && legacy.exists() {
+        true => (legacy, ["settings", "velocity-support"]),
+        false => (
+            directory.join("config").join("paper-global.yml"),
+            ["proxies", "velocity"],
+        ),
+    }
+}
+
+fn fabric_config(backend: &ServerStruct) -> PathBuf {
+    Path::new(&backend.path)
+        .join("config")
+        .join("FabricProxy-Lite.toml")
+}
+
+// Writes the proxy's secret into the backend and turns off its own authentication, the
+// proxy already did that for the player
+pub fn configure_backend(backend: &ServerStruct, secret: &str) -> Result<(), String> {
+    let directory = Path::new(&backend.path);
+    if is_paper(&backend.server_type) {
+        let (path, [section, key]) = paper_config(backend);
+        let mut content = fs::read_to_string(&path).unwrap_or_default();
+        for (name, setting) in [
+            ("enabled", "true".to_string()),
+            ("online-mode", "true".to_string()),
+            ("secret", format!("'{}'", secret.replace('\'', "''"))),
+        ] {
+            content = set_yaml(&content, &[section, key, name], &setting);
+        }
+        fs::create_dir_all(path.parent().unwrap()).map_err(|err| err.to_string())?;
+        fs::write(&path, content).map_err(|err| format!("Failed to write {:?}: {}", path, err))?;
+    } else if backend.server_type == "fabric" {
+        let path = fabric_config(backend);
+        let mut document: DocumentMut = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .parse()
+            .map_err(|err| format!("Invalid FabricProxy-Lite.toml: {}", err))?;
+        document["secret"] = value(secret);
+        if document.get("hackOnlineMode").is_none() {
+            document["hackOnlineMode"] = value(true);
+        }
+        fs::create_dir_all(path.parent().unwrap()).map_err(|err| err.to_string())?;
+        fs::write(&path, document.to_string())
+            .map_err(|err| format!("Failed to write {:?}: {}", path, err))?;
+    } else {
+        return Err(format!(
+            "{} servers don't support modern forwarding",
+            backend.server_type
+        ));
+    }
+    set_property(directory, "online-mode", "false")
+}
+
+// What's wrong with one backend, empty when it accepts players from the proxy
+fn backend_problems(backend: &ServerStruct, secret: Option<&str>) -> Vec<String> {
+    let mut problems = Vec::new();
+    let backend_secret = if is_paper(&backend.server_type) {
+        let (path, [section, key]) = paper_config(backend);
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        if get_yaml(&content, &[section, key, "enabled"]).as_deref() != Some("true") {
+            problems.push("velocity support is turned off in the paper config".to_string());
+        }
+        get_yaml(&content, &[section, key, "secret"])
+    } else if backend.server_type == "fabric" {
+        if !scan_mods(Path::new(&backend.path))
+            .iter()
+            .any(|info| info.id == FABRIC_PROXY_ID)
+        {
+            problems.push("FabricProxy-Lite is not installed".to_string());
+        }
+        fs::read_to_string(fabric_config(backend))
+            .ok()
+            .and_then(|content| content.parse::<DocumentMut>().ok())
+            .and_then(|document| {
+                document
+                    .get("secret")
+                    .and_then(|secret| secret.as_str())
+                    .map(str::to_string)
+            })
+    } else {
+        return vec![format!(
+            "{} servers don't support modern forwarding",
+            backend.server_type
+        )];
+    };
+    match (backend_secret.filter(|found| !found.is_empty()), secret) {
+        (None, _) => problems.push("no forwarding secret is set".to_string()),
+        (Some(found), Some(secret)) if found != secret => {
+            problems.push("the forwarding secret doesn't match the proxy's".to_string())
+        }
+        _ => {}
+    }
+    if read_properties(Path::new(&backend.path))
+        .get("online-mode")
+        .map(String::as_str)
+        != Some("false")
+    {
+        problems.push("online-mode is not false in server.properties".to_string());
+    }
+    problems
+}
+
+// Compares the proxy's mode and secret with what every linked backend has
+pub fn check(proxy: &ServerStruct, backends: &[ServerStruct]) -> Result<ForwardingReport, String> {
+    let document = read_velocity(proxy)?;
+    let mode = document
+        .get("player-info-forwarding-mode")
+        .and_then(|mode| mode.as_str())
+        .unwrap_or("none")
+        .to_lowercase();
+    let mut issues = Vec::new();
+    let issue = |server: &ServerStruct, problem: String| ForwardingIssue {
+        id: server.id,
+        name: server.name.clone(),
+        problem,
+    };
+    if mode != "modern" {
+        if !backends.is_empty() {
+            issues.push(issue(
+                proxy,
+                format!("forwarding mode is \"{}\", not \"modern\"", mode),
+            ));
+        }
+        return Ok(ForwardingReport { mode, issues });
+    }
+    let secret = read_secret(&secret_path(proxy, &document));
+    if secret.is_none() {
+        issues.push(issue(
+            proxy,
+            "the forwarding secret file is missing or empty".to_string(),
+        ));
+    }
+    for backend in backends {
+        for problem in backend_problems(backend, secret.as_deref()) {
+            issues.push(issue(backend, problem));
+        }
+    }
+    Ok(ForwardingReport { mode, issues })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAPER_GLOBAL: &str = "# This is the global configuration file for Paper.
+_version: 28
+proxies:
+  bungee-cord:
+    online-mode: true
+  # Whether velocity forwarding is used
+  velocity:
+    enabled: false
+    online-mode: false
+    secret: ''
+scoreboards:
+  save-empty-scoreboard-teams: false
+";
+
+    const PAPER_LEGACY: &str = "config-version: 20
+settings:
+  bungee-online-mode: true
+  velocity-support:
+    enabled: false
+    online-mode: false
+    secret: ''
+  incoming-packet-threshold: 300
+";
+
+    #[test]
+    fn fills_in_an_empty_file() {
+        let content = set_yaml("", &["proxies", "velocity", "enabled"], "true");
+        assert_eq!(content, "proxies:\n  velocity:\n    enabled: true\n");
+        assert_eq!(
+            get_yaml(&content, &["proxies", "velocity", "enabled"]).as_deref(),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn adds_a_missing_parent_section() {
+        let content = set_yaml(
+            "settings:\n  debug: false\n\n",
+            &["proxies", "velocity", "secret"],
+            "'abc'",
+        );
+        assert_eq!(
+            content,
+            "settings:\n  debug: false\nproxies:\n  velocity:\n    secret: 'abc'\n\n"
+        );
+    }
+
+    #[test]
+    fn opens_up_an_inline_parent() {
+        let content = set_yaml(
+            "proxies:\n  bungee-cord:\n    online-mode: true\n  velocity: {}\n",
+            &["proxies", "velocity", "enabled"],
+            "true",
+        );
+        assert_eq!(
+            content,
+            "proxies:\n  bungee-cord:\n    online-mode: true\n  velocity:\n    enabled: true\n"
+        );
+    }
+
+    #[test]
+    fn changes_an_existing_key_and_keeps_comments() {
+        let content = set_yaml(PAPER_GLOBAL, &["proxies", "velocity", "enabled"], "true");
+        assert_eq!(
+            content,
+            PAPER_GLOBAL.replace("    enabled: false", "    enabled: true")
+        );
+        // bungee-cord has an online-mode of its own that stays alone
+        let content = set_yaml(&content, &["proxies", "velocity", "online-mode"], "true");
+        assert_eq!(
+            get_yaml(&content, &["proxies", "bungee-cord", "online-mode"]).as_deref(),
+            Some("true")
+        );
+        assert!(content.contains("  # Whether velocity forwarding is used\n  velocity:\n"));
+        assert!(content.ends_with("scoreboards:\n  save-empty-scoreboard-teams: false\n"));
+        assert_eq!(
+            get_yaml(&content, &["proxies", "velocity", "missing"]),
+            None
+        );
+    }
+
+    #[test]
+    fn quoted_secrets_round_trip() {
+        for secret in ["plain", "it's", "''", "with \"double\" quotes", "a # b: c"] {
+            let quoted = format!("'{}'", secret.replace('\'', "''"));
+            let content = set_yaml(PAPER_GLOBAL, &["proxies", "velocity", "secret"], &quoted);
+            assert_eq!(
+                get_yaml(&content, &["proxies", "velocity", "secret"]).as_deref(),
+                Some(secret)
+            );
+        }
+        assert_eq!(
+            get_yaml("secret: \"a\\\"b\\\\c\"\n", &["secret"]).as_deref(),
+            Some("a\"b\\c")
+        );
+        assert_eq!(
+            get_yaml("port: 19132 # the bedrock port\n", &["port"]).as_deref(),
+            Some("19132")
+        );
+        assert_eq!(
+            get_yaml(PAPER_GLOBAL, &["proxies", "velocity", "secret"]).as_deref(),
+            Some("")
+        );
+    }
+
+    #[test]
+    fn configures_legacy_paper_yml() {
+        let path = std::env::temp_dir().join(format!("forwarding-{}", Uuid::new_v4()));
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("paper.yml"), PAPER_LEGACY).unwrap();
+        let backend = ServerStruct {
+            server_type: "paper".to_string(),
+            path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        configure_backend(&backend, "s3cr'et").unwrap();
+        let content = fs::read_to_string(path.join("paper.yml")).unwrap();
+        let get = |key| get_yaml(&content, &["settings", "velocity-support", key]);
+        assert_eq!(get("enabled").as_deref(), Some("true"));
+        assert_eq!(get("online-mode").as_deref(), Some("true"));
+        assert_eq!(get("secret").as_deref(), Some("s3cr'et"));
+        assert!(content.contains("  incoming-packet-threshold: 300\n"));
+        assert!(!path.join("config").exists());
+        assert!(backend_problems(&backend, Some("s3cr'et")).is_empty());
+        fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -0,0 +1,417 @@
+use serde::Serialize;
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use crate::gaia_core::{
+    core_structs::ServerStruct,
+    forwarding::{get_yaml, set_yaml},
+    mod_scanner::read_entry,
+    ports::server_ports,
+    proxy,
+    server_properties::read_properties,
+};
+
+pub const DEFAULT_BEDROCK_PORT: u16 = 19132;
+const DEFAULT_PREFIX: &str = ".";
+
+// Where Geyser and Floodgate live for one kind of server
+struct Platform {
+    name: &'static str,
+    // "plugins" or "mods"
+    folder: &'static str,
+    // the file in the jar that tells which loader it was built for
+    descriptor: &'static str,
+    geyser_config: &'static str,
+    floodgate_config: &'static str,
+}
+
+fn platform(server_type: &str) -> Option<Platform> {
+    let (name, folder, descriptor, geyser_config, floodgate_config) = match server_type {
+        "paper" | "purpur" | "folia" | "spigot" | "bukkit" => (
+            "Spigot",
+            "plugins",
+            "plugin.yml",
+            "plugins/Geyser-Spigot",
+            "plugins/floodgate",
+        ),
+        "fabric" => (
+            "Fabric",
+            "mods",
+            "fabric.mod.json",
+            "config/Geyser-Fabric",
+            "config/floodgate",
+        ),
+        "neoforge" => (
+            "NeoForge",
+            "mods",
+            "META-INF/neoforge.mods.toml",
+            "config/Geyser-NeoForge",
+            "config/floodgate",
+        ),
+        "velocity" => (
+            "Velocity",
+            "plugins",
+            "velocity-plugin.json",
+            "plugins/Geyser-Velocity",
+            "plugins/floodgate",
+        ),
+        "bungeecord" | "waterfall" => (
+            "BungeeCord",
+            "plugins",
+            "bungee.yml",
+            "plugins/Geyser-BungeeCord",
+            "plugins/floodgate",
+        ),
+        _ => return None,
+    };
+    Some(Platform {
+        name,
+        folder,
+        descriptor,
+        geyser_config,
+        floodgate_config,
+    })
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GeyserSetup {
+    // "Spigot", "Fabric", ... empty when Geyser has no build for the server type
+    pub platform: String,
+    pub geyser: bool,
+    pub floodgate: bool,
+    pub bedrock_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub auth_type: String,
+    // what floodgate puts in front of bedrock player names
+    pub username_prefix: String,
+}
+
+// "geyser" or "floodgate" jar in the plugins or mods folder, found by its file name
+fn find_jar(server: &ServerStruct, platform: &Platform, kind: &str) -> Option<PathBuf> {
+    fs::read_dir(Path::new(&server.path).join(platform.folder))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            name.starts_with(kind) && name.ends_with(".jar")
+        })
+}
+
+fn config_path(server: &ServerStruct, folder: &str) -> PathBuf {
+    Path::new(&server.path).join(folder).join("config.yml")
+}
+
+fn read_config(server: &ServerStruct, folder: &str) -> String {
+    fs::read_to_string(config_path(server, folder)).unwrap_or_default()
+}
+
+// The udp port Geyser takes, None when it isn't installed
+pub fn bedrock_port(server: &ServerStruct) -> Option<u16> {
+    let platform = platform(&server.server_type)?;
+    find_jar(server, &platform, "geyser")?;
+    let content = read_config(server, platform.geyser_config);
+    Some(
+        get_yaml(&content, &["bedrock", "port"])
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(DEFAULT_BEDROCK_PORT),
+    )
+}
+
+// Bedrock players joined through floodgate have their names start with this
+pub fn floodgate_prefix(server: &ServerStruct) -> Option<String> {
+    let platform = platform(&server.server_type)?;
+    find_jar(server, &platform, "floodgate")?;
+    let content = read_config(server, platform.floodgate_config);
+    Some(get_yaml(&content, &["username-prefix"]).unwrap_or_else(|| DEFAULT_PREFIX.to_string()))
+}
+
+// Whether a player name came through floodgate, an empty prefix can't tell them apart
+pub fn is_bedrock_player(prefix: Option<&str>, name: &str) -> bool {
+    prefix.is_some_and(|prefix| !prefix.is_empty() && name.starts_with(prefix))
+}
+
+pub fn setup(server: &ServerStruct) -> GeyserSetup {
+    let Some(platform) = platform(&server.server_type) else {
+        return GeyserSetup {
+            platform: String::new(),
+            geyser: false,
+            floodgate: false,
+            bedrock_port: DEFAULT_BEDROCK_PORT,
+            remote_address: String::new(),
+            remote_port: server.port,
+            auth_type: String::new(),
+            username_prefix: String::new(),
+        };
+    };
+    let content = read_config(server, platform.geyser_config);
+    let get = |path: &[&str]| get_yaml(&content, path).unwrap_or_default();
+    GeyserSetup {
+        platform: platform.name.to_string(),
+        geyser: find_jar(server, &platform, "geyser").is_some(),
+        floodgate: find_jar(server, &platform, "floodgate").is_some(),
+        bedrock_port: bedrock_port(server).unwrap_or(DEFAULT_BEDROCK_PORT),
+        remote_address: get(&["remote", "address"]),
+        remote_port: get(&["remote", "port"]).parse().unwrap_or(server.port),
+        auth_type: get(&["remote", "auth-type"]),
+        username_prefix: floodgate_prefix(server).unwrap_or_default(),
+    }
+}
+
+// Refuses jars built for another loader, a Geyser-Spigot jar does nothing in a fabric mods folder
+fn check_jar(path: &Path, platform: &Platform, kind: &str) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| format!("Failed to open {:?}: {}", path, err))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|err| format!("{:?} is not a jar: {}", path, err))?;
+    match read_entry(&mut archive, platform.descriptor) {
+        Some(descriptor) if descriptor.to_lowercase().contains(kind) => Ok(()),
+        Some(_) => Err(format!("{:?} is not a {} jar", path, kind)),
+        None => Err(format!(
+            "{:?} is not the {} build of {}",
+            path, platform.name, kind
+        )),
+    }
+}
+
+// Copies the jar in under a fixed name so find_jar sees it, replacing an older one
+fn install_jar(
+    server: &ServerStruct,
+    platform: &Platform,
+    kind: &str,
+    source: &Path,
+) -> Result<(), String> {
+    check_jar(source, platform, kind)?;
+    if let Some(old) = find_jar(server, platform, kind) {
+        fs::remove_file(&old).map_err(|err| format!("Failed to remove {:?}: {}", old, err))?;
+    }
+    let folder = Path::new(&server.path).join(platform.folder);
+    fs::create_dir_all(&folder).map_err(|err| err.to_string())?;
+    let name = match kind {
+        "geyser" => format!("Geyser-{}.jar", platform.name),
+        _ => format!("floodgate-{}.jar", platform.name.to_lowercase()),
+    };
+    fs::copy(source, folder.join(&name))
+        .map_err(|err| format!("Failed to copy {:?}: {}", source, err))?;
+    Ok(())
+}
+
+pub fn install(
+    server: &ServerStruct,
+    geyser: &Path,
+    floodgate: Option<&Path>,
+) -> Result<(), String> {
+    let platform = platform(&server.server_type)
+        .ok_or_else(|| format!("Geyser has no build for {} servers", server.server_type))?;
+    install_jar(server, &platform, "geyser", geyser)?;
+    if let Some(floodgate) = floodgate {
+        install_jar(server, &platform, "floodgate", floodgate)?;
+    }
+    Ok(())
+}
+
+// Points Geyser at the java side of this server. Floodgate auth when it's installed, otherwise
+// bedrock players log in like java ones would
+pub fn configure(server: &ServerStruct, port: u16) -> Result<(), String> {
+    let platform = platform(&server.server_type)
+        .ok_or_else(|| format!("Geyser has no build for {} servers", server.server_type))?;
+    if find_jar(server, &platform, "geyser").is_none() {
+        return Err("Geyser is not installed".to_string());
+    }
+    let (address, online) = match proxy::is_proxy(&server.server_type) {
+        true => ("127.0.0.1".to_string(), true),
+        false => {
+            let properties = read_properties(Path::new(&server.path));
+            let address = properties
+                .get("server-ip")
+                .filter(|ip| !ip.trim().is_empty())
+                .cloned()
+                .unwrap_or_else(|| "127.0.0.1".to_string());
+            (
+                address,
+                properties.get("online-mode").map(String::as_str) != Some("false"),
+            )
+        }
+    };
+    let auth_type = match (find_jar(server, &platform, "floodgate").is_some(), online) {
+        (true, _) => "floodgate",
+        (false, true) => "online",
+        (false, false) => "offline",
+    };
+    let java_port = server_ports(server)
+        .first()
+        .map_or(server.port, |(_, port)| *port);
+    let mut content = read_config(server, platform.geyser_config);
+    for (path, value) in [
+        (["bedrock", "port"], port.to_string()),
+        (["bedrock", "clone-remote-port"], "false".to_string()),
+        (["remote", "address"], address),
+        (["remote", "port"], java_port.to_string()),
+        (["remote", "auth-type"], auth_type.to_string()),
+    ] {
+        content = set_yaml(&content, &path, &value);
+    }
+    let path = config_path(server, platform.geyser_config);
+    fs::create_dir_all(path.parent().unwrap()).map_err(|err| err.to_string())?;
+    fs::write(&path, content).map_err(|err| format!("Failed to write {:?}: {}", path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const GEYSER_CONFIG: &str = include_str!("testdata/geyser/config.yml");
+
+    struct TestServer {
+        server: ServerStruct,
+    }
+
+    impl TestServer {
+        // A paper server with the Geyser plugin and the sample config.yml
+        fn new(properties: &str) -> TestServer {
+            let path = std::env::temp_dir().join(format!("gaia-geyser-{}", Uuid::new_v4()));
+            fs::create_dir_all(path.join("plugins/Geyser-Spigot")).unwrap();
+            fs::write(path.join("plugins/Geyser-Spigot.jar"), b"").unwrap();
+            fs::write(path.join("plugins/Geyser-Spigot/config.yml"), GEYSER_CONFIG).unwrap();
+            fs::write(path.join("server.properties"), properties).unwrap();
+            TestServer {
+                server: ServerStruct {
+                    path: path.to_string_lossy().to_string(),
+                    server_type: "paper".to_string(),
+                    ..Default::default()
+                },
+            }
+        }
+
+        fn add_floodgate(&self, config: Option<&str>) {
+            let path = Path::new(&self.server.path);
+            fs::write(path.join("plugins/floodgate-spigot.jar"), b"").unwrap();
+            if let Some(config) = config {
+                fs::create_dir_all(path.join("plugins/floodgate")).unwrap();
+                fs::write(path.join("plugins/floodgate/config.yml"), config).unwrap();
+            }
+        }
+
+        fn config(&self) -> String {
+            read_config(&self.server, "plugins/Geyser-Spigot")
+        }
+    }
+
+    impl Drop for TestServer {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.server.path);
+        }
+    }
+
+    #[test]
+    fn configure_points_geyser_at_the_server() {
+        let test = TestServer::new("server-ip=10.0.0.5\nserver-port=25570\nonline-mode=true\n");
+        configure(&test.server, 19200).unwrap();
+        let config = test.config();
+        assert_eq!(
+            get_yaml(&config, &["bedrock", "port"]).as_deref(),
+            Some("19200")
+        );
+        assert_eq!(
+            get_yaml(&config, &["bedrock", "clone-remote-port"]).as_deref(),
+            Some("false")
+        );
+        assert_eq!(
+            get_yaml(&config, &["remote", "address"]).as_deref(),
+            Some("10.0.0.5")
+        );
+        assert_eq!(
+            get_yaml(&config, &["remote", "port"]).as_deref(),
+            Some("25570")
+        );
+        assert_eq!(
+            get_yaml(&config, &["remote", "auth-type"]).as_deref(),
+            Some("online")
+        );
+        // the rest of the file is left alone
+        assert!(config.contains("# The port of the remote (Java Edition) server"));
+        assert_eq!(get_yaml(&config, &["config-version"]).as_deref(), Some("4"));
+
+        let setup = setup(&test.server);
+        assert!(setup.geyser);
+        assert!(!setup.floodgate);
+        assert_eq!(setup.bedrock_port, 19200);
+        assert_eq!(setup.remote_address, "10.0.0.5");
+        assert_eq!(setup.remote_port, 25570);
+        assert_eq!(setup.auth_type, "online");
+    }
+
+    #[test]
+    fn configure_uses_localhost_and_offline_auth() {
+        let test = TestServer::new("server-ip=\nserver-port=25565\nonline-mode=false\n");
+        configure(&test.server, DEFAULT_BEDROCK_PORT).unwrap();
+        let config = test.config();
+        assert_eq!(
+            get_yaml(&config, &["remote", "address"]).as_deref(),
+            Some("127.0.0.1")
+        );
+        assert_eq!(
+            get_yaml(&config, &["remote", "auth-type"]).as_deref(),
+            Some("offline")
+        );
+    }
+
+    #[test]
+    fn configure_uses_floodgate_auth_when_installed() {
+        let test = TestServer::new("server-port=25565\nonline-mode=false\n");
+        test.add_floodgate(None);
+        configure(&test.server, DEFAULT_BEDROCK_PORT).unwrap();
+        assert_eq!(
+            get_yaml(&test.config(), &["remote", "auth-type"]).as_deref(),
+            Some("floodgate")
+        );
+        assert!(setup(&test.server).floodgate);
+    }
+
+    #[test]
+    fn configure_needs_geyser() {
+        let test = TestServer::new("server-port=25565\n");
+        fs::remove_file(Path::new(&test.server.path).join("plugins/Geyser-Spigot.jar")).unwrap();
+        assert_eq!(
+            configure(&test.server, DEFAULT_BEDROCK_PORT),
+            Err("Geyser is not installed".to_string())
+        );
+        assert_eq!(test.config(), GEYSER_CONFIG);
+
+        let vanilla = ServerStruct {
+            server_type: "vanilla".to_string(),
+            ..test.server.clone()
+        };
+        assert!(configure(&vanilla, DEFAULT_BEDROCK_PORT).is_err());
+    }
+
+    #[test]
+    fn floodgate_prefix_from_config() {
+        let test = TestServer::new("server-port=25565\n");
+        assert_eq!(floodgate_prefix(&test.server), None);
+        test.add_floodgate(None);
+        assert_eq!(floodgate_prefix(&test.server).as_deref(), Some("."));
+        test.add_floodgate(Some(
+            "# Floodgate config\nusername-prefix: \"*\"\nreplace-spaces: true\n",
+        ));
+        assert_eq!(floodgate_prefix(&test.server).as_deref(), Some("*"));
+        assert_eq!(setup(&test.server).username_prefix, "*");
+    }
+
+    #[test]
+    fn bedrock_players_by_prefix() {
+        assert!(is_bedrock_player(Some("."), ".Steve"));
+        assert!(is_bedrock_player(Some("*"), "*Alex_2"));
+        assert!(!is_bedrock_player(Some("."), "Steve"));
+        assert!(!is_bedrock_player(Some("*"), ".Steve"));
+        // no floodgate, or a prefix that matches everyone
+        assert!(!is_bedrock_player(None, ".Steve"));
+        assert!(!is_bedrock_player(Some(""), "Steve"));
+    }
+}
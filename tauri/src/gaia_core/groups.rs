@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use std::thread;
+use tauri::{AppHandle, Manager, Runtime};
+use uuid::Uuid;
+
+use crate::gaia_core::{
+    auto_start::{dependency_order, manager_message, wait_until_ready},
+    core_structs::ServerStruct,
+    proxy,
+    registry::Registry,
+    restart_countdown::RestartCountdowns,
+    supervisor::Supervisor,
+};
+
+// Servers that are started and stopped together, like a proxy and its backends
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServerGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub members: Vec<Uuid>,
+}
+
+fn members<R: Runtime>(app: &AppHandle<R>, group: &ServerGroup) -> Vec<ServerStruct> {
+    let registry = app.state::<Registry>();
+    group
+        .members
+        .iter()
+        .filter_map(|id| registry.get(*id))
+        .collect()
+}
+
+// Backends first and proxies last, a server still comes after the one it waits on
+pub fn start_order(members: &[ServerStruct]) -> Vec<ServerStruct> {
+    let mut members = members.to_vec();
+    members.sort_by_key(|server| {
+        (
+            proxy::is_proxy(&server.server_type),
+            server.auto_start_order,
+        )
+    });
+    dependency_order(members)
+}
+
+// Starts the members one after another on its own thread. A proxy waits until every backend
+// is done loading so nobody gets sent to a server that's still starting
+pub fn start<R: Runtime>(app: AppHandle<R>, group: ServerGroup) {
+    let ordered = start_order(&members(&app, &group));
+    thread::spawn(move || {
+        let supervisor = app.state::<Supervisor>();
+        let mut started: Vec<ServerStruct> = Vec::new();
+        for server in ordered {
+            let waits_on: Vec<&ServerStruct> = started
+                .iter()
+                .filter(|other| match proxy::is_proxy(&server.server_type) {
+                    true => !proxy::is_proxy(&other.server_type),
+                    false => server.auto_start_after == Some(other.id),
+                })
+                .collect();
+            if let Some(other) = waits_on
+                .iter()
+                .find(|other| !wait_until_ready(&supervisor, other.id))
+            {
+                manager_message(
+                    &app,
+                    server.id,
+                    format!(
+                        "Not starting {}, {} never finished starting",
+                        server.name, other.name
+                    ),
+                );
+                continue;
+            }
+            if supervisor.is_running(server.id) {
+                started.push(server);
+                continue;
+            }
+            match supervisor.start(&app, server.clone()) {
+                Ok(_) => started.push(server),
+                Err(err) => manager_message(
+                    &app,
+                    server.id,
+                    format!("Failed to start with {}: {}", group.name, err),
+                ),
+            }
+        }
+    });
+}
+
+// Stops the members in the reverse of their start order, waiting for each one so the proxy
+// is gone before its backends go down
+pub fn stop<R: Runtime>(app: AppHandle<R>, group: ServerGroup) {
+    let ordered = start_order(&members(&app, &group));
+    thread::spawn(move || {
+        let supervisor = app.state::<Supervisor>();
+        for server in ordered.into_iter().rev() {
+            if !supervisor.is_running(server.id) {
+                continue;
+            }
+            app.state::<RestartCountdowns>().cancel(&app, server.id);
+            if let Err(err) = supervisor
+                .stop(server.id)
+                .and_then(|_| supervisor.wait_for_stop(server.id))
+            {
+                manager_message(
+                    &app,
+                    server.id,
+                    format!("Failed to stop with {}: {}", group.name, err),
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str, server_type: &str, order: u32, after: Option<Uuid>) -> ServerStruct {
+        ServerStruct {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            server_type: server_type.to_string(),
+            auto_start_order: order,
+            auto_start_after: after,
+            ..Default::default()
+        }
+    }
+
+    fn names(servers: &[ServerStruct]) -> Vec<&str> {
+        servers.iter().map(|server| server.name.as_str()).collect()
+    }
+
+    #[test]
+    fn starts_backends_in_their_chain_before_the_proxy() {
+        let proxy = server("proxy", "velocity", 0, None);
+        let lobby = server("lobby", "paper", 2, None);
+        // survival has the lower order but waits on the lobby
+        let survival = server("survival", "fabric", 1, Some(lobby.id));
+        let members = vec![proxy, survival, lobby];
+        assert_eq!(
+            names(&start_order(&members)),
+            vec!["lobby", "survival", "proxy"]
+        );
+    }
+
+    #[test]
+    fn ignores_dependencies_outside_of_the_group() {
+        let proxy = server("proxy", "waterfall", 0, None);
+        let lobby = server("lobby", "paper", 2, Some(Uuid::new_v4()));
+        let survival = server("survival", "paper", 1, None);
+        let members = vec![lobby, proxy, survival];
+        assert_eq!(
+            names(&start_order(&members)),
+            vec!["survival", "lobby", "proxy"]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+// "http://host:port/path" into host, port and path, there is no tls so https is refused
+fn split_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest = url
+        .trim()
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("Only http:// urls are supported: {}", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse::<u16>()
+                .map_err(|_| format!("Invalid port in {}", url))?,
+        ),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(format!("Missing host in {}", url));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+// A single HTTP/1.0 request, so the answer is never chunked and ends when the connection closes
+pub fn request(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> Result<Response, String> {
+    let (host, port, path) = split_url(url)?;
+    let address = (host.as_str(), port)
+        .to_socket_addrs()
+        .map_err(|err| format!("Failed to resolve {}: {}", host, err))?
+        .next()
+        .ok_or_else(|| format!("Failed to resolve {}", host))?;
+    let mut stream =
+        TcpStream::connect_timeout(&address, TIMEOUT).map_err(|err| err.to_string())?;
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(|err| err.to_string())?;
+    stream
+        .set_write_timeout(Some(TIMEOUT))
+        .map_err(|err| err.to_string())?;
+
+    let mut message = format!(
+        "{} {} HTTP/1.0\r\nHost: {}:{}\r\n",
+        method, path, host, port
+    );
+    for (name, value) in headers {
+        message.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    message.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    ));
+    stream
+        .write_all(message.as_bytes())
+        .map_err(|err| err.to_string())?;
+
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .map_err(|err| err.to_string())?;
+    parse_response(&response).map_err(|err| format!("{}: {}", url, err))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+// Some servers chunk the body even though an HTTP/1.0 request asks them not to
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = find(body, b"\r\n").ok_or("Truncated chunked body")?;
+        let size_line = String::from_utf8_lossy(&body[..line_end]).to_string();
+        // extensions after the ';' carry nothing we need
+        let size = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| format!("Invalid chunk size: {}", size_line))?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        if body.len() < size + 2 {
+            return Err("Truncated chunked body".to_string());
+        }
+        decoded.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+}
+
+// Status and body of a raw response. Redirects are never followed, so they're an error
+fn parse_response(response: &[u8]) -> Result<Response, String> {
+    let split = find(response, b"\r\n\r\n").ok_or("Invalid response")?;
+    let head = String::from_utf8_lossy(&response[..split]).to_string();
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or_default();
+    let mut parts = status_line.split_whitespace();
+    let status = match (parts.next(), parts.next()) {
+        (Some(version), Some(status)) if version.starts_with("HTTP/") => status.parse::<u16>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("Invalid status line: {}", status_line))?;
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    };
+    if (300..400).contains(&status) {
+        return Err(format!(
+            "Redirected to {}",
+            header("location").unwrap_or("nowhere")
+        ));
+    }
+    let body = &response[split + 4..];
+    let body = match header("transfer-encoding") {
+        Some(encoding) if encoding.eq_ignore_ascii_case("chunked") => decode_chunked(body)?,
+        _ => body.to_vec(),
+    };
+    Ok(Response {
+        status,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+pub fn get(url: &str) -> Result<Response, String> {
+    request("GET", url, &[], "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_urls() {
+        assert_eq!(
+            split_url("http://192.168.1.1:5000/rootDesc.xml").unwrap(),
+            ("192.168.1.1".to_string(), 5000, "/rootDesc.xml".to_string())
+        );
+        assert_eq!(
+            split_url(" http://api.ipify.org ").unwrap(),
+            ("api.ipify.org".to_string(), 80, "/".to_string())
+        );
+        assert!(split_url("https://api.ipify.org").is_err());
+        assert!(split_url("http://host:port/").is_err());
+        assert!(split_url("http:///path").is_err());
+    }
+
+    #[test]
+    fn reads_status_and_body() {
+        let response =
+            parse_response(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\n203.0.113.7\n")
+                .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "203.0.113.7\n");
+        let response = parse_response(b"HTTP/1.0 500 Internal Server Error\r\n\r\n").unwrap();
+        assert_eq!(response.status, 500);
+        assert_eq!(response.body, "");
+    }
+
+    #[test]
+    fn rejects_broken_status_lines() {
+        assert!(parse_response(b"").is_err());
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+        assert!(parse_response(b"HTTP/1.1 OK\r\n\r\n").is_err());
+        assert!(parse_response(b"SSH-2.0-OpenSSH 200\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn decodes_chunked_bodies() {
+        let response = parse_response(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: Chunked\r\n\r\n\
+              4\r\n203.\r\n9;name=value\r\n0.113.7\r\n\r\n0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(response.body, "203.0.113.7\r\n");
+        assert!(parse_response(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n10\r\nshort\r\n"
+        )
+        .is_err());
+        assert!(
+            parse_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n").is_err()
+        );
+    }
+
+    #[test]
+    fn redirects_are_errors() {
+        let err = parse_response(
+            b"HTTP/1.1 301 Moved Permanently\r\nlocation: https://api.ipify.org/\r\n\r\n",
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err, "Redirected to https://api.ipify.org/");
+        assert!(parse_response(b"HTTP/1.1 304 Not Modified\r\n\r\n").is_err());
+    }
+}
@@ -0,0 +1,590 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::gaia_core::nbt::{Compound, Format, NbtFile, Tag};
+
+// Bedrock keeps its game rules as plain keys next to everything else in level.dat
+const BEDROCK_GAME_RULES: [&str; 36] = [
+    "commandblockoutput",
+    "commandblocksenabled",
+    "dodaylightcycle",
+    "doentitydrops",
+    "dofiretick",
+    "doimmediaterespawn",
+    "doinsomnia",
+    "dolimitedcrafting",
+    "domobloot",
+    "domobspawning",
+    "dotiledrops",
+    "doweathercycle",
+    "drowningdamage",
+    "falldamage",
+    "firedamage",
+    "freezedamage",
+    "functioncommandlimit",
+    "keepinventory",
+    "maxcommandchainlength",
+    "mobgriefing",
+    "naturalregeneration",
+    "playerssleepingpercentage",
+    "projectilescanbreakblocks",
+    "pvp",
+    "randomtickspeed",
+    "recipesunlock",
+    "respawnblocksexplode",
+    "sendcommandfeedback",
+    "showbordereffect",
+    "showcoordinates",
+    "showdeathmessages",
+    "showrecipemessages",
+    "showtags",
+    "spawnradius",
+    "tntexplodes",
+    "tntexplosiondropdecay",
+];
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GameRule {
+    pub name: String,
+    // "true", "false" or a number, java stores every rule as a string
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WorldInfo {
+    // folder of the world relative to the server, like "world" or "worlds/Bedrock level"
+    pub folder: String,
+    pub bedrock: bool,
+    pub level_name: String,
+    // longs are sent as strings, javascript numbers can't hold every seed
+    pub seed: String,
+    // 0 survival, 1 creative, 2 adventure, 3 spectator
+    pub game_type: i32,
+    // 0 peaceful to 3 hard
+    pub difficulty: i32,
+    pub hardcore: bool,
+    pub spawn: [i32; 3],
+    // time of day in ticks, java keeps counting past 24000
+    pub day_time: String,
+    // ticks the world has run for, only shown
+    pub game_time: String,
+    // DataVersion on java, StorageVersion on bedrock
+    pub data_version: i32,
+    pub version_name: String,
+    pub game_rules: Vec<GameRule>,
+    // sha256 of level.dat when it was read, a save is refused if it changed since
+    pub hash: String,
+}
+
+fn hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// Every folder with a level.dat, java worlds sit in the server folder and bedrock ones in worlds/
+pub fn world_folders(server_path: &Path) -> Vec<String> {
+    let mut folders = Vec::new();
+    for parent in ["", "worlds"] {
+        let Ok(entries) = fs::read_dir(server_path.join(parent)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.path().join("level.dat").is_file() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                folders.push(match parent {
+                    "" => name,
+                    _ => format!("{}/{}", parent, name),
+                });
+            }
+        }
+    }
+    folders.sort();
+    folders
+}
+
+// Keeps a world folder from the frontend inside the server
+fn level_dat_path(server_path: &Path, folder: &str) -> Result<PathBuf, String> {
+    if folder.is_empty()
+        || Path::new(folder)
+            .components()
+            .any(|part| !matches!(part, Component::Normal(_)))
+    {
+        return Err(format!("Invalid world folder {}", folder));
+    }
+    let path = server_path.join(folder).join("level.dat");
+    match path.is_file() {
+        true => Ok(path),
+        false => Err(format!("{} has no level.dat", folder)),
+    }
+}
+
+// Java keeps everything under "Data", bedrock at the root
+fn data(file: &NbtFile) -> Result<&Compound, String> {
+    match file.format {
+        Format::Bedrock { .. } => Ok(&file.root),
+        Format::Java(_) => file
+            .root
+            .get("Data")
+            .and_then(Tag::as_compound)
+            .ok_or_else(|| "level.dat has no Data compound".to_string()),
+    }
+}
+
+fn data_mut(file: &mut NbtFile) -> Result<&mut Compound, String> {
+    match file.format {
+        Format::Bedrock { .. } => Ok(&mut file.root),
+        Format::Java(_) => file
+            .root
+            .get_mut("Data")
+            .and_then(Tag::as_compound_mut)
+            .ok_or_else(|| "level.dat has no Data compound".to_string()),
+    }
+}
+
+fn number(data: &Compound, key: &str) -> i64 {
+    data.get(key).and_then(Tag::as_i64).unwrap_or_default()
+}
+
+// Writes a number with the tag type the key already has so minecraft still reads it
+fn set_number(data: &mut Compound, key: &str, value: i64, missing: fn(i64) -> Tag) {
+    let tag = match data.get(key) {
+        Some(Tag::Byte(_)) => Tag::Byte(value as i8),
+        Some(Tag::Short(_)) => Tag::Short(value as i16),
+        Some(Tag::Int(_)) => Tag::Int(value as i32),
+        Some(Tag::Long(_)) => Tag::Long(value),
+        _ => missing(value),
+    };
+    data.insert(key, tag);
+}
+
+// 1.21.5 moved the spawn into a "spawn" compound with a pos int array
+fn java_spawn(data: &Compound) -> [i32; 3] {
+    if let Some(Tag::IntArray(pos)) = data
+        .get("spawn")
+        .and_then(Tag::as_compound)
+        .and_then(|spawn| spawn.get("pos"))
+    {
+        if let [x, y, z] = pos[..] {
+            return [x, y, z];
+        }
+    }
+    ["SpawnX", "SpawnY", "SpawnZ"].map(|key| number(data, key) as i32)
+}
+
+fn seed(data: &Compound, bedrock: bool) -> i64 {
+    match bedrock {
+        true => number(data, "RandomSeed"),
+        false => data
+            .get("WorldGenSettings")
+            .and_then(Tag::as_compound)
+            .and_then(|settings| settings.get("seed"))
+            .and_then(Tag::as_i64)
+            .unwrap_or_else(|| number(data, "RandomSeed")),
+    }
+}
+
+fn game_rules(data: &Compound, bedrock: bool) -> Vec<GameRule> {
+    if bedrock {
+        return BEDROCK_GAME_RULES
+            .iter()
+            .filter_map(|name| {
+                let value = match data.get(name)? {
+                    Tag::Byte(value) => (*value != 0).to_string(),
+                    tag => tag.as_i64()?.to_string(),
+                };
+                Some(GameRule {
+                    name: name.to_string(),
+                    value,
+                })
+            })
+            .collect();
+    }
+    let Some(rules) = data.get("GameRules").and_then(Tag::as_compound) else {
+        return Vec::new();
+    };
+    let mut rules: Vec<GameRule> = rules
+        .0
+        .iter()
+        .filter_map(|(name, value)| {
+            Some(GameRule {
+                name: name.clone(),
+                value: value.as_str()?.to_string(),
+            })
+        })
+        .collect();
+    rules.sort_by(|a, b| a.name.cmp(&b.name));
+    rules
+}
+
+pub fn read(server_path: &Path, folder: &str) -> Result<WorldInfo, String> {
+    let path = level_dat_path(server_path, folder)?;
+    let bytes = fs::read(&path).map_err(|err| format!("Failed to read {:?}: {}", path, err))?;
+    let file = NbtFile::read(&bytes).map_err(|err| format!("{:?}: {}", path, err))?;
+    let bedrock = matches!(file.format, Format::Bedrock { .. });
+    let data = data(&file)?;
+    let text = |key: &str| {
+        data.get(key)
+            .and_then(Tag::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let version_name = match bedrock {
+        true => data
+            .get("lastOpenedWithVersion")
+            .and_then(Tag::as_list)
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(Tag::as_i64)
+                    .map(|part| part.to_string())
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .unwrap_or_default(),
+        false => data
+            .get("Version")
+            .and_then(Tag::as_compound)
+            .and_then(|version| version.get("Name"))
+            .and_then(Tag::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    };
+    Ok(WorldInfo {
+        folder: folder.to_string(),
+        bedrock,
+        level_name: text("LevelName"),
+        seed: seed(data, bedrock).to_string(),
+        game_type: number(data, "GameType") as i32,
+        difficulty: number(data, "Difficulty") as i32,
+        hardcore: number(data, if bedrock { "IsHardcore" } else { "hardcore" }) != 0,
+        spawn: match bedrock {
+            true => ["SpawnX", "SpawnY", "SpawnZ"].map(|key| number(data, key) as i32),
+            false => java_spawn(data),
+        },
+        day_time: number(data, if bedrock { "Time" } else { "DayTime" }).to_string(),
+        game_time: number(data, if bedrock { "currentTick" } else { "Time" }).to_string(),
+        data_version: number(
+            data,
+            if bedrock {
+                "StorageVersion"
+            } else {
+                "DataVersion"
+            },
+        ) as i32,
+        version_name,
+        game_rules: game_rules(data, bedrock),
+        hash: hash(&bytes),
+    })
+}
+
+fn apply(data: &mut Compound, info: &WorldInfo, bedrock: bool) -> Result<(), String> {
+    let seed: i64 = info
+        .seed
+        .trim()
+        .parse()
+        .map_err(|_| format!("Seed {} is not a number", info.seed))?;
+    let day_time: i64 = info
+        .day_time
+        .trim()
+        .parse()
+        .map_err(|_| format!("Time {} is not a number", info.day_time))?;
+    if !(0..=3).contains(&info.game_type) {
+        return Err(format!("Unknown game type {}", info.game_type));
+    }
+    if !(0..=3).contains(&info.difficulty) {
+        return Err(format!("Unknown difficulty {}", info.difficulty));
+    }
+    set_number(data, "GameType", info.game_type as i64, |value| {
+        Tag::Int(value as i32)
+    });
+    // java has difficulty as a byte and bedrock as an int, the existing tag keeps its type
+    set_number(data, "Difficulty", info.difficulty as i64, |value| {
+        Tag::Byte(value as i8)
+    });
+    set_number(
+        data,
+        if bedrock { "IsHardcore" } else { "hardcore" },
+        info.hardcore as i64,
+        |value| Tag::Byte(value as i8),
+    );
+    set_number(
+        data,
+        if bedrock { "Time" } else { "DayTime" },
+        day_time,
+        Tag::Long,
+    );
+    match data
+        .get_mut("WorldGenSettings")
+        .and_then(Tag::as_compound_mut)
+    {
+        Some(settings) if !bedrock => set_number(settings, "seed", seed, Tag::Long),
+        _ => set_number(data, "RandomSeed", seed, Tag::Long),
+    }
+    let spawn_compound = data
+        .get_mut("spawn")
+        .and_then(Tag::as_compound_mut)
+        .filter(|spawn| !bedrock && spawn.get("pos").is_some());
+    if let Some(spawn) = spawn_compound {
+        spawn.insert("pos", Tag::IntArray(info.spawn.to_vec()));
+    } else {
+        for (key, value) in ["SpawnX", "SpawnY", "SpawnZ"].into_iter().zip(info.spawn) {
+            set_number(data, key, value as i64, |value| Tag::Int(value as i32));
+        }
+    }
+    for rule in &info.game_rules {
+        if bedrock {
+            if !BEDROCK_GAME_RULES.contains(&rule.name.as_str()) {
+                return Err(format!("Unknown game rule {}", rule.name));
+            }
+            // rules missing from the file are added as bytes for booleans and ints for numbers
+            let (value, missing): (i64, fn(i64) -> Tag) = match rule.value.as_str() {
+                "true" => (1, |value| Tag::Byte(value as i8)),
+                "false" => (0, |value| Tag::Byte(value as i8)),
+                value => (
+                    value
+                        .parse()
+                        .map_err(|_| format!("{} is not a valid value for {}", value, rule.name))?,
+                    |value| Tag::Int(value as i32),
+                ),
+            };
+            set_number(data, &rule.name, value, missing);
+        } else {
+            if data.get("GameRules").and_then(Tag::as_compound).is_none() {
+                data.insert("GameRules", Tag::Compound(Compound::default()));
+            }
+            let rules = data
+                .get_mut("GameRules")
+                .and_then(Tag::as_compound_mut)
+                .unwrap();
+            rules.insert(&rule.name, Tag::String(rule.value.trim().to_string()));
+        }
+    }
+    Ok(())
+}
+
+// Saves the editable settings back. The file has to hash the same as when it was read, and a
+// copy that hashes the same is kept as level.dat.bak before anything is written
+pub fn write(server_path: &Path, info: &WorldInfo) -> Result<WorldInfo, String> {
+    let path = level_dat_path(server_path, &info.folder)?;
+    let bytes = fs::read(&path).map_err(|err| format!("Failed to read {:?}: {}", path, err))?;
+    let current = hash(&bytes);
+    if current != info.hash {
+        return Err("level.dat changed since it was loaded, reload the world first".to_string());
+    }
+    let mut file = NbtFile::read(&bytes).map_err(|err| format!("{:?}: {}", path, err))?;
+    let bedrock = matches!(file.format, Format::Bedrock { .. });
+    apply(data_mut(&mut file)?, info, bedrock)?;
+
+    let backup = path.with_file_name("level.dat.bak");
+    fs::copy(&path, &backup).map_err(|err| format!("Failed to back up {:?}: {}", path, err))?;
+    let copied =
+        fs::read(&backup).map_err(|err| format!("Failed to read {:?}: {}", backup, err))?;
+    if hash(&copied) != current {
+        return Err(format!(
+            "The backup at {:?} doesn't match level.dat, nothing was changed",
+            backup
+        ));
+    }
+    file.save(&path)?;
+    read(server_path, &info.folder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const LEVEL_DAT: &[u8] = include_bytes!("testdata/nbt/level.dat");
+    const BEDROCK_LEVEL_DAT: &[u8] = include_bytes!("testdata/nbt/bedrock_level.dat");
+
+    struct TestServer {
+        path: PathBuf,
+    }
+
+    impl TestServer {
+        fn new(folder: &str, level_dat: &[u8]) -> TestServer {
+            let path = std::env::temp_dir().join(format!("gaia-level-dat-{}", Uuid::new_v4()));
+            fs::create_dir_all(path.join(folder)).unwrap();
+            fs::write(path.join(folder).join("level.dat"), level_dat).unwrap();
+            TestServer { path }
+        }
+    }
+
+    impl Drop for TestServer {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn rule<'a>(info: &'a WorldInfo, name: &str) -> Option<&'a str> {
+        info.game_rules
+            .iter()
+            .find(|rule| rule.name == name)
+            .map(|rule| rule.value.as_str())
+    }
+
+    fn set_rule(info: &mut WorldInfo, name: &str, value: &str) {
+        info.game_rules.retain(|rule| rule.name != name);
+        info.game_rules.push(GameRule {
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    #[test]
+    fn reads_java_level_dat() {
+        let server = TestServer::new("world", LEVEL_DAT);
+        assert_eq!(world_folders(&server.path), vec!["world".to_string()]);
+        let info = read(&server.path, "world").unwrap();
+        assert!(!info.bedrock);
+        assert_eq!(info.level_name, "Survival ✦ world");
+        assert_eq!(info.seed, "-4172144997902289642");
+        assert_eq!(info.data_version, 3955);
+        assert_eq!(info.hash, hash(LEVEL_DAT));
+    }
+
+    #[test]
+    fn java_edits_round_trip() {
+        let server = TestServer::new("world", LEVEL_DAT);
+        let mut info = read(&server.path, "world").unwrap();
+        info.seed = "1234567890123".to_string();
+        info.game_type = 1;
+        info.difficulty = 3;
+        info.hardcore = true;
+        info.spawn = [10, 80, -20];
+        info.day_time = "6000".to_string();
+        set_rule(&mut info, "keepInventory", "true");
+        set_rule(&mut info, "randomTickSpeed", "5");
+
+        let saved = write(&server.path, &info).unwrap();
+        assert_eq!(saved.seed, "1234567890123");
+        assert_eq!(saved.game_type, 1);
+        assert_eq!(saved.difficulty, 3);
+        assert!(saved.hardcore);
+        assert_eq!(saved.spawn, [10, 80, -20]);
+        assert_eq!(saved.day_time, "6000");
+        assert_eq!(rule(&saved, "keepInventory"), Some("true"));
+        assert_eq!(rule(&saved, "randomTickSpeed"), Some("5"));
+        // fields that weren't edited stay as they were
+        assert_eq!(saved.level_name, info.level_name);
+        assert_eq!(saved.data_version, info.data_version);
+        assert_eq!(saved.game_time, info.game_time);
+        assert_eq!(saved.version_name, info.version_name);
+        assert_eq!(read(&server.path, "world").unwrap().hash, saved.hash);
+        assert_ne!(saved.hash, info.hash);
+
+        // the existing tags kept their types
+        let file = NbtFile::read(&fs::read(server.path.join("world/level.dat")).unwrap()).unwrap();
+        let data = data(&file).unwrap();
+        assert_eq!(data.get("Difficulty"), Some(&Tag::Byte(3)));
+        assert_eq!(data.get("GameType"), Some(&Tag::Int(1)));
+    }
+
+    #[test]
+    fn backup_matches_the_original() {
+        let server = TestServer::new("world", LEVEL_DAT);
+        let mut info = read(&server.path, "world").unwrap();
+        info.difficulty = 0;
+        write(&server.path, &info).unwrap();
+        assert_eq!(
+            fs::read(server.path.join("world/level.dat.bak")).unwrap(),
+            LEVEL_DAT
+        );
+    }
+
+    #[test]
+    fn refuses_a_changed_file() {
+        let server = TestServer::new("world", LEVEL_DAT);
+        let mut info = read(&server.path, "world").unwrap();
+        // the server saved the world in the meantime
+        let mut file = NbtFile::read(LEVEL_DAT).unwrap();
+        set_number(data_mut(&mut file).unwrap(), "DayTime", 1, Tag::Long);
+        file.save(&server.path.join("world/level.dat")).unwrap();
+        let changed = fs::read(server.path.join("world/level.dat")).unwrap();
+
+        info.difficulty = 0;
+        assert_eq!(
+            write(&server.path, &info).unwrap_err(),
+            "level.dat changed since it was loaded, reload the world first"
+        );
+        assert_eq!(
+            fs::read(server.path.join("world/level.dat")).unwrap(),
+            changed
+        );
+        assert!(!server.path.join("world/level.dat.bak").exists());
+    }
+
+    #[test]
+    fn refuses_invalid_values() {
+        let server = TestServer::new("world", LEVEL_DAT);
+        let info = read(&server.path, "world").unwrap();
+        for invalid in [
+            WorldInfo {
+                seed: "not a seed".to_string(),
+                ..info.clone()
+            },
+            WorldInfo {
+                game_type: 4,
+                ..info.clone()
+            },
+            WorldInfo {
+                difficulty: -1,
+                ..info.clone()
+            },
+        ] {
+            assert!(write(&server.path, &invalid).is_err());
+        }
+        assert_eq!(
+            fs::read(server.path.join("world/level.dat")).unwrap(),
+            LEVEL_DAT
+        );
+    }
+
+    #[test]
+    fn bedrock_edits_round_trip() {
+        let server = TestServer::new("worlds/Bedrock level", BEDROCK_LEVEL_DAT);
+        assert_eq!(
+            world_folders(&server.path),
+            vec!["worlds/Bedrock level".to_string()]
+        );
+        let mut info = read(&server.path, "worlds/Bedrock level").unwrap();
+        assert!(info.bedrock);
+        assert_eq!(info.level_name, "Bedrock level");
+        assert_eq!(info.seed, "8234762347823");
+        info.difficulty = 2;
+        set_rule(&mut info, "keepinventory", "true");
+        set_rule(&mut info, "spawnradius", "3");
+
+        let saved = write(&server.path, &info).unwrap();
+        assert_eq!(saved.difficulty, 2);
+        assert_eq!(rule(&saved, "keepinventory"), Some("true"));
+        assert_eq!(rule(&saved, "spawnradius"), Some("3"));
+        assert_eq!(
+            fs::read(server.path.join("worlds/Bedrock level/level.dat.bak")).unwrap(),
+            BEDROCK_LEVEL_DAT
+        );
+        let file =
+            NbtFile::read(&fs::read(server.path.join("worlds/Bedrock level/level.dat")).unwrap())
+                .unwrap();
+        assert_eq!(file.format, Format::Bedrock { header: Some(10) });
+
+        set_rule(&mut info, "notarule", "true");
+        info.hash = saved.hash;
+        assert_eq!(
+            write(&server.path, &info).unwrap_err(),
+            "Unknown game rule notarule"
+        );
+    }
+
+    #[test]
+    fn folders_stay_inside_the_server() {
+        let server = TestServer::new("world", LEVEL_DAT);
+        for folder in ["", "../world", "/world", "world/../world"] {
+            assert!(level_dat_path(&server.path, folder).is_err());
+        }
+        assert!(read(&server.path, "missing").is_err());
+    }
+}
@@ -0,0 +1,378 @@
+use flate2::read::GzDecoder;
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Hard cap so a search across months of logs can't flood the frontend
+pub const MAX_SEARCH_HITS: usize = 2000;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogFile {
+    pub name: String,
+    // YYYY-MM-DD, latest.log uses its last modified date
+    pub date: String,
+    pub index: u32,
+    pub size: u64,
+    pub compressed: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogPage {
+    pub name: String,
+    pub page: usize,
+    pub page_size: usize,
+    pub lines: Vec<String>,
+    pub has_more: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogSearchHit {
+    pub name: String,
+    pub date: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+// Parses rotated log names like "2025-10-26-3.log.gz" into (date, index)
+fn parse_log_name(name: &str) -> Option<(String, u32)> {
+    let stem = name
+        .strip_suffix(".log.gz")
+        .or_else(|| name.strip_suffix(".log"))?;
+    if stem.len() < 12 {
+        return None;
+    }
+    let (date, index) = stem.split_at(10);
+    let valid_date = date.char_indices().all(|(i, c)| match i {
+        4 | 7 => c == '-',
+        _ => c.is_ascii_digit(),
+    });
+    if !valid_date {
+        return None;
+    }
+    let index = index.strip_prefix('-')?.parse::<u32>().ok()?;
+    Some((date.to_string(), index))
+}
+
+// Converts a unix timestamp to a UTC YYYY-MM-DD date
+pub fn system_time_to_date(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 86400)
+        .unwrap_or(0) as i64;
+    // Howard Hinnant's civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn logs_dir(server_path: &Path) -> PathBuf {
+    server_path.join("logs")
+}
+
+// Only the logs list_log_files hands out can be opened, never other files or paths
+fn log_path(server_path: &Path, name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid log file name: {}", name));
+    }
+    if !list_log_files(server_path)?
+        .iter()
+        .any(|file| file.name == name)
+    {
+        return Err(format!("Log file {} does not exist", name));
+    }
+    Ok(logs_dir(server_path).join(name))
+}
+
+fn open_log(path: &Path) -> Result<Box<dyn BufRead>, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        Ok(Box::new(BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+// Iterates a log lazily, invalid utf-8 gets replaced instead of failing the whole file
+fn log_lines(reader: Box<dyn BufRead>) -> impl Iterator<Item = Result<String, String>> {
+    reader.split(b'\n').map(|line| {
+        line.map(|bytes| {
+            String::from_utf8_lossy(&bytes)
+                .trim_end_matches('\r')
+                .to_string()
+        })
+        .map_err(|err| err.to_string())
+    })
+}
+
+// Lists latest.log and every rotated log, newest first
+pub fn list_log_files(server_path: &Path) -> Result<Vec<LogFile>, String> {
+    let dir = logs_dir(server_path);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|err| err.to_string())? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let (date, index) = if name == "latest.log" {
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            (system_time_to_date(modified), u32::MAX)
+        } else {
+            match parse_log_name(&name) {
+                Some(parsed) => parsed,
+                None => continue,
+            }
+        };
+        files.push(LogFile {
+            compressed: name.ends_with(".gz"),
+            name,
+            date,
+            index,
+            size: metadata.len(),
+        });
+    }
+    files.sort_by(|a, b| b.date.cmp(&a.date).then(b.index.cmp(&a.index)));
+    Ok(files)
+}
+
+// Decompresses only as much of the file as the requested page needs
+pub fn read_log_page(
+    server_path: &Path,
+    name: &str,
+    page: usize,
+    page_size: usize,
+) -> Result<LogPage, String> {
+    let page_size = page_size.max(1);
+    let path = log_path(server_path, name)?;
+    let mut lines = log_lines(open_log(&path)?).skip(page * page_size);
+    let mut page_lines = Vec::with_capacity(page_size);
+    for line in lines.by_ref().take(page_size) {
+        page_lines.push(line?);
+    }
+    Ok(LogPage {
+        name: name.to_string(),
+        page,
+        page_size,
+        lines: page_lines,
+        has_more: lines.next().is_some(),
+    })
+}
+
+// Searches every log with a date inside of [from, to], both bounds are optional
+pub fn search_logs(
+    server_path: &Path,
+    from: Option<&str>,
+    to: Option<&str>,
+    query: &str,
+    use_regex: bool,
+) -> Result<Vec<LogSearchHit>, String> {
+    // an empty pattern matches every line of every log
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pattern = match use_regex {
+        true => query.to_string(),
+        false => regex::escape(query),
+    };
+    let matcher = RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|err| err.to_string())?;
+    let mut files: Vec<LogFile> = list_log_files(server_path)?
+        .into_iter()
+        .filter(|file| from.is_none_or(|from| file.date.as_str() >= from))
+        .filter(|file| to.is_none_or(|to| file.date.as_str() <= to))
+        .collect();
+    // oldest first so hits read top to bottom like a console
+    files.reverse();
+    let mut hits = Vec::new();
+    for file in files {
+        let path = logs_dir(server_path).join(&file.name);
+        // a single corrupt archive shouldn't stop the search
+        let Ok(reader) = open_log(&path) else {
+            continue;
+        };
+        for (line_number, line) in log_lines(reader).enumerate() {
+            let Ok(line) = line else {
+                break;
+            };
+            if matcher.is_match(&line) {
+                hits.push(LogSearchHit {
+                    name: file.name.clone(),
+                    date: file.date.clone(),
+                    line_number: line_number + 1,
+                    line,
+                });
+                if hits.len() >= MAX_SEARCH_HITS {
+                    return Ok(hits);
+                }
+            }
+        }
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::{io::Write, time::Duration};
+
+    fn write_gz(path: &Path, content: &str) {
+        let mut encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    // A server folder with two archived days and latest.log
+    fn server_with_logs() -> PathBuf {
+        let server_path =
+            std::env::temp_dir().join(format!("log-archive-{}", uuid::Uuid::new_v4()));
+        let logs = logs_dir(&server_path);
+        fs::create_dir_all(&logs).unwrap();
+        write_gz(
+            &logs.join("2025-10-24-1.log.gz"),
+            "[10:00:00] Steve joined the game\n[10:05:00] Steve left the game\n",
+        );
+        write_gz(
+            &logs.join("2025-10-26-1.log.gz"),
+            "[09:00:00] Alex joined the game\n",
+        );
+        write_gz(
+            &logs.join("2025-10-26-2.log.gz"),
+            "[12:00:00] steve JOINED the game\r\n",
+        );
+        fs::write(logs.join("latest.log"), "[13:00:00] Server started\n").unwrap();
+        fs::write(logs.join("debug-1.log.gz"), "not a rotated log").unwrap();
+        server_path
+    }
+
+    #[test]
+    fn parses_rotated_log_names() {
+        assert_eq!(
+            parse_log_name("2025-10-26-3.log.gz"),
+            Some(("2025-10-26".to_string(), 3))
+        );
+        assert_eq!(
+            parse_log_name("2025-10-26-12.log"),
+            Some(("2025-10-26".to_string(), 12))
+        );
+        assert_eq!(parse_log_name("2025-10-26.log.gz"), None);
+        assert_eq!(parse_log_name("2025/10/26-1.log.gz"), None);
+        assert_eq!(parse_log_name("2025-10-26-x.log.gz"), None);
+        assert_eq!(parse_log_name("latest.log"), None);
+        assert_eq!(parse_log_name("2025-10-26-1.txt"), None);
+    }
+
+    #[test]
+    fn converts_unix_time_to_dates() {
+        assert_eq!(system_time_to_date(UNIX_EPOCH), "1970-01-01");
+        // 2024 is a leap year
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_164_800);
+        assert_eq!(system_time_to_date(leap_day), "2024-02-29");
+        assert_eq!(
+            system_time_to_date(leap_day + Duration::from_secs(86_399)),
+            "2024-02-29"
+        );
+        assert_eq!(
+            system_time_to_date(leap_day + Duration::from_secs(86_400)),
+            "2024-03-01"
+        );
+    }
+
+    #[test]
+    fn lists_logs_newest_first() {
+        let server_path = server_with_logs();
+        let files = list_log_files(&server_path).unwrap();
+        let names: Vec<&str> = files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "latest.log",
+                "2025-10-26-2.log.gz",
+                "2025-10-26-1.log.gz",
+                "2025-10-24-1.log.gz"
+            ]
+        );
+        assert!(files[1].compressed && !files[0].compressed);
+        fs::remove_dir_all(server_path).unwrap();
+    }
+
+    #[test]
+    fn pages_through_compressed_logs() {
+        let server_path = server_with_logs();
+        let first = read_log_page(&server_path, "2025-10-24-1.log.gz", 0, 1).unwrap();
+        assert_eq!(first.lines, ["[10:00:00] Steve joined the game"]);
+        assert!(first.has_more);
+        let second = read_log_page(&server_path, "2025-10-24-1.log.gz", 1, 1).unwrap();
+        assert_eq!(second.lines, ["[10:05:00] Steve left the game"]);
+        assert!(!second.has_more);
+        assert!(read_log_page(&server_path, "../latest.log", 0, 10).is_err());
+        assert!(read_log_page(&server_path, "missing.log", 0, 10).is_err());
+        // on disk but not a log the browser lists
+        assert!(read_log_page(&server_path, "debug-1.log.gz", 0, 10).is_err());
+        fs::remove_dir_all(server_path).unwrap();
+    }
+
+    #[test]
+    fn searches_inside_of_a_date_range() {
+        let server_path = server_with_logs();
+        let hits = search_logs(
+            &server_path,
+            Some("2025-10-25"),
+            Some("2025-10-26"),
+            "joined",
+            false,
+        )
+        .unwrap();
+        let found: Vec<(&str, usize, &str)> = hits
+            .iter()
+            .map(|hit| (hit.name.as_str(), hit.line_number, hit.line.as_str()))
+            .collect();
+        // oldest first, case insensitive and without the \r
+        assert_eq!(
+            found,
+            [
+                ("2025-10-26-1.log.gz", 1, "[09:00:00] Alex joined the game"),
+                ("2025-10-26-2.log.gz", 1, "[12:00:00] steve JOINED the game"),
+            ]
+        );
+        let all = search_logs(
+            &server_path,
+            None,
+            Some("2025-10-24"),
+            r"steve (joined|left)",
+            true,
+        )
+        .unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(search_logs(&server_path, None, None, "(", true).is_err());
+        assert!(search_logs(&server_path, None, None, "", false)
+            .unwrap()
+            .is_empty());
+        assert!(search_logs(&server_path, None, None, "", true)
+            .unwrap()
+            .is_empty());
+        fs::remove_dir_all(server_path).unwrap();
+    }
+}
@@ -3,6 +3,7 @@ use fs_more::directory::{
     copy_directory, BrokenSymlinkBehaviour, DestinationDirectoryRule, DirectoryCopyDepthLimit,
     DirectoryCopyOptions, SymlinkBehaviour,
 };
+use serde::Serialize;
 use std::{
     fs,
     path::{Path, PathBuf},
@@ -10,13 +11,65 @@ use std::{
 use tauri::{
     command,
     plugin::{Builder, TauriPlugin},
-    AppHandle, Manager, Runtime, Window,
+    AppHandle, Emitter, Manager, Runtime, Window,
 };
 use toml;
 use uuid::Uuid;
 
+mod anvil;
+mod auto_start;
+mod backup;
+mod backup_retention;
+mod backup_store;
+mod bedrock;
 mod core_structs;
-use crate::gaia_core::core_structs::{Config, ServerStruct};
+mod crash_report;
+mod cron;
+mod forwarding;
+mod geyser;
+mod groups;
+mod http;
+mod level_dat;
+mod log_archive;
+mod mod_scanner;
+mod nbt;
+mod network;
+mod port_forwarding;
+mod ports;
+mod proxy;
+mod prune;
+mod raknet;
+mod registry;
+mod resource_monitor;
+mod restart_countdown;
+mod restart_policy;
+mod scheduler;
+mod server_properties;
+mod supervisor;
+mod tick_monitor;
+mod worlds;
+use crate::gaia_core::anvil::DimensionStats;
+use crate::gaia_core::backup::{BackupManifest, BackupOptions};
+use crate::gaia_core::backup_retention::{RetentionDecision, RetentionPolicy};
+use crate::gaia_core::bedrock::BedrockAccess;
+use crate::gaia_core::core_structs::{Config, ServerStruct, STATUS_OFFLINE, STATUS_ONLINE};
+use crate::gaia_core::crash_report::CrashReport;
+use crate::gaia_core::forwarding::ForwardingReport;
+use crate::gaia_core::geyser::GeyserSetup;
+use crate::gaia_core::groups::ServerGroup;
+use crate::gaia_core::level_dat::WorldInfo;
+use crate::gaia_core::log_archive::{LogFile, LogPage, LogSearchHit};
+use crate::gaia_core::port_forwarding::{PortForwarding, PortMapping};
+use crate::gaia_core::ports::PortInUse;
+use crate::gaia_core::proxy::ProxyConfig;
+use crate::gaia_core::prune::{PruneOptions, PruneReport};
+use crate::gaia_core::registry::Registry;
+use crate::gaia_core::resource_monitor::{ResourceMonitor, ResourceSample};
+use crate::gaia_core::restart_countdown::RestartCountdowns;
+use crate::gaia_core::scheduler::{ScheduledTask, Scheduler, SystemClock, TaskRun};
+use crate::gaia_core::supervisor::{ConsolePayload, Supervisor};
+use crate::gaia_core::tick_monitor::{TickHistory, TickMonitor};
+use crate::gaia_core::worlds::WorldEntry;
 
 #[command]
 pub fn create_server<R: Runtime>(
@@ -26,6 +79,10 @@ pub fn create_server<R: Runtime>(
 ) -> Result<String, String> {
     let app_dir = app.path().app_data_dir().unwrap();
     let server = server.to_owned();
+    let servers = app.state::<Registry>().servers();
+    if let Some(conflict) = ports::find_conflicts(&server, &servers, true).first() {
+        return Err(conflict.to_string());
+    }
     // Check if server path exists
     if fs::exists(server.clone().path).unwrap_or_else(|_| return false) == false {
         fs::create_dir_all(server.clone().path).unwrap_or_else(|err| panic!("{}", err));
@@ -45,7 +102,7 @@ pub fn create_server<R: Runtime>(
         .to_str()
         .unwrap()
         .to_string();
-    let server_config = toml::to_string(&ServerStruct {
+    let server_config = ServerStruct {
         id: Uuid::new_v4(),
         status: 0,
         name: server.clone().name,
@@ -68,6 +125,9 @@ pub fn create_server<R: Runtime>(
         // TODO: Implement creation_date
         creation_date: "unkown".to_string(),
         auto_start: server.clone().auto_start,
+        auto_start_order: server.auto_start_order,
+        auto_start_delay_seconds: server.auto_start_delay_seconds,
+        auto_start_after: server.auto_start_after,
         force_save_on_stop: server.clone().force_save_on_stop,
         // TODO: Add some way to get all of the avaliable java paths
         java_path: "unknown".to_string(),
@@ -78,14 +138,1254 @@ pub fn create_server<R: Runtime>(
             jar_path.clone()
         ),
         ..Default::default()
-    });
+    };
+    app.state::<Registry>().save(&app_dir, server_config)?;
     Ok("Success".to_string())
 }
 
+// Logs are only ever read from the logs folder of a registered server
+fn server_path<R: Runtime>(app: &AppHandle<R>, id: Uuid) -> Result<PathBuf, String> {
+    app.state::<Registry>()
+        .get(id)
+        .map(|server| PathBuf::from(server.path))
+        .ok_or_else(|| "Server not found".to_string())
+}
+
+// Proxies are set up from a jar the user picked, the config is written here instead of
+// being copied from an existing server
+#[command]
+pub fn create_proxy_server<R: Runtime>(
+    app: AppHandle<R>,
+    server: ServerStruct,
+    jar_path: String,
+) -> Result<ServerStruct, String> {
+    if !proxy::is_proxy(&server.server_type) {
+        return Err(format!("{} is not a proxy", server.server_type));
+    }
+    let app_dir = app.path().app_data_dir().unwrap();
+    let servers = app.state::<Registry>().servers();
+    if let Some(conflict) = ports::find_conflicts(&server, &servers, true).first() {
+        return Err(conflict.to_string());
+    }
+    let jar = proxy::provision(&server, Path::new(&jar_path))?;
+    let proxy = ServerStruct {
+        id: Uuid::new_v4(),
+        icon_path: Path::new(&server.path)
+            .join("server-icon.png")
+            .to_string_lossy()
+            .to_string(),
+        server_jar_path: jar.to_string_lossy().to_string(),
+        minecraft_version: "unknown".to_string(),
+        server_version: "unknown".to_string(),
+        creation_date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        // there's no world to save
+        force_save_on_stop: false,
+        java_startup_line: format!(
+            "java -Xms{}M -Xmx{}M -jar {}",
+            server.java_allocated_memory,
+            server.java_allocated_memory,
+            jar.to_string_lossy()
+        ),
+        ..server
+    };
+    app.state::<Registry>().save(&app_dir, proxy.clone())?;
+    Ok(proxy)
+}
+
+// Bedrock servers are unpacked from the dedicated server zip mojang hands out
+#[command]
+pub fn create_bedrock_server<R: Runtime>(
+    app: AppHandle<R>,
+    server: ServerStruct,
+    source_path: String,
+) -> Result<ServerStruct, String> {
+    if !bedrock::is_bedrock(&server.server_type) {
+        return Err(format!("{} is not a bedrock server", server.server_type));
+    }
+    let app_dir = app.path().app_data_dir().unwrap();
+    let servers = app.state::<Registry>().servers();
+    if let Some(conflict) = ports::find_conflicts(&server, &servers, true).first() {
+        return Err(conflict.to_string());
+    }
+    let (binary, version) = bedrock::provision(&server, Path::new(&source_path))?;
+    let bedrock_server = ServerStruct {
+        id: Uuid::new_v4(),
+        icon_path: Path::new(&server.path)
+            .join("server-icon.png")
+            .to_string_lossy()
+            .to_string(),
+        server_jar_path: binary.to_string_lossy().to_string(),
+        minecraft_version: version.clone(),
+        server_version: version,
+        creation_date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        java_startup_line: format!("\"{}\"", binary.to_string_lossy()),
+        ..server
+    };
+    app.state::<Registry>()
+        .save(&app_dir, bedrock_server.clone())?;
+    Ok(bedrock_server)
+}
+
+fn get_bedrock<R: Runtime>(app: &AppHandle<R>, id: Uuid) -> Result<ServerStruct, String> {
+    app.state::<Registry>()
+        .get(id)
+        .filter(|server| bedrock::is_bedrock(&server.server_type))
+        .ok_or_else(|| "Bedrock server not found".to_string())
+}
+
+#[command]
+pub fn get_bedrock_access<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+) -> Result<BedrockAccess, String> {
+    bedrock::read_access(&get_bedrock(&app, id)?)
+}
+
+// A running server is told to reload both lists so the change applies right away
+#[command]
+pub fn save_bedrock_access<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    access: BedrockAccess,
+) -> Result<(), String> {
+    let server = get_bedrock(&app, id)?;
+    bedrock::write_access(&server, &access)?;
+    let supervisor = app.state::<Supervisor>();
+    if supervisor.is_running(id) {
+        supervisor.send_command(id, "allowlist reload")?;
+        supervisor.send_command(id, "permission reload")?;
+    }
+    Ok(())
+}
+
+#[command]
+pub fn get_geyser_setup<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<GeyserSetup, String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    Ok(geyser::setup(&server))
+}
+
+// The port is checked before it's written, find_conflicts only sees what's already on disk
+fn check_geyser_port<R: Runtime>(
+    app: &AppHandle<R>,
+    server: &ServerStruct,
+    port: u16,
+) -> Result<(), String> {
+    let servers = app.state::<Registry>().servers();
+    let owner = servers
+        .iter()
+        .filter(|other| other.id != server.id)
+        .find(|other| {
+            ports::server_ports(other)
+                .iter()
+                .any(|(_, other_port)| *other_port == port)
+        });
+    match owner {
+        Some(other) => Err(PortInUse {
+            kind: "geyser".to_string(),
+            port,
+            owner: format!("server \"{}\"", other.name),
+        }
+        .to_string()),
+        None => Ok(()),
+    }
+}
+
+// Copies Geyser, and Floodgate when given, into the server and points Geyser at it
+#[command]
+pub fn install_geyser<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    geyser_path: String,
+    floodgate_path: Option<String>,
+    bedrock_port: u16,
+) -> Result<GeyserSetup, String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    check_geyser_port(&app, &server, bedrock_port)?;
+    let floodgate_path = floodgate_path.filter(|path| !path.trim().is_empty());
+    geyser::install(
+        &server,
+        Path::new(&geyser_path),
+        floodgate_path.as_deref().map(Path::new),
+    )?;
+    geyser::configure(&server, bedrock_port)?;
+    Ok(geyser::setup(&server))
+}
+
+// Writes the bedrock port and the server's current java port and auth into Geyser's config
+#[command]
+pub fn configure_geyser<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    bedrock_port: u16,
+) -> Result<GeyserSetup, String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    check_geyser_port(&app, &server, bedrock_port)?;
+    geyser::configure(&server, bedrock_port)?;
+    Ok(geyser::setup(&server))
+}
+
+fn get_proxy<R: Runtime>(app: &AppHandle<R>, id: Uuid) -> Result<ServerStruct, String> {
+    app.state::<Registry>()
+        .get(id)
+        .filter(|server| proxy::is_proxy(&server.server_type))
+        .ok_or_else(|| "Proxy not found".to_string())
+}
+
+#[command]
+pub fn get_proxy_config<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<ProxyConfig, String> {
+    proxy::read_config(&get_proxy(&app, id)?)
+}
+
+#[command]
+pub fn save_proxy_config<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    config: ProxyConfig,
+) -> Result<(), String> {
+    let proxy = get_proxy(&app, id)?;
+    if let Some(missing) = config
+        .try_servers
+        .iter()
+        .find(|name| !config.servers.iter().any(|server| &server.name == *name))
+    {
+        return Err(format!(
+            "{} is in the try list but not in the servers",
+            missing
+        ));
+    }
+    proxy::write_config(&proxy, &config)?;
+    let app_dir = app.path().app_data_dir().unwrap();
+    app.state::<Registry>()
+        .update(&app_dir, id, |proxy| proxy.port = config.bind_port)?;
+    Ok(())
+}
+
+// Adds a registered server to the proxy's server list, returns the updated backend
+#[command]
+pub fn link_proxy_backend<R: Runtime>(
+    app: AppHandle<R>,
+    proxy_id: Uuid,
+    backend_id: Uuid,
+) -> Result<ServerStruct, String> {
+    let app_dir = app.path().app_data_dir().unwrap();
+    let registry = app.state::<Registry>();
+    let proxy = get_proxy(&app, proxy_id)?;
+    let backend = registry
+        .get(backend_id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    if proxy::is_proxy(&backend.server_type) {
+        return Err("A proxy can't be the backend of another proxy".to_string());
+    }
+    if let Some(old_proxy) = backend.proxy_id.and_then(|id| registry.get(id)) {
+        proxy::unlink(&old_proxy, &backend.proxy_name)?;
+    }
+    // two backends with the same name get told apart with a number
+    let taken: Vec<String> = registry
+        .servers()
+        .into_iter()
+        .filter(|other| other.id != backend_id && other.proxy_id == Some(proxy_id))
+        .map(|other| other.proxy_name)
+        .collect();
+    let base_name = proxy::proxy_name(&backend.name);
+    let name = (1..)
+        .map(|number| match number {
+            1 => base_name.clone(),
+            number => format!("{}-{}", base_name, number),
+        })
+        .find(|name| !taken.contains(name))
+        .unwrap();
+    proxy::link(&proxy, &backend, &name)?;
+    let backend = registry.update(&app_dir, backend_id, |backend| {
+        backend.proxy_id = Some(proxy_id);
+        backend.proxy_name = name.clone();
+    })?;
+    // modern forwarding would lock out backends that can't read it, those are left for the user
+    let backends = proxy_backends(&app, proxy_id);
+    if proxy.server_type == "velocity"
+        && backends
+            .iter()
+            .all(|other| forwarding::is_supported(&other.server_type))
+    {
+        let secret = forwarding::enable_modern(&proxy)?;
+        forwarding::configure_backend(&backend, &secret)?;
+        auto_start::manager_message(
+            &app,
+            backend_id,
+            format!(
+                "Set up modern forwarding with {}, restart both servers to apply it",
+                proxy.name
+            ),
+        );
+    }
+    Ok(backend)
+}
+
+fn proxy_backends<R: Runtime>(app: &AppHandle<R>, proxy_id: Uuid) -> Vec<ServerStruct> {
+    app.state::<Registry>()
+        .servers()
+        .into_iter()
+        .filter(|server| server.proxy_id == Some(proxy_id))
+        .collect()
+}
+
+fn get_velocity<R: Runtime>(app: &AppHandle<R>, id: Uuid) -> Result<ServerStruct, String> {
+    let proxy = get_proxy(app, id)?;
+    match proxy.server_type.as_str() {
+        "velocity" => Ok(proxy),
+        _ => Err("Modern forwarding is only supported by Velocity".to_string()),
+    }
+}
+
+#[command]
+pub fn check_proxy_forwarding<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+) -> Result<ForwardingReport, String> {
+    let proxy = get_velocity(&app, id)?;
+    forwarding::check(&proxy, &proxy_backends(&app, id))
+}
+
+// Switches the proxy to modern forwarding and hands its secret to every backend that can use it
+#[command]
+pub fn setup_proxy_forwarding<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+) -> Result<ForwardingReport, String> {
+    let proxy = get_velocity(&app, id)?;
+    let backends = proxy_backends(&app, id);
+    let secret = forwarding::enable_modern(&proxy)?;
+    for backend in backends
+        .iter()
+        .filter(|backend| forwarding::is_supported(&backend.server_type))
+    {
+        forwarding::configure_backend(backend, &secret)?;
+    }
+    forwarding::check(&proxy, &backends)
+}
+
+#[command]
+pub fn unlink_proxy_backend<R: Runtime>(
+    app: AppHandle<R>,
+    backend_id: Uuid,
+) -> Result<ServerStruct, String> {
+    let app_dir = app.path().app_data_dir().unwrap();
+    let registry = app.state::<Registry>();
+    let backend = registry
+        .get(backend_id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    if let Some(proxy) = backend.proxy_id.and_then(|id| registry.get(id)) {
+        proxy::unlink(&proxy, &backend.proxy_name)?;
+    }
+    registry.update(&app_dir, backend_id, |backend| {
+        backend.proxy_id = None;
+        backend.proxy_name = "".to_string();
+    })
+}
+
+// Ports of the server that another registered server or some program already uses
+#[command]
+pub fn check_server_ports<R: Runtime>(app: AppHandle<R>, server: ServerStruct) -> Vec<PortInUse> {
+    ports::find_conflicts(&server, &app.state::<Registry>().servers(), true)
+}
+
+#[command]
+pub fn suggest_server_port<R: Runtime>(app: AppHandle<R>, start: u16) -> Option<u16> {
+    ports::next_free_port(start, &app.state::<Registry>().servers())
+}
+
+#[command]
+pub fn get_config<R: Runtime>(app: AppHandle<R>) -> Config {
+    registry::load_config(&app.path().app_data_dir().unwrap())
+}
+
+#[derive(Serialize)]
+pub struct ServerIps {
+    local_ip: String,
+    public_ip: String,
+}
+
+// Looks up the server's lan and public address and saves them on registered servers.
+// A failed public lookup keeps the last known address
+#[command]
+pub async fn detect_server_ips<R: Runtime>(
+    app: AppHandle<R>,
+    server: ServerStruct,
+) -> Result<ServerIps, String> {
+    let app_dir = app.path().app_data_dir().unwrap();
+    let config = registry::load_config(&app_dir);
+    let id = server.id;
+    let ips = tauri::async_runtime::spawn_blocking(move || ServerIps {
+        local_ip: network::local_ip(&server).to_string(),
+        public_ip: match network::public_ip_source(&config).public_ip() {
+            Ok(ip) => ip.to_string(),
+            Err(err) => {
+                println!("Public ip lookup failed: {}", err);
+                server.public_ip
+            }
+        },
+    })
+    .await
+    .map_err(|err| err.to_string())?;
+    let registry = app.state::<Registry>();
+    if registry.get(id).is_some() {
+        registry.update(&app_dir, id, |server| {
+            server.local_ip = ips.local_ip.clone();
+            server.public_ip = ips.public_ip.clone();
+        })?;
+    }
+    Ok(ips)
+}
+
+#[command]
+pub async fn list_server_logs<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+) -> Result<Vec<LogFile>, String> {
+    let path = server_path(&app, id)?;
+    tauri::async_runtime::spawn_blocking(move || log_archive::list_log_files(&path))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+#[command]
+pub async fn read_server_log<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    name: String,
+    page: usize,
+    page_size: usize,
+) -> Result<LogPage, String> {
+    let path = server_path(&app, id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        log_archive::read_log_page(&path, &name, page, page_size)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[command]
+pub async fn search_server_logs<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    from: Option<String>,
+    to: Option<String>,
+    query: String,
+    use_regex: bool,
+) -> Result<Vec<LogSearchHit>, String> {
+    let path = server_path(&app, id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        log_archive::search_logs(&path, from.as_deref(), to.as_deref(), &query, use_regex)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+// Servers are only started with the startup line saved in the registry
+fn registered_server<R: Runtime>(app: &AppHandle<R>, id: Uuid) -> Result<ServerStruct, String> {
+    app.state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())
+}
+
+#[command]
+pub fn start_server<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<(), String> {
+    let server = registered_server(&app, id)?;
+    app.state::<Supervisor>().start(&app, server)
+}
+
+#[command]
+pub fn stop_server<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<(), String> {
+    app.state::<RestartCountdowns>().cancel(&app, id);
+    app.state::<Supervisor>().stop(id)
+}
+
+// Every registered server, marked online when it's running
+#[command]
+pub fn list_servers<R: Runtime>(app: AppHandle<R>) -> Vec<ServerStruct> {
+    let supervisor = app.state::<Supervisor>();
+    app.state::<Registry>()
+        .servers()
+        .into_iter()
+        .map(|server| ServerStruct {
+            status: match supervisor.is_running(server.id) {
+                true => STATUS_ONLINE,
+                false => STATUS_OFFLINE,
+            },
+            ..server
+        })
+        .collect()
+}
+
+#[command]
+pub fn get_server_groups<R: Runtime>(app: AppHandle<R>) -> Vec<ServerGroup> {
+    app.state::<Registry>().groups()
+}
+
+// Creates the group when its id is nil, returns every group since members may have moved
+#[command]
+pub fn save_server_group<R: Runtime>(
+    app: AppHandle<R>,
+    group: ServerGroup,
+) -> Result<Vec<ServerGroup>, String> {
+    let app_dir = app.path().app_data_dir().unwrap();
+    let registry = app.state::<Registry>();
+    if group.name.trim().is_empty() {
+        return Err("A group needs a name".to_string());
+    }
+    if let Some(missing) = group.members.iter().find(|id| registry.get(**id).is_none()) {
+        return Err(format!("Server {} not found", missing));
+    }
+    let group = ServerGroup {
+        id: match group.id.is_nil() {
+            true => Uuid::new_v4(),
+            false => group.id,
+        },
+        name: group.name.trim().to_string(),
+        ..group
+    };
+    registry.save_group(&app_dir, group)?;
+    Ok(registry.groups())
+}
+
+#[command]
+pub fn delete_server_group<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+) -> Result<Vec<ServerGroup>, String> {
+    let app_dir = app.path().app_data_dir().unwrap();
+    let registry = app.state::<Registry>();
+    registry.delete_group(&app_dir, id)?;
+    Ok(registry.groups())
+}
+
+// Returns right away, members report their progress through the usual server events
+#[command]
+pub fn start_server_group<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<(), String> {
+    let group = app
+        .state::<Registry>()
+        .get_group(id)
+        .ok_or_else(|| "Group not found".to_string())?;
+    groups::start(app, group);
+    Ok(())
+}
+
+#[command]
+pub fn stop_server_group<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<(), String> {
+    let group = app
+        .state::<Registry>()
+        .get_group(id)
+        .ok_or_else(|| "Group not found".to_string())?;
+    groups::stop(app, group);
+    Ok(())
+}
+
+#[command]
+pub fn kill_server<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<(), String> {
+    app.state::<RestartCountdowns>().cancel(&app, id);
+    app.state::<Supervisor>().kill(id)
+}
+
+#[command]
+pub async fn restart_server<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<(), String> {
+    let server = registered_server(&app, id)?;
+    app.state::<RestartCountdowns>().cancel(&app, id);
+    tauri::async_runtime::spawn_blocking(move || app.state::<Supervisor>().restart(&app, server))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+// Returns right away, the countdown reports its progress through server_countdown events
+#[command]
+pub fn restart_server_with_countdown<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+) -> Result<(), String> {
+    let server = registered_server(&app, id)?;
+    restart_countdown::parse_steps(&server.restart_countdown)?;
+    if !app.state::<Supervisor>().is_running(server.id) {
+        return Err("Server is not running".to_string());
+    }
+    std::thread::spawn(move || {
+        if let Err(err) = restart_countdown::run(&app, &server) {
+            auto_start::manager_message(&app, server.id, format!("Restart failed: {}", err));
+        }
+    });
+    Ok(())
+}
+
+#[command]
+pub fn cancel_restart_countdown<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<(), String> {
+    let server = registered_server(&app, id)?;
+    if !app.state::<RestartCountdowns>().cancel(&app, id) {
+        return Err("No restart countdown is running".to_string());
+    }
+    let notice = restart_countdown::broadcast_command(&server, "Restart cancelled");
+    let _ = app.state::<Supervisor>().send_command(server.id, &notice);
+    Ok(())
+}
+
+#[command]
+pub fn send_server_command<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    command: String,
+) -> Result<(), String> {
+    app.state::<Supervisor>().send_command(id, &command)
+}
+
+#[command]
+pub fn cancel_server_restart<R: Runtime>(app: AppHandle<R>, server: ServerStruct) {
+    app.state::<Supervisor>().cancel_restart(&app, &server)
+}
+
+#[command]
+pub fn get_resource_history<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Vec<ResourceSample> {
+    app.state::<ResourceMonitor>().history(id)
+}
+
+#[command]
+pub fn get_port_mappings<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Vec<PortMapping> {
+    app.state::<PortForwarding>().mappings(id)
+}
+
+#[command]
+pub fn get_tick_history<R: Runtime>(app: AppHandle<R>, id: Uuid) -> TickHistory {
+    app.state::<TickMonitor>().history(id)
+}
+
+#[command]
+pub fn get_crash_report<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Option<CrashReport> {
+    app.state::<Supervisor>().crash_report(id)
+}
+
+#[command]
+pub fn read_crash_report<R: Runtime>(
+    app: AppHandle<R>,
+    server_id: Uuid,
+    file_name: String,
+) -> Result<String, String> {
+    let server = app
+        .state::<Registry>()
+        .get(server_id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    // only ever hand out crash files of that server, not anything the frontend asks for
+    let path = crash_report::crash_file_path(Path::new(&server.path), &file_name)?;
+    fs::read(&path)
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .map_err(|err| err.to_string())
+}
+
+// Where a server's backups go, its own backup_path or the app data folder
+fn backup_dir<R: Runtime>(app: &AppHandle<R>, server: &ServerStruct) -> PathBuf {
+    match server.backup_path.is_empty() {
+        true => Path::join(
+            app.path().app_data_dir().unwrap().as_path(),
+            format!("backups/{}", server.id),
+        ),
+        false => PathBuf::from(&server.backup_path),
+    }
+}
+
+// Flushes the world if the server is running, backs it up and applies the retention policy
+pub(crate) fn backup_server<R: Runtime>(
+    app: &AppHandle<R>,
+    server: &ServerStruct,
+    options: &BackupOptions,
+) -> Result<BackupManifest, String> {
+    let supervisor = app.state::<Supervisor>();
+    let paused = supervisor.pause_saving(server.id)?;
+    let dest_dir = backup_dir(app, server);
+    let result = backup::create_backup(server, &dest_dir, options);
+    if paused {
+        supervisor.resume_saving(server.id);
+    }
+    let manifest = result?;
+    let policy = RetentionPolicy::from_server(server);
+    match backup_retention::apply(&dest_dir, server.id, &policy) {
+        Ok(removed) => {
+            for decision in removed {
+                let message = format!(
+                    "Deleted backup {} ({})",
+                    decision.created,
+                    decision.reasons.join(", ")
+                );
+                println!("{}: {}", server.name, message);
+                let _ = app.emit(
+                    "server_console",
+                    ConsolePayload {
+                        id: server.id,
+                        line: format!("[Gaia Manager] {}", message),
+                    },
+                );
+            }
+        }
+        Err(err) => println!("Failed to prune backups of {}: {}", server.name, err),
+    }
+    Ok(manifest)
+}
+
+#[command]
+pub async fn create_server_backup<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    options: BackupOptions,
+) -> Result<BackupManifest, String> {
+    let server = registered_server(&app, id)?;
+    tauri::async_runtime::spawn_blocking(move || backup_server(&app, &server, &options))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+// What a retention policy would keep and delete right now, nothing gets deleted
+#[command]
+pub fn preview_backup_retention<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    policy: RetentionPolicy,
+) -> Result<Vec<RetentionDecision>, String> {
+    let server = registered_server(&app, id)?;
+    Ok(backup_retention::plan(
+        &backup::list_backups(&backup_dir(&app, &server), id),
+        &policy,
+    ))
+}
+
+// Tasks are left alone, the frontend's copy of them may be older than the scheduler's
+#[command]
+pub fn save_server<R: Runtime>(app: AppHandle<R>, mut server: ServerStruct) -> Result<(), String> {
+    let app_dir = app.path().app_data_dir().unwrap();
+    let registry = app.state::<Registry>();
+    if let Some(existing) = registry.get(server.id) {
+        server.tasks = existing.tasks;
+    }
+    registry.save(&app_dir, server)
+}
+
+#[derive(Serialize)]
+pub struct ScheduledTaskInfo {
+    task: ScheduledTask,
+    // local time, none for disabled tasks and schedules that never fire
+    next_run: Option<String>,
+}
+
+fn task_infos<R: Runtime>(app: &AppHandle<R>, server: &ServerStruct) -> Vec<ScheduledTaskInfo> {
+    let scheduler = app.state::<Scheduler>();
+    server
+        .tasks
+        .iter()
+        .map(|task| ScheduledTaskInfo {
+            next_run: scheduler
+                .next_run(task)
+                .map(|time| time.format("%Y-%m-%d %H:%M").to_string()),
+            task: task.clone(),
+        })
+        .collect()
+}
+
+#[command]
+pub fn list_scheduled_tasks<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+) -> Result<Vec<ScheduledTaskInfo>, String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    Ok(task_infos(&app, &server))
+}
+
+// Adds the task, or replaces the one with the same id, and returns the server's tasks
+#[command]
+pub fn save_scheduled_task<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    mut task: ScheduledTask,
+) -> Result<Vec<ScheduledTaskInfo>, String> {
+    scheduler::validate_task(&task)?;
+    if task.id.is_nil() {
+        task.id = Uuid::new_v4();
+    }
+    let app_dir = app.path().app_data_dir().unwrap();
+    let server = app.state::<Registry>().update(&app_dir, id, |server| {
+        match server
+            .tasks
+            .iter_mut()
+            .find(|existing| existing.id == task.id)
+        {
+            Some(existing) => {
+                task.last_run = existing.last_run.take();
+                *existing = task.clone();
+            }
+            None => server.tasks.push(task.clone()),
+        }
+    })?;
+    app.state::<Scheduler>().reset(task.id);
+    Ok(task_infos(&app, &server))
+}
+
+#[command]
+pub fn delete_scheduled_task<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    task_id: Uuid,
+) -> Result<Vec<ScheduledTaskInfo>, String> {
+    let app_dir = app.path().app_data_dir().unwrap();
+    let server = app.state::<Registry>().update(&app_dir, id, |server| {
+        server.tasks.retain(|task| task.id != task_id)
+    })?;
+    app.state::<Scheduler>().reset(task_id);
+    Ok(task_infos(&app, &server))
+}
+
+#[command]
+pub async fn run_scheduled_task<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    task_id: Uuid,
+) -> Result<TaskRun, String> {
+    tauri::async_runtime::spawn_blocking(move || scheduler::run_task(&app, id, task_id))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+#[command]
+pub fn list_server_backups<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+) -> Result<Vec<BackupManifest>, String> {
+    let server = registered_server(&app, id)?;
+    Ok(backup::list_backups(&backup_dir(&app, &server), id))
+}
+
+#[command]
+pub fn delete_server_backup<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    name: String,
+) -> Result<(), String> {
+    let server = registered_server(&app, id)?;
+    backup::delete_backup(&backup_dir(&app, &server), id, &name)
+}
+
+#[command]
+pub async fn verify_server_backup<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    name: String,
+) -> Result<(), String> {
+    let server = registered_server(&app, id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        backup::verify_backup(&backup_dir(&app, &server), id, &name)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+// level.dat of every world in the server, ones that can't be read are logged and left out
+#[command]
+pub fn get_worlds<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<Vec<WorldInfo>, String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    let server_path = Path::new(&server.path);
+    Ok(level_dat::world_folders(server_path)
+        .iter()
+        .filter_map(|folder| match level_dat::read(server_path, folder) {
+            Ok(world) => Some(world),
+            Err(err) => {
+                println!("Failed to read {} of {}: {}", folder, server.name, err);
+                None
+            }
+        })
+        .collect())
+}
+
+// The server rewrites level.dat when it saves, so edits only happen while it's stopped
+#[command]
+pub fn save_world_settings<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    world: WorldInfo,
+) -> Result<WorldInfo, String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    if app.state::<Supervisor>().is_running(id) {
+        return Err(format!(
+            "Stop {} before changing its world settings",
+            server.name
+        ));
+    }
+    level_dat::write(Path::new(&server.path), &world)
+}
+
+// Worlds of the server with their size and which one level-name points at
+#[command]
+pub fn list_worlds<R: Runtime>(app: AppHandle<R>, id: Uuid) -> Result<Vec<WorldEntry>, String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    Ok(worlds::list(
+        Path::new(&server.path),
+        bedrock::is_bedrock(&server.server_type),
+    ))
+}
+
+// Reads every chunk, the active world of a running server is flushed first and saving paused
+// so half written chunks don't show up as damaged
+#[command]
+pub async fn get_chunk_stats<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    name: String,
+) -> Result<Vec<DimensionStats>, String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let server_path = Path::new(&server.path);
+        let bedrock = bedrock::is_bedrock(&server.server_type);
+        let supervisor = app.state::<Supervisor>();
+        let paused = match !bedrock && server_properties::level_name(server_path) == name {
+            true => supervisor.pause_saving(id)?,
+            false => false,
+        };
+        let result = worlds::chunk_stats(server_path, bedrock, &name);
+        if paused {
+            supervisor.resume_saving(id);
+        }
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+// A dry run only reports what would go. A real one needs the server stopped and backs the world
+// up first, the whole server when it isn't the active world since only that one is a world backup
+#[command]
+pub async fn prune_world<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    name: String,
+    options: PruneOptions,
+    dry_run: bool,
+) -> Result<PruneReport, String> {
+    let server = match dry_run {
+        true => app
+            .state::<Registry>()
+            .get(id)
+            .ok_or_else(|| "Server not found".to_string())?,
+        false => stopped_server(&app, id, "pruning its world")?,
+    };
+    if bedrock::is_bedrock(&server.server_type) {
+        return Err("Bedrock worlds aren't stored in region files".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let server_path = Path::new(&server.path);
+        let active = server_properties::level_name(server_path) == name;
+        if dry_run {
+            let supervisor = app.state::<Supervisor>();
+            let paused = match active {
+                true => supervisor.pause_saving(id)?,
+                false => false,
+            };
+            let result = prune::prune(server_path, &name, &options, true);
+            if paused {
+                supervisor.resume_saving(id);
+            }
+            return result;
+        }
+        let backup_options = BackupOptions {
+            format: "zip".to_string(),
+            scope: match active {
+                true => "worlds".to_string(),
+                false => "full".to_string(),
+            },
+        };
+        backup_server(&app, &server, &backup_options)?;
+        prune::prune(server_path, &name, &options, false)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+// Everything but exporting swaps or deletes world folders the server would have open
+fn stopped_server<R: Runtime>(
+    app: &AppHandle<R>,
+    id: Uuid,
+    action: &str,
+) -> Result<ServerStruct, String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    if app.state::<Supervisor>().is_running(id) {
+        return Err(format!("Stop {} before {}", server.name, action));
+    }
+    Ok(server)
+}
+
+#[command]
+pub fn set_active_world<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    name: String,
+) -> Result<(), String> {
+    let server = stopped_server(&app, id, "switching its world")?;
+    worlds::set_active(
+        Path::new(&server.path),
+        bedrock::is_bedrock(&server.server_type),
+        &name,
+    )
+}
+
+// source_path is a world folder or a zip with one in it
+#[command]
+pub async fn import_world<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    source_path: String,
+    name: String,
+) -> Result<(), String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        worlds::import(
+            Path::new(&server.path),
+            bedrock::is_bedrock(&server.server_type),
+            Path::new(&source_path),
+            &name,
+        )
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+// The active world of a running server is flushed and saving paused while it's zipped
+#[command]
+pub async fn export_world<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    name: String,
+    dest_path: String,
+) -> Result<(), String> {
+    let server = app
+        .state::<Registry>()
+        .get(id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let server_path = Path::new(&server.path);
+        let supervisor = app.state::<Supervisor>();
+        let paused = match server_properties::level_name(server_path) == name {
+            true => supervisor.pause_saving(id)?,
+            false => false,
+        };
+        let result = worlds::export(
+            server_path,
+            bedrock::is_bedrock(&server.server_type),
+            &name,
+            Path::new(&dest_path),
+        );
+        if paused {
+            supervisor.resume_saving(id);
+        }
+        result
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+// Backs the world up first, there's no getting it back otherwise
+#[command]
+pub async fn regenerate_world<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    name: String,
+    seed: String,
+) -> Result<BackupManifest, String> {
+    let server = stopped_server(&app, id, "regenerating its world")?;
+    tauri::async_runtime::spawn_blocking(move || {
+        worlds::check_regenerate(
+            Path::new(&server.path),
+            bedrock::is_bedrock(&server.server_type),
+            &name,
+        )?;
+        let options = BackupOptions {
+            format: "zip".to_string(),
+            scope: "worlds".to_string(),
+        };
+        let manifest = backup_server(&app, &server, &options)?;
+        worlds::regenerate(
+            Path::new(&server.path),
+            bedrock::is_bedrock(&server.server_type),
+            &name,
+            &seed,
+        )?;
+        Ok(manifest)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[command]
+pub fn delete_world<R: Runtime>(app: AppHandle<R>, id: Uuid, name: String) -> Result<(), String> {
+    let server = stopped_server(&app, id, "deleting a world")?;
+    worlds::delete(
+        Path::new(&server.path),
+        bedrock::is_bedrock(&server.server_type),
+        &name,
+    )
+}
+
+// Restores over the server itself, or into new_path as a new server which gets returned
+#[command]
+pub async fn restore_server_backup<R: Runtime>(
+    app: AppHandle<R>,
+    id: Uuid,
+    name: String,
+    new_path: Option<String>,
+) -> Result<Option<ServerStruct>, String> {
+    let server = registered_server(&app, id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let dest_dir = backup_dir(&app, &server);
+        let Some(new_path) = new_path else {
+            if app.state::<Supervisor>().is_running(server.id) {
+                return Err(format!("Stop {} before restoring a backup", server.name));
+            }
+            backup::restore_backup(&dest_dir, server.id, &name, Path::new(&server.path))?;
+            return Ok(None);
+        };
+        let manifest = backup::load_backup(&dest_dir, server.id, &name)?;
+        let has_files = fs::read_dir(&new_path).is_ok_and(|mut entries| entries.next().is_some());
+        if has_files {
+            return Err(format!("{} is not empty", new_path));
+        }
+        // a world backup has no server files, those come from the server it was taken from
+        if manifest.scope == "worlds" {
+            copy_directory(
+                &server.path,
+                &new_path,
+                DirectoryCopyOptions {
+                    destination_directory_rule: DestinationDirectoryRule::AllowEmpty,
+                    copy_depth_limit: DirectoryCopyDepthLimit::Unlimited,
+                    symlink_behaviour: SymlinkBehaviour::Keep,
+                    broken_symlink_behaviour: BrokenSymlinkBehaviour::Keep,
+                },
+            )
+            .map_err(|err| err.to_string())?;
+        }
+        backup::restore_backup(&dest_dir, server.id, &name, Path::new(&new_path))?;
+        let mut restored = ServerStruct {
+            id: Uuid::new_v4(),
+            status: 0,
+            name: format!("{} ({})", server.name, manifest.created),
+            icon_path: server.icon_path.replace(&server.path, &new_path),
+            server_jar_path: server.server_jar_path.replace(&server.path, &new_path),
+            java_startup_line: server.java_startup_line.replace(&server.path, &new_path),
+            path: new_path,
+            auto_start: false,
+            // the copy shouldn't back up or restart alongside the original
+            tasks: Vec::new(),
+            // the proxy only knows the original
+            proxy_id: None,
+            proxy_name: "".to_string(),
+            ..server
+        };
+        ports::reassign_ports(&mut restored, &app.state::<Registry>().servers())?;
+        let app_dir = app.path().app_data_dir().unwrap();
+        app.state::<Registry>().save(&app_dir, restored.clone())?;
+        Ok(Some(restored))
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
     Builder::new("gaia_core")
-        .invoke_handler(tauri::generate_handler![create_server])
+        .invoke_handler(tauri::generate_handler![
+            create_server,
+            create_proxy_server,
+            create_bedrock_server,
+            get_bedrock_access,
+            get_geyser_setup,
+            install_geyser,
+            configure_geyser,
+            save_bedrock_access,
+            get_proxy_config,
+            save_proxy_config,
+            link_proxy_backend,
+            check_proxy_forwarding,
+            setup_proxy_forwarding,
+            unlink_proxy_backend,
+            check_server_ports,
+            suggest_server_port,
+            get_config,
+            detect_server_ips,
+            list_server_logs,
+            read_server_log,
+            search_server_logs,
+            start_server,
+            stop_server,
+            list_servers,
+            get_server_groups,
+            save_server_group,
+            delete_server_group,
+            start_server_group,
+            stop_server_group,
+            kill_server,
+            send_server_command,
+            cancel_server_restart,
+            restart_server,
+            restart_server_with_countdown,
+            cancel_restart_countdown,
+            get_resource_history,
+            get_tick_history,
+            get_port_mappings,
+            get_crash_report,
+            read_crash_report,
+            create_server_backup,
+            list_server_backups,
+            delete_server_backup,
+            verify_server_backup,
+            restore_server_backup,
+            get_worlds,
+            save_world_settings,
+            list_worlds,
+            get_chunk_stats,
+            prune_world,
+            set_active_world,
+            import_world,
+            export_world,
+            regenerate_world,
+            delete_world,
+            preview_backup_retention,
+            save_server,
+            list_scheduled_tasks,
+            save_scheduled_task,
+            delete_scheduled_task,
+            run_scheduled_task
+        ])
         .setup(|app, _api| {
+            app.manage(Supervisor::default());
+            app.manage(RestartCountdowns::default());
+            app.manage(ResourceMonitor::default());
+            app.manage(TickMonitor::default());
+            app.manage(PortForwarding::default());
             let app_dir = app.path().app_data_dir().unwrap();
             if fs::exists(app_dir.clone()).unwrap_or_else(|_| return false) == false {
                 fs::create_dir(app_dir.clone()).unwrap_or_else(|err| println!("{}", err));
@@ -107,6 +1407,15 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
                 )
                 .unwrap_or_else(|err| println!("{}", err));
             }
+            app.manage(Registry::load(&app_dir));
+            app.manage(Scheduler::new(Box::new(SystemClock)));
+            scheduler::spawn(app.clone());
+            resource_monitor::spawn(app.clone());
+            tick_monitor::spawn(app.clone());
+            port_forwarding::spawn(app.clone());
+            if registry::load_config(&app_dir).auto_start {
+                auto_start::start_servers(app.clone(), app.state::<Registry>().servers());
+            }
             for (_, window) in app.webview_windows() {
                 let _ = window.set_title(format!("{:?}", app_dir.clone()).as_str());
             }
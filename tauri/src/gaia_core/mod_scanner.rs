@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::Read,
+    path::Path,
+};
+
+// How many folders deep a class package gets recorded, "com/simibubi/create" is enough
+// to tell mods apart without storing every package in the jar
+const PACKAGE_DEPTH: usize = 3;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub file_name: String,
+    // dotted package prefixes found in the jar, eg. "com.simibubi.create"
+    pub packages: Vec<String>,
+}
+
+pub fn read_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    entry.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+// Placeholders like "${file.jarVersion}" get filled in by the loader, they mean nothing to us
+fn clean_version(version: &str) -> String {
+    match version.starts_with("${") {
+        true => "".to_string(),
+        false => version.to_string(),
+    }
+}
+
+// Reads the (neo)forge mods.toml, one jar can contain multiple mods
+fn parse_mods_toml(content: &str) -> Vec<(String, String, String)> {
+    let Ok(value) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let Some(mods) = value.get("mods").and_then(|mods| mods.as_array()) else {
+        return Vec::new();
+    };
+    mods.iter()
+        .filter_map(|entry| {
+            let id = entry.get("modId")?.as_str()?.to_string();
+            let name = entry
+                .get("displayName")
+                .and_then(|name| name.as_str())
+                .unwrap_or(&id)
+                .to_string();
+            let version = entry
+                .get("version")
+                .and_then(|version| version.as_str())
+                .map(clean_version)
+                .unwrap_or_default();
+            Some((id, name, version))
+        })
+        .collect()
+}
+
+fn parse_fabric_mod_json(content: &str) -> Option<(String, String, String)> {
+    let value: serde_json::Value = serde_json::from_str(content).ok()?;
+    let id = value.get("id")?.as_str()?.to_string();
+    let name = value
+        .get("name")
+        .and_then(|name| name.as_str())
+        .unwrap_or(&id)
+        .to_string();
+    let version = value
+        .get("version")
+        .and_then(|version| version.as_str())
+        .map(clean_version)
+        .unwrap_or_default();
+    Some((id, name, version))
+}
+
+// Bukkit plugins only have a flat plugin.yml, the name doubles as the id
+fn parse_plugin_yml(content: &str) -> Option<(String, String, String)> {
+    let value = |key: &str| {
+        content.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            let value = value.trim().trim_matches(|c| c == '\'' || c == '"');
+            (name == key && !value.is_empty()).then(|| value.to_string())
+        })
+    };
+    let name = value("name")?;
+    let version = value("version")
+        .map(|version| clean_version(&version))
+        .unwrap_or_default();
+    Some((name.to_lowercase(), name, version))
+}
+
+pub fn scan_jar(path: &Path) -> Vec<ModInfo> {
+    let Ok(file) = File::open(path) else {
+        return Vec::new();
+    };
+    let Ok(mut archive) = zip::ZipArchive::new(file) else {
+        return Vec::new();
+    };
+    let mut packages = BTreeSet::new();
+    for name in archive.file_names() {
+        if !name.ends_with(".class") || name.starts_with("META-INF") {
+            continue;
+        }
+        let folders: Vec<&str> = name.split('/').collect();
+        if folders.len() < 2 {
+            continue;
+        }
+        let depth = (folders.len() - 1).min(PACKAGE_DEPTH);
+        packages.insert(folders[..depth].join("."));
+    }
+    let packages: Vec<String> = packages.into_iter().collect();
+    let mut found = Vec::new();
+    for descriptor in ["META-INF/neoforge.mods.toml", "META-INF/mods.toml"] {
+        if let Some(content) = read_entry(&mut archive, descriptor) {
+            found.extend(parse_mods_toml(&content));
+            break;
+        }
+    }
+    if found.is_empty() {
+        if let Some(content) = read_entry(&mut archive, "fabric.mod.json") {
+            found.extend(parse_fabric_mod_json(&content));
+        }
+    }
+    if found.is_empty() {
+        if let Some(content) = read_entry(&mut archive, "plugin.yml") {
+            found.extend(parse_plugin_yml(&content));
+        }
+    }
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    found
+        .into_iter()
+        .map(|(id, name, version)| ModInfo {
+            id,
+            name,
+            version,
+            file_name: file_name.clone(),
+            packages: packages.clone(),
+        })
+        .collect()
+}
+
+// Scans every jar in the server's mods and plugins folders
+pub fn scan_mods(server_path: &Path) -> Vec<ModInfo> {
+    let mut mods: Vec<ModInfo> = ["mods", "plugins"]
+        .iter()
+        .filter_map(|folder| fs::read_dir(server_path.join(folder)).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "jar"))
+        .flat_map(|path| scan_jar(&path))
+        .collect();
+    mods.sort_by(|a, b| a.id.cmp(&b.id));
+    mods
+}
+
+// Finds the mod that owns a fully qualified class name by the longest matching package
+pub fn mod_for_class<'a>(mods: &'a [ModInfo], class: &str) -> Option<&'a ModInfo> {
+    if class.starts_with("net.minecraft.")
+        || class.starts_with("com.mojang.")
+        || class.starts_with("java.")
+        || class.starts_with("jdk.")
+        || class.starts_with("sun.")
+    {
+        return None;
+    }
+    mods.iter()
+        .flat_map(|info| info.packages.iter().map(move |package| (info, package)))
+        .filter(|(_, package)| {
+            class.starts_with(package.as_str()) && class[package.len()..].starts_with('.')
+        })
+        .max_by_key(|(_, package)| package.len())
+        .map(|(info, _)| info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    // Writes a jar with the given descriptor next to a couple of classes
+    fn write_jar(path: &Path, descriptor: &str, content: &str, classes: &[&str]) {
+        let mut jar = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        jar.start_file(descriptor, options).unwrap();
+        jar.write_all(content.as_bytes()).unwrap();
+        for class in classes {
+            jar.start_file(*class, options).unwrap();
+        }
+        jar.finish().unwrap();
+    }
+
+    #[test]
+    fn reads_fabric_mod_json() {
+        let found = parse_fabric_mod_json(include_str!("testdata/mods/fabric.mod.json"));
+        assert_eq!(
+            found,
+            Some((
+                "sodium".to_string(),
+                "Sodium".to_string(),
+                "0.5.8+mc1.20.4".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn reads_every_mod_in_mods_toml() {
+        let found = parse_mods_toml(include_str!("testdata/mods/mods.toml"));
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "create".to_string(),
+                    "Create".to_string(),
+                    "0.5.1.f".to_string()
+                ),
+                // no display name and a placeholder version
+                (
+                    "flywheel".to_string(),
+                    "flywheel".to_string(),
+                    "".to_string()
+                ),
+            ]
+        );
+        assert!(parse_mods_toml("modLoader = \"javafml\"").is_empty());
+    }
+
+    #[test]
+    fn reads_plugin_yml() {
+        let found = parse_plugin_yml(include_str!("testdata/mods/plugin.yml"));
+        assert_eq!(
+            found,
+            Some((
+                "essentials".to_string(),
+                "Essentials".to_string(),
+                "2.20.1".to_string()
+            ))
+        );
+        assert_eq!(parse_plugin_yml("main: a.b.C"), None);
+    }
+
+    #[test]
+    fn scans_mods_and_plugins_folders() {
+        let dir = std::env::temp_dir().join(format!("gaia-mods-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("mods")).unwrap();
+        fs::create_dir_all(dir.join("plugins")).unwrap();
+        write_jar(
+            &dir.join("mods/create.jar"),
+            "META-INF/mods.toml",
+            include_str!("testdata/mods/mods.toml"),
+            &[
+                "com/simibubi/create/Create.class",
+                "com/simibubi/create/foundation/A.class",
+            ],
+        );
+        write_jar(
+            &dir.join("mods/sodium.jar"),
+            "fabric.mod.json",
+            include_str!("testdata/mods/fabric.mod.json"),
+            &["me/jellysquid/mods/sodium/client/SodiumClientMod.class"],
+        );
+        write_jar(
+            &dir.join("plugins/EssentialsX.jar"),
+            "plugin.yml",
+            include_str!("testdata/mods/plugin.yml"),
+            &["com/earth2me/essentials/Essentials.class"],
+        );
+        fs::write(dir.join("mods/notes.txt"), "not a jar").unwrap();
+
+        let mods = scan_mods(&dir);
+        let ids: Vec<&str> = mods.iter().map(|info| info.id.as_str()).collect();
+        assert_eq!(ids, vec!["create", "essentials", "flywheel", "sodium"]);
+        let create = mods.iter().find(|info| info.id == "create").unwrap();
+        assert_eq!(create.file_name, "create.jar");
+        assert_eq!(create.packages, vec!["com.simibubi.create"]);
+
+        let owner = |class| mod_for_class(&mods, class).map(|info| info.id.as_str());
+        assert_eq!(
+            owner("me.jellysquid.mods.sodium.client.render.Chunk"),
+            Some("sodium")
+        );
+        assert_eq!(owner("com.earth2me.essentials.User"), Some("essentials"));
+        assert_eq!(owner("net.minecraft.server.MinecraftServer"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
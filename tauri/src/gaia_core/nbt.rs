@@ -0,0 +1,1160 @@
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
+
+// Same limit minecraft reads with, deeper files are broken or built to crash readers
+const MAX_DEPTH: usize = 512;
+
+const END: u8 = 0;
+const BYTE: u8 = 1;
+const SHORT: u8 = 2;
+const INT: u8 = 3;
+const LONG: u8 = 4;
+const FLOAT: u8 = 5;
+const DOUBLE: u8 = 6;
+const BYTE_ARRAY: u8 = 7;
+const STRING: u8 = 8;
+const LIST: u8 = 9;
+const COMPOUND: u8 = 10;
+const INT_ARRAY: u8 = 11;
+const LONG_ARRAY: u8 = 12;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    // the element tag id is kept so empty lists are written back the way they were read
+    List(u8, Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => BYTE,
+            Tag::Short(_) => SHORT,
+            Tag::Int(_) => INT,
+            Tag::Long(_) => LONG,
+            Tag::Float(_) => FLOAT,
+            Tag::Double(_) => DOUBLE,
+            Tag::ByteArray(_) => BYTE_ARRAY,
+            Tag::String(_) => STRING,
+            Tag::List(_, _) => LIST,
+            Tag::Compound(_) => COMPOUND,
+            Tag::IntArray(_) => INT_ARRAY,
+            Tag::LongArray(_) => LONG_ARRAY,
+        }
+    }
+
+    // Any of the integer tags, booleans are stored as bytes
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(value) => Some(*value as i64),
+            Tag::Short(value) => Some(*value as i64),
+            Tag::Int(value) => Some(*value as i64),
+            Tag::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Tag::Float(value) => Some(*value as f64),
+            Tag::Double(value) => Some(*value),
+            _ => self.as_i64().map(|value| value as f64),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(_, items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(compound) => Some(compound),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound_mut(&mut self) -> Option<&mut Compound> {
+        match self {
+            Tag::Compound(compound) => Some(compound),
+            _ => None,
+        }
+    }
+}
+
+// Entries stay in the order they were read, minecraft doesn't care but byte for byte
+// round trips do
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Compound(pub Vec<(String, Tag)>);
+
+impl Compound {
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        self.0
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, tag)| tag)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Tag> {
+        self.0
+            .iter_mut()
+            .find(|(name, _)| name == key)
+            .map(|(_, tag)| tag)
+    }
+
+    // Replaces the value in place when the key exists, appends it otherwise
+    pub fn insert(&mut self, key: &str, tag: Tag) {
+        match self.get_mut(key) {
+            Some(existing) => *existing = tag,
+            None => self.0.push((key.to_string(), tag)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Tag> {
+        let index = self.0.iter().position(|(name, _)| name == key)?;
+        Some(self.0.remove(index).1)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zlib,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    // big endian with strings in java's modified utf-8, level.dat and playerdata are gzipped
+    Java(Compression),
+    // little endian and never compressed, bedrock's level.dat starts with its storage version
+    // and the length of the rest
+    Bedrock { header: Option<i32> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NbtFile {
+    pub format: Format,
+    // name of the root compound, empty in every file minecraft writes
+    pub name: String,
+    pub root: Compound,
+}
+
+impl NbtFile {
+    // Tells the format apart by the first bytes, a bedrock file without its header can't be
+    // told from a java one so it needs read_bedrock
+    pub fn read(bytes: &[u8]) -> Result<NbtFile, String> {
+        match bytes {
+            [0x1f, 0x8b, ..] => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(bytes)
+                    .read_to_end(&mut decoded)
+                    .map_err(|err| format!("Invalid gzip: {}", err))?;
+                read_root(&decoded, Format::Java(Compression::Gzip))
+            }
+            [0x78, ..] => {
+                let mut decoded = Vec::new();
+                ZlibDecoder::new(bytes)
+                    .read_to_end(&mut decoded)
+                    .map_err(|err| format!("Invalid zlib: {}", err))?;
+                read_root(&decoded, Format::Java(Compression::Zlib))
+            }
+            [a, b, c, d, e, f, g, h, COMPOUND, ..]
+                if u32::from_le_bytes([*e, *f, *g, *h]) as usize == bytes.len() - 8 =>
+            {
+                let version = i32::from_le_bytes([*a, *b, *c, *d]);
+                read_root(
+                    &bytes[8..],
+                    Format::Bedrock {
+                        header: Some(version),
+                    },
+                )
+            }
+            _ => read_root(bytes, Format::Java(Compression::None)),
+        }
+    }
+
+    // Little endian nbt without the level.dat header, like the values in bedrock's leveldb
+    pub fn read_bedrock(bytes: &[u8]) -> Result<NbtFile, String> {
+        read_root(bytes, Format::Bedrock { header: None })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let little = matches!(self.format, Format::Bedrock { .. });
+        let mut writer = Writer {
+            bytes: Vec::new(),
+            little,
+        };
+        writer.u8(COMPOUND);
+        writer.string(&self.name)?;
+        writer.compound(&self.root)?;
+        let payload = writer.bytes;
+        let compressed = match self.format {
+            Format::Java(Compression::None) => payload,
+            Format::Java(Compression::Gzip) => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&payload).map_err(|err| err.to_string())?;
+                encoder.finish().map_err(|err| err.to_string())?
+            }
+            Format::Java(Compression::Zlib) => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&payload).map_err(|err| err.to_string())?;
+                encoder.finish().map_err(|err| err.to_string())?
+            }
+            Format::Bedrock { header: None } => payload,
+            Format::Bedrock {
+                header: Some(version),
+            } => {
+                let length =
+                    u32::try_from(payload.len()).map_err(|_| "NBT is too large".to_string())?;
+                let mut bytes = version.to_le_bytes().to_vec();
+                bytes.extend(length.to_le_bytes());
+                bytes.extend(payload);
+                bytes
+            }
+        };
+        Ok(compressed)
+    }
+
+    pub fn load(path: &Path) -> Result<NbtFile, String> {
+        let bytes = fs::read(path).map_err(|err| format!("Failed to read {:?}: {}", path, err))?;
+        NbtFile::read(&bytes).map_err(|err| format!("{:?}: {}", path, err))
+    }
+
+    // Written next to the file first so a crash halfway never leaves a cut off level.dat
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let bytes = self.to_bytes()?;
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        fs::write(&temporary, bytes)
+            .map_err(|err| format!("Failed to write {:?}: {}", temporary, err))?;
+        fs::rename(&temporary, path).map_err(|err| format!("Failed to replace {:?}: {}", path, err))
+    }
+}
+
+pub fn read_root(bytes: &[u8], format: Format) -> Result<NbtFile, String> {
+    let mut reader = Reader {
+        bytes,
+        position: 0,
+        little: matches!(format, Format::Bedrock { .. }),
+    };
+    let id = reader.u8()?;
+    if id != COMPOUND {
+        return Err(format!("Root tag is {}, not a compound", id));
+    }
+    let name = reader.string()?;
+    let root = reader.compound(0)?;
+    Ok(NbtFile { format, name, root })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    little: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| format!("NBT ends early at byte {}", self.position))?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.array()?;
+        Ok(match self.little {
+            true => u16::from_le_bytes(bytes),
+            false => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        let bytes = self.array()?;
+        Ok(match self.little {
+            true => i32::from_le_bytes(bytes),
+            false => i32::from_be_bytes(bytes),
+        })
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        let bytes = self.array()?;
+        Ok(match self.little {
+            true => i64::from_le_bytes(bytes),
+            false => i64::from_be_bytes(bytes),
+        })
+    }
+
+    // A negative or impossible length is refused before anything gets allocated for it
+    fn length(&mut self, element_size: usize) -> Result<usize, String> {
+        let length = self.i32()?;
+        let length = usize::try_from(length).map_err(|_| format!("Negative length {}", length))?;
+        if length.saturating_mul(element_size) > self.bytes.len() - self.position {
+            return Err(format!("Length {} runs past the end of the NBT", length));
+        }
+        Ok(length)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.u16()? as usize;
+        let bytes = self.take(length)?;
+        match self.little {
+            true => String::from_utf8(bytes.to_vec())
+                .map_err(|err| format!("Invalid utf-8 string: {}", err)),
+            false => decode_mutf8(bytes),
+        }
+    }
+
+    fn compound(&mut self, depth: usize) -> Result<Compound, String> {
+        let mut compound = Compound::default();
+        loop {
+            let id = self.u8()?;
+            if id == END {
+                return Ok(compound);
+            }
+            let name = self.string()?;
+            let tag = self.payload(id, depth + 1)?;
+            compound.0.push((name, tag));
+        }
+    }
+
+    fn payload(&mut self, id: u8, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_DEPTH {
+            return Err("NBT is nested too deep".to_string());
+        }
+        Ok(match id {
+            BYTE => Tag::Byte(self.u8()? as i8),
+            SHORT => Tag::Short(self.i16()?),
+            INT => Tag::Int(self.i32()?),
+            LONG => Tag::Long(self.i64()?),
+            FLOAT => Tag::Float(f32::from_bits(self.i32()? as u32)),
+            DOUBLE => Tag::Double(f64::from_bits(self.i64()? as u64)),
+            BYTE_ARRAY => {
+                let length = self.length(1)?;
+                Tag::ByteArray(self.take(length)?.iter().map(|byte| *byte as i8).collect())
+            }
+            STRING => Tag::String(self.string()?),
+            LIST => {
+                let element = self.u8()?;
+                // an element id of 0 only works for empty lists, every other tag takes a byte at least
+                let length = self.length(1)?;
+                if element == END && length > 0 {
+                    return Err("List of end tags isn't empty".to_string());
+                }
+                let items = (0..length)
+                    .map(|_| self.payload(element, depth + 1))
+                    .collect::<Result<Vec<Tag>, String>>()?;
+                Tag::List(element, items)
+            }
+            COMPOUND => Tag::Compound(self.compound(depth)?),
+            INT_ARRAY => {
+                let length = self.length(4)?;
+                Tag::IntArray((0..length).map(|_| self.i32()).collect::<Result<_, _>>()?)
+            }
+            LONG_ARRAY => {
+                let length = self.length(8)?;
+                Tag::LongArray((0..length).map(|_| self.i64()).collect::<Result<_, _>>()?)
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown tag id {} at byte {}",
+                    id,
+                    self.position - 1
+                ))
+            }
+        })
+    }
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+    little: bool,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        match self.little {
+            true => self.bytes.extend(value.to_le_bytes()),
+            false => self.bytes.extend(value.to_be_bytes()),
+        }
+    }
+
+    fn i32(&mut self, value: i32) {
+        match self.little {
+            true => self.bytes.extend(value.to_le_bytes()),
+            false => self.bytes.extend(value.to_be_bytes()),
+        }
+    }
+
+    fn i64(&mut self, value: i64) {
+        match self.little {
+            true => self.bytes.extend(value.to_le_bytes()),
+            false => self.bytes.extend(value.to_be_bytes()),
+        }
+    }
+
+    fn length(&mut self, length: usize) -> Result<(), String> {
+        let length =
+            i32::try_from(length).map_err(|_| format!("{} entries don't fit in NBT", length))?;
+        self.i32(length);
+        Ok(())
+    }
+
+    fn string(&mut self, value: &str) -> Result<(), String> {
+        let encoded = match self.little {
+            true => value.as_bytes().to_vec(),
+            false => encode_mutf8(value),
+        };
+        let length =
+            u16::try_from(encoded.len()).map_err(|_| "String is too long for NBT".to_string())?;
+        self.u16(length);
+        self.bytes.extend(encoded);
+        Ok(())
+    }
+
+    fn compound(&mut self, compound: &Compound) -> Result<(), String> {
+        for (name, tag) in &compound.0 {
+            self.u8(tag.id());
+            self.string(name)?;
+            self.payload(tag)?;
+        }
+        self.u8(END);
+        Ok(())
+    }
+
+    fn payload(&mut self, tag: &Tag) -> Result<(), String> {
+        match tag {
+            Tag::Byte(value) => self.u8(*value as u8),
+            Tag::Short(value) => self.u16(*value as u16),
+            Tag::Int(value) => self.i32(*value),
+            Tag::Long(value) => self.i64(*value),
+            Tag::Float(value) => self.i32(value.to_bits() as i32),
+            Tag::Double(value) => self.i64(value.to_bits() as i64),
+            Tag::ByteArray(values) => {
+                self.length(values.len())?;
+                self.bytes.extend(values.iter().map(|value| *value as u8));
+            }
+            Tag::String(value) => self.string(value)?,
+            Tag::List(element, items) => {
+                // a list that got items since it was read has them decide its type
+                let element = items.first().map_or(*element, Tag::id);
+                if let Some(other) = items.iter().find(|item| item.id() != element) {
+                    return Err(format!(
+                        "List of tag {} also holds tag {}",
+                        element,
+                        other.id()
+                    ));
+                }
+                self.u8(element);
+                self.length(items.len())?;
+                for item in items {
+                    self.payload(item)?;
+                }
+            }
+            Tag::Compound(compound) => self.compound(compound)?,
+            Tag::IntArray(values) => {
+                self.length(values.len())?;
+                for value in values {
+                    self.i32(*value);
+                }
+            }
+            Tag::LongArray(values) => {
+                self.length(values.len())?;
+                for value in values {
+                    self.i64(*value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Java writes strings as utf-16 units in utf-8's byte patterns, "\0" takes two bytes and
+// characters outside the basic plane become two three byte surrogates
+fn encode_mutf8(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x01..=0x7f => bytes.push(unit as u8),
+            0x00 | 0x80..=0x7ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    bytes
+}
+
+fn decode_mutf8(bytes: &[u8]) -> Result<String, String> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    let continuation = |index: usize| match bytes.get(index) {
+        Some(byte) if byte & 0xc0 == 0x80 => Ok((byte & 0x3f) as u16),
+        _ => Err("Invalid modified utf-8 string".to_string()),
+    };
+    while index < bytes.len() {
+        let byte = bytes[index];
+        match byte {
+            0x00..=0x7f => {
+                units.push(byte as u16);
+                index += 1;
+            }
+            0xc0..=0xdf => {
+                units.push(((byte & 0x1f) as u16) << 6 | continuation(index + 1)?);
+                index += 2;
+            }
+            0xe0..=0xef => {
+                units.push(
+                    ((byte & 0x0f) as u16) << 12
+                        | continuation(index + 1)? << 6
+                        | continuation(index + 2)?,
+                );
+                index += 3;
+            }
+            _ => return Err("Invalid modified utf-8 string".to_string()),
+        }
+    }
+    String::from_utf16(&units).map_err(|err| format!("Invalid modified utf-8 string: {}", err))
+}
+
+// SNBT, the text form of nbt that /data and the game's commands use
+pub fn to_snbt(tag: &Tag) -> String {
+    let mut text = String::new();
+    write_snbt(&mut text, tag, None, 0);
+    text
+}
+
+// Compounds and lists of them one entry per line, everything else stays inline
+pub fn to_snbt_pretty(tag: &Tag) -> String {
+    let mut text = String::new();
+    write_snbt(&mut text, tag, Some(4), 0);
+    text
+}
+
+fn is_bare(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(is_bare_char)
+}
+
+fn is_bare_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn write_snbt(text: &mut String, tag: &Tag, indent: Option<usize>, depth: usize) {
+    let join =
+        |text: &mut String, items: Vec<String>, multiline: bool, open: &str, close: &str| match (
+            indent,
+            multiline && !items.is_empty(),
+        ) {
+            (Some(indent), true) => {
+                let inner = " ".repeat(indent * (depth + 1));
+                text.push_str(open);
+                text.push('\n');
+                text.push_str(
+                    &items
+                        .iter()
+                        .map(|item| format!("{}{}", inner, item))
+                        .collect::<Vec<_>>()
+                        .join(",\n"),
+                );
+                text.push('\n');
+                text.push_str(&" ".repeat(indent * depth));
+                text.push_str(close);
+            }
+            _ => text.push_str(&format!("{}{}{}", open, items.join(","), close)),
+        };
+    let nested = |tag: &Tag| {
+        let mut item = String::new();
+        write_snbt(&mut item, tag, indent, depth + 1);
+        item
+    };
+    match tag {
+        Tag::Byte(value) => text.push_str(&format!("{}b", value)),
+        Tag::Short(value) => text.push_str(&format!("{}s", value)),
+        Tag::Int(value) => text.push_str(&value.to_string()),
+        Tag::Long(value) => text.push_str(&format!("{}L", value)),
+        Tag::Float(value) => text.push_str(&format!("{}f", value)),
+        Tag::Double(value) => text.push_str(&format!("{}d", value)),
+        Tag::ByteArray(values) => {
+            let items = values.iter().map(|value| format!("{}b", value)).collect();
+            join(text, items, false, "[B;", "]");
+        }
+        Tag::String(value) => text.push_str(&quote(value)),
+        Tag::List(_, items) => {
+            let multiline = items
+                .iter()
+                .any(|item| matches!(item, Tag::Compound(_) | Tag::List(_, _)));
+            join(
+                text,
+                items.iter().map(nested).collect(),
+                multiline,
+                "[",
+                "]",
+            );
+        }
+        Tag::Compound(compound) => {
+            let separator = if indent.is_some() { ": " } else { ":" };
+            let items = compound
+                .0
+                .iter()
+                .map(|(name, tag)| {
+                    let key = if is_bare(name) {
+                        name.clone()
+                    } else {
+                        quote(name)
+                    };
+                    format!("{}{}{}", key, separator, nested(tag))
+                })
+                .collect();
+            join(text, items, true, "{", "}");
+        }
+        Tag::IntArray(values) => {
+            let items = values.iter().map(|value| value.to_string()).collect();
+            join(text, items, false, "[I;", "]");
+        }
+        Tag::LongArray(values) => {
+            let items = values.iter().map(|value| format!("{}L", value)).collect();
+            join(text, items, false, "[L;", "]");
+        }
+    }
+}
+
+pub fn parse_snbt(text: &str) -> Result<Tag, String> {
+    let mut parser = SnbtParser {
+        chars: text.chars().collect(),
+        position: 0,
+    };
+    let tag = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.position < parser.chars.len() {
+        return Err(parser.error("Unexpected text after the value"));
+    }
+    Ok(tag)
+}
+
+struct SnbtParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl SnbtParser {
+    fn error(&self, message: &str) -> String {
+        format!("{} at character {}", message, self.position)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.position).copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(self.error(&format!("Expected '{}'", expected))),
+        }
+    }
+
+    // Commas between entries, the closing bracket ends it. Returns false once it's closed
+    fn next_entry(&mut self, close: char, first: bool) -> Result<bool, String> {
+        match self.peek() {
+            Some(c) if c == close => {
+                self.position += 1;
+                Ok(false)
+            }
+            _ if first => Ok(true),
+            Some(',') => {
+                self.position += 1;
+                Ok(true)
+            }
+            _ => Err(self.error(&format!("Expected ',' or '{}'", close))),
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let quote = self.chars[self.position];
+        self.position += 1;
+        let mut text = String::new();
+        loop {
+            match self.chars.get(self.position).copied() {
+                None => return Err(self.error("Unclosed string")),
+                Some('\\') => {
+                    let escaped = self.chars.get(self.position + 1).copied();
+                    match escaped {
+                        Some(c @ ('\\' | '"' | '\'')) => text.push(c),
+                        Some('n') => text.push('\n'),
+                        Some('t') => text.push('\t'),
+                        _ => return Err(self.error("Invalid escape")),
+                    }
+                    self.position += 2;
+                }
+                Some(c) if c == quote => {
+                    self.position += 1;
+                    return Ok(text);
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    fn bare(&mut self) -> String {
+        let start = self.position;
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| is_bare_char(*c))
+        {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"' | '\'') => self.quoted(),
+            _ => match self.bare() {
+                key if key.is_empty() => Err(self.error("Expected a key")),
+                key => Ok(key),
+            },
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("SNBT is nested too deep"));
+        }
+        match self.peek() {
+            Some('{') => {
+                self.position += 1;
+                let mut compound = Compound::default();
+                let mut first = true;
+                while self.next_entry('}', first)? {
+                    first = false;
+                    let key = self.key()?;
+                    self.expect(':')?;
+                    let tag = self.value(depth + 1)?;
+                    compound.insert(&key, tag);
+                }
+                Ok(Tag::Compound(compound))
+            }
+            Some('[') => {
+                self.position += 1;
+                let prefix: String = self.chars.iter().skip(self.position).take(2).collect();
+                if matches!(prefix.as_str(), "B;" | "I;" | "L;") {
+                    self.position += 2;
+                    return self.typed_array(prefix.chars().next().unwrap(), depth);
+                }
+                let mut items: Vec<Tag> = Vec::new();
+                let mut first = true;
+                while self.next_entry(']', first)? {
+                    first = false;
+                    let item = self.value(depth + 1)?;
+                    if let Some(other) = items.first().filter(|other| other.id() != item.id()) {
+                        return Err(self.error(&format!(
+                            "List of tag {} can't hold tag {}",
+                            other.id(),
+                            item.id()
+                        )));
+                    }
+                    items.push(item);
+                }
+                Ok(Tag::List(items.first().map_or(END, Tag::id), items))
+            }
+            Some('"' | '\'') => Ok(Tag::String(self.quoted()?)),
+            Some(_) => {
+                let token = self.bare();
+                if token.is_empty() {
+                    return Err(self.error("Expected a value"));
+                }
+                Ok(parse_bare(&token))
+            }
+            None => Err(self.error("Expected a value")),
+        }
+    }
+
+    fn typed_array(&mut self, kind: char, depth: usize) -> Result<Tag, String> {
+        let mut items = Vec::new();
+        let mut first = true;
+        while self.next_entry(']', first)? {
+            first = false;
+            items.push(self.value(depth + 1)?);
+        }
+        let wrong = || format!("[{};] array holds a value of another type", kind);
+        Ok(match kind {
+            'B' => Tag::ByteArray(
+                items
+                    .iter()
+                    .map(|item| match item {
+                        Tag::Byte(value) => Ok(*value),
+                        _ => Err(wrong()),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            'I' => Tag::IntArray(
+                items
+                    .iter()
+                    .map(|item| match item {
+                        Tag::Int(value) => Ok(*value),
+                        _ => Err(wrong()),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => Tag::LongArray(
+                items
+                    .iter()
+                    .map(|item| match item {
+                        Tag::Long(value) => Ok(*value),
+                        _ => Err(wrong()),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+}
+
+// A number with its type suffix, true and false, or else an unquoted string like minecraft does
+fn parse_bare(token: &str) -> Tag {
+    match token {
+        "true" => return Tag::Byte(1),
+        "false" => return Tag::Byte(0),
+        _ => {}
+    }
+    let (number, suffix) = token.split_at(token.len() - 1);
+    let parsed = match suffix {
+        "b" | "B" => number.parse().ok().map(Tag::Byte),
+        "s" | "S" => number.parse().ok().map(Tag::Short),
+        "l" | "L" => number.parse().ok().map(Tag::Long),
+        "f" | "F" => number.parse().ok().map(Tag::Float),
+        "d" | "D" => number.parse().ok().map(Tag::Double),
+        _ => None,
+    };
+    if let Some(tag) = parsed {
+        return tag;
+    }
+    if let Ok(value) = token.parse::<i32>() {
+        return Tag::Int(value);
+    }
+    if token.contains(['.', 'e', 'E'])
+        && token.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'))
+    {
+        if let Ok(value) = token.parse::<f64>() {
+            return Tag::Double(value);
+        }
+    }
+    Tag::String(token.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL_DAT: &[u8] = include_bytes!("testdata/nbt/level.dat");
+    const PLAYER_DAT: &[u8] =
+        include_bytes!("testdata/nbt/playerdata/069a79f4-44e9-4726-a5be-fca90e38aaf5.dat");
+    const BEDROCK_LEVEL_DAT: &[u8] = include_bytes!("testdata/nbt/bedrock_level.dat");
+
+    fn gunzip(bytes: &[u8]) -> Vec<u8> {
+        let mut decoded = Vec::new();
+        GzDecoder::new(bytes).read_to_end(&mut decoded).unwrap();
+        decoded
+    }
+
+    fn path<'a>(compound: &'a Compound, keys: &[&str]) -> &'a Tag {
+        let (last, parents) = keys.split_last().unwrap();
+        let parent = parents.iter().fold(compound, |compound, key| {
+            compound.get(key).unwrap().as_compound().unwrap()
+        });
+        parent.get(last).unwrap()
+    }
+
+    // Writing what was read gives back the same bytes, gzip aside since its header differs
+    fn assert_round_trip(original: &[u8]) -> NbtFile {
+        let file = NbtFile::read(original).unwrap();
+        let written = file.to_bytes().unwrap();
+        assert_eq!(NbtFile::read(&written).unwrap(), file);
+        match file.format {
+            Format::Java(Compression::Gzip) => assert_eq!(gunzip(&written), gunzip(original)),
+            _ => assert_eq!(written, original),
+        }
+        file
+    }
+
+    #[test]
+    fn reads_the_spec_example() {
+        let bytes = b"\x0a\x00\x0bhello world\x08\x00\x04name\x00\x09Bananrama\x00";
+        let file = assert_round_trip(bytes);
+        assert_eq!(file.format, Format::Java(Compression::None));
+        assert_eq!(file.name, "hello world");
+        assert_eq!(
+            file.root.get("name").and_then(Tag::as_str),
+            Some("Bananrama")
+        );
+    }
+
+    #[test]
+    fn java_level_dat_round_trips() {
+        let file = assert_round_trip(LEVEL_DAT);
+        assert_eq!(file.format, Format::Java(Compression::Gzip));
+        let root = &file.root;
+        assert_eq!(
+            path(root, &["Data", "LevelName"]).as_str(),
+            Some("Survival ✦ world")
+        );
+        assert_eq!(path(root, &["Data", "DataVersion"]), &Tag::Int(3955));
+        assert_eq!(
+            path(root, &["Data", "WorldGenSettings", "seed"]),
+            &Tag::Long(-4172144997902289642)
+        );
+        assert_eq!(
+            path(root, &["Data", "GameRules", "keepInventory"]).as_str(),
+            Some("false")
+        );
+        assert_eq!(
+            path(root, &["Data", "ScheduledEvents"]),
+            &Tag::List(END, Vec::new())
+        );
+        assert_eq!(
+            path(root, &["Data", "removed_features"]),
+            &Tag::LongArray(vec![i64::MIN, 0, i64::MAX])
+        );
+        assert_eq!(
+            path(root, &["Data", "icon_bytes"]),
+            &Tag::ByteArray(vec![-128, 0, 127, 1])
+        );
+    }
+
+    #[test]
+    fn playerdata_round_trips() {
+        let file = assert_round_trip(PLAYER_DAT);
+        let root = &file.root;
+        let position: Vec<f64> = path(root, &["Pos"])
+            .as_list()
+            .unwrap()
+            .iter()
+            .filter_map(Tag::as_f64)
+            .collect();
+        assert_eq!(position, vec![-104.5, 71.0, 250.30000001192093]);
+        assert_eq!(
+            path(root, &["Rotation"]).as_list().unwrap()[0],
+            Tag::Float(-91.35)
+        );
+        let inventory = path(root, &["Inventory"]).as_list().unwrap();
+        assert_eq!(inventory.len(), 3);
+        let sword = inventory[0].as_compound().unwrap();
+        assert_eq!(
+            path(sword, &["components", "minecraft:custom_name"]).as_str(),
+            Some("{\"text\":\"Nul\0l \\\"quoted\\\"\"}")
+        );
+        assert_eq!(
+            inventory[2].as_compound().unwrap().get("Slot"),
+            Some(&Tag::Byte(-106))
+        );
+        assert_eq!(
+            path(root, &["UUID"]),
+            &Tag::IntArray(vec![110787060, 1156138790, -1514210135, 238594805])
+        );
+    }
+
+    #[test]
+    fn bedrock_level_dat_round_trips() {
+        let file = assert_round_trip(BEDROCK_LEVEL_DAT);
+        assert_eq!(file.format, Format::Bedrock { header: Some(10) });
+        assert_eq!(
+            file.root.get("LevelName").and_then(Tag::as_str),
+            Some("Bedrock level")
+        );
+        assert_eq!(file.root.get("RandomSeed"), Some(&Tag::Long(8234762347823)));
+        assert_eq!(
+            path(&file.root, &["abilities", "flySpeed"]),
+            &Tag::Float(0.05)
+        );
+        assert_eq!(
+            file.root.get("lastOpenedWithVersion"),
+            Some(&Tag::List(
+                INT,
+                vec![
+                    Tag::Int(1),
+                    Tag::Int(21),
+                    Tag::Int(50),
+                    Tag::Int(7),
+                    Tag::Int(0)
+                ]
+            ))
+        );
+
+        let headerless = NbtFile::read_bedrock(&BEDROCK_LEVEL_DAT[8..]).unwrap();
+        assert_eq!(headerless.root, file.root);
+        assert_eq!(headerless.to_bytes().unwrap(), &BEDROCK_LEVEL_DAT[8..]);
+    }
+
+    #[test]
+    fn zlib_and_uncompressed_round_trip() {
+        let mut file = NbtFile::read(LEVEL_DAT).unwrap();
+        for compression in [Compression::Zlib, Compression::None] {
+            file.format = Format::Java(compression);
+            let written = file.to_bytes().unwrap();
+            assert_eq!(NbtFile::read(&written).unwrap(), file);
+        }
+    }
+
+    #[test]
+    fn modified_utf8_strings() {
+        assert_eq!(encode_mutf8("a\0b"), vec![b'a', 0xc0, 0x80, b'b']);
+        // U+1F600 is written as its two surrogates, not as four byte utf-8
+        assert_eq!(encode_mutf8("😀"), vec![0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]);
+        for text in ["", "plain", "a\0b", "Ünïcødé ✦", "😀 emoji"] {
+            assert_eq!(decode_mutf8(&encode_mutf8(text)).unwrap(), text);
+        }
+        assert!(decode_mutf8(&[0xc0]).is_err());
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let bytes = b"\x0a\x00\x00\x08\x00\x04name\x00\x09Banan";
+        assert!(NbtFile::read(bytes).is_err());
+        // a byte array claiming two billion entries
+        let huge = b"\x0a\x00\x00\x07\x00\x01a\x7f\xff\xff\xff\x00";
+        assert!(NbtFile::read(huge).is_err());
+        assert!(NbtFile::read(b"\x08\x00\x00\x00\x00").is_err());
+        let mut deep = b"\x0a\x00\x00".to_vec();
+        for _ in 0..600 {
+            deep.extend(b"\x0a\x00\x01a");
+        }
+        assert!(NbtFile::read(&deep).is_err());
+    }
+
+    #[test]
+    fn snbt_round_trips() {
+        for bytes in [LEVEL_DAT, PLAYER_DAT, BEDROCK_LEVEL_DAT] {
+            let root = Tag::Compound(NbtFile::read(bytes).unwrap().root);
+            assert_eq!(parse_snbt(&to_snbt(&root)).unwrap(), root);
+            assert_eq!(parse_snbt(&to_snbt_pretty(&root)).unwrap(), root);
+        }
+    }
+
+    #[test]
+    fn snbt_formatting() {
+        let mut compound = Compound::default();
+        compound.insert("Count", Tag::Byte(3));
+        compound.insert("minecraft:name", Tag::String("say \"hi\"".to_string()));
+        compound.insert(
+            "Pos",
+            Tag::List(DOUBLE, vec![Tag::Double(1.5), Tag::Double(-2.0)]),
+        );
+        compound.insert("UUID", Tag::IntArray(vec![1, -2]));
+        compound.insert("empty", Tag::Compound(Compound::default()));
+        assert_eq!(
+            to_snbt(&Tag::Compound(compound.clone())),
+            "{Count:3b,\"minecraft:name\":\"say \\\"hi\\\"\",Pos:[1.5d,-2d],UUID:[I;1,-2],empty:{}}"
+        );
+        assert_eq!(
+            to_snbt_pretty(&Tag::Compound(compound)),
+            "{\n    Count: 3b,\n    \"minecraft:name\": \"say \\\"hi\\\"\",\n    Pos: [1.5d,-2d],\n    UUID: [I;1,-2],\n    empty: {}\n}"
+        );
+    }
+
+    #[test]
+    fn snbt_parsing() {
+        let tag = parse_snbt(
+            "{ a: 1b, b: 2S, c: 3, d: 4l, e: 5.5F, f: 6.25, g: 7D, h: true, i: 'it\\'s', j: [B; 1b, -1b],
+               k: [L;], l: [], m: [{}, {x: \"y\"}], n: stone, o: 1e3, p: 300b }",
+        );
+        let Tag::Compound(compound) = tag.unwrap() else {
+            panic!("not a compound");
+        };
+        let get = |key: &str| compound.get(key).unwrap().clone();
+        assert_eq!(get("a"), Tag::Byte(1));
+        assert_eq!(get("b"), Tag::Short(2));
+        assert_eq!(get("c"), Tag::Int(3));
+        assert_eq!(get("d"), Tag::Long(4));
+        assert_eq!(get("e"), Tag::Float(5.5));
+        assert_eq!(get("f"), Tag::Double(6.25));
+        assert_eq!(get("g"), Tag::Double(7.0));
+        assert_eq!(get("h"), Tag::Byte(1));
+        assert_eq!(get("j"), Tag::ByteArray(vec![1, -1]));
+        assert_eq!(get("k"), Tag::LongArray(Vec::new()));
+        assert_eq!(get("l"), Tag::List(END, Vec::new()));
+        assert_eq!(get("m").as_list().unwrap().len(), 2);
+        assert_eq!(get("i"), Tag::String("it's".to_string()));
+        assert_eq!(get("n"), Tag::String("stone".to_string()));
+        assert_eq!(get("o"), Tag::Double(1000.0));
+        // out of range for a byte, minecraft reads it as a string too
+        assert_eq!(get("p"), Tag::String("300b".to_string()));
+
+        assert!(parse_snbt("[1, 2b]").is_err());
+        assert!(parse_snbt("[I; 1, 2L]").is_err());
+        assert!(parse_snbt("{a: 1").is_err());
+        assert!(parse_snbt("{a 1}").is_err());
+        assert!(parse_snbt("\"open").is_err());
+        assert!(parse_snbt("1 2").is_err());
+    }
+}
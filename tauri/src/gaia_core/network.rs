@@ -0,0 +1,119 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, UdpSocket},
+    path::Path,
+};
+
+use crate::gaia_core::{
+    core_structs::{Config, ServerStruct},
+    http, port_forwarding,
+    server_properties::read_properties,
+};
+
+// Where the public address comes from, picked with Config::public_ip_source
+pub trait PublicIpSource: Send + Sync {
+    fn public_ip(&self) -> Result<IpAddr, String>;
+}
+
+// Asks a "what's my ip" service that answers with the bare address
+pub struct EndpointSource {
+    pub url: String,
+}
+
+impl PublicIpSource for EndpointSource {
+    fn public_ip(&self) -> Result<IpAddr, String> {
+        let response = http::get(&self.url)?;
+        if response.status != 200 {
+            return Err(format!(
+                "{} answered with status {}",
+                self.url, response.status
+            ));
+        }
+        parse_ip(&response.body)
+    }
+}
+
+// For setups detection gets wrong, like a vpn or a tunnel in front of the server
+pub struct ManualSource {
+    pub ip: String,
+}
+
+impl PublicIpSource for ManualSource {
+    fn public_ip(&self) -> Result<IpAddr, String> {
+        parse_ip(&self.ip)
+    }
+}
+
+// Asks the router over UPnP or NAT-PMP, doesn't need anything outside the network
+pub struct RouterSource;
+
+impl PublicIpSource for RouterSource {
+    fn public_ip(&self) -> Result<IpAddr, String> {
+        port_forwarding::discover()?.external_ip()
+    }
+}
+
+fn parse_ip(value: &str) -> Result<IpAddr, String> {
+    value
+        .trim()
+        .parse::<IpAddr>()
+        .map_err(|_| format!("Not an ip address: {}", value.trim()))
+}
+
+pub fn public_ip_source(config: &Config) -> Box<dyn PublicIpSource> {
+    match config.public_ip_source.as_str() {
+        "manual" => Box::new(ManualSource {
+            ip: config.public_ip_manual.clone(),
+        }),
+        "router" => Box::new(RouterSource),
+        _ => Box::new(EndpointSource {
+            url: config.public_ip_endpoint.clone(),
+        }),
+    }
+}
+
+// Address of the interface the default route goes through. Connecting a udp socket
+// doesn't send anything, it only makes the os pick the interface
+fn lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(8, 8, 8, 8), 80)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_unspecified()).then_some(ip)
+}
+
+// server-ip in server.properties wins, the server only listens on that address then
+pub fn local_ip(server: &ServerStruct) -> IpAddr {
+    read_properties(Path::new(&server.path))
+        .get("server-ip")
+        .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+        .filter(|ip| !ip.is_unspecified())
+        .or_else(lan_ip)
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bare_addresses() {
+        assert_eq!(
+            parse_ip(" 203.0.113.7\n").unwrap(),
+            IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7))
+        );
+        assert!(parse_ip("2001:db8::1").unwrap().is_ipv6());
+        assert!(parse_ip("<html>203.0.113.7</html>").is_err());
+    }
+
+    #[test]
+    fn manual_source_uses_the_configured_address() {
+        let config = Config {
+            public_ip_source: "manual".to_string(),
+            public_ip_manual: "198.51.100.2".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            public_ip_source(&config).public_ip().unwrap().to_string(),
+            "198.51.100.2"
+        );
+    }
+}
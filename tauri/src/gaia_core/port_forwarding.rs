@@ -0,0 +1,816 @@
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use uuid::Uuid;
+
+use crate::gaia_core::{
+    auto_start::manager_message, core_structs::ServerStruct, http, ports::server_ports,
+    supervisor::Supervisor,
+};
+
+pub const NAT_PMP_PORT: u16 = 5351;
+const SSDP_ADDRESS: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(239, 255, 255, 250)), 1900);
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+// routers forget mappings after this, they're renewed at half of it
+const LEASE_SECONDS: u32 = 3600;
+const RENEW_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// A router that can open ports, either over UPnP or NAT-PMP
+pub trait PortMapper: Send + Sync {
+    // "UPnP" or "NAT-PMP"
+    fn method(&self) -> &'static str;
+    fn external_ip(&self) -> Result<IpAddr, String>;
+    // Forwards the router's port to the same port on this machine. Returns the lifetime the
+    // router granted, 0 means the mapping stays until it's removed
+    fn add_mapping(
+        &self,
+        protocol: &str,
+        port: u16,
+        lifetime: u32,
+        description: &str,
+    ) -> Result<u32, String>;
+    fn remove_mapping(&self, protocol: &str, port: u16) -> Result<(), String>;
+}
+
+// NAT-PMP (RFC 6886), answered by the gateway on udp 5351
+pub struct NatPmp {
+    gateway: SocketAddr,
+}
+
+impl NatPmp {
+    pub fn new(gateway: SocketAddr) -> NatPmp {
+        NatPmp { gateway }
+    }
+
+    // Sends the request with the backoff from the rfc and checks the answer's header
+    fn send(&self, request: &[u8], response_length: usize) -> Result<Vec<u8>, String> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|err| err.to_string())?;
+        socket
+            .connect(self.gateway)
+            .map_err(|err| err.to_string())?;
+        let mut timeout = Duration::from_millis(250);
+        for _ in 0..4 {
+            socket.send(request).map_err(|err| err.to_string())?;
+            socket
+                .set_read_timeout(Some(timeout))
+                .map_err(|err| err.to_string())?;
+            let mut response = [0u8; 16];
+            let Ok(length) = socket.recv(&mut response) else {
+                timeout *= 2;
+                continue;
+            };
+            if length < response_length || response[0] != 0 || response[1] != request[1] + 128 {
+                return Err("Invalid NAT-PMP response".to_string());
+            }
+            return match u16::from_be_bytes([response[2], response[3]]) {
+                0 => Ok(response[..length].to_vec()),
+                1 => Err("NAT-PMP version is not supported by the router".to_string()),
+                2 => Err("The router refused the NAT-PMP request".to_string()),
+                3 => Err("The router isn't connected to the internet".to_string()),
+                4 => Err("The router is out of port mappings".to_string()),
+                code => Err(format!("NAT-PMP request failed with code {}", code)),
+            };
+        }
+        Err(format!("No NAT-PMP answer from {}", self.gateway))
+    }
+
+    fn map(
+        &self,
+        protocol: &str,
+        port: u16,
+        external_port: u16,
+        lifetime: u32,
+    ) -> Result<u32, String> {
+        let opcode = match protocol {
+            "UDP" => 1,
+            _ => 2,
+        };
+        let mut request = vec![0, opcode, 0, 0];
+        request.extend(port.to_be_bytes());
+        request.extend(external_port.to_be_bytes());
+        request.extend(lifetime.to_be_bytes());
+        let response = self.send(&request, 16)?;
+        let mapped_port = u16::from_be_bytes([response[10], response[11]]);
+        if lifetime > 0 && mapped_port != port {
+            // someone else has the port on the router, don't leave a mapping nobody asked for
+            let _ = self.map(protocol, port, 0, 0);
+            return Err(format!(
+                "The router gave out port {} instead of {}",
+                mapped_port, port
+            ));
+        }
+        Ok(u32::from_be_bytes([
+            response[12],
+            response[13],
+            response[14],
+            response[15],
+        ]))
+    }
+}
+
+impl PortMapper for NatPmp {
+    fn method(&self) -> &'static str {
+        "NAT-PMP"
+    }
+
+    fn external_ip(&self) -> Result<IpAddr, String> {
+        let response = self.send(&[0, 0], 12)?;
+        Ok(IpAddr::V4(Ipv4Addr::new(
+            response[8],
+            response[9],
+            response[10],
+            response[11],
+        )))
+    }
+
+    fn add_mapping(
+        &self,
+        protocol: &str,
+        port: u16,
+        lifetime: u32,
+        _: &str,
+    ) -> Result<u32, String> {
+        self.map(protocol, port, port, lifetime)
+    }
+
+    // a lifetime of 0 deletes the mapping
+    fn remove_mapping(&self, protocol: &str, port: u16) -> Result<(), String> {
+        self.map(protocol, port, 0, 0).map(|_| ())
+    }
+}
+
+// UPnP internet gateway device, found over SSDP and controlled with SOAP requests
+pub struct Upnp {
+    control_url: String,
+    service_type: String,
+    // the address the router reaches this machine on
+    local_ip: IpAddr,
+}
+
+// Text between <tag> and </tag>, the first one from `xml`
+fn tag_text<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = xml[start..].find(&format!("</{}>", tag))? + start;
+    Some(xml[start..end].trim())
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// "http://host:port" of a url
+fn url_origin(url: &str) -> &str {
+    let after_scheme = url.find("://").map_or(0, |index| index + 3);
+    match url[after_scheme..].find('/') {
+        Some(index) => &url[..after_scheme + index],
+        None => url,
+    }
+}
+
+fn url_host(url: &str) -> Option<IpAddr> {
+    let origin = url_origin(url);
+    let host = &origin[origin.find("://")? + 3..];
+    let host = host.rsplit_once(':').map_or(host, |(host, _)| host);
+    host.parse().ok()
+}
+
+// The wan connection service and where to send its requests, from the device description
+fn find_wan_service(description: &str, location: &str) -> Option<(String, String)> {
+    let base = tag_text(description, "URLBase").unwrap_or(url_origin(location));
+    description
+        .split("<service>")
+        .skip(1)
+        .filter_map(|service| {
+            let service_type = tag_text(service, "serviceType")?;
+            let control_url = tag_text(service, "controlURL")?;
+            Some((service_type, control_url))
+        })
+        .find(|(service_type, _)| {
+            service_type.contains(":WANIPConnection:")
+                || service_type.contains(":WANPPPConnection:")
+        })
+        .map(|(service_type, control_url)| {
+            let control_url = match control_url.starts_with("http://") {
+                true => control_url.to_string(),
+                false => format!(
+                    "{}/{}",
+                    base.trim_end_matches('/'),
+                    control_url.trim_start_matches('/')
+                ),
+            };
+            (service_type.to_string(), control_url)
+        })
+}
+
+impl Upnp {
+    // Asks for internet gateways at `ssdp` and uses the first one with a wan connection
+    pub fn discover(ssdp: SocketAddr, timeout: Duration) -> Result<Upnp, String> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|err| err.to_string())?;
+        let search = format!(
+            "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\n\r\n",
+            ssdp
+        );
+        socket
+            .send_to(search.as_bytes(), ssdp)
+            .map_err(|err| err.to_string())?;
+        let started = Instant::now();
+        let mut response = [0u8; 2048];
+        while let Some(remaining) = timeout
+            .checked_sub(started.elapsed())
+            .filter(|remaining| !remaining.is_zero())
+        {
+            socket
+                .set_read_timeout(Some(remaining))
+                .map_err(|err| err.to_string())?;
+            let Ok(length) = socket.recv(&mut response) else {
+                break;
+            };
+            let answer = String::from_utf8_lossy(&response[..length]);
+            let Some(location) = answer.lines().find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("location")
+                    .then(|| value.trim().to_string())
+            }) else {
+                continue;
+            };
+            let Ok(description) = http::get(&location) else {
+                continue;
+            };
+            let Some((service_type, control_url)) = find_wan_service(&description.body, &location)
+            else {
+                continue;
+            };
+            // the source address of a socket pointed at the router is the one it can reach
+            let Some(local_ip) = url_host(&control_url).and_then(|router| {
+                let probe = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+                probe.connect((router, 1900)).ok()?;
+                Some(probe.local_addr().ok()?.ip())
+            }) else {
+                continue;
+            };
+            return Ok(Upnp {
+                control_url,
+                service_type,
+                local_ip,
+            });
+        }
+        Err("No UPnP gateway answered".to_string())
+    }
+
+    fn soap(&self, action: &str, arguments: &[(&str, String)]) -> Result<String, String> {
+        let arguments: String = arguments
+            .iter()
+            .map(|(name, value)| format!("<{}>{}</{}>", name, escape_xml(value), name))
+            .collect();
+        let body = format!(
+            "<?xml version=\"1.0\"?><s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\"><s:Body><u:{} xmlns:u=\"{}\">{}</u:{}></s:Body></s:Envelope>",
+            action, self.service_type, arguments, action
+        );
+        let soap_action = format!("\"{}#{}\"", self.service_type, action);
+        let response = http::request(
+            "POST",
+            &self.control_url,
+            &[
+                ("Content-Type", "text/xml; charset=\"utf-8\""),
+                ("SOAPAction", &soap_action),
+            ],
+            &body,
+        )?;
+        if response.status != 200 {
+            return Err(format!(
+                "UPnP {} failed: {} {}",
+                action,
+                tag_text(&response.body, "errorCode").unwrap_or(&response.status.to_string()),
+                tag_text(&response.body, "errorDescription").unwrap_or_default()
+            ));
+        }
+        Ok(response.body)
+    }
+
+    fn add_port_mapping(
+        &self,
+        protocol: &str,
+        port: u16,
+        lifetime: u32,
+        description: &str,
+    ) -> Result<(), String> {
+        self.soap(
+            "AddPortMapping",
+            &[
+                ("NewRemoteHost", String::new()),
+                ("NewExternalPort", port.to_string()),
+                ("NewProtocol", protocol.to_string()),
+                ("NewInternalPort", port.to_string()),
+                ("NewInternalClient", self.local_ip.to_string()),
+                ("NewEnabled", "1".to_string()),
+                ("NewPortMappingDescription", description.to_string()),
+                ("NewLeaseDuration", lifetime.to_string()),
+            ],
+        )
+        .map(|_| ())
+    }
+}
+
+impl PortMapper for Upnp {
+    fn method(&self) -> &'static str {
+        "UPnP"
+    }
+
+    fn external_ip(&self) -> Result<IpAddr, String> {
+        let response = self.soap("GetExternalIPAddress", &[])?;
+        tag_text(&response, "NewExternalIPAddress")
+            .and_then(|ip| ip.parse().ok())
+            .ok_or_else(|| "The router didn't report its external address".to_string())
+    }
+
+    fn add_mapping(
+        &self,
+        protocol: &str,
+        port: u16,
+        lifetime: u32,
+        description: &str,
+    ) -> Result<u32, String> {
+        match self.add_port_mapping(protocol, port, lifetime, description) {
+            // 725 is OnlyPermanentLeasesSupported, plenty of routers only take lease 0
+            Err(err) if err.contains(": 725 ") => {
+                self.add_port_mapping(protocol, port, 0, description)?;
+                Ok(0)
+            }
+            result => result.map(|_| lifetime),
+        }
+    }
+
+    fn remove_mapping(&self, protocol: &str, port: u16) -> Result<(), String> {
+        self.soap(
+            "DeletePortMapping",
+            &[
+                ("NewRemoteHost", String::new()),
+                ("NewExternalPort", port.to_string()),
+                ("NewProtocol", protocol.to_string()),
+            ],
+        )
+        .map(|_| ())
+    }
+}
+
+// Gateway of the default route from /proc/net/route, only works on linux
+fn default_gateway() -> Option<Ipv4Addr> {
+    let routes = fs::read_to_string("/proc/net/route").ok()?;
+    routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.get(1) != Some(&"00000000") {
+            return None;
+        }
+        // stored as a little endian hex number
+        let gateway = u32::from_str_radix(fields.get(2)?, 16).ok()?;
+        Some(Ipv4Addr::from(gateway.to_le_bytes())).filter(|gateway| !gateway.is_unspecified())
+    })
+}
+
+// UPnP first since most routers speak it, NAT-PMP for the ones that don't
+pub fn discover() -> Result<Arc<dyn PortMapper>, String> {
+    let upnp_error = match Upnp::discover(SSDP_ADDRESS, DISCOVERY_TIMEOUT) {
+        Ok(upnp) => return Ok(Arc::new(upnp)),
+        Err(err) => err,
+    };
+    let gateway = default_gateway()
+        .ok_or_else(|| format!("{}, no default gateway for NAT-PMP", upnp_error))?;
+    let nat_pmp = NatPmp::new(SocketAddr::new(IpAddr::V4(gateway), NAT_PMP_PORT));
+    match nat_pmp.external_ip() {
+        Ok(_) => Ok(Arc::new(nat_pmp)),
+        Err(err) => Err(format!("{}, {}", upnp_error, err)),
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PortMapping {
+    pub method: String,
+    // "TCP" or "UDP"
+    pub protocol: String,
+    pub port: u16,
+    pub external_ip: Option<String>,
+    // 0 for mappings that stay until they're removed
+    pub lifetime_seconds: u32,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PortMappingsPayload {
+    pub id: Uuid,
+    pub mappings: Vec<PortMapping>,
+}
+
+#[derive(Clone)]
+struct ServerMappings {
+    mapper: Arc<dyn PortMapper>,
+    description: String,
+    mappings: Vec<PortMapping>,
+    renewed_at: Instant,
+}
+
+// Ports the app opened on the router for each running server
+#[derive(Default)]
+pub struct PortForwarding {
+    servers: Mutex<HashMap<Uuid, ServerMappings>>,
+}
+
+impl PortForwarding {
+    pub fn mappings(&self, id: Uuid) -> Vec<PortMapping> {
+        self.servers
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|server| server.mappings.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn emit_mappings<R: Runtime>(app: &AppHandle<R>, id: Uuid, mappings: Vec<PortMapping>) {
+    let _ = app.emit("server_port_mappings", PortMappingsPayload { id, mappings });
+}
+
+// The game port over tcp and the query and bedrock ports over udp, rcon never leaves the network
+fn forwarded_ports(server: &ServerStruct) -> Vec<(&'static str, u16)> {
+    server_ports(server)
+        .into_iter()
+        .filter_map(|(kind, port)| match kind {
+            "game" => Some(("TCP", port)),
+            // the router only maps ipv4, the bedrock_v6 port doesn't need it
+            "query" | "geyser" | "bedrock" => Some(("UDP", port)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Opens the server's ports on the router, blocks while the router is searched for
+pub fn open<R: Runtime>(app: &AppHandle<R>, server: &ServerStruct) {
+    if !server.port_forwarding {
+        return;
+    }
+    let mapper = match discover() {
+        Ok(mapper) => mapper,
+        Err(err) => {
+            manager_message(app, server.id, format!("Port forwarding failed: {}", err));
+            return;
+        }
+    };
+    let external_ip = mapper.external_ip().ok().map(|ip| ip.to_string());
+    let description = format!("Gaia Manager {}", server.name);
+    let mut mappings = Vec::new();
+    for (protocol, port) in forwarded_ports(server) {
+        match mapper.add_mapping(protocol, port, LEASE_SECONDS, &description) {
+            Ok(lifetime_seconds) => {
+                manager_message(
+                    app,
+                    server.id,
+                    format!(
+                        "Forwarded {} port {} with {}",
+                        protocol,
+                        port,
+                        mapper.method()
+                    ),
+                );
+                mappings.push(PortMapping {
+                    method: mapper.method().to_string(),
+                    protocol: protocol.to_string(),
+                    port,
+                    external_ip: external_ip.clone(),
+                    lifetime_seconds,
+                });
+            }
+            Err(err) => manager_message(
+                app,
+                server.id,
+                format!("Failed to forward {} port {}: {}", protocol, port, err),
+            ),
+        }
+    }
+    app.state::<PortForwarding>()
+        .servers
+        .lock()
+        .unwrap()
+        .insert(
+            server.id,
+            ServerMappings {
+                mapper,
+                description,
+                mappings: mappings.clone(),
+                renewed_at: Instant::now(),
+            },
+        );
+    emit_mappings(app, server.id, mappings);
+    // the server went down while the router was being searched for
+    if !app.state::<Supervisor>().is_running(server.id) {
+        close(app, server.id);
+    }
+}
+
+// Removes every mapping the server has on the router
+pub fn close<R: Runtime>(app: &AppHandle<R>, id: Uuid) {
+    let Some(server) = app
+        .state::<PortForwarding>()
+        .servers
+        .lock()
+        .unwrap()
+        .remove(&id)
+    else {
+        return;
+    };
+    for mapping in &server.mappings {
+        if let Err(err) = server
+            .mapper
+            .remove_mapping(&mapping.protocol, mapping.port)
+        {
+            manager_message(
+                app,
+                id,
+                format!(
+                    "Failed to remove {} port {} from the router: {}",
+                    mapping.protocol, mapping.port, err
+                ),
+            );
+        }
+    }
+    emit_mappings(app, id, Vec::new());
+}
+
+// Renews mappings before the router lets them expire, for as long as the app runs
+pub fn spawn<R: Runtime>(app: AppHandle<R>) {
+    thread::spawn(move || loop {
+        thread::sleep(RENEW_CHECK_INTERVAL);
+        let forwarding = app.state::<PortForwarding>();
+        let due: Vec<(Uuid, ServerMappings)> = forwarding
+            .servers
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter_map(|(id, server)| {
+                let shortest = server
+                    .mappings
+                    .iter()
+                    .map(|mapping| mapping.lifetime_seconds)
+                    .filter(|lifetime| *lifetime > 0)
+                    .min()?;
+                if server.renewed_at.elapsed() < Duration::from_secs(shortest as u64 / 2) {
+                    return None;
+                }
+                server.renewed_at = Instant::now();
+                Some((*id, server.clone()))
+            })
+            .collect();
+        for (id, server) in due {
+            for mapping in server
+                .mappings
+                .iter()
+                .filter(|mapping| mapping.lifetime_seconds > 0)
+            {
+                if let Err(err) = server.mapper.add_mapping(
+                    &mapping.protocol,
+                    mapping.port,
+                    LEASE_SECONDS,
+                    &server.description,
+                ) {
+                    manager_message(
+                        &app,
+                        id,
+                        format!(
+                            "Failed to renew {} port {}: {}",
+                            mapping.protocol, mapping.port, err
+                        ),
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    // Answers NAT-PMP like a router with the external address 203.0.113.5, keeping every
+    // request it got. Mappings for port 25566 are refused
+    fn fake_nat_pmp() -> (SocketAddr, Arc<Mutex<Vec<Vec<u8>>>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        thread::spawn(move || loop {
+            let mut request = [0u8; 12];
+            let Ok((length, client)) = socket.recv_from(&mut request) else {
+                return;
+            };
+            received.lock().unwrap().push(request[..length].to_vec());
+            let opcode = request[1];
+            let mut response = vec![0, opcode + 128];
+            let port = u16::from_be_bytes([request[4], request[5]]);
+            response.extend(
+                match port {
+                    25566 => 2u16,
+                    _ => 0,
+                }
+                .to_be_bytes(),
+            );
+            response.extend(1000u32.to_be_bytes());
+            match opcode {
+                0 => response.extend([203, 0, 113, 5]),
+                _ => {
+                    response.extend(&request[4..8]);
+                    // the router cuts lifetimes down to 30 minutes
+                    let lifetime =
+                        u32::from_be_bytes([request[8], request[9], request[10], request[11]]);
+                    response.extend(lifetime.min(1800).to_be_bytes());
+                }
+            }
+            socket.send_to(&response, client).unwrap();
+        });
+        (address, requests)
+    }
+
+    #[test]
+    fn nat_pmp_maps_and_removes_ports() {
+        let (gateway, requests) = fake_nat_pmp();
+        let nat_pmp = NatPmp::new(gateway);
+        assert_eq!(nat_pmp.external_ip(), Ok("203.0.113.5".parse().unwrap()));
+        assert_eq!(nat_pmp.add_mapping("TCP", 25565, 3600, ""), Ok(1800));
+        assert_eq!(nat_pmp.add_mapping("UDP", 25565, 3600, ""), Ok(1800));
+        nat_pmp.remove_mapping("TCP", 25565).unwrap();
+        assert!(nat_pmp.add_mapping("TCP", 25566, 3600, "").is_err());
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0], vec![0, 0]);
+        assert_eq!(
+            requests[1],
+            vec![0, 2, 0, 0, 0x63, 0xdd, 0x63, 0xdd, 0, 0, 0x0e, 0x10]
+        );
+        assert_eq!(requests[2][1], 1);
+        // removing asks for a lifetime of 0 without an external port
+        assert_eq!(requests[3], vec![0, 2, 0, 0, 0x63, 0xdd, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn nat_pmp_gives_up_without_an_answer() {
+        // nothing reads from this socket
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let nat_pmp = NatPmp::new(silent.local_addr().unwrap());
+        assert!(nat_pmp.external_ip().is_err());
+    }
+
+    const DESCRIPTION: &str = "<?xml version=\"1.0\"?>
+<root xmlns=\"urn:schemas-upnp-org:device-1-0\">
+<device><deviceType>urn:schemas-upnp-org:device:InternetGatewayDevice:1</deviceType>
+<serviceList><service><serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>
+<controlURL>/ctl/L3F</controlURL></service></serviceList>
+<deviceList><device><deviceType>urn:schemas-upnp-org:device:WANConnectionDevice:1</deviceType>
+<serviceList><service><serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>
+<controlURL>/ctl/IPConn</controlURL></service></serviceList></device></deviceList>
+</device></root>";
+
+    fn read_request(stream: &mut std::net::TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let length = stream.read(&mut buffer).unwrap();
+            request.extend(&buffer[..length]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let content_length = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .and_then(|length| length.parse::<usize>().ok())
+                    .unwrap_or(0);
+                if body.len() >= content_length {
+                    return text;
+                }
+            }
+            if length == 0 {
+                return text;
+            }
+        }
+    }
+
+    // An internet gateway that answers SSDP searches and SOAP requests, with the external
+    // address 203.0.113.9. It only takes permanent leases like a lot of real routers
+    fn fake_igd() -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+        let http = TcpListener::bind("127.0.0.1:0").unwrap();
+        let location = format!("http://{}/rootDesc.xml", http.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        thread::spawn(move || {
+            for stream in http.incoming() {
+                let mut stream = stream.unwrap();
+                let request = read_request(&mut stream);
+                received.lock().unwrap().push(request.clone());
+                let (status, body) = if request.starts_with("GET /rootDesc.xml") {
+                    ("200 OK", DESCRIPTION.to_string())
+                } else if request.contains("GetExternalIPAddress") {
+                    ("200 OK", "<s:Envelope><s:Body><u:GetExternalIPAddressResponse><NewExternalIPAddress>203.0.113.9</NewExternalIPAddress></u:GetExternalIPAddressResponse></s:Body></s:Envelope>".to_string())
+                } else if request.contains("AddPortMapping")
+                    && !request.contains("<NewLeaseDuration>0<")
+                {
+                    ("500 Internal Server Error", "<s:Envelope><s:Body><s:Fault><detail><UPnPError><errorCode>725</errorCode><errorDescription>OnlyPermanentLeasesSupported</errorDescription></UPnPError></detail></s:Fault></s:Body></s:Envelope>".to_string())
+                } else {
+                    (
+                        "200 OK",
+                        "<s:Envelope><s:Body></s:Body></s:Envelope>".to_string(),
+                    )
+                };
+                let _ = stream.write_all(
+                    format!(
+                        "HTTP/1.1 {}\r\nContent-Type: text/xml\r\n\r\n{}",
+                        status, body
+                    )
+                    .as_bytes(),
+                );
+            }
+        });
+        let ssdp = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let ssdp_address = ssdp.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut search = [0u8; 1024];
+            while let Ok((length, client)) = ssdp.recv_from(&mut search) {
+                if !String::from_utf8_lossy(&search[..length]).starts_with("M-SEARCH") {
+                    continue;
+                }
+                let answer = format!(
+                    "HTTP/1.1 200 OK\r\nST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\nLOCATION: {}\r\n\r\n",
+                    location
+                );
+                ssdp.send_to(answer.as_bytes(), client).unwrap();
+            }
+        });
+        (ssdp_address, requests)
+    }
+
+    #[test]
+    fn upnp_maps_and_removes_ports() {
+        let (ssdp, requests) = fake_igd();
+        let upnp = Upnp::discover(ssdp, Duration::from_secs(2)).unwrap();
+        assert!(upnp.control_url.ends_with("/ctl/IPConn"));
+        assert_eq!(
+            upnp.service_type,
+            "urn:schemas-upnp-org:service:WANIPConnection:1"
+        );
+        assert_eq!(upnp.external_ip(), Ok("203.0.113.9".parse().unwrap()));
+        // the lease is refused, so the mapping falls back to a permanent one
+        assert_eq!(
+            upnp.add_mapping("TCP", 25565, 3600, "Gaia Manager A & B"),
+            Ok(0)
+        );
+        upnp.remove_mapping("TCP", 25565).unwrap();
+
+        let requests = requests.lock().unwrap();
+        let added = requests
+            .iter()
+            .rfind(|request| request.contains("#AddPortMapping"))
+            .unwrap();
+        assert!(added.starts_with("POST /ctl/IPConn "));
+        assert!(added.contains("<NewExternalPort>25565</NewExternalPort>"));
+        assert!(added.contains("<NewProtocol>TCP</NewProtocol>"));
+        assert!(added.contains("<NewInternalClient>127.0.0.1</NewInternalClient>"));
+        assert!(added.contains(
+            "<NewPortMappingDescription>Gaia Manager A &amp; B</NewPortMappingDescription>"
+        ));
+        let deleted = requests.last().unwrap();
+        assert!(deleted.contains(
+            "SOAPAction: \"urn:schemas-upnp-org:service:WANIPConnection:1#DeletePortMapping\""
+        ));
+        assert!(deleted.contains("<NewExternalPort>25565</NewExternalPort>"));
+    }
+
+    #[test]
+    fn upnp_discovery_times_out() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        assert!(Upnp::discover(silent.local_addr().unwrap(), Duration::from_millis(200)).is_err());
+    }
+
+    #[test]
+    fn control_url_is_resolved_against_the_description() {
+        let location = "http://192.168.1.1:5000/rootDesc.xml";
+        assert_eq!(
+            find_wan_service(DESCRIPTION, location).unwrap().1,
+            "http://192.168.1.1:5000/ctl/IPConn"
+        );
+        let with_base = DESCRIPTION.replace(
+            "<device>",
+            "<URLBase>http://192.168.1.1:49152/</URLBase><device>",
+        );
+        assert_eq!(
+            find_wan_service(&with_base, location).unwrap().1,
+            "http://192.168.1.1:49152/ctl/IPConn"
+        );
+        assert!(find_wan_service("<root></root>", location).is_none());
+    }
+}
@@ -0,0 +1,457 @@
+use serde::Serialize;
+use std::{
+    fmt, fs,
+    net::{TcpListener, UdpSocket},
+    path::Path,
+};
+
+use crate::gaia_core::{
+    bedrock,
+    core_structs::ServerStruct,
+    geyser, proxy,
+    server_properties::{read_properties, set_property},
+};
+
+const DEFAULT_RCON_PORT: u16 = 25575;
+// Every error a port conflict turns into starts with this, callers tell them apart by it
+pub const PORT_IN_USE: &str = "PortInUse:";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PortInUse {
+    // "game", "query", "rcon", "geyser", or "bedrock" and "bedrock_v6" on bedrock servers
+    pub kind: String,
+    pub port: u16,
+    // who has it, e.g. server "Survival" or process java (pid 1234)
+    pub owner: String,
+}
+
+impl fmt::Display for PortInUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} port {} is already used by {}",
+            PORT_IN_USE, self.kind, self.port, self.owner
+        )
+    }
+}
+
+// Every port the server listens on, server.properties wins over the registry when it's set
+pub fn server_ports(server: &ServerStruct) -> Vec<(&'static str, u16)> {
+    // proxies only listen on the bind address from their own config
+    if proxy::is_proxy(&server.server_type) {
+        let port = proxy::read_config(server).map_or(server.port, |config| config.bind_port);
+        let mut ports = vec![("game", port)];
+        ports.extend(geyser::bedrock_port(server).map(|port| ("geyser", port)));
+        return ports;
+    }
+    let properties = read_properties(Path::new(&server.path));
+    let port = |key: &str| {
+        properties
+            .get(key)
+            .and_then(|port| port.parse::<u16>().ok())
+    };
+    let enabled = |key: &str| properties.get(key).is_some_and(|value| value == "true");
+    let game = port("server-port").unwrap_or(server.port);
+    // bedrock listens on udp, once for ipv4 and once for ipv6
+    if bedrock::is_bedrock(&server.server_type) {
+        return vec![
+            ("bedrock", game),
+            (
+                "bedrock_v6",
+                port("server-portv6").unwrap_or(game.saturating_add(1)),
+            ),
+        ];
+    }
+    let mut ports = vec![("game", game)];
+    if enabled("enable-query") {
+        ports.push(("query", port("query.port").unwrap_or(game)));
+    }
+    if enabled("enable-rcon") {
+        ports.push(("rcon", port("rcon.port").unwrap_or(DEFAULT_RCON_PORT)));
+    }
+    // bedrock players reach java servers through geyser's own udp port
+    ports.extend(geyser::bedrock_port(server).map(|port| ("geyser", port)));
+    ports
+}
+
+// Query, geyser and bedrock run over udp, the java game port and rcon over tcp
+pub fn is_udp(kind: &str) -> bool {
+    matches!(kind, "query" | "geyser" | "bedrock" | "bedrock_v6")
+}
+
+fn is_bound(kind: &str, port: u16) -> bool {
+    match is_udp(kind) {
+        true => UdpSocket::bind(("0.0.0.0", port)).is_err(),
+        false => TcpListener::bind(("0.0.0.0", port)).is_err(),
+    }
+}
+
+// Socket inodes listening on the port, from /proc/net/{tcp,tcp6,udp,udp6}
+fn socket_inodes(kind: &str, port: u16) -> Vec<String> {
+    let tables: &[&str] = match is_udp(kind) {
+        true => &["udp", "udp6"],
+        false => &["tcp", "tcp6"],
+    };
+    let mut inodes = Vec::new();
+    for table in tables {
+        let Ok(content) = fs::read_to_string(format!("/proc/net/{}", table)) else {
+            continue;
+        };
+        for line in content.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let Some(local_port) = fields
+                .get(1)
+                .and_then(|address| address.rsplit_once(':'))
+                .and_then(|(_, port)| u16::from_str_radix(port, 16).ok())
+            else {
+                continue;
+            };
+            // 0A is LISTEN, udp sockets are bound in state 07
+            if local_port == port && matches!(fields.get(3), Some(&"0A") | Some(&"07")) {
+                inodes.extend(fields.get(9).map(|inode| inode.to_string()));
+            }
+        }
+    }
+    inodes
+}
+
+// Name and pid of the process holding the port, only works on linux
+fn port_owner(kind: &str, port: u16) -> Option<String> {
+    let inodes = socket_inodes(kind, port);
+    if inodes.is_empty() {
+        return None;
+    }
+    for process in fs::read_dir("/proc").ok()?.flatten() {
+        let pid = process.file_name().to_string_lossy().to_string();
+        if !pid.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(descriptors) = fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+        let owns = descriptors.flatten().any(|descriptor| {
+            fs::read_link(descriptor.path()).is_ok_and(|target| {
+                let target = target.to_string_lossy();
+                inodes
+                    .iter()
+                    .any(|inode| target == format!("socket:[{}]", inode))
+            })
+        });
+        if owns {
+            let name = fs::read_to_string(process.path().join("comm")).unwrap_or_default();
+            return Some(format!("process {} (pid {})", name.trim(), pid));
+        }
+    }
+    None
+}
+
+// Ports of `server` that one of `others` uses too, or that something on the system
+// already bound when check_system is set
+pub fn find_conflicts(
+    server: &ServerStruct,
+    others: &[ServerStruct],
+    check_system: bool,
+) -> Vec<PortInUse> {
+    let other_ports: Vec<(&ServerStruct, Vec<(&'static str, u16)>)> = others
+        .iter()
+        .filter(|other| other.id != server.id)
+        .map(|other| (other, server_ports(other)))
+        .collect();
+    let mut conflicts = Vec::new();
+    for (kind, port) in server_ports(server) {
+        let taken_by = other_ports
+            .iter()
+            .find(|(_, ports)| ports.iter().any(|(_, other_port)| *other_port == port));
+        let owner = match taken_by {
+            Some((other, _)) => format!("server \"{}\"", other.name),
+            None if check_system && is_bound(kind, port) => {
+                port_owner(kind, port).unwrap_or_else(|| "another program".to_string())
+            }
+            None => continue,
+        };
+        conflicts.push(PortInUse {
+            kind: kind.to_string(),
+            port,
+            owner,
+        });
+    }
+    conflicts
+}
+
+// First port from `start` up that no registered server uses and nothing has bound
+pub fn next_free_port(start: u16, servers: &[ServerStruct]) -> Option<u16> {
+    let taken: Vec<u16> = servers
+        .iter()
+        .flat_map(server_ports)
+        .map(|(_, port)| port)
+        .collect();
+    (start..=u16::MAX)
+        .find(|port| !taken.contains(port) && !is_bound("game", *port) && !is_bound("query", *port))
+}
+
+// Moves a copy of a server off the ports of the original, query follows the game port
+pub fn reassign_ports(server: &mut ServerStruct, servers: &[ServerStruct]) -> Result<(), String> {
+    let port = next_free_port(server.port, servers).ok_or("No free port left")?;
+    // proxies keep their port in their own config
+    if proxy::is_proxy(&server.server_type) {
+        let mut config = proxy::read_config(server)?;
+        config.bind_port = port;
+        proxy::write_config(server, &config)?;
+        server.port = port;
+        return Ok(());
+    }
+    let path = Path::new(&server.path);
+    let properties = read_properties(path);
+    set_property(path, "server-port", &port.to_string())?;
+    if bedrock::is_bedrock(&server.server_type) {
+        let v6 = next_free_port(port.saturating_add(1), servers).ok_or("No free port left")?;
+        set_property(path, "server-portv6", &v6.to_string())?;
+    }
+    if properties.contains_key("query.port") {
+        set_property(path, "query.port", &port.to_string())?;
+    }
+    if properties
+        .get("enable-rcon")
+        .is_some_and(|value| value == "true")
+    {
+        let rcon = (DEFAULT_RCON_PORT..=u16::MAX)
+            .filter(|rcon| *rcon != port)
+            .find_map(|rcon| next_free_port(rcon, servers).filter(|free| *free != port))
+            .ok_or("No free port left")?;
+        set_property(path, "rcon.port", &rcon.to_string())?;
+    }
+    server.port = port;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    struct TestServers {
+        root: std::path::PathBuf,
+    }
+
+    impl TestServers {
+        fn new() -> TestServers {
+            let root = std::env::temp_dir().join(format!("gaia-ports-{}", Uuid::new_v4()));
+            fs::create_dir_all(&root).unwrap();
+            TestServers { root }
+        }
+
+        fn server(&self, name: &str, port: u16, properties: &str) -> ServerStruct {
+            let path = self.root.join(name);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("server.properties"), properties).unwrap();
+            ServerStruct {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                port,
+                path: path.to_string_lossy().to_string(),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl Drop for TestServers {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn lists_game_query_and_rcon_ports() {
+        let servers = TestServers::new();
+        let plain = servers.server("plain", 25565, "");
+        assert_eq!(server_ports(&plain), vec![("game", 25565)]);
+        // server.properties wins over the registry
+        let moved = servers.server("moved", 25565, "server-port=25570\n");
+        assert_eq!(server_ports(&moved), vec![("game", 25570)]);
+        let everything = servers.server(
+            "everything",
+            25565,
+            "server-port=25566\nenable-query=true\nenable-rcon=true\n",
+        );
+        assert_eq!(
+            server_ports(&everything),
+            vec![("game", 25566), ("query", 25566), ("rcon", 25575)]
+        );
+        let custom = servers.server(
+            "custom",
+            25565,
+            "enable-query=true\nquery.port=25600\nenable-rcon=true\nrcon.port=25601\n",
+        );
+        assert_eq!(
+            server_ports(&custom),
+            vec![("game", 25565), ("query", 25600), ("rcon", 25601)]
+        );
+    }
+
+    #[test]
+    fn finds_conflicts_with_other_servers() {
+        let servers = TestServers::new();
+        let survival = servers.server("survival", 25565, "enable-rcon=true\n");
+        let creative = servers.server("creative", 25566, "enable-rcon=true\n");
+        let conflicts = find_conflicts(&creative, std::slice::from_ref(&survival), false);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, "rcon");
+        assert_eq!(conflicts[0].port, 25575);
+        assert_eq!(conflicts[0].owner, "server \"survival\"");
+
+        // a query port clashes with another server's game port too
+        let query = servers.server("query", 25567, "enable-query=true\nquery.port=25565\n");
+        let conflicts = find_conflicts(&query, std::slice::from_ref(&survival), false);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, "query");
+    }
+
+    #[test]
+    fn ignores_the_server_itself_and_stopped_servers() {
+        let servers = TestServers::new();
+        let survival = servers.server("survival", 25565, "");
+        let copy = servers.server("copy", 25565, "");
+        // saving a server compares it against the registry, which holds its old self
+        assert!(find_conflicts(&survival, std::slice::from_ref(&survival), false).is_empty());
+        // starting only passes the running servers, stopped ones may share a port
+        assert!(find_conflicts(&copy, &[], false).is_empty());
+        assert_eq!(find_conflicts(&copy, &[survival], false).len(), 1);
+    }
+
+    #[test]
+    fn tells_tcp_and_udp_ports_apart() {
+        let servers = TestServers::new();
+        let udp = UdpSocket::bind(("0.0.0.0", 0)).unwrap();
+        let port = udp.local_addr().unwrap().port();
+        assert!(is_bound("query", port));
+        let game = servers.server("game", port, "");
+        let query = servers.server(
+            "query",
+            port.wrapping_add(1),
+            &format!("enable-query=true\nquery.port={}\n", port),
+        );
+        // only udp is taken, the game port listens on tcp
+        if !is_bound("game", port) {
+            assert!(find_conflicts(&game, &[], true).is_empty());
+        }
+        let conflicts = find_conflicts(&query, &[], true);
+        assert!(conflicts.iter().any(|conflict| conflict.kind == "query"));
+
+        let tcp = TcpListener::bind(("0.0.0.0", 0)).unwrap();
+        let port = tcp.local_addr().unwrap().port();
+        assert!(is_bound("game", port));
+        let game = servers.server("tcp", port, "");
+        let conflict = find_conflicts(&game, &[], true).remove(0);
+        assert_eq!(conflict.kind, "game");
+        assert_ne!(conflict.owner, "");
+    }
+
+    #[test]
+    fn errors_start_with_the_port_in_use_prefix() {
+        let conflict = PortInUse {
+            kind: "game".to_string(),
+            port: 25565,
+            owner: "server \"survival\"".to_string(),
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "PortInUse: game port 25565 is already used by server \"survival\""
+        );
+        assert!(conflict.to_string().starts_with(PORT_IN_USE));
+    }
+
+    #[test]
+    fn next_free_port_skips_taken_ports() {
+        let servers = TestServers::new();
+        let tcp = TcpListener::bind(("0.0.0.0", 0)).unwrap();
+        let bound = tcp.local_addr().unwrap().port();
+        let start = bound.saturating_sub(2);
+        let registered = vec![
+            servers.server("a", start, ""),
+            servers.server(
+                "b",
+                start.saturating_add(5),
+                &format!("enable-query=true\nquery.port={}\n", start + 1),
+            ),
+        ];
+        let free = next_free_port(start, &registered).unwrap();
+        assert!(free > start + 1 && free != bound, "{} {}", free, bound);
+        assert_eq!(
+            next_free_port(u16::MAX, &[servers.server("max", u16::MAX, "")]),
+            None
+        );
+    }
+
+    #[test]
+    fn copies_move_to_free_ports() {
+        let servers = TestServers::new();
+        let original = servers.server(
+            "original",
+            30565,
+            "server-port=30565\nenable-query=true\nquery.port=30565\nenable-rcon=true\n",
+        );
+        let mut copy = ServerStruct {
+            id: Uuid::new_v4(),
+            ..servers.server(
+                "copy",
+                30565,
+                "server-port=30565\nenable-query=true\nquery.port=30565\nenable-rcon=true\n",
+            )
+        };
+        reassign_ports(&mut copy, std::slice::from_ref(&original)).unwrap();
+        assert_ne!(copy.port, 30565);
+        assert!(find_conflicts(&copy, std::slice::from_ref(&original), false).is_empty());
+        let properties = read_properties(Path::new(&copy.path));
+        assert_eq!(properties["server-port"], copy.port.to_string());
+        assert_eq!(properties["query.port"], copy.port.to_string());
+        assert_ne!(properties["rcon.port"], "25575");
+    }
+
+    #[test]
+    fn proxy_copies_move_their_bind_port() {
+        let servers = TestServers::new();
+        let velocity = |name: &str| {
+            let server = ServerStruct {
+                server_type: "velocity".to_string(),
+                ..servers.server(name, 25577, "")
+            };
+            fs::remove_file(Path::new(&server.path).join("server.properties")).unwrap();
+            proxy::write_config(
+                &server,
+                &proxy::ProxyConfig {
+                    bind_port: 25577,
+                    servers: Vec::new(),
+                    try_servers: Vec::new(),
+                },
+            )
+            .unwrap();
+            server
+        };
+        let original = velocity("original");
+        let mut copy = velocity("copy");
+        reassign_ports(&mut copy, std::slice::from_ref(&original)).unwrap();
+        assert_ne!(copy.port, 25577);
+        assert_eq!(proxy::read_config(&copy).unwrap().bind_port, copy.port);
+        assert!(!Path::new(&copy.path).join("server.properties").exists());
+    }
+
+    #[test]
+    fn bedrock_copies_move_both_ports() {
+        let servers = TestServers::new();
+        let bedrock = |name: &str| ServerStruct {
+            server_type: "bedrock".to_string(),
+            ..servers.server(name, 29132, "server-port=29132\nserver-portv6=29133\n")
+        };
+        let original = bedrock("original");
+        let mut copy = bedrock("copy");
+        assert_eq!(
+            find_conflicts(&copy, std::slice::from_ref(&original), false).len(),
+            2
+        );
+        reassign_ports(&mut copy, std::slice::from_ref(&original)).unwrap();
+        assert!(find_conflicts(&copy, std::slice::from_ref(&original), false).is_empty());
+        let ports = server_ports(&copy);
+        assert_eq!(ports[0], ("bedrock", copy.port));
+        assert_ne!(ports[1].1, copy.port);
+    }
+}
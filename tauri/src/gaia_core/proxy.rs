@@ -0,0 +1,602 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use toml_edit::{value, Array, DocumentMut, Item, Table};
+
+use crate::gaia_core::{core_structs::ServerStruct, ports::server_ports};
+
+pub const PROXY_TYPES: [&str; 3] = ["velocity", "bungeecord", "waterfall"];
+// what the proxy's jar gets copied to when it's provisioned
+const PROXY_JAR: &str = "proxy.jar";
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ProxyServer {
+    pub name: String,
+    // "host:port" the proxy connects players to
+    pub address: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ProxyConfig {
+    pub bind_port: u16,
+    pub servers: Vec<ProxyServer>,
+    // servers players are sent to on join, the next one is tried when one is down
+    pub try_servers: Vec<String>,
+}
+
+pub fn is_proxy(server_type: &str) -> bool {
+    PROXY_TYPES.contains(&server_type)
+}
+
+// Proxies shut down with "end", "stop" only exists on the backends
+pub fn stop_command(server_type: &str) -> &'static str {
+    match is_proxy(server_type) {
+        true => "end",
+        false => "stop",
+    }
+}
+
+pub fn config_path(server: &ServerStruct) -> PathBuf {
+    match server.server_type.as_str() {
+        "velocity" => Path::new(&server.path).join("velocity.toml"),
+        _ => Path::new(&server.path).join("config.yml"),
+    }
+}
+
+// What a backend is called in the proxy's server list, velocity only takes a few characters
+pub fn proxy_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '_' {
+            true => c,
+            false => '-',
+        })
+        .collect();
+    match name.trim_matches('-') {
+        "" => "server".to_string(),
+        name => name.to_string(),
+    }
+}
+
+fn port_of(address: &str) -> Option<u16> {
+    address.rsplit_once(':')?.1.trim().parse().ok()
+}
+
+// Swaps the port of "host:port", keeping the host
+fn with_port(address: &str, port: u16) -> String {
+    let host = address.rsplit_once(':').map_or("0.0.0.0", |(host, _)| host);
+    format!("{}:{}", host, port)
+}
+
+fn read_velocity(content: &str) -> Result<ProxyConfig, String> {
+    let document: DocumentMut = content
+        .parse()
+        .map_err(|err| format!("Invalid velocity.toml: {}", err))?;
+    let bind_port = document
+        .get("bind")
+        .and_then(|bind| bind.as_str())
+        .and_then(port_of)
+        .unwrap_or(25577);
+    let mut config = ProxyConfig {
+        bind_port,
+        ..Default::default()
+    };
+    if let Some(servers) = document
+        .get("servers")
+        .and_then(|servers| servers.as_table_like())
+    {
+        for (name, address) in servers.iter() {
+            match (name, address.as_str()) {
+                ("try", _) => {
+                    config.try_servers = address
+                        .as_array()
+                        .map(|names| {
+                            names
+                                .iter()
+                                .filter_map(|name| name.as_str())
+                                .map(str::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                }
+                (name, Some(address)) => config.servers.push(ProxyServer {
+                    name: name.to_string(),
+                    address: address.to_string(),
+                }),
+                _ => {}
+            }
+        }
+    }
+    Ok(config)
+}
+
+// Only touches bind and [servers], everything else keeps its comments and formatting
+fn write_velocity(content: &str, config: &ProxyConfig) -> Result<String, String> {
+    let mut document: DocumentMut = content
+        .parse()
+        .map_err(|err| format!("Invalid velocity.toml: {}", err))?;
+    let bind = document
+        .get("bind")
+        .and_then(|bind| bind.as_str())
+        .unwrap_or("0.0.0.0:25577");
+    document["bind"] = value(with_port(bind, config.bind_port));
+    if !document.contains_table("servers") {
+        document.insert("servers", Item::Table(Table::new()));
+    }
+    let servers = document["servers"]
+        .as_table_mut()
+        .ok_or_else(|| "servers in velocity.toml is not a table".to_string())?;
+    servers.clear();
+    for server in &config.servers {
+        servers.insert(&server.name, value(server.address.clone()));
+    }
+    servers.insert(
+        "try",
+        value(Array::from_iter(
+            config.try_servers.iter().map(String::as_str),
+        )),
+    );
+    Ok(document.to_string())
+}
+
+pub fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+// Where a key's text starts, "- " opens a list item so the key sits after it
+fn key_indent(line: &str) -> usize {
+    match line.trim_start().starts_with("- ") {
+        true => indent(line) + 2,
+        false => indent(line),
+    }
+}
+
+pub fn unquote(text: &str) -> &str {
+    text.trim().trim_matches(|c| c == '\'' || c == '"')
+}
+
+// Index after the last line that belongs under the key at `start`
+pub fn block_end(lines: &[String], start: usize) -> usize {
+    let key_indent = indent(&lines[start]);
+    let mut end = start + 1;
+    while end < lines.len() && (lines[end].trim().is_empty() || indent(&lines[end]) > key_indent) {
+        end += 1;
+    }
+    // trailing blank lines stay outside of the block
+    while end > start + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    end
+}
+
+fn find_key(lines: &[String], key: &str, top_level: bool) -> Option<usize> {
+    lines.iter().position(|line| {
+        let trimmed = line.trim_start().trim_start_matches("- ");
+        trimmed.starts_with(&format!("{}:", key)) && (!top_level || indent(line) == 0)
+    })
+}
+
+// Entries of the top level servers map, each with the lines that belong to it
+fn bungee_server_entries(lines: &[String]) -> Vec<(String, Vec<String>)> {
+    let Some(start) = find_key(lines, "servers", true) else {
+        return Vec::new();
+    };
+    let end = block_end(lines, start);
+    let Some(child_indent) = lines[start + 1..end]
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent(line))
+        .min()
+    else {
+        return Vec::new();
+    };
+    let mut entries: Vec<(String, Vec<String>)> = Vec::new();
+    for line in &lines[start + 1..end] {
+        if indent(line) == child_indent && line.trim_end().ends_with(':') {
+            let name = unquote(line.trim().trim_end_matches(':')).to_string();
+            entries.push((name, vec![line.clone()]));
+        } else if let Some((_, entry)) = entries.last_mut() {
+            entry.push(line.clone());
+        }
+    }
+    entries
+}
+
+// The first listener's priorities, either "priorities: [a, b]" or a "- a" line per server
+fn bungee_priorities(lines: &[String]) -> (Option<(usize, usize)>, Vec<String>) {
+    let Some(start) = find_key(lines, "priorities", false) else {
+        return (None, Vec::new());
+    };
+    let inline = lines[start]
+        .split_once("priorities:")
+        .map_or("", |(_, rest)| rest.trim());
+    if inline.starts_with('[') {
+        let names = inline
+            .trim_matches(|c| c == '[' || c == ']')
+            .split(',')
+            .map(unquote)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect();
+        return (Some((start, start + 1)), names);
+    }
+    let item_indent = key_indent(&lines[start]);
+    let mut end = start + 1;
+    let mut names = Vec::new();
+    while end < lines.len()
+        && indent(&lines[end]) >= item_indent
+        && lines[end].trim_start().starts_with("- ")
+    {
+        names.push(unquote(&lines[end].trim_start()[2..]).to_string());
+        end += 1;
+    }
+    (Some((start, end)), names)
+}
+
+fn read_bungee(content: &str) -> ProxyConfig {
+    let lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let bind_port = find_key(&lines, "host", false)
+        .and_then(|index| {
+            lines[index]
+                .split_once("host:")
+                .map(|(_, host)| unquote(host).to_string())
+        })
+        .and_then(|host| port_of(&host))
+        .unwrap_or(25577);
+    let servers = bungee_server_entries(&lines)
+        .into_iter()
+        .map(|(name, entry)| ProxyServer {
+            name,
+            address: entry
+                .iter()
+                .find_map(|line| {
+                    line.trim()
+                        .strip_prefix("address:")
+                        .map(|address| unquote(address).to_string())
+                })
+                .unwrap_or_default(),
+        })
+        .collect();
+    ProxyConfig {
+        bind_port,
+        servers,
+        try_servers: bungee_priorities(&lines).1,
+    }
+}
+
+// config.yml is edited line by line so the rest of the file stays how bungeecord wrote it
+fn write_bungee(content: &str, config: &ProxyConfig) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    if let Some(index) = find_key(&lines, "host", false) {
+        let (key, host) = lines[index].split_once("host:").unwrap();
+        lines[index] = format!(
+            "{}host: {}",
+            key,
+            with_port(unquote(host), config.bind_port)
+        );
+    }
+
+    let (range, _) = bungee_priorities(&lines);
+    let (start, end, prefix) = match range {
+        Some((start, end)) => {
+            let prefix = lines[start]
+                .split_once("priorities:")
+                .map_or("", |(prefix, _)| prefix);
+            (start, end, prefix.to_string())
+        }
+        // a listener without priorities gets them after its host line
+        None => {
+            let index = find_key(&lines, "host", false).map_or(lines.len(), |index| index + 1);
+            (
+                index,
+                index,
+                " ".repeat(
+                    index
+                        .checked_sub(1)
+                        .and_then(|index| lines.get(index))
+                        .map_or(2, |line| key_indent(line)),
+                ),
+            )
+        }
+    };
+    let item_indent = " ".repeat(key_indent(&format!("{}priorities:", prefix)));
+    let mut priorities = vec![match config.try_servers.is_empty() {
+        true => format!("{}priorities: []", prefix),
+        false => format!("{}priorities:", prefix),
+    }];
+    priorities.extend(
+        config
+            .try_servers
+            .iter()
+            .map(|name| format!("{}- {}", item_indent, name)),
+    );
+    lines.splice(start..end, priorities);
+
+    let existing = bungee_server_entries(&lines);
+    let mut servers = vec![match config.servers.is_empty() {
+        true => "servers: {}".to_string(),
+        false => "servers:".to_string(),
+    }];
+    for server in &config.servers {
+        match existing.iter().find(|(name, _)| *name == server.name) {
+            // known servers keep their motd and restricted setting
+            Some((_, entry)) => {
+                servers.extend(
+                    entry
+                        .iter()
+                        .map(|line| match line.trim().starts_with("address:") {
+                            true => {
+                                format!("{}address: {}", " ".repeat(indent(line)), server.address)
+                            }
+                            false => line.clone(),
+                        }),
+                )
+            }
+            None => servers.extend([
+                format!("  {}:", server.name),
+                format!("    motd: '&1{}'", server.name),
+                format!("    address: {}", server.address),
+                "    restricted: false".to_string(),
+            ]),
+        }
+    }
+    match find_key(&lines, "servers", true) {
+        Some(start) => {
+            let end = block_end(&lines, start);
+            lines.splice(start..end, servers);
+        }
+        None => lines.extend(servers),
+    }
+    lines.join("\n") + "\n"
+}
+
+pub fn read_config(server: &ServerStruct) -> Result<ProxyConfig, String> {
+    let path = config_path(server);
+    let content =
+        fs::read_to_string(&path).map_err(|err| format!("Failed to read {:?}: {}", path, err))?;
+    match server.server_type.as_str() {
+        "velocity" => read_velocity(&content),
+        _ => Ok(read_bungee(&content)),
+    }
+}
+
+pub fn write_config(server: &ServerStruct, config: &ProxyConfig) -> Result<(), String> {
+    let path = config_path(server);
+    let content = fs::read_to_string(&path)
+        .unwrap_or_else(|_| default_config(&server.server_type, config.bind_port));
+    let content = match server.server_type.as_str() {
+        "velocity" => write_velocity(&content, config)?,
+        _ => write_bungee(&content, config),
+    };
+    fs::write(&path, content).map_err(|err| format!("Failed to write {:?}: {}", path, err))
+}
+
+// Just enough for the proxy to start, it fills in everything else on its first run
+fn default_config(server_type: &str, port: u16) -> String {
+    match server_type {
+        "velocity" => format!(
+            "config-version = \"2.7\"\nbind = \"0.0.0.0:{}\"\nmotd = \"<#09add3>A Velocity Server\"\nshow-max-players = 500\nonline-mode = true\nplayer-info-forwarding-mode = \"none\"\nforwarding-secret-file = \"forwarding.secret\"\n\n[servers]\ntry = []\n\n[forced-hosts]\n",
+            port
+        ),
+        _ => format!(
+            "listeners:\n- host: 0.0.0.0:{}\n  motd: '&1A BungeeCord Server'\n  max_players: 20\n  priorities: []\n  force_default_server: false\n  forced_hosts: {{}}\n  query_enabled: false\n  query_port: {}\nonline_mode: true\nip_forward: false\nservers: {{}}\n",
+            port, port
+        ),
+    }
+}
+
+// Copies the proxy's jar into its folder and writes a starting config. Returns the jar's new path
+pub fn provision(server: &ServerStruct, jar_path: &Path) -> Result<PathBuf, String> {
+    let directory = Path::new(&server.path);
+    fs::create_dir_all(directory).map_err(|err| err.to_string())?;
+    let jar = directory.join(PROXY_JAR);
+    fs::copy(jar_path, &jar).map_err(|err| format!("Failed to copy {:?}: {}", jar_path, err))?;
+    let config = config_path(server);
+    if !config.exists() {
+        fs::write(&config, default_config(&server.server_type, server.port))
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(jar)
+}
+
+// Adds the backend to the proxy's servers under `name`, it becomes the first server players
+// join when the proxy has none yet
+pub fn link(proxy: &ServerStruct, backend: &ServerStruct, name: &str) -> Result<(), String> {
+    let mut config = read_config(proxy)?;
+    let port = server_ports(backend)
+        .first()
+        .map_or(backend.port, |(_, port)| *port);
+    let address = format!("127.0.0.1:{}", port);
+    match config.servers.iter_mut().find(|server| server.name == name) {
+        Some(server) => server.address = address,
+        None => config.servers.push(ProxyServer {
+            name: name.to_string(),
+            address,
+        }),
+    }
+    if config.try_servers.is_empty() {
+        config.try_servers.push(name.to_string());
+    }
+    write_config(proxy, &config)
+}
+
+pub fn unlink(proxy: &ServerStruct, name: &str) -> Result<(), String> {
+    let mut config = read_config(proxy)?;
+    config.servers.retain(|server| server.name != name);
+    config.try_servers.retain(|server| server != name);
+    write_config(proxy, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VELOCITY: &str = "config-version = \"2.7\"
+# What port should the proxy be bound to?
+bind = \"0.0.0.0:25577\"
+motd = \"<#09add3>A Velocity Server\"
+
+[servers]
+# Configure your servers here
+lobby = \"127.0.0.1:30066\"
+factions = \"127.0.0.1:30067\"
+try = [
+    \"lobby\"
+]
+
+[forced-hosts]
+\"lobby.example.com\" = [\"lobby\"]
+";
+
+    const BUNGEE_INLINE: &str = "listeners:
+- query_port: 25577
+  motd: '&1Another Bungee server'
+  priorities: [lobby, 'survival']
+  host: 0.0.0.0:25577
+  max_players: 1
+servers:
+  lobby:
+    motd: '&1Just another BungeeCord - Forced Host'
+    address: localhost:25565
+    restricted: true
+  survival:
+    motd: '&1Survival'
+    address: localhost:25566
+    restricted: false
+online_mode: true
+";
+
+    const BUNGEE_LIST: &str = "listeners:
+- host: 0.0.0.0:25577
+  priorities:
+  - lobby
+  - survival
+  force_default_server: false
+servers:
+  lobby:
+    address: localhost:25565
+  survival:
+    address: localhost:25566
+ip_forward: false
+";
+
+    fn server(name: &str, address: &str) -> ProxyServer {
+        ProxyServer {
+            name: name.to_string(),
+            address: address.to_string(),
+        }
+    }
+
+    fn config(bind_port: u16, servers: &[(&str, &str)], try_servers: &[&str]) -> ProxyConfig {
+        ProxyConfig {
+            bind_port,
+            servers: servers
+                .iter()
+                .map(|(name, address)| server(name, address))
+                .collect(),
+            try_servers: try_servers.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn reads_velocity() {
+        assert_eq!(
+            read_velocity(VELOCITY).unwrap(),
+            config(
+                25577,
+                &[
+                    ("lobby", "127.0.0.1:30066"),
+                    ("factions", "127.0.0.1:30067")
+                ],
+                &["lobby"]
+            )
+        );
+    }
+
+    #[test]
+    fn velocity_round_trips_and_keeps_the_rest() {
+        let wanted = config(
+            25600,
+            &[
+                ("lobby", "127.0.0.1:30070"),
+                ("creative", "127.0.0.1:30071"),
+            ],
+            &["creative", "lobby"],
+        );
+        let written = write_velocity(VELOCITY, &wanted).unwrap();
+        assert_eq!(read_velocity(&written).unwrap(), wanted);
+        assert!(written.contains("# What port should the proxy be bound to?"));
+        assert!(written.contains("bind = \"0.0.0.0:25600\""));
+        assert!(written.contains("\"lobby.example.com\" = [\"lobby\"]"));
+        assert!(!written.contains("factions"));
+
+        let empty = config(25577, &[], &[]);
+        let written = write_velocity(&written, &empty).unwrap();
+        assert_eq!(read_velocity(&written).unwrap(), empty);
+        let fresh = write_velocity(&default_config("velocity", 25577), &wanted).unwrap();
+        assert_eq!(read_velocity(&fresh).unwrap(), wanted);
+    }
+
+    #[test]
+    fn reads_bungee_priorities_inline_and_as_a_list() {
+        let servers = [
+            ("lobby", "localhost:25565"),
+            ("survival", "localhost:25566"),
+        ];
+        let wanted = config(25577, &servers, &["lobby", "survival"]);
+        assert_eq!(read_bungee(BUNGEE_INLINE), wanted);
+        assert_eq!(read_bungee(BUNGEE_LIST), wanted);
+    }
+
+    #[test]
+    fn bungee_round_trips_and_keeps_motd_and_restricted() {
+        let wanted = config(
+            25600,
+            &[
+                ("lobby", "127.0.0.1:30070"),
+                ("creative", "127.0.0.1:30071"),
+            ],
+            &["creative", "lobby"],
+        );
+        for content in [BUNGEE_INLINE, BUNGEE_LIST] {
+            let written = write_bungee(content, &wanted);
+            assert_eq!(read_bungee(&written), wanted);
+            // writing it again changes nothing
+            assert_eq!(write_bungee(&written, &wanted), written);
+            assert!(!written.contains("survival"));
+            assert!(written.contains("  creative:\n    motd: '&1creative'\n"));
+        }
+        let written = write_bungee(BUNGEE_INLINE, &wanted);
+        assert!(written.contains(
+            "  lobby:\n    motd: '&1Just another BungeeCord - Forced Host'\n    address: 127.0.0.1:30070\n    restricted: true\n"
+        ));
+        assert!(written.contains("  max_players: 1\n"));
+        assert!(written.ends_with("online_mode: true\n"));
+        let written = write_bungee(BUNGEE_LIST, &wanted);
+        assert!(written
+            .contains("  priorities:\n  - creative\n  - lobby\n  force_default_server: false\n"));
+        assert!(written.ends_with("ip_forward: false\n"));
+    }
+
+    #[test]
+    fn bungee_writes_an_empty_servers_map() {
+        let empty = config(25577, &[], &[]);
+        let written = write_bungee(BUNGEE_INLINE, &empty);
+        assert!(written.contains("  priorities: []\n"));
+        assert!(written.contains("\nservers: {}\n"));
+        assert_eq!(read_bungee(&written), empty);
+        let fresh = write_bungee(&default_config("bungeecord", 25577), &empty);
+        assert_eq!(read_bungee(&fresh), empty);
+    }
+
+    #[test]
+    fn bungee_fills_in_an_empty_file() {
+        let wanted = config(25577, &[("lobby", "127.0.0.1:30070")], &["lobby"]);
+        assert_eq!(read_bungee(&write_bungee("", &wanted)), wanted);
+    }
+}
@@ -0,0 +1,430 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::gaia_core::{
+    anvil::{region_position, ChunkSlot, Region},
+    level_dat,
+    nbt::Compound,
+    worlds,
+};
+
+// Chunks in these sit in the same slots as in region/, they're pruned along with them
+const CHUNK_FOLDERS: [&str; 2] = ["entities", "poi"];
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PruneOptions {
+    // chunks players spent fewer ticks near than this are pruned, 0 leaves InhabitedTime out
+    pub min_inhabited_ticks: i64,
+    // "none", "radius" or "box", overworld chunks outside of it around spawn are pruned
+    pub area: String,
+    // blocks from spawn
+    pub radius: u32,
+    // half the box's size along x and z in blocks
+    pub box_size: [u32; 2],
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DimensionPrune {
+    pub dimension: String,
+    pub chunks_checked: u64,
+    pub chunks_pruned: u64,
+    // region files with no chunk left, they're deleted
+    pub regions_deleted: u64,
+    // region, entity, poi and .mcc files together
+    pub bytes_saved: u64,
+    // region files that were left alone and why
+    pub skipped: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PruneReport {
+    pub dry_run: bool,
+    pub chunks_pruned: u64,
+    pub bytes_saved: u64,
+    pub dimensions: Vec<DimensionPrune>,
+}
+
+// 1.18 moved everything out of the Level compound
+fn inhabited_time(chunk: &Compound) -> i64 {
+    chunk
+        .get("InhabitedTime")
+        .or_else(|| {
+            chunk
+                .get("Level")
+                .and_then(|level| level.as_compound()?.get("InhabitedTime"))
+        })
+        .and_then(|tag| tag.as_i64())
+        .unwrap_or(0)
+}
+
+// Whether the middle of the chunk is inside of the area around spawn
+fn in_area(options: &PruneOptions, spawn: [i32; 3], x: i32, z: i32) -> bool {
+    let dx = (x as i64 * 16 + 8 - spawn[0] as i64).abs();
+    let dz = (z as i64 * 16 + 8 - spawn[2] as i64).abs();
+    match options.area.as_str() {
+        "radius" => dx * dx + dz * dz <= options.radius as i64 * options.radius as i64,
+        "box" => dx <= options.box_size[0] as i64 && dz <= options.box_size[1] as i64,
+        _ => true,
+    }
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0)
+}
+
+// Replaces the file with bytes, or deletes it when there's nothing left. Returns the bytes saved
+fn replace_file(path: &Path, bytes: Option<Vec<u8>>, dry_run: bool) -> Result<u64, String> {
+    let saved =
+        file_size(path).saturating_sub(bytes.as_ref().map_or(0, |bytes| bytes.len() as u64));
+    if dry_run {
+        return Ok(saved);
+    }
+    match bytes {
+        Some(bytes) => {
+            // written next to it first so a crash never leaves a cut off region file
+            let mut temporary = path.as_os_str().to_owned();
+            temporary.push(".tmp");
+            fs::write(&temporary, bytes)
+                .map_err(|err| format!("Failed to write {:?}: {}", path, err))?;
+            fs::rename(&temporary, path)
+                .map_err(|err| format!("Failed to replace {:?}: {}", path, err))?;
+        }
+        None => {
+            fs::remove_file(path).map_err(|err| format!("Failed to delete {:?}: {}", path, err))?
+        }
+    }
+    Ok(saved)
+}
+
+// Decides per chunk of one region file and rewrites it with its entities and poi files.
+// Every file is rebuilt in memory before any is written, so a region that can't be rebuilt is
+// skipped as a whole
+fn prune_region(
+    stats: &mut DimensionPrune,
+    path: &Path,
+    prune: &dyn Fn(&Region, &ChunkSlot) -> Result<bool, String>,
+    dry_run: bool,
+) -> Result<(), String> {
+    let region = Region::load(path)?;
+    let mut pruned = Vec::new();
+    let slots = region.slots();
+    for slot in &slots {
+        if prune(&region, slot)? {
+            pruned.push(slot.index);
+        }
+    }
+    stats.chunks_checked += slots.len() as u64;
+    if pruned.is_empty() {
+        return Ok(());
+    }
+    let folder = path.parent().unwrap();
+    let mut files = vec![(
+        path.to_path_buf(),
+        region.rebuild(|slot| !pruned.contains(&slot.index))?,
+    )];
+    for name in CHUNK_FOLDERS {
+        let other = folder.with_file_name(name).join(path.file_name().unwrap());
+        if other.is_file() {
+            let rebuilt = Region::load(&other)?
+                .rebuild(|slot| !pruned.contains(&slot.index))
+                .map_err(|err| format!("{}: {}", name, err))?;
+            files.push((other, rebuilt));
+        }
+    }
+    // oversized chunks of pruned slots
+    let external: Vec<PathBuf> = pruned
+        .iter()
+        .map(|index| {
+            let (x, z) = region.chunk_position(*index);
+            folder.join(format!("c.{}.{}.mcc", x, z))
+        })
+        .filter(|path| path.is_file())
+        .collect();
+
+    stats.chunks_pruned += pruned.len() as u64;
+    if files[0].1.is_none() {
+        stats.regions_deleted += 1;
+    }
+    for (path, bytes) in files {
+        stats.bytes_saved += replace_file(&path, bytes, dry_run)?;
+    }
+    for path in external {
+        stats.bytes_saved += replace_file(&path, None, dry_run)?;
+    }
+    Ok(())
+}
+
+// Prunes every dimension of a java world, or only works out what would go with dry_run. A region
+// with a chunk that can't be read and isn't outside of the area is left alone as a whole
+pub fn prune(
+    server_path: &Path,
+    name: &str,
+    options: &PruneOptions,
+    dry_run: bool,
+) -> Result<PruneReport, String> {
+    if options.min_inhabited_ticks <= 0 && !matches!(options.area.as_str(), "radius" | "box") {
+        return Err("Set an InhabitedTime threshold or an area to keep".to_string());
+    }
+    let spawn = level_dat::read(server_path, name)?.spawn;
+    let mut report = PruneReport {
+        dry_run,
+        ..Default::default()
+    };
+    for (dimension, folder) in worlds::dimension_folders(server_path, name) {
+        let mut stats = DimensionPrune {
+            dimension: dimension.to_string(),
+            ..Default::default()
+        };
+        // spawn is an overworld position, the other dimensions only go by InhabitedTime
+        let use_area = dimension == "overworld";
+        let prune = |region: &Region, slot: &ChunkSlot| -> Result<bool, String> {
+            let (x, z) = region.chunk_position(slot.index);
+            if use_area && !in_area(options, spawn, x, z) {
+                return Ok(true);
+            }
+            if options.min_inhabited_ticks <= 0 {
+                return Ok(false);
+            }
+            let chunk = region
+                .chunk(slot)
+                .map_err(|err| format!("Chunk {}, {}: {}", x, z, err))?;
+            Ok(inhabited_time(&chunk) < options.min_inhabited_ticks)
+        };
+        let mut files: Vec<PathBuf> = fs::read_dir(folder.join("region"))
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        files.retain(|path| region_position(path).is_some());
+        files.sort();
+        for path in files {
+            if let Err(err) = prune_region(&mut stats, &path, &prune, dry_run) {
+                let file = path.file_name().unwrap().to_string_lossy().to_string();
+                stats.skipped.push(format!("{}: {}", file, err));
+            }
+        }
+        report.chunks_pruned += stats.chunks_pruned;
+        report.bytes_saved += stats.bytes_saved;
+        report.dimensions.push(stats);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gaia_core::anvil::{dimension_stats, HEADER, SECTOR};
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    const LEVEL_DAT: &[u8] = include_bytes!("testdata/nbt/level.dat");
+    const ZLIB: u8 = 2;
+    const EXTERNAL: u8 = 0x80;
+
+    // { xPos: x, zPos: z, InhabitedTime: inhabited } as zlib compressed nbt
+    fn chunk(x: i32, z: i32, inhabited: i64) -> Vec<u8> {
+        let mut bytes = vec![10, 0, 0];
+        for (name, value) in [("xPos", x), ("zPos", z)] {
+            bytes.extend([3, 0, 4]);
+            bytes.extend(name.as_bytes());
+            bytes.extend(value.to_be_bytes());
+        }
+        bytes.extend([4, 0, 13]);
+        bytes.extend(b"InhabitedTime");
+        bytes.extend(inhabited.to_be_bytes());
+        bytes.push(0);
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    // A region with the given chunks packed one after the other
+    fn region(chunks: &[(usize, u8, Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = vec![0; HEADER];
+        for (index, compression, data) in chunks {
+            let offset = bytes.len() / SECTOR;
+            bytes.extend((data.len() as u32 + 1).to_be_bytes());
+            bytes.push(*compression);
+            bytes.extend(data);
+            bytes.resize(bytes.len().div_ceil(SECTOR) * SECTOR, 0);
+            let sectors = bytes.len() / SECTOR - offset;
+            bytes[index * 4..index * 4 + 4]
+                .copy_from_slice(&((offset as u32) << 8 | sectors as u32).to_be_bytes());
+        }
+        bytes
+    }
+
+    // Spawn is at -112, 256, everything around region 0, 0 is within 2000 blocks of it
+    fn world() -> PathBuf {
+        let server_path = std::env::temp_dir().join(format!("prune-{}", uuid::Uuid::new_v4()));
+        let world = server_path.join("world");
+        for folder in ["region", "entities", "poi", "DIM-1/region"] {
+            fs::create_dir_all(world.join(folder)).unwrap();
+        }
+        fs::write(world.join("level.dat"), LEVEL_DAT).unwrap();
+        fs::write(
+            world.join("region/r.0.0.mca"),
+            region(&[
+                (0, ZLIB, chunk(0, 0, 5000)),
+                (1, ZLIB, chunk(1, 0, 0)),
+                (2, ZLIB | EXTERNAL, Vec::new()),
+                (3, ZLIB | EXTERNAL, Vec::new()),
+                (33, ZLIB, chunk(1, 1, 100)),
+            ]),
+        )
+        .unwrap();
+        fs::write(world.join("region/c.2.0.mcc"), chunk(2, 0, 10)).unwrap();
+        fs::write(world.join("region/c.3.0.mcc"), chunk(3, 0, 9000)).unwrap();
+        fs::write(
+            world.join("entities/r.0.0.mca"),
+            region(&[(0, ZLIB, chunk(0, 0, 0)), (1, ZLIB, chunk(1, 0, 0))]),
+        )
+        .unwrap();
+        fs::write(
+            world.join("poi/r.0.0.mca"),
+            region(&[(1, ZLIB, chunk(1, 0, 0))]),
+        )
+        .unwrap();
+        // lived in but far outside of the radius
+        fs::write(
+            world.join("region/r.40.40.mca"),
+            region(&[(0, ZLIB, chunk(1280, 1280, 99_999))]),
+        )
+        .unwrap();
+        // the area only applies to the overworld
+        fs::write(
+            world.join("DIM-1/region/r.40.0.mca"),
+            region(&[
+                (0, ZLIB, chunk(1280, 0, 10)),
+                (5, ZLIB, chunk(1285, 0, 99_999)),
+            ]),
+        )
+        .unwrap();
+        server_path
+    }
+
+    fn options() -> PruneOptions {
+        PruneOptions {
+            min_inhabited_ticks: 1000,
+            area: "radius".to_string(),
+            radius: 2000,
+            box_size: [0, 0],
+        }
+    }
+
+    // Every file in the world with its bytes
+    fn snapshot(server_path: &Path) -> Vec<(PathBuf, Vec<u8>)> {
+        let mut files: Vec<(PathBuf, Vec<u8>)> = walkdir::WalkDir::new(server_path)
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| (entry.path().to_path_buf(), fs::read(entry.path()).unwrap()))
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn total_size(files: &[(PathBuf, Vec<u8>)]) -> u64 {
+        files.iter().map(|(_, bytes)| bytes.len() as u64).sum()
+    }
+
+    fn slot_indexes(path: &Path) -> Vec<usize> {
+        Region::load(path)
+            .unwrap()
+            .slots()
+            .iter()
+            .map(|slot| slot.index)
+            .collect()
+    }
+
+    #[test]
+    fn dry_run_reports_without_touching_files() {
+        let server_path = world();
+        let before = snapshot(&server_path);
+        let report = prune(&server_path, "world", &options(), true).unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.chunks_pruned, 5);
+        assert!(report.bytes_saved > 0);
+        let overworld = &report.dimensions[0];
+        assert_eq!(overworld.dimension, "overworld");
+        assert_eq!((overworld.chunks_checked, overworld.chunks_pruned), (6, 4));
+        assert_eq!(overworld.regions_deleted, 1);
+        assert!(overworld.skipped.is_empty());
+        let nether = &report.dimensions[1];
+        assert_eq!((nether.chunks_checked, nether.chunks_pruned), (2, 1));
+        assert_eq!(snapshot(&server_path), before);
+        fs::remove_dir_all(server_path).unwrap();
+    }
+
+    #[test]
+    fn prunes_idle_and_distant_chunks() {
+        let server_path = world();
+        let world = server_path.join("world");
+        let dry_run = prune(&server_path, "world", &options(), true).unwrap();
+        let before = total_size(&snapshot(&server_path));
+        let report = prune(&server_path, "world", &options(), false).unwrap();
+        assert_eq!(
+            serde_json::to_string(&report.dimensions).unwrap(),
+            serde_json::to_string(&dry_run.dimensions).unwrap()
+        );
+        assert_eq!(
+            before - total_size(&snapshot(&server_path)),
+            report.bytes_saved
+        );
+
+        assert_eq!(slot_indexes(&world.join("region/r.0.0.mca")), [0, 3]);
+        assert!(!world.join("region/r.40.40.mca").exists());
+        assert_eq!(slot_indexes(&world.join("entities/r.0.0.mca")), [0]);
+        assert!(!world.join("poi/r.0.0.mca").exists());
+        assert_eq!(slot_indexes(&world.join("DIM-1/region/r.40.0.mca")), [5]);
+        // the pruned chunk's .mcc goes, the kept one's stays
+        assert!(!world.join("region/c.2.0.mcc").exists());
+        assert!(world.join("region/c.3.0.mcc").exists());
+
+        // what's left still reads
+        let region = Region::load(&world.join("region/r.0.0.mca")).unwrap();
+        let inhabited: Vec<i64> = region
+            .slots()
+            .iter()
+            .map(|slot| inhabited_time(&region.chunk(slot).unwrap()))
+            .collect();
+        assert_eq!(inhabited, [5000, 9000]);
+        let stats = dimension_stats("overworld", &world);
+        assert_eq!(stats.chunk_count, 2);
+        assert!(stats.problems.is_empty());
+        let entities = Region::load(&world.join("entities/r.0.0.mca")).unwrap();
+        for slot in entities.slots() {
+            entities.chunk(&slot).unwrap();
+        }
+
+        // a second run has nothing left to do
+        let again = prune(&server_path, "world", &options(), false).unwrap();
+        assert_eq!(again.chunks_pruned, 0);
+        fs::remove_dir_all(server_path).unwrap();
+    }
+
+    #[test]
+    fn box_area_and_missing_rules() {
+        let server_path = world();
+        let box_only = PruneOptions {
+            min_inhabited_ticks: 0,
+            area: "box".to_string(),
+            box_size: [200, 300],
+            ..options()
+        };
+        // only InhabitedTime reads chunks, the box alone leaves the nether alone
+        let report = prune(&server_path, "world", &box_only, true).unwrap();
+        assert_eq!(report.dimensions[0].chunks_pruned, 1);
+        assert_eq!(report.dimensions[1].chunks_pruned, 0);
+        let nothing = PruneOptions {
+            min_inhabited_ticks: 0,
+            area: "none".to_string(),
+            ..options()
+        };
+        assert!(prune(&server_path, "world", &nothing, true).is_err());
+        fs::remove_dir_all(server_path).unwrap();
+    }
+}
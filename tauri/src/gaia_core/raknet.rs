@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::{
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+const UNCONNECTED_PING: u8 = 0x01;
+const UNCONNECTED_PONG: u8 = 0x1c;
+// "offline message data id", every unconnected raknet packet carries it
+const MAGIC: [u8; 16] = [
+    0x00, 0xff, 0xff, 0x00, 0xfe, 0xfe, 0xfe, 0xfe, 0xfd, 0xfd, 0xfd, 0xfd, 0x12, 0x34, 0x56, 0x78,
+];
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BedrockPong {
+    pub motd: String,
+    pub protocol: u32,
+    pub version: String,
+    pub players: u32,
+    pub max_players: u32,
+    pub level_name: String,
+    pub game_mode: String,
+}
+
+fn ping_packet(time: u64, client_guid: u64) -> Vec<u8> {
+    let mut packet = vec![UNCONNECTED_PING];
+    packet.extend(time.to_be_bytes());
+    packet.extend(MAGIC);
+    packet.extend(client_guid.to_be_bytes());
+    packet
+}
+
+// The pong's server id string, "MCPE;motd;protocol;version;players;max;guid;level;gamemode;..."
+pub fn parse_pong(packet: &[u8]) -> Result<BedrockPong, String> {
+    if packet.first() != Some(&UNCONNECTED_PONG) || packet.len() < 35 {
+        return Err("Not a RakNet pong".to_string());
+    }
+    if packet[17..33] != MAGIC {
+        return Err("RakNet pong has the wrong magic".to_string());
+    }
+    let length = u16::from_be_bytes([packet[33], packet[34]]) as usize;
+    let text = packet
+        .get(35..35 + length)
+        .ok_or_else(|| "RakNet pong is cut off".to_string())?;
+    let text = String::from_utf8_lossy(text);
+    let fields: Vec<&str> = text.split(';').collect();
+    let field = |index: usize| fields.get(index).copied().unwrap_or_default();
+    if !matches!(field(0), "MCPE" | "MCEE") {
+        return Err(format!("Unknown RakNet server: {}", field(0)));
+    }
+    Ok(BedrockPong {
+        motd: field(1).to_string(),
+        protocol: field(2).parse().unwrap_or_default(),
+        version: field(3).to_string(),
+        players: field(4).parse().unwrap_or_default(),
+        max_players: field(5).parse().unwrap_or_default(),
+        level_name: field(7).to_string(),
+        game_mode: field(8).to_string(),
+    })
+}
+
+// Sends an unconnected ping, bedrock answers these without a session so it's cheap to poll
+pub fn ping(address: SocketAddr, timeout: Duration) -> Result<BedrockPong, String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|err| err.to_string())?;
+    socket
+        .set_read_timeout(Some(timeout))
+        .map_err(|err| err.to_string())?;
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    socket
+        .send_to(&ping_packet(time, client_guid()), address)
+        .map_err(|err| err.to_string())?;
+    let mut buffer = [0u8; 1500];
+    loop {
+        let (length, from) = socket
+            .recv_from(&mut buffer)
+            .map_err(|err| format!("No answer from {}: {}", address, err))?;
+        if from.port() == address.port() {
+            return parse_pong(&buffer[..length]);
+        }
+    }
+}
+
+// Random per ping, the server doesn't remember unconnected clients anyway
+fn client_guid() -> u64 {
+    let bytes = Uuid::new_v4().into_bytes();
+    u64::from_be_bytes(bytes[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER_ID: &str = "MCPE;Dedicated Server;766;1.21.50;2;10;13253860892328930865;Bedrock level;Survival;1;19132;19133;";
+
+    // A bedrock dedicated server's reply, the time echoes the ping and the guid is the server's
+    fn captured_pong() -> Vec<u8> {
+        let mut packet = vec![
+            0x1c, 0x00, 0x00, 0x01, 0x93, 0x5b, 0x2e, 0x1f, 0x40, 0xb7, 0xef, 0x5a, 0x3c, 0x11,
+            0x8e, 0x07, 0x31, 0x00, 0xff, 0xff, 0x00, 0xfe, 0xfe, 0xfe, 0xfe, 0xfd, 0xfd, 0xfd,
+            0xfd, 0x12, 0x34, 0x56, 0x78, 0x00, 0x61,
+        ];
+        packet.extend(SERVER_ID.as_bytes());
+        packet
+    }
+
+    #[test]
+    fn parses_a_captured_pong() {
+        assert_eq!(captured_pong().len(), 35 + SERVER_ID.len());
+        let pong = parse_pong(&captured_pong()).unwrap();
+        assert_eq!(pong.motd, "Dedicated Server");
+        assert_eq!(pong.protocol, 766);
+        assert_eq!(pong.version, "1.21.50");
+        assert_eq!((pong.players, pong.max_players), (2, 10));
+        assert_eq!(pong.level_name, "Bedrock level");
+        assert_eq!(pong.game_mode, "Survival");
+    }
+
+    #[test]
+    fn rejects_truncated_pongs() {
+        let packet = captured_pong();
+        assert_eq!(parse_pong(&packet[..20]).unwrap_err(), "Not a RakNet pong");
+        assert_eq!(
+            parse_pong(&packet[..60]).unwrap_err(),
+            "RakNet pong is cut off"
+        );
+        assert!(parse_pong(&[]).is_err());
+    }
+
+    #[test]
+    fn rejects_other_packets() {
+        let mut packet = captured_pong();
+        packet[18] = 0x00;
+        assert_eq!(
+            parse_pong(&packet).unwrap_err(),
+            "RakNet pong has the wrong magic"
+        );
+        let mut packet = captured_pong();
+        packet[0] = UNCONNECTED_PING;
+        assert_eq!(parse_pong(&packet).unwrap_err(), "Not a RakNet pong");
+        let mut packet = captured_pong();
+        packet[35..39].copy_from_slice(b"JAVA");
+        assert!(parse_pong(&packet).is_err());
+        // education edition answers the same way
+        let mut packet = captured_pong();
+        packet[35..39].copy_from_slice(b"MCEE");
+        assert!(parse_pong(&packet).is_ok());
+    }
+}
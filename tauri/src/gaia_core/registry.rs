@@ -0,0 +1,192 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use uuid::Uuid;
+
+use crate::gaia_core::{
+    core_structs::{Config, ServerStruct},
+    groups::ServerGroup,
+};
+
+// Every server gets its own <id>.toml in here
+fn servers_dir(app_dir: &Path) -> PathBuf {
+    app_dir.join("data").join("servers")
+}
+
+// Groups live next to the servers, one <id>.toml each
+fn groups_dir(app_dir: &Path) -> PathBuf {
+    app_dir.join("data").join("groups")
+}
+
+pub fn load_config(app_dir: &Path) -> Config {
+    fs::read_to_string(app_dir.join("config.toml"))
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// Reads every server file, a broken file is skipped instead of hiding every other server
+pub fn load_servers(app_dir: &Path) -> Vec<ServerStruct> {
+    let Ok(entries) = fs::read_dir(servers_dir(app_dir)) else {
+        return Vec::new();
+    };
+    let mut servers: Vec<ServerStruct> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            toml::from_str::<ServerStruct>(&content)
+                .map_err(|err| println!("Failed to load {:?}: {}", path, err))
+                .ok()
+        })
+        .collect();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+    servers
+}
+
+pub fn save_server(app_dir: &Path, server: &ServerStruct) -> Result<(), String> {
+    let dir = servers_dir(app_dir);
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    let content = toml::to_string(server).map_err(|err| err.to_string())?;
+    fs::write(dir.join(format!("{}.toml", server.id)), content).map_err(|err| err.to_string())
+}
+
+pub fn load_groups(app_dir: &Path) -> Vec<ServerGroup> {
+    let Ok(entries) = fs::read_dir(groups_dir(app_dir)) else {
+        return Vec::new();
+    };
+    let mut groups: Vec<ServerGroup> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            toml::from_str::<ServerGroup>(&content)
+                .map_err(|err| println!("Failed to load {:?}: {}", path, err))
+                .ok()
+        })
+        .collect();
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    groups
+}
+
+fn save_group(app_dir: &Path, group: &ServerGroup) -> Result<(), String> {
+    let dir = groups_dir(app_dir);
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    let content = toml::to_string(group).map_err(|err| err.to_string())?;
+    fs::write(dir.join(format!("{}.toml", group.id)), content).map_err(|err| err.to_string())
+}
+
+// Servers and groups known to the app, loaded once on plugin setup
+#[derive(Default)]
+pub struct Registry {
+    servers: Mutex<Vec<ServerStruct>>,
+    groups: Mutex<Vec<ServerGroup>>,
+}
+
+impl Registry {
+    pub fn load(app_dir: &Path) -> Registry {
+        Registry {
+            servers: Mutex::new(load_servers(app_dir)),
+            groups: Mutex::new(load_groups(app_dir)),
+        }
+    }
+
+    pub fn servers(&self) -> Vec<ServerStruct> {
+        self.servers.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<ServerStruct> {
+        self.servers
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|server| server.id == id)
+            .cloned()
+    }
+
+    // Changes a server and writes it to disk, returns the changed server
+    pub fn update(
+        &self,
+        app_dir: &Path,
+        id: Uuid,
+        change: impl FnOnce(&mut ServerStruct),
+    ) -> Result<ServerStruct, String> {
+        let mut servers = self.servers.lock().unwrap();
+        let server = servers
+            .iter_mut()
+            .find(|server| server.id == id)
+            .ok_or_else(|| "Server not found".to_string())?;
+        let mut changed = server.clone();
+        change(&mut changed);
+        save_server(app_dir, &changed)?;
+        *server = changed.clone();
+        Ok(changed)
+    }
+
+    // Writes the server to disk and adds it, or replaces the one with the same id
+    pub fn save(&self, app_dir: &Path, server: ServerStruct) -> Result<(), String> {
+        save_server(app_dir, &server)?;
+        let mut servers = self.servers.lock().unwrap();
+        match servers.iter_mut().find(|existing| existing.id == server.id) {
+            Some(existing) => *existing = server,
+            None => servers.push(server),
+        }
+        Ok(())
+    }
+
+    pub fn groups(&self) -> Vec<ServerGroup> {
+        self.groups.lock().unwrap().clone()
+    }
+
+    pub fn get_group(&self, id: Uuid) -> Option<ServerGroup> {
+        self.groups
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|group| group.id == id)
+            .cloned()
+    }
+
+    // A server is only ever in one group, saving a group takes its members out of the others
+    pub fn save_group(&self, app_dir: &Path, group: ServerGroup) -> Result<(), String> {
+        let mut groups = self.groups.lock().unwrap();
+        for other in groups.iter_mut().filter(|other| other.id != group.id) {
+            if other
+                .members
+                .iter()
+                .any(|member| group.members.contains(member))
+            {
+                other
+                    .members
+                    .retain(|member| !group.members.contains(member));
+                save_group(app_dir, other)?;
+            }
+        }
+        save_group(app_dir, &group)?;
+        match groups.iter_mut().find(|existing| existing.id == group.id) {
+            Some(existing) => *existing = group,
+            None => groups.push(group),
+        }
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    }
+
+    pub fn delete_group(&self, app_dir: &Path, id: Uuid) -> Result<(), String> {
+        let path = groups_dir(app_dir).join(format!("{}.toml", id));
+        if path.exists() {
+            fs::remove_file(&path).map_err(|err| err.to_string())?;
+        }
+        self.groups.lock().unwrap().retain(|group| group.id != id);
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::Path,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::gaia_core::{registry::Registry, supervisor::Supervisor};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+// 10 minutes of samples
+const HISTORY_LENGTH: usize = 300;
+// walking a big world folder is slow, its size barely changes between samples anyway
+const DISK_INTERVAL: Duration = Duration::from_secs(60);
+// /proc reports cpu time in USER_HZ, which is 100 on every platform linux runs on
+const CLOCK_TICKS_PER_SECOND: f64 = 100.0;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ResourceSample {
+    pub time: u64,
+    // share of the whole machine, 100 is every core busy
+    pub cpu_percent: f64,
+    pub memory_bytes: u64,
+    pub threads: u64,
+    pub disk_bytes: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ResourcePayload {
+    pub id: Uuid,
+    pub sample: ResourceSample,
+}
+
+struct ProcessStat {
+    parent: u32,
+    cpu_ticks: u64,
+    threads: u64,
+}
+
+fn read_stat(pid: u32) -> Option<ProcessStat> {
+    parse_stat(&fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+}
+
+fn parse_stat(content: &str) -> Option<ProcessStat> {
+    // the process name is in parentheses and can contain spaces, the fields come after it
+    let (_, fields) = content.rsplit_once(')')?;
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    let field = |index: usize| fields.get(index)?.parse::<u64>().ok();
+    Some(ProcessStat {
+        parent: field(1)? as u32,
+        cpu_ticks: field(11)? + field(12)?,
+        threads: field(17)?,
+    })
+}
+
+fn read_resident_bytes(pid: u32) -> Option<u64> {
+    parse_resident_bytes(&fs::read_to_string(format!("/proc/{}/status", pid)).ok()?)
+}
+
+fn parse_resident_bytes(content: &str) -> Option<u64> {
+    let line = content.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kilobytes * 1024)
+}
+
+// The process and everything it started, start scripts run java as a child
+fn process_tree(root: u32) -> Vec<u32> {
+    let mut parents: Vec<(u32, u32)> = Vec::new();
+    if let Ok(entries) = fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse().ok())
+            else {
+                continue;
+            };
+            if let Some(stat) = read_stat(pid) {
+                parents.push((pid, stat.parent));
+            }
+        }
+    }
+    let mut tree = vec![root];
+    let mut index = 0;
+    while index < tree.len() {
+        let parent = tree[index];
+        tree.extend(
+            parents
+                .iter()
+                .filter(|(_, process_parent)| *process_parent == parent)
+                .map(|(pid, _)| *pid),
+        );
+        index += 1;
+    }
+    tree
+}
+
+fn directory_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+struct TrackedServer {
+    pid: u32,
+    cpu_ticks: u64,
+    sampled_at: Instant,
+    disk_bytes: u64,
+    disk_sampled_at: Option<Instant>,
+    history: VecDeque<ResourceSample>,
+}
+
+#[derive(Default)]
+pub struct ResourceMonitor {
+    servers: Mutex<HashMap<Uuid, TrackedServer>>,
+}
+
+impl ResourceMonitor {
+    pub fn history(&self, id: Uuid) -> Vec<ResourceSample> {
+        self.servers
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|server| server.history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    // Forgets servers that aren't running anymore
+    fn retain(&self, running: &[(Uuid, u32)]) {
+        self.servers
+            .lock()
+            .unwrap()
+            .retain(|id, server| running.contains(&(*id, server.pid)));
+    }
+
+    // Reads the process from /proc, none when it's gone or this isn't linux
+    fn sample(&self, id: Uuid, pid: u32, server_path: &Path) -> Option<ResourceSample> {
+        let tree = process_tree(pid);
+        let stats: Vec<ProcessStat> = tree.iter().filter_map(|pid| read_stat(*pid)).collect();
+        if stats.is_empty() {
+            return None;
+        }
+        let cpu_ticks = stats.iter().map(|stat| stat.cpu_ticks).sum();
+        let threads = stats.iter().map(|stat| stat.threads).sum();
+        let memory_bytes = tree
+            .iter()
+            .filter_map(|pid| read_resident_bytes(*pid))
+            .sum();
+        let now = Instant::now();
+        // the folder is walked without holding the lock, history() is called from commands
+        let disk_due = self.servers.lock().unwrap().get(&id).is_none_or(|server| {
+            server
+                .disk_sampled_at
+                .is_none_or(|sampled_at| now.duration_since(sampled_at) >= DISK_INTERVAL)
+        });
+        let disk_bytes = disk_due.then(|| directory_size(server_path));
+
+        let mut servers = self.servers.lock().unwrap();
+        let server = servers.entry(id).or_insert_with(|| TrackedServer {
+            pid,
+            cpu_ticks,
+            sampled_at: now,
+            disk_bytes: 0,
+            disk_sampled_at: None,
+            history: VecDeque::new(),
+        });
+        let elapsed = now.duration_since(server.sampled_at).as_secs_f64();
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get()) as f64;
+        let cpu_percent = match elapsed > 0.0 {
+            true => {
+                let used =
+                    cpu_ticks.saturating_sub(server.cpu_ticks) as f64 / CLOCK_TICKS_PER_SECOND;
+                (used / elapsed / cores * 100.0).min(100.0)
+            }
+            false => 0.0,
+        };
+        server.cpu_ticks = cpu_ticks;
+        server.sampled_at = now;
+        if let Some(disk_bytes) = disk_bytes {
+            server.disk_bytes = disk_bytes;
+            server.disk_sampled_at = Some(now);
+        }
+        let sample = ResourceSample {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or(0),
+            cpu_percent,
+            memory_bytes,
+            threads,
+            disk_bytes: server.disk_bytes,
+        };
+        if server.history.len() >= HISTORY_LENGTH {
+            server.history.pop_front();
+        }
+        server.history.push_back(sample.clone());
+        Some(sample)
+    }
+}
+
+// Samples every running server for as long as the app runs
+pub fn spawn<R: Runtime>(app: AppHandle<R>) {
+    thread::spawn(move || loop {
+        thread::sleep(SAMPLE_INTERVAL);
+        let running = app.state::<Supervisor>().pids();
+        let monitor = app.state::<ResourceMonitor>();
+        monitor.retain(&running);
+        for (id, pid) in running {
+            let Some(server) = app.state::<Registry>().get(id) else {
+                continue;
+            };
+            if let Some(sample) = monitor.sample(id, pid, Path::new(&server.path)) {
+                let _ = app.emit("server_resources", ResourcePayload { id, sample });
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stat_with_spaces_and_parentheses_in_the_name() {
+        let content = "4242 (java (server) x) S 4200 4242 4200 0 -1 4194560 81234 0 12 0 \
+            1500 250 3 1 20 0 57 0 123456 8000000000 250000 18446744073709551615 1 1 0 0 0 0 0 \
+            2 16384 0 0 0 17 3 0 0 0 0 0\n";
+        let stat = parse_stat(content).unwrap();
+        assert_eq!(stat.parent, 4200);
+        assert_eq!(stat.cpu_ticks, 1750);
+        assert_eq!(stat.threads, 57);
+    }
+
+    #[test]
+    fn rejects_truncated_stat() {
+        assert!(parse_stat("").is_none());
+        assert!(parse_stat("4242 (java) S 4200 4242").is_none());
+        assert!(parse_stat("4242 java S 4200").is_none());
+    }
+
+    #[test]
+    fn parses_resident_memory_from_status() {
+        let content = "Name:\tjava\nState:\tS (sleeping)\nVmPeak:\t 9000000 kB\n\
+            VmRSS:\t  2048000 kB\nThreads:\t57\n";
+        assert_eq!(parse_resident_bytes(content), Some(2048000 * 1024));
+        // kernel threads have no memory of their own
+        assert_eq!(parse_resident_bytes("Name:\tkthreadd\nThreads:\t1\n"), None);
+    }
+
+    #[test]
+    fn disk_size_is_only_walked_once_per_interval() {
+        let dir = std::env::temp_dir().join(format!("gaia-resources-{}", Uuid::new_v4()));
+        fs::create_dir_all(dir.join("world/region")).unwrap();
+        fs::write(dir.join("world/region/r.0.0.mca"), vec![0; 4096]).unwrap();
+        fs::write(dir.join("server.properties"), "motd=hi\n").unwrap();
+        assert_eq!(directory_size(&dir), 4096 + 8);
+
+        let monitor = ResourceMonitor::default();
+        let pid = std::process::id();
+        let Some(first) = monitor.sample(Uuid::nil(), pid, &dir) else {
+            // not linux, nothing to read
+            fs::remove_dir_all(&dir).unwrap();
+            return;
+        };
+        assert_eq!(first.disk_bytes, 4096 + 8);
+        assert!(first.threads >= 1);
+        fs::write(dir.join("world/region/r.1.0.mca"), vec![0; 4096]).unwrap();
+        let second = monitor.sample(Uuid::nil(), pid, &dir).unwrap();
+        assert_eq!(second.disk_bytes, 4096 + 8);
+        assert_eq!(monitor.history(Uuid::nil()).len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,263 @@
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use uuid::Uuid;
+
+use crate::gaia_core::{core_structs::ServerStruct, supervisor::Supervisor};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Serialize)]
+pub struct CountdownPayload {
+    pub id: Uuid,
+    // 0 once the countdown finished or was cancelled
+    pub remaining_seconds: u64,
+}
+
+// Running countdowns by server, each one gets a new token so an old countdown that was
+// cancelled and replaced can tell it's no longer the current one
+#[derive(Default)]
+pub struct RestartCountdowns {
+    active: Mutex<HashMap<Uuid, u64>>,
+    next_token: Mutex<u64>,
+}
+
+impl RestartCountdowns {
+    fn begin(&self, id: Uuid) -> u64 {
+        let mut next_token = self.next_token.lock().unwrap();
+        *next_token += 1;
+        self.active.lock().unwrap().insert(id, *next_token);
+        *next_token
+    }
+
+    fn is_current(&self, id: Uuid, token: u64) -> bool {
+        self.active.lock().unwrap().get(&id) == Some(&token)
+    }
+
+    fn finish(&self, id: Uuid, token: u64) {
+        let mut active = self.active.lock().unwrap();
+        if active.get(&id) == Some(&token) {
+            active.remove(&id);
+        }
+    }
+
+    // Returns false when there was no countdown to cancel
+    pub fn cancel<R: Runtime>(&self, app: &AppHandle<R>, id: Uuid) -> bool {
+        let cancelled = self.active.lock().unwrap().remove(&id).is_some();
+        if cancelled {
+            emit_remaining(app, id, 0);
+        }
+        cancelled
+    }
+}
+
+// Parses "10m, 5m, 1m, 30s, 10s" into seconds, longest first. Plain numbers are seconds
+pub fn parse_steps(steps: &str) -> Result<Vec<u64>, String> {
+    let mut seconds = Vec::new();
+    for step in steps
+        .split(',')
+        .map(str::trim)
+        .filter(|step| !step.is_empty())
+    {
+        let (number, unit) = match step.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+            Some((index, _)) => step.split_at(index),
+            None => (step, "s"),
+        };
+        let number = number
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid countdown step: {}", step))?;
+        let multiplier = match unit.trim() {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            _ => return Err(format!("Invalid countdown step: {}", step)),
+        };
+        if number > 0 {
+            seconds.push(number * multiplier);
+        }
+    }
+    seconds.sort_unstable_by(|a, b| b.cmp(a));
+    seconds.dedup();
+    Ok(seconds)
+}
+
+// "5 minutes", "1 minute", "30 seconds"
+pub fn format_remaining(seconds: u64) -> String {
+    let (amount, unit) = match seconds {
+        seconds if seconds >= 3600 && seconds % 3600 == 0 => (seconds / 3600, "hour"),
+        seconds if seconds >= 60 && seconds % 60 == 0 => (seconds / 60, "minute"),
+        seconds => (seconds, "second"),
+    };
+    match amount {
+        1 => format!("1 {}", unit),
+        amount => format!("{} {}s", amount, unit),
+    }
+}
+
+// The console command that warns players, {time} in the message is replaced with what's left
+pub fn warning_command(server: &ServerStruct, seconds: u64) -> String {
+    broadcast_command(
+        server,
+        &server
+            .restart_countdown_message
+            .replace("{time}", &format_remaining(seconds)),
+    )
+}
+
+// Sends a message to everyone online the way the server's countdown is set up to
+pub fn broadcast_command(server: &ServerStruct, message: &str) -> String {
+    if server.restart_countdown_format != "tellraw" {
+        // a line break would end the command and run the rest as another one
+        return format!("say {}", message.replace(['\r', '\n'], " "));
+    }
+    // messages that already are a json text component are sent as they are
+    let trimmed = message.trim();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        return format!("tellraw @a {}", trimmed);
+    }
+    let component = serde_json::json!({ "text": message, "color": "gold" });
+    format!("tellraw @a {}", component)
+}
+
+fn emit_remaining<R: Runtime>(app: &AppHandle<R>, id: Uuid, remaining_seconds: u64) {
+    let _ = app.emit(
+        "server_countdown",
+        CountdownPayload {
+            id,
+            remaining_seconds,
+        },
+    );
+}
+
+// Warns players at every step of the server's countdown and restarts it at zero.
+// Returns false if the countdown was cancelled or the server went down on its own
+pub fn run<R: Runtime>(app: &AppHandle<R>, server: &ServerStruct) -> Result<bool, String> {
+    let steps = parse_steps(&server.restart_countdown)?;
+    let supervisor = app.state::<Supervisor>();
+    let countdowns = app.state::<RestartCountdowns>();
+    let token = countdowns.begin(server.id);
+    let total = steps.first().copied().unwrap_or(0);
+    let started = Instant::now();
+    let mut pending = steps.into_iter().peekable();
+    loop {
+        if !countdowns.is_current(server.id, token) {
+            return Ok(false);
+        }
+        if !supervisor.is_running(server.id) {
+            countdowns.cancel(app, server.id);
+            return Ok(false);
+        }
+        let remaining = total.saturating_sub(started.elapsed().as_secs());
+        if remaining == 0 {
+            break;
+        }
+        emit_remaining(app, server.id, remaining);
+        // a step is sent once its time came, even if a slow tick skipped past it
+        let mut warning = None;
+        while pending.peek().is_some_and(|step| *step >= remaining) {
+            warning = pending.next();
+        }
+        if let Some(step) = warning {
+            if let Err(err) = supervisor.send_command(server.id, &warning_command(server, step)) {
+                countdowns.cancel(app, server.id);
+                return Err(err);
+            }
+        }
+        thread::sleep(TICK_INTERVAL);
+    }
+    countdowns.finish(server.id, token);
+    emit_remaining(app, server.id, 0);
+    supervisor.restart(app, server.clone())?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(format: &str) -> ServerStruct {
+        ServerStruct {
+            restart_countdown_format: format.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_steps_longest_first() {
+        assert_eq!(
+            parse_steps("10m, 5m, 1m, 30s, 10s, 5s").unwrap(),
+            vec![600, 300, 60, 30, 10, 5]
+        );
+        assert_eq!(parse_steps("1h,90,2m").unwrap(), vec![3600, 120, 90]);
+        assert_eq!(parse_steps(" 5 s , 1 m ").unwrap(), vec![60, 5]);
+    }
+
+    #[test]
+    fn sorts_and_drops_duplicate_and_zero_steps() {
+        assert_eq!(parse_steps("10s, 1m, 60s, 0s, 10").unwrap(), vec![60, 10]);
+        assert_eq!(parse_steps("").unwrap(), Vec::<u64>::new());
+        assert_eq!(parse_steps(" , ,").unwrap(), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn rejects_invalid_steps() {
+        for steps in ["5x", "m", "-5s", "1.5m", "10m, soon", "5mm"] {
+            assert!(parse_steps(steps).is_err(), "{}", steps);
+        }
+    }
+
+    #[test]
+    fn formats_remaining_time() {
+        assert_eq!(format_remaining(1), "1 second");
+        assert_eq!(format_remaining(30), "30 seconds");
+        assert_eq!(format_remaining(60), "1 minute");
+        assert_eq!(format_remaining(90), "90 seconds");
+        assert_eq!(format_remaining(600), "10 minutes");
+        assert_eq!(format_remaining(3600), "1 hour");
+        assert_eq!(format_remaining(5400), "90 minutes");
+        assert_eq!(format_remaining(7200), "2 hours");
+    }
+
+    #[test]
+    fn say_sends_the_message_as_is_on_one_line() {
+        assert_eq!(
+            broadcast_command(&server("say"), r#"Restarting in "5" \o/"#),
+            r#"say Restarting in "5" \o/"#
+        );
+        assert_eq!(
+            broadcast_command(&server("say"), "bye\nop Steve"),
+            "say bye op Steve"
+        );
+    }
+
+    #[test]
+    fn tellraw_escapes_quotes_and_backslashes() {
+        assert_eq!(
+            broadcast_command(&server("tellraw"), r#"Restarting in "5" \o/"#),
+            r#"tellraw @a {"color":"gold","text":"Restarting in \"5\" \\o/"}"#
+        );
+        assert_eq!(
+            broadcast_command(&server("tellraw"), "bye\nop Steve"),
+            r#"tellraw @a {"color":"gold","text":"bye\nop Steve"}"#
+        );
+        // already a text component
+        assert_eq!(
+            broadcast_command(&server("tellraw"), r#" {"text":"hi"} "#),
+            r#"tellraw @a {"text":"hi"}"#
+        );
+    }
+
+    #[test]
+    fn warnings_fill_in_the_time() {
+        let server = ServerStruct {
+            restart_countdown_message: "Restart in {time}!".to_string(),
+            ..server("say")
+        };
+        assert_eq!(warning_command(&server, 300), "say Restart in 5 minutes!");
+    }
+}
@@ -0,0 +1,259 @@
+use serde::Serialize;
+use std::time::{Duration, SystemTime};
+
+use crate::gaia_core::core_structs::ServerStruct;
+
+// Backoff never waits longer than this between restarts
+pub const MAX_RESTART_DELAY: Duration = Duration::from_secs(600);
+
+// Restart bookkeeping for one server, reset whenever the user starts it by hand
+#[derive(Clone, Debug, Default)]
+pub struct RestartHistory {
+    // when each automatic restart in the current window happened
+    pub restarts: Vec<SystemTime>,
+    // crashes in a row that happened within crash_loop_seconds of starting
+    pub quick_crashes: u32,
+    pub gave_up: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RestartDecision {
+    DontRestart,
+    RestartAfter(Duration),
+    GiveUp(String),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RestartPayload {
+    pub id: uuid::Uuid,
+    // "waiting", "gave_up" or "idle"
+    pub state: String,
+    pub delay_seconds: u64,
+    pub attempt: u32,
+    pub max_restarts: u32,
+    pub reason: String,
+}
+
+pub fn backoff_delay(server: &ServerStruct, attempt: u32) -> Duration {
+    let base = Duration::from_secs(server.restart_backoff_seconds);
+    base.saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_RESTART_DELAY)
+}
+
+// Decides what to do after the server exited on its own (not stopped by the user)
+pub fn decide(
+    server: &ServerStruct,
+    history: &mut RestartHistory,
+    crashed: bool,
+    uptime: Duration,
+    now: SystemTime,
+) -> RestartDecision {
+    let wanted = match server.restart_policy.as_str() {
+        "always" => true,
+        "on-crash" => crashed,
+        _ => false,
+    };
+    if !wanted || history.gave_up {
+        return RestartDecision::DontRestart;
+    }
+    if crashed && uptime < Duration::from_secs(server.crash_loop_seconds) {
+        history.quick_crashes += 1;
+    } else {
+        history.quick_crashes = 0;
+    }
+    if server.crash_loop_limit > 0 && history.quick_crashes >= server.crash_loop_limit {
+        history.gave_up = true;
+        return RestartDecision::GiveUp(format!(
+            "Crashed {} times within {}s of starting",
+            history.quick_crashes, server.crash_loop_seconds
+        ));
+    }
+    let window = Duration::from_secs(server.restart_window_seconds);
+    history.restarts.retain(|restart| {
+        now.duration_since(*restart)
+            .map(|age| age < window)
+            .unwrap_or(true)
+    });
+    if history.restarts.len() as u32 >= server.max_restarts {
+        history.gave_up = true;
+        return RestartDecision::GiveUp(format!(
+            "Restarted {} times in the last {}s",
+            history.restarts.len(),
+            server.restart_window_seconds
+        ));
+    }
+    let delay = backoff_delay(server, history.restarts.len() as u32);
+    history.restarts.push(now);
+    RestartDecision::RestartAfter(delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(policy: &str) -> ServerStruct {
+        ServerStruct {
+            restart_policy: policy.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    const LONG_UPTIME: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn never_is_the_default() {
+        let server = ServerStruct::default();
+        let mut history = RestartHistory::default();
+        assert_eq!(server.restart_policy, "never");
+        assert_eq!(
+            decide(&server, &mut history, true, LONG_UPTIME, at(0)),
+            RestartDecision::DontRestart
+        );
+        assert!(history.restarts.is_empty());
+    }
+
+    #[test]
+    fn follows_each_policy() {
+        for (policy, crashed, restarts) in [
+            ("always", true, true),
+            ("always", false, true),
+            ("on-crash", true, true),
+            ("on-crash", false, false),
+            ("never", true, false),
+            ("something else", true, false),
+        ] {
+            let decision = decide(
+                &server(policy),
+                &mut RestartHistory::default(),
+                crashed,
+                LONG_UPTIME,
+                at(0),
+            );
+            assert_eq!(
+                matches!(decision, RestartDecision::RestartAfter(_)),
+                restarts,
+                "{} crashed={}",
+                policy,
+                crashed
+            );
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let server = ServerStruct {
+            max_restarts: 20,
+            ..server("always")
+        };
+        let mut history = RestartHistory::default();
+        let delays: Vec<u64> = (0..8)
+            .map(
+                |i| match decide(&server, &mut history, false, LONG_UPTIME, at(i)) {
+                    RestartDecision::RestartAfter(delay) => delay.as_secs(),
+                    other => panic!("{:?}", other),
+                },
+            )
+            .collect();
+        assert_eq!(delays, vec![10, 20, 40, 80, 160, 320, 600, 600]);
+        assert_eq!(backoff_delay(&server, u32::MAX), MAX_RESTART_DELAY);
+    }
+
+    #[test]
+    fn gives_up_after_max_restarts_in_the_window() {
+        let server = ServerStruct {
+            max_restarts: 2,
+            restart_window_seconds: 100,
+            ..server("always")
+        };
+        let mut history = RestartHistory::default();
+        assert!(matches!(
+            decide(&server, &mut history, false, LONG_UPTIME, at(0)),
+            RestartDecision::RestartAfter(_)
+        ));
+        assert!(matches!(
+            decide(&server, &mut history, false, LONG_UPTIME, at(50)),
+            RestartDecision::RestartAfter(_)
+        ));
+        assert!(matches!(
+            decide(&server, &mut history, false, LONG_UPTIME, at(99)),
+            RestartDecision::GiveUp(_)
+        ));
+        // stays given up until the history is reset
+        assert_eq!(
+            decide(&server, &mut history, false, LONG_UPTIME, at(1000)),
+            RestartDecision::DontRestart
+        );
+    }
+
+    #[test]
+    fn old_restarts_drop_out_of_the_window() {
+        let server = ServerStruct {
+            max_restarts: 2,
+            restart_window_seconds: 100,
+            ..server("always")
+        };
+        let mut history = RestartHistory::default();
+        decide(&server, &mut history, false, LONG_UPTIME, at(0));
+        decide(&server, &mut history, false, LONG_UPTIME, at(50));
+        // the restart at 0 is exactly one window old and no longer counts
+        assert_eq!(
+            decide(&server, &mut history, false, LONG_UPTIME, at(100)),
+            RestartDecision::RestartAfter(Duration::from_secs(20))
+        );
+        assert_eq!(history.restarts, vec![at(50), at(100)]);
+    }
+
+    #[test]
+    fn gives_up_on_a_crash_loop() {
+        let server = ServerStruct {
+            crash_loop_seconds: 60,
+            crash_loop_limit: 3,
+            ..server("on-crash")
+        };
+        let quick = Duration::from_secs(59);
+        let mut history = RestartHistory::default();
+        decide(&server, &mut history, true, quick, at(0));
+        decide(&server, &mut history, true, quick, at(1));
+        assert_eq!(history.quick_crashes, 2);
+        assert!(matches!(
+            decide(&server, &mut history, true, quick, at(2)),
+            RestartDecision::GiveUp(_)
+        ));
+        assert!(history.gave_up);
+    }
+
+    #[test]
+    fn a_long_run_resets_the_crash_loop() {
+        let server = server("on-crash");
+        let mut history = RestartHistory::default();
+        decide(&server, &mut history, true, Duration::from_secs(5), at(0));
+        decide(&server, &mut history, true, Duration::from_secs(5), at(1));
+        // reaching the crash loop window counts as a normal crash
+        decide(&server, &mut history, true, Duration::from_secs(60), at(2));
+        assert_eq!(history.quick_crashes, 0);
+        assert!(matches!(
+            decide(&server, &mut history, true, Duration::from_secs(5), at(3)),
+            RestartDecision::RestartAfter(_)
+        ));
+    }
+
+    #[test]
+    fn a_zero_crash_loop_limit_never_gives_up_on_quick_crashes() {
+        let server = ServerStruct {
+            crash_loop_limit: 0,
+            max_restarts: 100,
+            ..server("on-crash")
+        };
+        let mut history = RestartHistory::default();
+        for i in 0..10 {
+            assert!(matches!(
+                decide(&server, &mut history, true, Duration::ZERO, at(i)),
+                RestartDecision::RestartAfter(_)
+            ));
+        }
+    }
+}
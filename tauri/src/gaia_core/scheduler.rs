@@ -0,0 +1,530 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use uuid::Uuid;
+
+use crate::gaia_core::{
+    backup::BackupOptions, backup_server, core_structs::ServerStruct, cron::CronSchedule,
+    registry::Registry, restart_countdown, supervisor::Supervisor,
+};
+
+// How often the scheduler checks for due tasks
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TaskStep {
+    // "start", "stop", "restart", "countdown_restart", "backup", "command", "broadcast" or "wait"
+    pub action: String,
+    // the command, message, or seconds for a wait
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TaskRun {
+    pub time: String,
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScheduledTask {
+    pub id: Uuid,
+    pub name: String,
+    pub enabled: bool,
+    // "cron" or "interval"
+    pub schedule: String,
+    pub cron: String,
+    pub interval_seconds: u64,
+    // run in order, a single step for simple tasks
+    pub steps: Vec<TaskStep>,
+    pub last_run: Option<TaskRun>,
+}
+
+impl Default for ScheduledTask {
+    fn default() -> ScheduledTask {
+        ScheduledTask {
+            id: Uuid::nil(),
+            name: "".to_string(),
+            enabled: true,
+            schedule: "cron".to_string(),
+            cron: "0 4 * * *".to_string(),
+            interval_seconds: 3600,
+            steps: Vec::new(),
+            last_run: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct TaskRunPayload {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub run: TaskRun,
+}
+
+// Format of TaskRun::time, local time
+const RUN_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub trait Clock<Tz: TimeZone = Local>: Send + Sync {
+    fn now(&self) -> DateTime<Tz>;
+}
+
+// Wall clock in the computer's timezone, cron expressions are written in local time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+pub fn validate_task(task: &ScheduledTask) -> Result<(), String> {
+    match task.schedule.as_str() {
+        "cron" => {
+            CronSchedule::parse(&task.cron)?;
+        }
+        "interval" if task.interval_seconds == 0 => {
+            return Err("Interval has to be at least one second".to_string())
+        }
+        "interval" => {}
+        schedule => return Err(format!("Unknown schedule: {}", schedule)),
+    }
+    if task.steps.is_empty() {
+        return Err("Task has nothing to do".to_string());
+    }
+    for step in &task.steps {
+        match step.action.as_str() {
+            "start" | "stop" | "restart" | "countdown_restart" | "backup" => {}
+            "command" | "broadcast" if step.value.trim().is_empty() => {
+                return Err(format!("{} step needs a value", step.action))
+            }
+            "command" | "broadcast" => {}
+            "wait" if step.value.trim().parse::<u64>().is_err() => {
+                return Err(format!(
+                    "Wait needs a number of seconds, got {}",
+                    step.value
+                ))
+            }
+            "wait" => {}
+            action => return Err(format!("Unknown action: {}", action)),
+        }
+    }
+    Ok(())
+}
+
+// The first run of a task after `after`, none for broken or impossible schedules
+pub fn next_occurrence<Tz: TimeZone>(
+    task: &ScheduledTask,
+    after: &DateTime<Tz>,
+) -> Option<DateTime<Tz>> {
+    match task.schedule.as_str() {
+        "interval" if task.interval_seconds > 0 => {
+            Some(after.clone() + ChronoDuration::seconds(task.interval_seconds as i64))
+        }
+        "cron" => CronSchedule::parse(&task.cron).ok()?.next_after(after),
+        _ => None,
+    }
+}
+
+// Interval tasks count from their last run so restarting the app doesn't push them back,
+// a run that's overdue happens right away
+fn first_occurrence<Tz: TimeZone>(
+    task: &ScheduledTask,
+    now: &DateTime<Tz>,
+) -> Option<DateTime<Tz>> {
+    let last_run = task
+        .last_run
+        .as_ref()
+        .filter(|_| task.schedule == "interval")
+        .and_then(|run| NaiveDateTime::parse_from_str(&run.time, RUN_TIME_FORMAT).ok())
+        .and_then(|time| now.timezone().from_local_datetime(&time).earliest())
+        .filter(|time| time <= now);
+    next_occurrence(task, last_run.as_ref().unwrap_or(now))
+}
+
+pub struct Scheduler<Tz: TimeZone = Local> {
+    clock: Box<dyn Clock<Tz>>,
+    // when each task runs next, worked out the first time the scheduler sees it
+    next_runs: Mutex<HashMap<Uuid, DateTime<Tz>>>,
+    running: Mutex<HashSet<Uuid>>,
+}
+
+impl<Tz: TimeZone> Scheduler<Tz> {
+    pub fn new(clock: Box<dyn Clock<Tz>>) -> Scheduler<Tz> {
+        Scheduler {
+            clock,
+            next_runs: Mutex::new(HashMap::new()),
+            running: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn now(&self) -> DateTime<Tz> {
+        self.clock.now()
+    }
+
+    // Forgets the planned run, call after a task's schedule changed
+    pub fn reset(&self, task_id: Uuid) {
+        self.next_runs.lock().unwrap().remove(&task_id);
+    }
+
+    pub fn next_run(&self, task: &ScheduledTask) -> Option<DateTime<Tz>> {
+        if !task.enabled {
+            return None;
+        }
+        if let Some(next) = self.next_runs.lock().unwrap().get(&task.id) {
+            return Some(next.clone());
+        }
+        first_occurrence(task, &self.now())
+    }
+
+    // Ids of the tasks that are due now, their next run gets planned right away so a task
+    // fires once per occurrence no matter how often this is called
+    pub fn due(&self, tasks: &[ScheduledTask]) -> Vec<Uuid> {
+        let now = self.now();
+        let mut next_runs = self.next_runs.lock().unwrap();
+        next_runs.retain(|id, _| tasks.iter().any(|task| task.id == *id && task.enabled));
+        let mut due = Vec::new();
+        for task in tasks.iter().filter(|task| task.enabled) {
+            let next = match next_runs.get(&task.id) {
+                Some(next) => next.clone(),
+                None => match first_occurrence(task, &now) {
+                    Some(next) => {
+                        next_runs.insert(task.id, next);
+                        continue;
+                    }
+                    None => continue,
+                },
+            };
+            if now < next {
+                continue;
+            }
+            due.push(task.id);
+            match next_occurrence(task, &now) {
+                Some(next) => next_runs.insert(task.id, next),
+                None => next_runs.remove(&task.id),
+            };
+        }
+        due
+    }
+
+    // Marks a task as running, false when it still is from last time
+    fn begin(&self, task_id: Uuid) -> bool {
+        self.running.lock().unwrap().insert(task_id)
+    }
+
+    fn finish(&self, task_id: Uuid) {
+        self.running.lock().unwrap().remove(&task_id);
+    }
+}
+
+fn run_step<R: Runtime>(
+    app: &AppHandle<R>,
+    server: &ServerStruct,
+    step: &TaskStep,
+) -> Result<(), String> {
+    let supervisor = app.state::<Supervisor>();
+    match step.action.as_str() {
+        "start" => supervisor.start(app, server.clone()),
+        "stop" => {
+            supervisor.stop(server.id)?;
+            supervisor.wait_for_stop(server.id)
+        }
+        "restart" => supervisor.restart(app, server.clone()),
+        "countdown_restart" => match restart_countdown::run(app, server)? {
+            true => Ok(()),
+            false => Err("Restart was cancelled".to_string()),
+        },
+        "backup" => {
+            let options = BackupOptions {
+                format: server.backup_format.clone(),
+                scope: server.backup_scope.clone(),
+            };
+            backup_server(app, server, &options).map(|_| ())
+        }
+        "command" => supervisor.send_command(server.id, step.value.trim()),
+        "broadcast" => supervisor.send_command(server.id, &format!("say {}", step.value.trim())),
+        "wait" => {
+            let seconds = step
+                .value
+                .trim()
+                .parse::<u64>()
+                .map_err(|err| err.to_string())?;
+            thread::sleep(Duration::from_secs(seconds));
+            Ok(())
+        }
+        action => Err(format!("Unknown action: {}", action)),
+    }
+}
+
+// Runs every step of a task and stores how it went on the task in the registry
+pub fn run_task<R: Runtime>(
+    app: &AppHandle<R>,
+    server_id: Uuid,
+    task_id: Uuid,
+) -> Result<TaskRun, String> {
+    let scheduler = app.state::<Scheduler>();
+    let registry = app.state::<Registry>();
+    let server = registry
+        .get(server_id)
+        .ok_or_else(|| "Server not found".to_string())?;
+    let task = server
+        .tasks
+        .iter()
+        .find(|task| task.id == task_id)
+        .cloned()
+        .ok_or_else(|| "Task not found".to_string())?;
+    if !scheduler.begin(task_id) {
+        return Err(format!("{} is still running", task.name));
+    }
+    let time = scheduler.now().format(RUN_TIME_FORMAT).to_string();
+    let result = task.steps.iter().enumerate().try_for_each(|(index, step)| {
+        run_step(app, &server, step)
+            .map_err(|err| format!("Step {} ({}) failed: {}", index + 1, step.action, err))
+    });
+    scheduler.finish(task_id);
+    let run = TaskRun {
+        time,
+        success: result.is_ok(),
+        message: result.err().unwrap_or_else(|| "Finished".to_string()),
+    };
+    let app_dir = app.path().app_data_dir().unwrap();
+    let saved = registry.update(&app_dir, server_id, |server| {
+        if let Some(task) = server.tasks.iter_mut().find(|task| task.id == task_id) {
+            task.last_run = Some(run.clone());
+        }
+    });
+    if let Err(err) = saved {
+        println!("Failed to save the result of {}: {}", task.name, err);
+    }
+    let _ = app.emit(
+        "server_task_run",
+        TaskRunPayload {
+            id: server_id,
+            task_id,
+            run: run.clone(),
+        },
+    );
+    Ok(run)
+}
+
+// Checks for due tasks every second for as long as the app runs
+pub fn spawn<R: Runtime>(app: AppHandle<R>) {
+    thread::spawn(move || loop {
+        thread::sleep(TICK_INTERVAL);
+        let servers = app.state::<Registry>().servers();
+        let tasks: Vec<ScheduledTask> = servers
+            .iter()
+            .flat_map(|server| server.tasks.iter().cloned())
+            .collect();
+        for task_id in app.state::<Scheduler>().due(&tasks) {
+            let Some(server) = servers
+                .iter()
+                .find(|server| server.tasks.iter().any(|task| task.id == task_id))
+            else {
+                continue;
+            };
+            let (app, server_id) = (app.clone(), server.id);
+            thread::spawn(move || {
+                if let Err(err) = run_task(&app, server_id, task_id) {
+                    println!("Scheduled task failed to run: {}", err);
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use chrono_tz::{Europe::Berlin, Tz};
+    use std::sync::Arc;
+
+    // A clock the test moves forward by hand
+    #[derive(Clone)]
+    struct ManualClock(Arc<Mutex<DateTime<FixedOffset>>>);
+
+    impl ManualClock {
+        fn new(time: &str) -> ManualClock {
+            ManualClock(Arc::new(Mutex::new(
+                DateTime::parse_from_rfc3339(time).unwrap(),
+            )))
+        }
+
+        fn advance(&self, seconds: i64) {
+            *self.0.lock().unwrap() += ChronoDuration::seconds(seconds);
+        }
+    }
+
+    impl Clock<FixedOffset> for ManualClock {
+        fn now(&self) -> DateTime<FixedOffset> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    // Same clock seen from Berlin, which has daylight saving time
+    impl Clock<Tz> for ManualClock {
+        fn now(&self) -> DateTime<Tz> {
+            self.0.lock().unwrap().with_timezone(&Berlin)
+        }
+    }
+
+    fn task(schedule: &str, cron: &str, interval_seconds: u64) -> ScheduledTask {
+        ScheduledTask {
+            id: Uuid::new_v4(),
+            name: "test".to_string(),
+            schedule: schedule.to_string(),
+            cron: cron.to_string(),
+            interval_seconds,
+            steps: vec![TaskStep {
+                action: "broadcast".to_string(),
+                value: "hello".to_string(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn interval_task_runs_once_per_interval() {
+        let clock = ManualClock::new("2025-10-26T12:00:00+00:00");
+        let scheduler = Scheduler::<FixedOffset>::new(Box::new(clock.clone()));
+        let tasks = vec![task("interval", "", 60)];
+        // seeing a task for the first time plans it, it doesn't run right away
+        assert!(scheduler.due(&tasks).is_empty());
+        clock.advance(59);
+        assert!(scheduler.due(&tasks).is_empty());
+        clock.advance(1);
+        assert_eq!(scheduler.due(&tasks), vec![tasks[0].id]);
+        assert!(scheduler.due(&tasks).is_empty());
+        clock.advance(60);
+        assert_eq!(scheduler.due(&tasks), vec![tasks[0].id]);
+    }
+
+    #[test]
+    fn cron_task_runs_at_the_matching_minute() {
+        let clock = ManualClock::new("2025-10-26T03:58:30+02:00");
+        let scheduler = Scheduler::<FixedOffset>::new(Box::new(clock.clone()));
+        let tasks = vec![task("cron", "0 4 * * *", 0)];
+        assert!(scheduler.due(&tasks).is_empty());
+        assert_eq!(
+            scheduler.next_run(&tasks[0]).unwrap().to_rfc3339(),
+            "2025-10-26T04:00:00+02:00"
+        );
+        clock.advance(60);
+        assert!(scheduler.due(&tasks).is_empty());
+        clock.advance(30);
+        assert_eq!(scheduler.due(&tasks), vec![tasks[0].id]);
+        clock.advance(5);
+        assert!(scheduler.due(&tasks).is_empty());
+        assert_eq!(
+            scheduler.next_run(&tasks[0]).unwrap().to_rfc3339(),
+            "2025-10-27T04:00:00+02:00"
+        );
+    }
+
+    #[test]
+    fn missed_runs_only_fire_once() {
+        let clock = ManualClock::new("2025-10-26T12:00:00+00:00");
+        let scheduler = Scheduler::<FixedOffset>::new(Box::new(clock.clone()));
+        let tasks = vec![task("cron", "*/5 * * * *", 0)];
+        scheduler.due(&tasks);
+        // the computer slept through several occurrences
+        clock.advance(3600);
+        assert_eq!(scheduler.due(&tasks).len(), 1);
+        assert!(scheduler.due(&tasks).is_empty());
+    }
+
+    #[test]
+    fn disabled_and_reset_tasks() {
+        let clock = ManualClock::new("2025-10-26T12:00:00+00:00");
+        let scheduler = Scheduler::<FixedOffset>::new(Box::new(clock.clone()));
+        let mut tasks = vec![task("interval", "", 10)];
+        scheduler.due(&tasks);
+        tasks[0].enabled = false;
+        clock.advance(10);
+        assert!(scheduler.due(&tasks).is_empty());
+        assert_eq!(scheduler.next_run(&tasks[0]), None);
+        // enabling again plans from now instead of firing for the time it was off
+        tasks[0].enabled = true;
+        assert!(scheduler.due(&tasks).is_empty());
+        // a changed schedule is planned again from the moment it was saved
+        tasks[0].interval_seconds = 100;
+        scheduler.reset(tasks[0].id);
+        assert!(scheduler.due(&tasks).is_empty());
+        clock.advance(99);
+        assert!(scheduler.due(&tasks).is_empty());
+        clock.advance(1);
+        assert_eq!(scheduler.due(&tasks), vec![tasks[0].id]);
+    }
+
+    #[test]
+    fn cron_task_keeps_local_time_over_daylight_saving() {
+        // Berlin goes from +02:00 to +01:00 at 03:00 on 2025-10-26
+        let clock = ManualClock::new("2025-10-25T04:00:30+02:00");
+        let scheduler = Scheduler::<Tz>::new(Box::new(clock.clone()));
+        let tasks = vec![task("cron", "0 4 * * *", 0)];
+        assert!(scheduler.due(&tasks).is_empty());
+        assert_eq!(
+            scheduler.next_run(&tasks[0]).unwrap().to_rfc3339(),
+            "2025-10-26T04:00:00+01:00"
+        );
+        // 24 hours later it's only 03:00:30 on the local clock
+        clock.advance(24 * 3600);
+        assert!(scheduler.due(&tasks).is_empty());
+        clock.advance(3600);
+        assert_eq!(scheduler.due(&tasks), vec![tasks[0].id]);
+    }
+
+    #[test]
+    fn interval_task_counts_from_its_last_run() {
+        let clock = ManualClock::new("2025-10-26T12:00:00+00:00");
+        let scheduler = Scheduler::<FixedOffset>::new(Box::new(clock.clone()));
+        let mut recent = task("interval", "", 3600);
+        recent.last_run = Some(TaskRun {
+            time: "2025-10-26 11:30:00".to_string(),
+            ..Default::default()
+        });
+        let mut overdue = task("interval", "", 3600);
+        overdue.last_run = Some(TaskRun {
+            time: "2025-10-26 09:00:00".to_string(),
+            ..Default::default()
+        });
+        let tasks = vec![recent, overdue];
+        assert_eq!(
+            scheduler.next_run(&tasks[0]).unwrap().to_rfc3339(),
+            "2025-10-26T12:30:00+00:00"
+        );
+        // planned in the past, so it runs on the next check
+        assert!(scheduler.due(&tasks).is_empty());
+        assert_eq!(scheduler.due(&tasks), vec![tasks[1].id]);
+        clock.advance(1800);
+        assert_eq!(scheduler.due(&tasks), vec![tasks[0].id]);
+        assert_eq!(
+            scheduler.next_run(&tasks[1]).unwrap().to_rfc3339(),
+            "2025-10-26T13:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn validation() {
+        assert!(validate_task(&task("cron", "0 4 * * *", 0)).is_ok());
+        assert!(validate_task(&task("cron", "0 25 * * *", 0)).is_err());
+        assert!(validate_task(&task("interval", "", 0)).is_err());
+        let mut wait = task("interval", "", 10);
+        wait.steps.push(TaskStep {
+            action: "wait".to_string(),
+            value: "soon".to_string(),
+        });
+        assert!(validate_task(&wait).is_err());
+        let mut empty = task("interval", "", 10);
+        empty.steps.clear();
+        assert!(validate_task(&empty).is_err());
+    }
+}
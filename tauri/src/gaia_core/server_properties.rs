@@ -0,0 +1,66 @@
+use std::{collections::HashMap, fs, path::Path};
+
+// server.properties is a java properties file, values may escape ':' '=' and '\'
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+pub fn read_properties(server_path: &Path) -> HashMap<String, String> {
+    let Ok(content) = fs::read_to_string(server_path.join("server.properties")) else {
+        return HashMap::new();
+    };
+    content
+        .lines()
+        .map(|line| line.trim_start())
+        .filter(|line| !line.starts_with('#') && !line.starts_with('!'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), unescape(value.trim())))
+        .collect()
+}
+
+// Name of the main world folder, the nether and end sit next to it on bukkit based servers
+pub fn level_name(server_path: &Path) -> String {
+    read_properties(server_path)
+        .remove("level-name")
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "world".to_string())
+}
+
+// Changes one key in place so comments and the order of the file stay, a missing key is appended
+pub fn set_property(server_path: &Path, key: &str, value: &str) -> Result<(), String> {
+    let path = server_path.join("server.properties");
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    let line = format!("{}={}", key, value.replace('\\', "\\\\"));
+    let mut found = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|existing| {
+            let trimmed = existing.trim_start();
+            let is_key = !trimmed.starts_with('#')
+                && !trimmed.starts_with('!')
+                && trimmed
+                    .split_once('=')
+                    .is_some_and(|(name, _)| name.trim() == key);
+            match is_key && !found {
+                true => {
+                    found = true;
+                    line.clone()
+                }
+                false => existing.to_string(),
+            }
+        })
+        .collect();
+    if !found {
+        lines.push(line);
+    }
+    fs::write(&path, lines.join("\n") + "\n")
+        .map_err(|err| format!("Failed to write {:?}: {}", path, err))
+}
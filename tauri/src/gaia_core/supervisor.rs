@@ -0,0 +1,588 @@
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader, Read, Write},
+    path::PathBuf,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::Mutex,
+    thread,
+    time::{Duration, SystemTime},
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use uuid::Uuid;
+
+use crate::gaia_core::{
+    bedrock,
+    core_structs::{ServerStruct, STATUS_OFFLINE, STATUS_ONLINE},
+    crash_report::{self, CrashReport},
+    geyser, mod_scanner, port_forwarding, ports, proxy,
+    registry::Registry,
+    restart_policy::{self, RestartDecision, RestartHistory, RestartPayload},
+    tick_monitor::TickMonitor,
+};
+
+// How often the exit watcher checks on the java process
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+// Big modded worlds can take a while to flush every chunk
+const SAVE_TIMEOUT: Duration = Duration::from_secs(120);
+// how often bedrock gets asked whether its save is done
+const BEDROCK_QUERY_INTERVAL: Duration = Duration::from_secs(1);
+// How long a restart waits for the server to go down
+const STOP_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Serialize)]
+pub struct ConsolePayload {
+    pub id: Uuid,
+    pub line: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct StatusPayload {
+    pub id: Uuid,
+    pub status: i8,
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OnlinePlayer {
+    pub name: String,
+    // joined from bedrock through geyser, floodgate put its prefix in front of the name
+    pub bedrock: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PlayersPayload {
+    pub id: Uuid,
+    pub count: usize,
+    // empty when only the count is known, like on bedrock servers
+    pub players: Vec<OnlinePlayer>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CrashPayload {
+    pub id: Uuid,
+    pub report: CrashReport,
+}
+
+struct ServerProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    started_at: SystemTime,
+    // set once the server logged "Done", it's accepting players from then on
+    ready: bool,
+    // bumped every time the server logs "Saved the game"
+    saves: u64,
+    // set when the exit was asked for so it isn't treated as a crash
+    stop_requested: bool,
+    // "stop", or "end" on proxies
+    stop_command: &'static str,
+    // players online, from the join and leave lines
+    players: Vec<OnlinePlayer>,
+    // floodgate's username prefix when it's installed
+    floodgate_prefix: Option<String>,
+    // bedrock saves with "save hold" and reports its player count through raknet pings
+    bedrock: bool,
+}
+
+#[derive(Default)]
+pub struct Supervisor {
+    processes: Mutex<HashMap<Uuid, ServerProcess>>,
+    crash_reports: Mutex<HashMap<Uuid, CrashReport>>,
+    restart_history: Mutex<HashMap<Uuid, RestartHistory>>,
+    // servers waiting out their backoff delay, removing one cancels its restart
+    pending_restarts: Mutex<HashSet<Uuid>>,
+}
+
+// Splits the java startup line into arguments, double quotes keep paths with spaces together
+pub fn split_command_line(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut has_arg = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+    args
+}
+
+// Servers log "Done (3.2s)!" once the world finished loading, proxies once they're listening
+fn is_done_line(line: &str) -> bool {
+    line.contains("]: Done (") || line.contains("[INFO] Listening on /")
+}
+
+// "[12:00:00 INFO]: Steve joined the game", chat can't fake it since it's prefixed with <Steve>
+fn player_event(line: &str) -> Option<(&str, bool)> {
+    let (_, message) = line.split_once("]: ")?;
+    let (name, joined) = match message.strip_suffix(" joined the game") {
+        Some(name) => (name, true),
+        None => (message.strip_suffix(" left the game")?, false),
+    };
+    // floodgate names can start with its prefix, "." unless it was changed
+    let rest = name
+        .trim_start_matches(|c: char| !c.is_ascii_alphanumeric() && c != '_' && !c.is_whitespace());
+    let valid = !rest.is_empty() && rest.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then_some((name, joined))
+}
+
+// Forwards every line of a process stream to the frontend console
+fn pipe_output<R: Runtime>(app: AppHandle<R>, id: Uuid, stream: impl Read + Send + 'static) {
+    thread::spawn(move || {
+        for line in BufReader::new(stream).split(b'\n') {
+            let Ok(line) = line else {
+                break;
+            };
+            let line = String::from_utf8_lossy(&line)
+                .trim_end_matches('\r')
+                .to_string();
+            if is_done_line(&line) {
+                app.state::<Supervisor>().mark_ready(id);
+            } else if line.contains("Saved the game")
+                || line.contains("Files are now ready to be copied")
+            {
+                app.state::<Supervisor>().mark_saved(id);
+            } else if let Some((name, joined)) = player_event(&line) {
+                app.state::<Supervisor>()
+                    .player_event(&app, id, name, joined);
+            }
+            // replies to the tick monitor's own polls stay out of the console
+            if app.state::<TickMonitor>().handle_line(&app, id, &line) {
+                continue;
+            }
+            let _ = app.emit("server_console", ConsolePayload { id, line });
+        }
+    });
+}
+
+impl Supervisor {
+    pub fn is_running(&self, id: Uuid) -> bool {
+        self.processes.lock().unwrap().contains_key(&id)
+    }
+
+    // Process ids of every running server
+    pub fn pids(&self) -> Vec<(Uuid, u32)> {
+        self.processes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, process)| (*id, process.child.id()))
+            .collect()
+    }
+
+    pub fn is_ready(&self, id: Uuid) -> bool {
+        self.processes
+            .lock()
+            .unwrap()
+            .get(&id)
+            .is_some_and(|process| process.ready)
+    }
+
+    fn is_bedrock(&self, id: Uuid) -> bool {
+        self.processes
+            .lock()
+            .unwrap()
+            .get(&id)
+            .is_some_and(|process| process.bedrock)
+    }
+
+    pub fn mark_ready(&self, id: Uuid) {
+        if let Some(process) = self.processes.lock().unwrap().get_mut(&id) {
+            process.ready = true;
+        }
+    }
+
+    fn mark_saved(&self, id: Uuid) {
+        if let Some(process) = self.processes.lock().unwrap().get_mut(&id) {
+            process.saves += 1;
+        }
+    }
+
+    fn player_event<R: Runtime>(&self, app: &AppHandle<R>, id: Uuid, name: &str, joined: bool) {
+        let players = {
+            let mut processes = self.processes.lock().unwrap();
+            let Some(process) = processes.get_mut(&id) else {
+                return;
+            };
+            process.players.retain(|player| player.name != name);
+            if joined {
+                let bedrock = geyser::is_bedrock_player(process.floodgate_prefix.as_deref(), name);
+                process.players.push(OnlinePlayer {
+                    name: name.to_string(),
+                    bedrock,
+                });
+            }
+            process.players.clone()
+        };
+        let count = players.len();
+        let _ = app.emit("server_players", PlayersPayload { id, count, players });
+    }
+
+    fn save_count(&self, id: Uuid) -> Option<u64> {
+        self.processes
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|process| process.saves)
+    }
+
+    // Turns off autosave and waits for the world to be flushed to disk so files can be copied
+    // safely, returns false when the server isn't running and there was nothing to pause
+    pub fn pause_saving(&self, id: Uuid) -> Result<bool, String> {
+        let Some(saves) = self.save_count(id) else {
+            return Ok(false);
+        };
+        let bedrock = self.is_bedrock(id);
+        match bedrock {
+            true => self.send_command(id, "save hold")?,
+            false => {
+                self.send_command(id, "save-off")?;
+                self.send_command(id, "save-all flush")?;
+            }
+        }
+        let started = SystemTime::now();
+        while self.save_count(id).is_some_and(|count| count == saves) {
+            if started.elapsed().unwrap_or_default() > SAVE_TIMEOUT {
+                self.resume_saving(id);
+                return Err("Timed out waiting for the server to save".to_string());
+            }
+            // bedrock only tells when its files are ready if it's asked
+            if bedrock {
+                let _ = self.send_command(id, "save query");
+                thread::sleep(BEDROCK_QUERY_INTERVAL);
+            } else {
+                thread::sleep(EXIT_POLL_INTERVAL);
+            }
+        }
+        if self.save_count(id).is_none() {
+            return Err("Server stopped while saving".to_string());
+        }
+        Ok(true)
+    }
+
+    pub fn resume_saving(&self, id: Uuid) {
+        let command = match self.is_bedrock(id) {
+            true => "save resume",
+            false => "save-on",
+        };
+        if let Err(err) = self.send_command(id, command) {
+            println!("Failed to turn saving back on: {}", err);
+        }
+    }
+
+    // Starting by hand forgets about earlier crashes and any restart that was pending
+    pub fn start<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        server: ServerStruct,
+    ) -> Result<(), String> {
+        self.pending_restarts.lock().unwrap().remove(&server.id);
+        self.restart_history.lock().unwrap().remove(&server.id);
+        emit_restart_state(app, &server, "idle", 0, 0, "");
+        self.spawn_process(app, server)
+    }
+
+    fn spawn_process<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        server: ServerStruct,
+    ) -> Result<(), String> {
+        if self.is_running(server.id) {
+            return Err(format!("{} is already running", server.name));
+        }
+        // its own ports would show up as taken by java while it runs, so this goes first
+        let running: Vec<ServerStruct> = app
+            .state::<Registry>()
+            .servers()
+            .into_iter()
+            .filter(|other| self.is_running(other.id))
+            .collect();
+        if let Some(conflict) = ports::find_conflicts(&server, &running, true).first() {
+            return Err(conflict.to_string());
+        }
+        let mut processes = self.processes.lock().unwrap();
+        if processes.contains_key(&server.id) {
+            return Err(format!("{} is already running", server.name));
+        }
+        let args = split_command_line(&server.java_startup_line);
+        let Some((program, args)) = args.split_first() else {
+            return Err(format!("{} has no java startup line", server.name));
+        };
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(&server.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let bedrock = bedrock::is_bedrock(&server.server_type);
+        if bedrock {
+            // bedrock_server loads the libraries shipped next to it
+            command.env("LD_LIBRARY_PATH", &server.path);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|err| format!("Failed to start {}: {}", server.name, err))?;
+        if let Some(stdout) = child.stdout.take() {
+            pipe_output(app.clone(), server.id, stdout);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            pipe_output(app.clone(), server.id, stderr);
+        }
+        let stdin = child.stdin.take();
+        processes.insert(
+            server.id,
+            ServerProcess {
+                child,
+                stdin,
+                started_at: SystemTime::now(),
+                ready: false,
+                saves: 0,
+                stop_requested: false,
+                stop_command: proxy::stop_command(&server.server_type),
+                players: Vec::new(),
+                floodgate_prefix: geyser::floodgate_prefix(&server),
+                bedrock,
+            },
+        );
+        drop(processes);
+        self.crash_reports.lock().unwrap().remove(&server.id);
+        let _ = app.emit(
+            "server_status",
+            StatusPayload {
+                id: server.id,
+                status: STATUS_ONLINE,
+                exit_code: None,
+            },
+        );
+        if server.port_forwarding {
+            let (forwarding_app, forwarding_server) = (app.clone(), server.clone());
+            thread::spawn(move || port_forwarding::open(&forwarding_app, &forwarding_server));
+        }
+        if bedrock {
+            bedrock::spawn_probe(app.clone(), server.clone());
+        }
+        let watcher_app = app.clone();
+        thread::spawn(move || watch_exit(watcher_app, server));
+        Ok(())
+    }
+
+    // Writes a line to the server's console like the user typed it
+    pub fn send_command(&self, id: Uuid, command: &str) -> Result<(), String> {
+        let mut processes = self.processes.lock().unwrap();
+        let process = processes
+            .get_mut(&id)
+            .ok_or_else(|| "Server is not running".to_string())?;
+        let stdin = process
+            .stdin
+            .as_mut()
+            .ok_or_else(|| "Server console is closed".to_string())?;
+        writeln!(stdin, "{}", command)
+            .and_then(|_| stdin.flush())
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn stop(&self, id: Uuid) -> Result<(), String> {
+        let stop_command = match self.processes.lock().unwrap().get_mut(&id) {
+            Some(process) => {
+                process.stop_requested = true;
+                process.stop_command
+            }
+            None => "stop",
+        };
+        self.send_command(id, stop_command)
+    }
+
+    pub fn kill(&self, id: Uuid) -> Result<(), String> {
+        let mut processes = self.processes.lock().unwrap();
+        let process = processes
+            .get_mut(&id)
+            .ok_or_else(|| "Server is not running".to_string())?;
+        process.stop_requested = true;
+        process.child.kill().map_err(|err| err.to_string())
+    }
+
+    pub fn wait_for_stop(&self, id: Uuid) -> Result<(), String> {
+        let started = SystemTime::now();
+        while self.is_running(id) {
+            if started.elapsed().unwrap_or_default() > STOP_TIMEOUT {
+                return Err("Server did not stop in time".to_string());
+            }
+            thread::sleep(EXIT_POLL_INTERVAL);
+        }
+        Ok(())
+    }
+
+    // Stops the server if it's up and starts it again once it's down
+    pub fn restart<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        server: ServerStruct,
+    ) -> Result<(), String> {
+        if self.is_running(server.id) {
+            self.stop(server.id)?;
+            self.wait_for_stop(server.id)?;
+        }
+        self.start(app, server)
+    }
+
+    pub fn crash_report(&self, id: Uuid) -> Option<CrashReport> {
+        self.crash_reports.lock().unwrap().get(&id).cloned()
+    }
+
+    pub fn cancel_restart<R: Runtime>(&self, app: &AppHandle<R>, server: &ServerStruct) {
+        self.pending_restarts.lock().unwrap().remove(&server.id);
+        emit_restart_state(app, server, "idle", 0, 0, "");
+    }
+
+    // Applies the server's restart policy after it went down without being asked to
+    fn schedule_restart<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        server: ServerStruct,
+        crashed: bool,
+        uptime: Duration,
+    ) {
+        let (decision, attempt) = {
+            let mut history = self.restart_history.lock().unwrap();
+            let history = history.entry(server.id).or_default();
+            let decision =
+                restart_policy::decide(&server, history, crashed, uptime, SystemTime::now());
+            (decision, history.restarts.len() as u32)
+        };
+        match decision {
+            RestartDecision::DontRestart => {}
+            RestartDecision::GiveUp(reason) => {
+                println!("Not restarting {}: {}", server.name, reason);
+                emit_restart_state(app, &server, "gave_up", 0, attempt, &reason);
+            }
+            RestartDecision::RestartAfter(delay) => {
+                self.pending_restarts.lock().unwrap().insert(server.id);
+                emit_restart_state(app, &server, "waiting", delay.as_secs(), attempt, "");
+                let app = app.clone();
+                thread::spawn(move || {
+                    thread::sleep(delay);
+                    let supervisor = app.state::<Supervisor>();
+                    // cancelled or started by hand while we were waiting
+                    if !supervisor
+                        .pending_restarts
+                        .lock()
+                        .unwrap()
+                        .remove(&server.id)
+                    {
+                        return;
+                    }
+                    emit_restart_state(&app, &server, "idle", 0, attempt, "");
+                    if let Err(err) = supervisor.spawn_process(&app, server.clone()) {
+                        emit_restart_state(&app, &server, "gave_up", 0, attempt, &err);
+                    }
+                });
+            }
+        }
+    }
+}
+
+fn emit_restart_state<R: Runtime>(
+    app: &AppHandle<R>,
+    server: &ServerStruct,
+    state: &str,
+    delay_seconds: u64,
+    attempt: u32,
+    reason: &str,
+) {
+    let _ = app.emit(
+        "server_restart",
+        RestartPayload {
+            id: server.id,
+            state: state.to_string(),
+            delay_seconds,
+            attempt,
+            max_restarts: server.max_restarts,
+            reason: reason.to_string(),
+        },
+    );
+}
+
+// Waits for the process to exit, then reports the exit and looks for a crash report
+fn watch_exit<R: Runtime>(app: AppHandle<R>, server: ServerStruct) {
+    let supervisor = app.state::<Supervisor>();
+    let (status, started_at, stop_requested) = loop {
+        thread::sleep(EXIT_POLL_INTERVAL);
+        let mut processes = supervisor.processes.lock().unwrap();
+        let Some(process) = processes.get_mut(&server.id) else {
+            return;
+        };
+        match process.child.try_wait() {
+            Ok(Some(status)) => {
+                let process = processes.remove(&server.id).unwrap();
+                break (status, process.started_at, process.stop_requested);
+            }
+            Ok(None) => continue,
+            Err(err) => {
+                println!("Lost track of {}: {}", server.name, err);
+                let process = processes.remove(&server.id).unwrap();
+                break (Default::default(), process.started_at, true);
+            }
+        }
+    };
+    let exit_code = status.code();
+    let _ = app.emit(
+        "server_status",
+        StatusPayload {
+            id: server.id,
+            status: STATUS_OFFLINE,
+            exit_code,
+        },
+    );
+    port_forwarding::close(&app, server.id);
+    if stop_requested {
+        return;
+    }
+    let uptime = SystemTime::now()
+        .duration_since(started_at)
+        .unwrap_or_default();
+    if status.success() {
+        supervisor.schedule_restart(&app, server, false, uptime);
+        return;
+    }
+    let path = PathBuf::from(&server.path);
+    let mods = mod_scanner::scan_mods(&path);
+    let report = crash_report::analyze_crash(&path, started_at, exit_code, &mods).unwrap_or(
+        // no report was written, still let the user know it went down
+        CrashReport {
+            kind: "unknown".to_string(),
+            description: "The server exited without writing a crash report".to_string(),
+            exit_code,
+            ..Default::default()
+        },
+    );
+    supervisor
+        .crash_reports
+        .lock()
+        .unwrap()
+        .insert(server.id, report.clone());
+    let _ = app.emit(
+        "server_crash",
+        CrashPayload {
+            id: server.id,
+            report,
+        },
+    );
+    supervisor.schedule_restart(&app, server, true, uptime);
+}
@@ -0,0 +1,558 @@
+use regex::Regex;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{LazyLock, Mutex},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use uuid::Uuid;
+
+use crate::gaia_core::{
+    auto_start::manager_message, core_structs::ServerStruct, registry::Registry,
+    supervisor::Supervisor,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+// an hour of samples
+const HISTORY_LENGTH: usize = 240;
+const SPIKE_HISTORY_LENGTH: usize = 100;
+// replies to our own polls are kept out of the console for this long after asking
+const RESPONSE_WINDOW: Duration = Duration::from_secs(5);
+
+static COLOR_CODES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*m|§.").unwrap());
+// paper: "TPS from last 1m, 5m, 15m: 20.0, 20.0, 20.0", capped values get a *
+static PAPER_TPS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^TPS from last 1m, 5m, 15m: \*?([\d.]+)").unwrap());
+// paper: "◴ 1.2/0.8/3.4, 1.1/0.5/5.0, 1.3/0.4/10.2", avg/min/max of the last 5s first
+static PAPER_MSPT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\W*([\d.]+)/[\d.]+/[\d.]+, [\d.]+/[\d.]+/[\d.]+").unwrap());
+// forge: "Dim minecraft:overworld (minecraft:overworld): Mean tick time: 0.345 ms. Mean TPS: 20.000"
+// and "Overall: Mean tick time: 0.5 ms. Mean TPS: 20.000"
+static FORGE_TPS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:Dim\s+(\S+).*?|Overall)\s*:\s*Mean tick time: ([\d.]+) ms\. Mean TPS: ([\d.]+)",
+    )
+    .unwrap()
+});
+// neoforge: "minecraft:overworld: 20.000 TPS (0.345 ms/tick)" and "Overall: 20.000 TPS (0.5 ms/tick)"
+static NEOFORGE_TPS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\S+?):\s+([\d.]+) TPS \(([\d.]+) ms/tick\)").unwrap());
+// vanilla tick query
+static TARGET_RATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Target tick rate: ([\d.]+)").unwrap());
+static AVERAGE_TICK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Average time per tick: ([\d.]+) ?ms").unwrap());
+// "Can't keep up! Is the server overloaded? Running 2034ms or 40 ticks behind",
+// older versions end in "Running 2034ms behind, skipping 40 tick(s)"
+static LAG_SPIKE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^Can't keep up!.*Running (\d+)ms (?:or (\d+) ticks behind|behind, skipping (\d+) tick)",
+    )
+    .unwrap()
+});
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DimensionTick {
+    pub name: String,
+    pub tps: f64,
+    pub mspt: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct TickSample {
+    pub time: u64,
+    pub tps: Option<f64>,
+    pub mspt: Option<f64>,
+    // forge and neoforge report every dimension on its own
+    pub dimensions: Vec<DimensionTick>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LagSpike {
+    pub time: u64,
+    pub behind_ms: u64,
+    pub ticks: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TickHistory {
+    pub samples: Vec<TickSample>,
+    pub spikes: Vec<LagSpike>,
+    pub alerting: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct TickPayload {
+    pub id: Uuid,
+    pub sample: TickSample,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LagSpikePayload {
+    pub id: Uuid,
+    pub spike: LagSpike,
+}
+
+#[derive(Clone, Serialize)]
+pub struct TickAlertPayload {
+    pub id: Uuid,
+    pub alerting: bool,
+    pub tps: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TickLine {
+    Tps(f64),
+    Mspt(f64),
+    Dimension(DimensionTick),
+    Overall { tps: f64, mspt: f64 },
+    TargetRate(f64),
+    AverageTick(f64),
+    LagSpike { behind_ms: u64, ticks: u64 },
+    // the rest of a reply, nothing to read from it
+    Other,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+// "tick query" came with 1.20.3, snapshots and odd version names don't get polled
+fn supports_tick_query(version: &str) -> bool {
+    let parts: Vec<u32> = version
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect();
+    match parts[..] {
+        [1, minor, ..] if minor > 20 => true,
+        [1, 20, patch] => patch >= 3,
+        [major, ..] => major > 1,
+        [] => false,
+    }
+}
+
+// Commands that make the server report its tick times, empty when it has none
+pub fn tick_commands(server: &ServerStruct) -> Vec<&'static str> {
+    match server.server_type.as_str() {
+        "paper" | "purpur" | "folia" => vec!["tps", "mspt"],
+        "forge" => vec!["forge tps"],
+        "neoforge" => vec!["neoforge tps"],
+        // proxies don't tick and bedrock has no command that reports it
+        "velocity" | "bungeecord" | "waterfall" | "bedrock" => Vec::new(),
+        _ if supports_tick_query(&server.minecraft_version) => vec!["tick query"],
+        _ => Vec::new(),
+    }
+}
+
+pub fn parse_line(line: &str) -> Option<TickLine> {
+    let line = COLOR_CODES.replace_all(line, "");
+    // drop the "[12:00:00] [Server thread/INFO]: " prefix
+    let message = line
+        .split_once("]: ")
+        .map_or(&*line, |(_, message)| message)
+        .trim();
+    let number = |text: &str| text.parse::<f64>().ok();
+    if let Some(captures) = LAG_SPIKE.captures(message) {
+        return Some(TickLine::LagSpike {
+            behind_ms: captures[1].parse().ok()?,
+            ticks: captures.get(2).or(captures.get(3))?.as_str().parse().ok()?,
+        });
+    }
+    if let Some(captures) = PAPER_TPS.captures(message) {
+        return Some(TickLine::Tps(number(&captures[1])?));
+    }
+    if let Some(captures) = PAPER_MSPT.captures(message) {
+        return Some(TickLine::Mspt(number(&captures[1])?));
+    }
+    if let Some(captures) = FORGE_TPS.captures(message) {
+        let (mspt, tps) = (number(&captures[2])?, number(&captures[3])?);
+        return Some(match captures.get(1) {
+            Some(name) => TickLine::Dimension(DimensionTick {
+                name: name.as_str().to_string(),
+                tps,
+                mspt,
+            }),
+            None => TickLine::Overall { tps, mspt },
+        });
+    }
+    if let Some(captures) = NEOFORGE_TPS.captures(message) {
+        let (tps, mspt) = (number(&captures[2])?, number(&captures[3])?);
+        return Some(match &captures[1] {
+            "Overall" => TickLine::Overall { tps, mspt },
+            name => TickLine::Dimension(DimensionTick {
+                name: name.to_string(),
+                tps,
+                mspt,
+            }),
+        });
+    }
+    if let Some(captures) = TARGET_RATE.captures(message) {
+        return Some(TickLine::TargetRate(number(&captures[1])?));
+    }
+    if let Some(captures) = AVERAGE_TICK.captures(message) {
+        return Some(TickLine::AverageTick(number(&captures[1])?));
+    }
+    if message.starts_with("Server tick times")
+        || message.starts_with("Percentiles:")
+        || message.starts_with("The game is running")
+    {
+        return Some(TickLine::Other);
+    }
+    None
+}
+
+#[derive(Default)]
+struct ServerTicks {
+    // filled in by the lines of a reply until it's complete
+    pending: TickSample,
+    target_rate: Option<f64>,
+    polled_at: Option<Instant>,
+    history: VecDeque<TickSample>,
+    spikes: VecDeque<LagSpike>,
+    low_since: Option<Instant>,
+    alerting: bool,
+}
+
+#[derive(Default)]
+pub struct TickMonitor {
+    servers: Mutex<HashMap<Uuid, ServerTicks>>,
+}
+
+impl TickMonitor {
+    pub fn history(&self, id: Uuid) -> TickHistory {
+        self.servers
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|ticks| TickHistory {
+                samples: ticks.history.iter().cloned().collect(),
+                spikes: ticks.spikes.iter().cloned().collect(),
+                alerting: ticks.alerting,
+            })
+            .unwrap_or_default()
+    }
+
+    // Reads tick times out of a console line, returns true when the line was a reply
+    // to our own poll and shouldn't clutter up the console
+    pub fn handle_line<R: Runtime>(&self, app: &AppHandle<R>, id: Uuid, line: &str) -> bool {
+        let Some(parsed) = parse_line(line) else {
+            return false;
+        };
+        let mut servers = self.servers.lock().unwrap();
+        let ticks = servers.entry(id).or_default();
+        let polling = ticks
+            .polled_at
+            .is_some_and(|polled_at| polled_at.elapsed() < RESPONSE_WINDOW);
+        let complete = match parsed {
+            TickLine::LagSpike {
+                behind_ms,
+                ticks: behind,
+            } => {
+                let spike = LagSpike {
+                    time: unix_now(),
+                    behind_ms,
+                    ticks: behind,
+                };
+                if ticks.spikes.len() >= SPIKE_HISTORY_LENGTH {
+                    ticks.spikes.pop_front();
+                }
+                ticks.spikes.push_back(spike.clone());
+                let _ = app.emit("server_lag_spike", LagSpikePayload { id, spike });
+                return false;
+            }
+            TickLine::Tps(tps) => {
+                ticks.pending.tps = Some(tps);
+                false
+            }
+            TickLine::Mspt(mspt) => {
+                ticks.pending.mspt = Some(mspt);
+                true
+            }
+            TickLine::Dimension(dimension) => {
+                ticks.pending.dimensions.push(dimension);
+                false
+            }
+            TickLine::Overall { tps, mspt } => {
+                ticks.pending.tps = Some(tps);
+                ticks.pending.mspt = Some(mspt);
+                true
+            }
+            TickLine::TargetRate(rate) => {
+                ticks.target_rate = Some(rate);
+                false
+            }
+            TickLine::AverageTick(mspt) => {
+                // a tick can't run more often than the target rate, only less
+                let target = ticks.target_rate.unwrap_or(20.0);
+                ticks.pending.tps = Some(match mspt > 0.0 {
+                    true => target.min(1000.0 / mspt),
+                    false => target,
+                });
+                ticks.pending.mspt = Some(mspt);
+                true
+            }
+            TickLine::Other => false,
+        };
+        if complete {
+            let sample = finish_sample(ticks);
+            drop(servers);
+            self.record(app, id, sample);
+        }
+        polling
+    }
+
+    // Marks a poll as sent, a reply that never completed (spigot has no mspt) is kept as is
+    fn begin_poll<R: Runtime>(&self, app: &AppHandle<R>, id: Uuid) {
+        let mut servers = self.servers.lock().unwrap();
+        let ticks = servers.entry(id).or_default();
+        ticks.polled_at = Some(Instant::now());
+        if ticks.pending == TickSample::default() {
+            return;
+        }
+        let sample = finish_sample(ticks);
+        drop(servers);
+        self.record(app, id, sample);
+    }
+
+    fn record<R: Runtime>(&self, app: &AppHandle<R>, id: Uuid, sample: TickSample) {
+        let _ = app.emit(
+            "server_ticks",
+            TickPayload {
+                id,
+                sample: sample.clone(),
+            },
+        );
+        let Some(tps) = sample.tps else {
+            return;
+        };
+        let Some(server) = app.state::<Registry>().get(id) else {
+            return;
+        };
+        let changed = {
+            let mut servers = self.servers.lock().unwrap();
+            let ticks = servers.entry(id).or_default();
+            update_alert(ticks, &server, tps)
+        };
+        if let Some(alerting) = changed {
+            let message = match alerting {
+                true => format!(
+                    "TPS has been below {} for {} seconds, it's at {:.1}",
+                    server.tps_alert_threshold, server.tps_alert_seconds, tps
+                ),
+                false => format!("TPS is back up to {:.1}", tps),
+            };
+            manager_message(app, id, message);
+            let _ = app.emit("server_tick_alert", TickAlertPayload { id, alerting, tps });
+        }
+    }
+
+    // Forgets servers that aren't running anymore
+    fn retain(&self, running: &[Uuid]) {
+        self.servers
+            .lock()
+            .unwrap()
+            .retain(|id, _| running.contains(id));
+    }
+}
+
+fn finish_sample(ticks: &mut ServerTicks) -> TickSample {
+    let mut sample = std::mem::take(&mut ticks.pending);
+    sample.time = unix_now();
+    if ticks.history.len() >= HISTORY_LENGTH {
+        ticks.history.pop_front();
+    }
+    ticks.history.push_back(sample.clone());
+    sample
+}
+
+// Returns the new alert state when it changed
+fn update_alert(ticks: &mut ServerTicks, server: &ServerStruct, tps: f64) -> Option<bool> {
+    if !server.tps_alert_enabled || tps >= server.tps_alert_threshold {
+        ticks.low_since = None;
+        if ticks.alerting {
+            ticks.alerting = false;
+            return Some(false);
+        }
+        return None;
+    }
+    let low_since = *ticks.low_since.get_or_insert_with(Instant::now);
+    if !ticks.alerting && low_since.elapsed().as_secs() >= server.tps_alert_seconds {
+        ticks.alerting = true;
+        return Some(true);
+    }
+    None
+}
+
+// Asks every server that finished starting for its tick times
+pub fn spawn<R: Runtime>(app: AppHandle<R>) {
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        let supervisor = app.state::<Supervisor>();
+        let monitor = app.state::<TickMonitor>();
+        let running: Vec<Uuid> = supervisor.pids().into_iter().map(|(id, _)| id).collect();
+        monitor.retain(&running);
+        for id in running {
+            if !supervisor.is_ready(id) {
+                continue;
+            }
+            let Some(server) = app.state::<Registry>().get(id) else {
+                continue;
+            };
+            let commands = tick_commands(&server);
+            if commands.is_empty() {
+                continue;
+            }
+            monitor.begin_poll(&app, id);
+            for command in commands {
+                if let Err(err) = supervisor.send_command(id, command) {
+                    println!("Failed to poll tick times of {}: {}", server.name, err);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dimension(name: &str, tps: f64, mspt: f64) -> Option<TickLine> {
+        Some(TickLine::Dimension(DimensionTick {
+            name: name.to_string(),
+            tps,
+            mspt,
+        }))
+    }
+
+    #[test]
+    fn parses_paper_tps_and_mspt() {
+        assert_eq!(
+            parse_line("[12:00:00 INFO]: TPS from last 1m, 5m, 15m: 19.5, 20.0, 20.0"),
+            Some(TickLine::Tps(19.5))
+        );
+        // colored and capped at 20
+        assert_eq!(
+            parse_line("[12:00:00 INFO]: §6TPS from last 1m, 5m, 15m: §a*20.0, §a*20.0, §a*20.0"),
+            Some(TickLine::Tps(20.0))
+        );
+        assert_eq!(
+            parse_line("[12:00:00 INFO]: ◴ 1.2/0.8/3.4, 1.1/0.5/5.0, 1.3/0.4/10.2"),
+            Some(TickLine::Mspt(1.2))
+        );
+    }
+
+    #[test]
+    fn parses_forge_tps() {
+        let prefix = "[12:00:00] [Server thread/INFO] [minecraft/DedicatedServer]: ";
+        assert_eq!(
+            parse_line(&format!(
+                "{}Dim minecraft:overworld (minecraft:overworld): Mean tick time: 0.345 ms. Mean TPS: 20.000",
+                prefix
+            )),
+            dimension("minecraft:overworld", 20.0, 0.345)
+        );
+        assert_eq!(
+            parse_line(&format!(
+                "{}Overall: Mean tick time: 0.5 ms. Mean TPS: 20.000",
+                prefix
+            )),
+            Some(TickLine::Overall {
+                tps: 20.0,
+                mspt: 0.5
+            })
+        );
+    }
+
+    #[test]
+    fn parses_neoforge_tps() {
+        let prefix = "[12:00:00] [Server thread/INFO] [minecraft/MinecraftServer]: ";
+        assert_eq!(
+            parse_line(&format!(
+                "{}minecraft:the_nether: 19.800 TPS (0.345 ms/tick)",
+                prefix
+            )),
+            dimension("minecraft:the_nether", 19.8, 0.345)
+        );
+        assert_eq!(
+            parse_line(&format!("{}Overall: 20.000 TPS (0.5 ms/tick)", prefix)),
+            Some(TickLine::Overall {
+                tps: 20.0,
+                mspt: 0.5
+            })
+        );
+    }
+
+    #[test]
+    fn parses_tick_query() {
+        let prefix = "[12:00:00] [Server thread/INFO]: ";
+        assert_eq!(
+            parse_line(&format!("{}The game is running normally", prefix)),
+            Some(TickLine::Other)
+        );
+        assert_eq!(
+            parse_line(&format!("{}Target tick rate: 20.0 per second.", prefix)),
+            Some(TickLine::TargetRate(20.0))
+        );
+        assert_eq!(
+            parse_line(&format!(
+                "{}Average time per tick: 1.5ms (Target: 50.0ms)",
+                prefix
+            )),
+            Some(TickLine::AverageTick(1.5))
+        );
+        assert_eq!(
+            parse_line(&format!(
+                "{}Percentiles: P50: 1.2ms P95: 2.0ms P99: 3.1ms, sample: 100",
+                prefix
+            )),
+            Some(TickLine::Other)
+        );
+    }
+
+    #[test]
+    fn parses_lag_spikes() {
+        assert_eq!(
+            parse_line("[12:00:00] [Server thread/WARN]: Can't keep up! Is the server overloaded? Running 2034ms or 40 ticks behind"),
+            Some(TickLine::LagSpike {
+                behind_ms: 2034,
+                ticks: 40
+            })
+        );
+        assert_eq!(
+            parse_line("[12:00:00] [Server thread/WARN]: Can't keep up! Did the system time change, or is the server overloaded? Running 5012ms behind, skipping 100 tick(s)"),
+            Some(TickLine::LagSpike {
+                behind_ms: 5012,
+                ticks: 100
+            })
+        );
+    }
+
+    #[test]
+    fn chat_is_not_a_sample() {
+        assert_eq!(
+            parse_line(
+                "[12:00:00] [Server thread/INFO]: <Steve> TPS from last 1m, 5m, 15m: 1.0, 1.0, 1.0"
+            ),
+            None
+        );
+        assert_eq!(
+            parse_line("[12:00:00] [Server thread/INFO]: <Steve> Can't keep up! Running 9999ms or 200 ticks behind"),
+            None
+        );
+        assert_eq!(
+            parse_line(
+                "[12:00:00] [Server thread/INFO]: <Steve> 1.2/0.8/3.4, 1.1/0.5/5.0, 1.3/0.4/10.2"
+            ),
+            None
+        );
+        assert_eq!(
+            parse_line("[12:00:00] [Server thread/INFO]: Steve joined the game"),
+            None
+        );
+    }
+}
@@ -0,0 +1,633 @@
+use fs_more::directory::{
+    copy_directory, BrokenSymlinkBehaviour, DestinationDirectoryRule, DirectoryCopyDepthLimit,
+    DirectoryCopyOptions, SymlinkBehaviour,
+};
+use serde::Serialize;
+use std::{
+    fs::{self, File},
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::gaia_core::{
+    anvil::{self, DimensionStats},
+    backup::{timestamp, write_zip},
+    level_dat,
+    server_properties::{level_name, set_property},
+};
+
+// Bukkit based servers split the nether and the end off into folders next to the world
+const BUKKIT_DIMENSIONS: [(&str, &str); 2] = [("nether", "_nether"), ("the_end", "_the_end")];
+// Vanilla and forge keep them inside of the world
+const VANILLA_DIMENSIONS: [(&str, &str); 2] = [("nether", "DIM-1"), ("the_end", "DIM1")];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WorldEntry {
+    pub name: String,
+    // folder relative to the server, bedrock worlds are in worlds/
+    pub folder: String,
+    // the world level-name points at, the one the server loads
+    pub active: bool,
+    // bytes on disk, bukkit's nether and end folders included
+    pub size: u64,
+    // UTC, YYYY-MM-DD HH:MM:SS of the newest file in the world
+    pub modified: String,
+    // 0 when level.dat can't be read
+    pub data_version: i32,
+    // "nether" and "the_end" when the world has them, bedrock keeps every dimension in one db
+    pub dimensions: Vec<String>,
+}
+
+fn parent(bedrock: bool) -> &'static str {
+    match bedrock {
+        true => "worlds",
+        false => "",
+    }
+}
+
+// A world name is one folder name, nothing that could point outside of the server
+fn check_name(name: &str) -> Result<(), String> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if !name.contains(['/', '\\']) => Ok(()),
+        _ => Err(format!("Invalid world name {}", name)),
+    }
+}
+
+fn world_path(server_path: &Path, bedrock: bool, name: &str) -> PathBuf {
+    server_path.join(parent(bedrock)).join(name)
+}
+
+fn existing_world(server_path: &Path, bedrock: bool, name: &str) -> Result<PathBuf, String> {
+    check_name(name)?;
+    let path = world_path(server_path, bedrock, name);
+    match path.join("level.dat").is_file() {
+        true => Ok(path),
+        false => Err(format!("There is no world named {}", name)),
+    }
+}
+
+// The world's folder followed by bukkit's dimension folders of it
+fn world_folders(server_path: &Path, bedrock: bool, name: &str) -> Vec<PathBuf> {
+    let mut folders = vec![world_path(server_path, bedrock, name)];
+    if !bedrock {
+        folders.extend(
+            BUKKIT_DIMENSIONS
+                .iter()
+                .map(|(_, suffix)| server_path.join(format!("{}{}", name, suffix)))
+                .filter(|path| path.is_dir()),
+        );
+    }
+    folders
+}
+
+fn dimensions(server_path: &Path, bedrock: bool, name: &str) -> Vec<String> {
+    if bedrock {
+        return Vec::new();
+    }
+    let world = world_path(server_path, false, name);
+    BUKKIT_DIMENSIONS
+        .iter()
+        .zip(VANILLA_DIMENSIONS)
+        .filter(|((_, suffix), (_, folder))| {
+            server_path.join(format!("{}{}", name, suffix)).is_dir() || world.join(folder).is_dir()
+        })
+        .map(|((dimension, _), _)| dimension.to_string())
+        .collect()
+}
+
+// Folders holding each dimension's region folder, bukkit's own dimension folders first
+pub fn dimension_folders(server_path: &Path, name: &str) -> Vec<(&'static str, PathBuf)> {
+    let world = world_path(server_path, false, name);
+    let mut folders = vec![("overworld", world.clone())];
+    for ((dimension, suffix), (_, folder)) in BUKKIT_DIMENSIONS.iter().zip(VANILLA_DIMENSIONS) {
+        let bukkit = server_path.join(format!("{}{}", name, suffix)).join(folder);
+        folders.extend(
+            [bukkit, world.join(folder)]
+                .into_iter()
+                .find(|path| path.join("region").is_dir())
+                .map(|path| (*dimension, path)),
+        );
+    }
+    folders
+}
+
+// Chunk counts, sizes and damaged chunks of every dimension, bedrock worlds aren't in region files
+pub fn chunk_stats(
+    server_path: &Path,
+    bedrock: bool,
+    name: &str,
+) -> Result<Vec<DimensionStats>, String> {
+    if bedrock {
+        return Err("Bedrock worlds aren't stored in region files".to_string());
+    }
+    existing_world(server_path, false, name)?;
+    Ok(dimension_folders(server_path, name)
+        .iter()
+        .map(|(dimension, folder)| anvil::dimension_stats(dimension, folder))
+        .collect())
+}
+
+// Every world with a level.dat. Bukkit's dimension folders have one too, they're shown as
+// part of their world instead
+pub fn list(server_path: &Path, bedrock: bool) -> Vec<WorldEntry> {
+    let Ok(entries) = fs::read_dir(server_path.join(parent(bedrock))) else {
+        return Vec::new();
+    };
+    let names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().join("level.dat").is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    let is_dimension = |name: &String| {
+        !bedrock
+            && BUKKIT_DIMENSIONS.iter().any(|(_, suffix)| {
+                name.strip_suffix(suffix)
+                    .is_some_and(|base| names.iter().any(|other| other == base))
+            })
+    };
+    let active = level_name(server_path);
+    let mut worlds: Vec<WorldEntry> = names
+        .iter()
+        .filter(|name| !is_dimension(name))
+        .map(|name| {
+            let (mut size, mut modified) = (0, UNIX_EPOCH);
+            for folder in world_folders(server_path, bedrock, name) {
+                for entry in WalkDir::new(folder).into_iter().flatten() {
+                    let Ok(metadata) = entry.metadata() else {
+                        continue;
+                    };
+                    if metadata.is_file() {
+                        size += metadata.len();
+                        modified = modified.max(metadata.modified().unwrap_or(UNIX_EPOCH));
+                    }
+                }
+            }
+            let folder = Path::new(parent(bedrock))
+                .join(name)
+                .to_string_lossy()
+                .replace('\\', "/");
+            WorldEntry {
+                name: name.clone(),
+                data_version: level_dat::read(server_path, &folder)
+                    .map(|info| info.data_version)
+                    .unwrap_or(0),
+                folder,
+                active: *name == active,
+                size,
+                modified: timestamp(modified).1,
+                dimensions: dimensions(server_path, bedrock, name),
+            }
+        })
+        .collect();
+    worlds.sort_by(|a, b| a.name.cmp(&b.name));
+    worlds
+}
+
+pub fn set_active(server_path: &Path, bedrock: bool, name: &str) -> Result<(), String> {
+    existing_world(server_path, bedrock, name)?;
+    set_property(server_path, "level-name", name)
+}
+
+// The shallowest folder with a level.dat, worlds are often zipped inside of a folder of their own.
+// Bukkit's world_nether next to world has one as well, world is the one wanted
+fn find_world(directory: &Path) -> Option<PathBuf> {
+    let is_dimension = |folder: &Path| {
+        let name = folder.file_name().unwrap_or_default().to_string_lossy();
+        BUKKIT_DIMENSIONS.iter().any(|(_, suffix)| {
+            name.strip_suffix(suffix)
+                .is_some_and(|base| folder.with_file_name(base).join("level.dat").is_file())
+        })
+    };
+    WalkDir::new(directory)
+        .max_depth(4)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_name() == "level.dat" && entry.file_type().is_file())
+        .filter_map(|entry| Some((entry.depth(), entry.path().parent()?.to_path_buf())))
+        .min_by_key(|(depth, folder)| (*depth, is_dimension(folder)))
+        .map(|(_, folder)| folder)
+}
+
+fn copy_folder(source: &Path, destination: &Path) -> Result<(), String> {
+    copy_directory(
+        source,
+        destination,
+        DirectoryCopyOptions {
+            destination_directory_rule: DestinationDirectoryRule::DisallowExisting,
+            copy_depth_limit: DirectoryCopyDepthLimit::Unlimited,
+            symlink_behaviour: SymlinkBehaviour::Keep,
+            broken_symlink_behaviour: BrokenSymlinkBehaviour::Keep,
+        },
+    )
+    .map(|_| ())
+    .map_err(|err| format!("Failed to copy {:?}: {}", source, err))
+}
+
+fn move_folder(source: &Path, destination: &Path) -> Result<(), String> {
+    fs::rename(source, destination).map_err(|err| format!("Failed to move {:?}: {}", source, err))
+}
+
+// Puts the world found in source in as name, with bukkit's dimension folders next to it if
+// they came along
+fn place_world(
+    server_path: &Path,
+    bedrock: bool,
+    source: &Path,
+    name: &str,
+    transfer: fn(&Path, &Path) -> Result<(), String>,
+) -> Result<(), String> {
+    let root =
+        find_world(source).ok_or_else(|| format!("{:?} doesn't contain a level.dat", source))?;
+    let destination = world_path(server_path, bedrock, name);
+    fs::create_dir_all(destination.parent().unwrap()).map_err(|err| err.to_string())?;
+    transfer(&root, &destination)?;
+    let (Some(folder), Some(root_name)) = (root.parent(), root.file_name()) else {
+        return Ok(());
+    };
+    if bedrock {
+        return Ok(());
+    }
+    for (_, suffix) in BUKKIT_DIMENSIONS {
+        let dimension = folder.join(format!("{}{}", root_name.to_string_lossy(), suffix));
+        let target = server_path.join(format!("{}{}", name, suffix));
+        if dimension.is_dir() && !target.exists() {
+            transfer(&dimension, &target)?;
+        }
+    }
+    Ok(())
+}
+
+// Copies a world folder in, or unpacks a zip (an .mcworld is one too), under a new name
+pub fn import(server_path: &Path, bedrock: bool, source: &Path, name: &str) -> Result<(), String> {
+    check_name(name)?;
+    if world_path(server_path, bedrock, name).exists() {
+        return Err(format!("A world named {} already exists", name));
+    }
+    if source.is_dir() {
+        return place_world(server_path, bedrock, source, name, copy_folder);
+    }
+    let file = File::open(source).map_err(|err| format!("Failed to open {:?}: {}", source, err))?;
+    // unpacked inside of the server so the world can be moved into place instead of copied
+    let temp = server_path.join(format!(".import-{}", Uuid::new_v4()));
+    let result = zip::ZipArchive::new(file)
+        .and_then(|mut archive| archive.extract(&temp))
+        .map_err(|err| format!("Failed to unpack {:?}: {}", source, err))
+        .and_then(|_| place_world(server_path, bedrock, &temp, name, move_folder));
+    let _ = fs::remove_dir_all(&temp);
+    result
+}
+
+// Zips the world as <name>/..., with bukkit's dimension folders as <name>_nether/... and so on
+pub fn export(
+    server_path: &Path,
+    bedrock: bool,
+    name: &str,
+    destination: &Path,
+) -> Result<(), String> {
+    existing_world(server_path, bedrock, name)?;
+    let base = server_path.join(parent(bedrock));
+    let mut files = Vec::new();
+    for folder in world_folders(server_path, bedrock, name) {
+        for entry in WalkDir::new(&folder) {
+            let entry = entry.map_err(|err| err.to_string())?;
+            // the running server holds this one, it means nothing in another server
+            if !entry.file_type().is_file() || entry.file_name() == "session.lock" {
+                continue;
+            }
+            let relative = entry
+                .path()
+                .strip_prefix(&base)
+                .map_err(|err| err.to_string())?;
+            files.push((
+                entry.path().to_path_buf(),
+                relative.to_string_lossy().replace('\\', "/"),
+            ));
+        }
+    }
+    let partial = PathBuf::from(format!("{}.partial", destination.to_string_lossy()));
+    if let Err(err) = write_zip(&partial, &files) {
+        let _ = fs::remove_file(&partial);
+        return Err(err);
+    }
+    fs::rename(&partial, destination).map_err(|err| err.to_string())
+}
+
+fn remove_world(server_path: &Path, bedrock: bool, name: &str) -> Result<(), String> {
+    for folder in world_folders(server_path, bedrock, name) {
+        fs::remove_dir_all(&folder)
+            .map_err(|err| format!("Failed to delete {:?}: {}", folder, err))?;
+    }
+    Ok(())
+}
+
+// Only the active world can be regenerated, level-seed is used for whatever world gets made
+pub fn check_regenerate(server_path: &Path, bedrock: bool, name: &str) -> Result<(), String> {
+    existing_world(server_path, bedrock, name)?;
+    match level_name(server_path) == name {
+        true => Ok(()),
+        false => Err(format!(
+            "{} isn't the active world, switch to it first",
+            name
+        )),
+    }
+}
+
+// Deletes the active world so the server generates it again on its next start, an empty
+// seed leaves the server to pick a random one
+pub fn regenerate(server_path: &Path, bedrock: bool, name: &str, seed: &str) -> Result<(), String> {
+    check_regenerate(server_path, bedrock, name)?;
+    remove_world(server_path, bedrock, name)?;
+    set_property(server_path, "level-seed", seed.trim())
+}
+
+// The active world can't be deleted, the server would just generate a new one in its place
+pub fn delete(server_path: &Path, bedrock: bool, name: &str) -> Result<(), String> {
+    existing_world(server_path, bedrock, name)?;
+    if level_name(server_path) == name {
+        return Err(format!(
+            "{} is the active world, switch to another world first",
+            name
+        ));
+    }
+    remove_world(server_path, bedrock, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gaia_core::server_properties::read_properties;
+
+    struct TestDir {
+        path: PathBuf,
+    }
+
+    impl TestDir {
+        fn new() -> TestDir {
+            let path = std::env::temp_dir().join(format!("gaia-worlds-{}", Uuid::new_v4()));
+            fs::create_dir_all(&path).unwrap();
+            TestDir { path }
+        }
+
+        fn write(&self, relative: &str, content: &str) {
+            let path = self.path.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        fn exists(&self, relative: &str) -> bool {
+            self.path.join(relative).exists()
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    // A bukkit server running "world", with its nether and a second world "old"
+    fn bukkit_server() -> TestDir {
+        let server = TestDir::new();
+        server.write("server.properties", "level-name=world\nlevel-seed=\n");
+        server.write("world/level.dat", "world");
+        server.write("world/region/r.0.0.mca", "chunks");
+        server.write("world/session.lock", "lock");
+        server.write("world_nether/level.dat", "nether");
+        server.write("world_nether/DIM-1/region/r.0.0.mca", "nether chunks");
+        server.write("old/level.dat", "old");
+        server.write("old_nether/level.dat", "old nether");
+        server
+    }
+
+    #[test]
+    fn finds_nested_worlds() {
+        let source = TestDir::new();
+        source.write("My Map v2/My Map/level.dat", "map");
+        source.write("My Map v2/My Map/data/idcounts.dat", "");
+        source.write("My Map v2/README.txt", "");
+        assert_eq!(
+            find_world(&source.path),
+            Some(source.path.join("My Map v2/My Map"))
+        );
+        // the shallowest level.dat wins
+        source.write("My Map v2/level.dat", "outer");
+        assert_eq!(
+            find_world(&source.path),
+            Some(source.path.join("My Map v2"))
+        );
+        assert_eq!(find_world(&TestDir::new().path), None);
+    }
+
+    #[test]
+    fn finds_the_world_next_to_its_bukkit_dimensions() {
+        let source = TestDir::new();
+        source.write("export/survival_nether/level.dat", "nether");
+        source.write("export/survival_the_end/level.dat", "end");
+        source.write("export/survival/level.dat", "world");
+        assert_eq!(
+            find_world(&source.path),
+            Some(source.path.join("export/survival"))
+        );
+        // a lone _nether folder is still a world
+        let lone = TestDir::new();
+        lone.write("hub_nether/level.dat", "nether");
+        assert_eq!(find_world(&lone.path), Some(lone.path.join("hub_nether")));
+    }
+
+    #[test]
+    fn lists_bukkit_dimensions_as_part_of_their_world() {
+        let server = bukkit_server();
+        let worlds = list(&server.path, false);
+        let names: Vec<&str> = worlds.iter().map(|world| world.name.as_str()).collect();
+        assert_eq!(names, vec!["old", "world"]);
+        let world = &worlds[1];
+        assert!(world.active);
+        assert!(!worlds[0].active);
+        assert_eq!(world.dimensions, vec!["nether".to_string()]);
+        // world_nether is counted in
+        assert_eq!(world.size, 5 + 6 + 4 + 6 + 13);
+    }
+
+    #[test]
+    fn imports_a_nested_zip_with_its_dimensions() {
+        let server = bukkit_server();
+        let source = TestDir::new();
+        source.write("pack/Cool World/level.dat", "cool");
+        source.write("pack/Cool World/region/r.0.0.mca", "chunks");
+        source.write("pack/Cool World_nether/level.dat", "cool nether");
+        source.write("pack/Cool World_nether/DIM-1/region/r.0.0.mca", "chunks");
+        let files: Vec<(PathBuf, String)> = WalkDir::new(&source.path)
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| {
+                let name = entry.path().strip_prefix(&source.path).unwrap();
+                (
+                    entry.path().to_path_buf(),
+                    name.to_string_lossy().replace('\\', "/"),
+                )
+            })
+            .collect();
+        let archive = source.path.join("cool.zip");
+        write_zip(&archive, &files).unwrap();
+
+        import(&server.path, false, &archive, "cool").unwrap();
+        assert_eq!(
+            fs::read_to_string(server.path.join("cool/level.dat")).unwrap(),
+            "cool"
+        );
+        assert!(server.exists("cool/region/r.0.0.mca"));
+        assert!(server.exists("cool_nether/DIM-1/region/r.0.0.mca"));
+        // nothing unpacked is left behind
+        let leftovers = fs::read_dir(&server.path)
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(".import-"))
+            .count();
+        assert_eq!(leftovers, 0);
+        assert!(archive.is_file());
+    }
+
+    #[test]
+    fn imports_folders_and_bedrock_worlds() {
+        let server = bukkit_server();
+        let source = TestDir::new();
+        source.write("Bedrock level/level.dat", "bedrock");
+        source.write("Bedrock level/db/CURRENT", "MANIFEST-000001");
+
+        import(&server.path, true, &source.path, "Island").unwrap();
+        assert!(server.exists("worlds/Island/db/CURRENT"));
+        // copied, the source stays
+        assert!(source.exists("Bedrock level/level.dat"));
+        assert_eq!(list(&server.path, true)[0].name, "Island");
+    }
+
+    #[test]
+    fn import_refuses_bad_names() {
+        let server = bukkit_server();
+        let source = TestDir::new();
+        source.write("world/level.dat", "new");
+        assert_eq!(
+            import(&server.path, false, &source.path, "old"),
+            Err("A world named old already exists".to_string())
+        );
+        assert_eq!(
+            fs::read_to_string(server.path.join("old/level.dat")).unwrap(),
+            "old"
+        );
+        for name in ["", "..", "../outside", "a/b", "a\\b"] {
+            assert!(import(&server.path, false, &source.path, name).is_err());
+        }
+        assert!(!server.path.parent().unwrap().join("outside").exists());
+        let empty = TestDir::new();
+        assert!(import(&server.path, false, &empty.path, "empty").is_err());
+        assert!(!server.exists("empty"));
+    }
+
+    #[test]
+    fn exports_the_world_with_its_dimensions() {
+        let server = bukkit_server();
+        let destination = TestDir::new();
+        let archive = destination.path.join("world.zip");
+        export(&server.path, false, "world", &archive).unwrap();
+        assert!(!destination.exists("world.zip.partial"));
+
+        let archive = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "world/level.dat",
+                "world/region/r.0.0.mca",
+                "world_nether/DIM-1/region/r.0.0.mca",
+                "world_nether/level.dat",
+            ]
+        );
+
+        // session.lock stays out, and it imports back as the same world
+        let copy = TestDir::new();
+        import(
+            &copy.path,
+            false,
+            &destination.path.join("world.zip"),
+            "copy",
+        )
+        .unwrap();
+        assert!(copy.exists("copy/region/r.0.0.mca"));
+        assert!(copy.exists("copy_nether/DIM-1/region/r.0.0.mca"));
+        assert!(export(
+            &server.path,
+            false,
+            "missing",
+            &destination.path.join("x.zip")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn delete_keeps_the_active_world() {
+        let server = bukkit_server();
+        assert_eq!(
+            delete(&server.path, false, "world"),
+            Err("world is the active world, switch to another world first".to_string())
+        );
+        assert!(server.exists("world/level.dat"));
+        assert!(server.exists("world_nether/level.dat"));
+
+        delete(&server.path, false, "old").unwrap();
+        assert!(!server.exists("old"));
+        assert!(!server.exists("old_nether"));
+        assert!(server.exists("world/level.dat"));
+        assert!(server.exists("world_nether/level.dat"));
+
+        assert!(delete(&server.path, false, "old").is_err());
+        assert!(delete(&server.path, false, "../world").is_err());
+        // a folder without a level.dat isn't a world
+        server.write("plugins/Essentials/config.yml", "");
+        assert!(delete(&server.path, false, "plugins").is_err());
+        assert!(server.exists("plugins/Essentials/config.yml"));
+    }
+
+    #[test]
+    fn regenerate_only_the_active_world() {
+        let server = bukkit_server();
+        assert_eq!(
+            regenerate(&server.path, false, "old", "123"),
+            Err("old isn't the active world, switch to it first".to_string())
+        );
+        assert!(server.exists("old/level.dat"));
+        assert!(regenerate(&server.path, false, "missing", "123").is_err());
+        assert!(regenerate(&server.path, true, "world", "123").is_err());
+
+        regenerate(&server.path, false, "world", " 123 ").unwrap();
+        assert!(!server.exists("world"));
+        assert!(!server.exists("world_nether"));
+        assert!(server.exists("old/level.dat"));
+        assert!(server.exists("old_nether/level.dat"));
+        let properties = read_properties(&server.path);
+        assert_eq!(
+            properties.get("level-seed").map(String::as_str),
+            Some("123")
+        );
+        assert_eq!(
+            properties.get("level-name").map(String::as_str),
+            Some("world")
+        );
+    }
+
+    #[test]
+    fn set_active_needs_a_world() {
+        let server = bukkit_server();
+        set_active(&server.path, false, "old").unwrap();
+        assert_eq!(level_name(&server.path), "old");
+        assert!(set_active(&server.path, false, "missing").is_err());
+        assert_eq!(level_name(&server.path), "old");
+        // switching made the old active world deletable
+        delete(&server.path, false, "world").unwrap();
+        assert!(!server.exists("world_nether"));
+    }
+}
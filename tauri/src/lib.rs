@@ -36,14 +36,6 @@ fn image_from_path_to_base64(path: String) -> Option<String> {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
-        .setup(|app| {
-            #[cfg(desktop)]
-            {
-                let _ = app.handle().plugin(gaia_core::init());
-            }
-
-            Ok(())
-        })
         .plugin(gaia_core::init())
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![